@@ -0,0 +1,97 @@
+//! The auth module holds credentials of password-protected sites, like staging environments
+//! crawled before their public release, sent with every sitemap and page request of a scrape.
+
+use crate::{Error, Result, secrets::Secret};
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+
+/// Standard base64 alphabet of Basic credentials.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Credentials sent in the `Authorization` header of requests.
+///
+/// Its `Debug` output leaves out passwords and tokens, so that it can be logged.
+#[derive(Clone)]
+pub enum Auth {
+    /// HTTP Basic authentication
+    Basic { user: String, password: Secret },
+    /// Bearer token authentication
+    Bearer(Secret),
+}
+
+impl Auth {
+    /// Reads HTTP Basic credentials written as `user:pass`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::Config`] if there is no colon between the user and the password.
+    pub fn basic(credentials: &str) -> Result<Self> {
+        let (user, password) = credentials.split_once(':').ok_or_else(|| {
+            Error::Config("Basic auth credentials must be written as user:pass".into())
+        })?;
+
+        Ok(Self::Basic {
+            user: user.to_string(),
+            password: Secret::from(password),
+        })
+    }
+
+    /// Returns the value of the `Authorization` header carrying the credentials.
+    pub fn header_value(&self) -> String {
+        match self {
+            Self::Basic { user, password } => {
+                format!(
+                    "Basic {}",
+                    encode_base64(format!("{user}:{}", password.expose()).as_bytes())
+                )
+            }
+            Self::Bearer(token) => format!("Bearer {}", token.expose()),
+        }
+    }
+
+    /// Returns the headers carrying the credentials, marked sensitive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::Config`] if the credentials contain characters
+    /// which aren't allowed in a header, like line breaks.
+    pub fn headers(&self) -> Result<HeaderMap> {
+        let mut value = HeaderValue::from_str(&self.header_value())
+            .map_err(|error| Error::Config(error.into()).context("Invalid auth credentials"))?;
+        value.set_sensitive(true);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, value);
+        Ok(headers)
+    }
+}
+
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Basic { user, .. } => write!(formatter, "Basic({user})"),
+            Self::Bearer(_) => write!(formatter, "Bearer"),
+        }
+    }
+}
+
+/// Encodes bytes as padded standard base64.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .chain(std::iter::repeat(&0))
+            .take(3)
+            .fold(0, |triple, &byte| (triple << 8) | usize::from(byte));
+        for position in 0..4 {
+            if position <= chunk.len() {
+                let index = (triple >> (18 - 6 * position)) & 0x3f;
+                encoded.extend(BASE64_ALPHABET.get(index).map(|&symbol| char::from(symbol)));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
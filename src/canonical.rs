@@ -0,0 +1,80 @@
+//! The canonical module normalizes page URLs so that variants of the same page
+//! (tracking parameters, fragments, trailing slashes) are stored only once.
+
+use url::Url;
+
+use crate::TrailingSlash;
+
+/// Query parameter prefixes used by analytics and ad tracking.
+const TRACKING_PARAM_PREFIXES: [&str; 2] = ["utm_", "pk_"];
+
+/// Query parameters used by analytics and ad tracking.
+const TRACKING_PARAMS: [&str; 9] = [
+    "gclid", "dclid", "fbclid", "msclkid", "yclid", "mc_cid", "mc_eid", "_ga", "ref_src",
+];
+
+/// Normalizes an URL by stripping its fragment and tracking query parameters,
+/// and applying the trailing slash policy to its path.
+///
+/// Default ports are dropped and the host is lowercased when the URL is parsed.
+///
+/// # Arguments
+///
+/// * `url` - The URL to normalize
+/// * `trailing_slash` - What to do with a trailing slash of the path
+pub fn normalize_url(url: &Url, trailing_slash: &TrailingSlash) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+
+    if url.query().is_some() {
+        let kept_pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !is_tracking_param(name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        let removed_any = url.query_pairs().count() != kept_pairs.len();
+
+        if kept_pairs.is_empty() {
+            url.set_query(None);
+        } else if removed_any {
+            url.query_pairs_mut().clear().extend_pairs(kept_pairs);
+        }
+    }
+
+    let path = url.path().to_string();
+    match trailing_slash {
+        TrailingSlash::Keep => {}
+        TrailingSlash::Strip => {
+            if path.len() > 1 && path.ends_with('/') {
+                url.set_path(path.trim_end_matches('/'));
+            }
+        }
+        TrailingSlash::Add => {
+            let last_segment = path.rsplit('/').next().unwrap_or_default();
+            if !path.ends_with('/') && !last_segment.contains('.') {
+                url.set_path(&format!("{path}/"));
+            }
+        }
+    }
+
+    url
+}
+
+/// Normalizes an URL given as a string, see [`normalize_url`].
+///
+/// Returns the string unchanged if it's not a valid URL.
+pub fn normalize_url_str(url: &str, trailing_slash: &TrailingSlash) -> String {
+    match Url::parse(url) {
+        Ok(parsed_url) => normalize_url(&parsed_url, trailing_slash).to_string(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Returns `true` if the query parameter is only used for tracking.
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    TRACKING_PARAMS.contains(&name.as_str())
+        || TRACKING_PARAM_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
@@ -0,0 +1,150 @@
+//! The client module exposes the whole pipeline over a single database connection,
+//! for services embedding llamap instead of running its commands.
+
+use llm::builder::LLMBuilder;
+use url::Url;
+
+use crate::ParseTarget;
+use crate::compose::{ComposeOptions, compose_with_storage};
+use crate::error::Result;
+use crate::parse::{ParseOptions, parse_with_storage};
+use crate::scrape::{
+    ScrapeOptions, ScrapeReport, ScrapeSource, fetch_with_storage, process_source,
+};
+use crate::storage::Storage;
+use crate::summarize::{
+    SummarizeOptions, SummaryModel, scrape_and_summarize, summarize_with_storage,
+};
+
+/// Scrapes, parses, summarizes and composes pages of a database opened once.
+///
+/// Every run is recorded in the audit log like the runs of the matching commands.
+#[derive(Clone)]
+pub struct Llamap {
+    storage: Storage,
+}
+
+impl Llamap {
+    /// Opens the database at the given path, creating it if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `db_path` - Path to the database, `:memory:` for a database living as long as the client
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database can't be opened or initialized
+    pub fn open(db_path: &str) -> Result<Self> {
+        Ok(Llamap {
+            storage: Storage::new(db_path)?,
+        })
+    }
+
+    /// Creates a client over an already opened database, e.g. [`Storage::in_memory`] in tests.
+    pub fn with_storage(storage: Storage) -> Self {
+        Llamap { storage }
+    }
+
+    /// Returns the database of the client, e.g. to query stored pages or runs.
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// Scrapes pages listed by a sitemap or feed, see [`crate::process_sitemap`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sitemap can't be read, or network or database operations fail
+    pub async fn scrape(&self, sitemap_url: Url, options: ScrapeOptions) -> Result<ScrapeReport> {
+        process_source(
+            ScrapeSource::Sitemaps(vec![sitemap_url]),
+            self.storage.clone(),
+            options,
+        )
+        .await
+    }
+
+    /// Scrapes pages listed by a sitemap or feed and summarizes them as they are parsed,
+    /// see [`crate::summarize_while_scraping`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if pages aren't parsed on scrape or the summarize options don't target
+    /// unsummarized pages, or if the scrape or the summarize run fails
+    pub async fn scrape_and_summarize(
+        &self,
+        sitemap_url: Url,
+        scrape_options: ScrapeOptions,
+        model: SummaryModel,
+        options: SummarizeOptions,
+    ) -> Result<ScrapeReport> {
+        scrape_and_summarize(
+            ScrapeSource::Sitemaps(vec![sitemap_url]),
+            self.storage.clone(),
+            scrape_options,
+            model,
+            options,
+        )
+        .await
+    }
+
+    /// Scrapes URLs listed one per line in a file, see [`crate::process_url_list`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the list can't be read, or network or database operations fail
+    pub async fn scrape_url_list(
+        &self,
+        list_path: &str,
+        options: ScrapeOptions,
+    ) -> Result<ScrapeReport> {
+        process_source(
+            ScrapeSource::UrlList(list_path.to_string()),
+            self.storage.clone(),
+            options,
+        )
+        .await
+    }
+
+    /// Downloads and stores a single page, see [`crate::process_page_url`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the crawler can't be set up, or database operations fail
+    pub async fn fetch(&self, url: &Url, options: ScrapeOptions) -> Result<ScrapeReport> {
+        fetch_with_storage(url, self.storage.clone(), options).await
+    }
+
+    /// Extracts text of stored pages, see [`crate::parse_db_html`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operations fail, or a page fails to be extracted
+    /// while parsing a single page or failing fast
+    pub async fn parse(&self, target: ParseTarget, options: &ParseOptions) -> Result<()> {
+        parse_with_storage(&self.storage, target, options).await
+    }
+
+    /// Summarizes stored pages, see [`crate::summarize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model fails to build or to answer, or database operations fail
+    pub async fn summarize(&self, model: SummaryModel, options: SummarizeOptions) -> Result<()> {
+        summarize_with_storage(&self.storage, model, options).await
+    }
+
+    /// Composes stored pages to an llms.txt file, see [`crate::compose`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model is missing or fails, or database or file operations fail
+    pub async fn compose(
+        &self,
+        output_path: &str,
+        options: ComposeOptions,
+        llm_builder: Option<LLMBuilder>,
+    ) -> Result<()> {
+        compose_with_storage(&self.storage, output_path, options, llm_builder).await
+    }
+}
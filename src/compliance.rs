@@ -0,0 +1,114 @@
+//! The compliance module reports how a stored site was crawled against what it asks of
+//! crawlers: the `robots.txt` rules of every host, pages skipped as they are disallowed, pages
+//! flagged noindex and pages stored although disallowed, so that teams can show they crawl
+//! responsibly.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use url::Url;
+
+use crate::Result;
+use crate::error::Context;
+use crate::init::fetch_text;
+use crate::robots::{RobotsTxt, parse_robots_txt};
+use crate::storage::{PageFilter, Storage};
+
+/// Compliance of the stored pages of a host
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostCompliance {
+    /// Origin of the host, e.g. `https://example.com`
+    pub origin: String,
+    /// The `robots.txt` of the host as it's served now, None if it can't be fetched
+    pub robots: Option<RobotsTxt>,
+    /// Number of stored pages of the host
+    pub stored: usize,
+    /// Stored pages whose robots meta tag or `X-Robots-Tag` header asks not to index them
+    pub noindex: Vec<String>,
+    /// Stored pages `robots.txt` disallows, which should be removed or excluded
+    pub disallowed: Vec<String>,
+    /// Listed or linked pages which aren't stored as `robots.txt` disallows them
+    pub skipped: Vec<String>,
+}
+
+impl HostCompliance {
+    /// Returns whether the `robots.txt` of the host allows crawling the URL, all URLs being
+    /// allowed without one.
+    fn is_allowed(&self, url: &Url) -> bool {
+        self.robots
+            .as_ref()
+            .is_none_or(|robots| robots.is_allowed(url))
+    }
+}
+
+/// Reports the compliance of every host of the stored pages, fetching their `robots.txt`.
+///
+/// # Arguments
+///
+/// * `storage` - The storage with the scraped pages
+///
+/// # Errors
+///
+/// Returns an error if database operations fail or the HTTP client can't be built.
+/// Hosts whose `robots.txt` can't be fetched are reported without one instead.
+pub async fn compliance_report(storage: &Storage) -> Result<Vec<HostCompliance>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .context("Unable to build the HTTP client")?;
+
+    let mut hosts: BTreeMap<String, HostCompliance> = BTreeMap::new();
+    let stored = parse_urls(storage.list_urls()?);
+    let unstored = parse_urls(storage.list_unstored_urls()?);
+    for url in stored.iter().chain(&unstored) {
+        let origin = url.origin().ascii_serialization();
+        if !hosts.contains_key(&origin) {
+            let robots = match url.join("/robots.txt") {
+                Ok(robots_url) => fetch_text(&client, &robots_url)
+                    .await
+                    .map(|content| parse_robots_txt(&content)),
+                Err(_) => None,
+            };
+            hosts.insert(
+                origin.clone(),
+                HostCompliance {
+                    origin,
+                    robots,
+                    ..HostCompliance::default()
+                },
+            );
+        }
+    }
+
+    for url in &stored {
+        if let Some(host) = hosts.get_mut(&url.origin().ascii_serialization()) {
+            host.stored += 1;
+            if !host.is_allowed(url) {
+                host.disallowed.push(url.to_string());
+            }
+        }
+    }
+    for url in &unstored {
+        if let Some(host) = hosts.get_mut(&url.origin().ascii_serialization())
+            && !host.is_allowed(url)
+        {
+            host.skipped.push(url.to_string());
+        }
+    }
+    for url in parse_urls(storage.find_urls(&PageFilter::new().noindex(true))?) {
+        if let Some(host) = hosts.get_mut(&url.origin().ascii_serialization()) {
+            host.noindex.push(url.to_string());
+        }
+    }
+
+    Ok(hosts.into_values().collect())
+}
+
+/// Parses URLs in order, skipping invalid ones.
+fn parse_urls(urls: Vec<String>) -> Vec<Url> {
+    let mut urls: Vec<Url> = urls
+        .into_iter()
+        .filter_map(|url| Url::parse(&url).ok())
+        .collect();
+    urls.sort();
+    urls
+}
@@ -2,12 +2,93 @@
 
 extern crate spider;
 
-use anyhow::Result;
+use llm::LLMProvider;
+use llm::builder::LLMBuilder;
 use log::info;
-use std::fs::OpenOptions;
-use std::io::Write;
 
-use crate::storage::{Page, Storage};
+use crate::parse::StructuredData;
+use crate::progress::Progress;
+use crate::stdio::is_stdio;
+use crate::storage::{
+    ArchivedPage, Page, PageFilter, PageSection, PageSelection, PageStatus, Storage,
+    UNKNOWN_TIMESTAMP,
+};
+use crate::summarize::SummaryLanguage;
+use crate::{
+    ArchivedPages, Byline, ComposeFormat, ComposeSource, Error, ExistingOutput, MediaNotes,
+    NoindexPages, OutputBackup, PageSort, Result, SectionLinks, UpdatedDate,
+};
+
+mod budget;
+mod export;
+mod front_matter;
+mod merge;
+mod output;
+mod redact;
+mod slug;
+mod spec;
+
+pub use export::MarkdownExport;
+pub use front_matter::FrontMatter;
+pub use merge::{MANAGED_BEGIN, MANAGED_END};
+pub use redact::{Redaction, RegexScrubber, Scrubber};
+pub use slug::{HashSlugger, PathSlugger, SlugStrategy, Slugger, TitleSlugger};
+pub(crate) use spec::section_of;
+
+/// Options controlling which pages are composed and how
+#[derive(Clone, Debug, Default)]
+pub struct ComposeOptions {
+    /// Source to compose from: text, summary or the best of them
+    pub source: ComposeSource,
+    /// Pages to compose, every composable page by default
+    pub filter: PageFilter,
+    /// Order of the composed pages
+    pub sort: PageSort,
+    /// Maximal size of the output file in bytes
+    pub max_bytes: Option<usize>,
+    /// Maximal number of composed pages
+    pub max_entries: Option<usize>,
+    /// Layout of the output file
+    pub format: ComposeFormat,
+    /// Whether to write an LLM generated overview under each section of the spec format
+    pub section_overviews: bool,
+    /// Whether to write an LLM generated site description under the heading of the spec format
+    pub describe: bool,
+    /// Whether to list keywords of pages summarized with structured summaries
+    pub keywords: bool,
+    /// Whether to note the last modification date of pages after their content
+    pub updated: UpdatedDate,
+    /// Whether to note the author and publication date of articles after their content
+    pub byline: Byline,
+    /// Whether to note the video duration and image captions sitemaps list for pages
+    pub media: MediaNotes,
+    /// Whether to list the sections of pages split by parse under them
+    pub sections: SectionLinks,
+    /// Whether the pages archived by scrape are listed under an "Archived" section after the others
+    pub archived: ArchivedPages,
+    /// Language of the composed summaries, pages without a summary in it are composed as if unsummarized
+    pub language: Option<SummaryLanguage>,
+    /// Whether to overwrite the output file or regenerate only its managed region
+    pub existing_output: ExistingOutput,
+    /// Whether a copy of the replaced output file is kept
+    pub backup: OutputBackup,
+    /// Where the full text of composed pages is exported as markdown files linked by the
+    /// entries instead of the pages, no export if None
+    pub markdown_export: Option<MarkdownExport>,
+    /// Whether pages flagged noindex are composed
+    pub noindex: NoindexPages,
+    /// Scrubbers of the texts and summaries of composed pages, applied before anything is written
+    pub redaction: Redaction,
+    /// Reporter of the completed run
+    pub progress: Progress,
+}
+
+impl ComposeOptions {
+    /// Returns the filter of the composed pages, leaving out excluded pages and noindex pages unless included.
+    fn page_filter(&self) -> PageFilter {
+        self.noindex.restrict(self.filter.clone()).skip_excluded()
+    }
+}
 
 /// Composes the output file by reading already summarized pages from the database
 /// and writing them to the specified output file.
@@ -15,9 +96,10 @@ use crate::storage::{Page, Storage};
 ///
 /// # Arguments
 ///
-/// * `output_file` - Path to the output file where the composed content will be written
+/// * `output_file` - Path to the output file where the composed content will be written, "-" for stdout
 /// * `db_path` - Path to the database containing scraped pages with summaries
-/// * `source` - Source to compose from: "text", "summary", or "best" (default)
+/// * `options` - Source to compose from, pages to compose, their order, size limits and layout
+/// * `llm_builder` - The LLM builder to create the model writing section overviews and site description, if enabled
 ///
 /// # Returns
 ///
@@ -26,49 +108,519 @@ use crate::storage::{Page, Storage};
 /// # Errors
 ///
 /// Returns an error if:
+/// * Section overviews or site description are enabled without a model, or the LLM model fails to build
+/// * The output is written to stdout while merged into the existing output or backed up
 /// * Database operations fail
+/// * LLM chat operation fails
 /// * File operations fail
-pub async fn compose(db_path: &str, output_path: &str, source: super::ComposeSource) -> Result<()> {
-    let storage = Storage::new(db_path)?;
-
+pub async fn compose(
+    db_path: &str,
+    output_path: &str,
+    options: ComposeOptions,
+    llm_builder: Option<LLMBuilder>,
+) -> Result<(), Error> {
     info!("Composing pages from database {db_path} to {output_path}...");
+    compose_with_storage(&Storage::new(db_path)?, output_path, options, llm_builder).await
+}
 
-    let urls = storage.list_composable_urls()?;
+/// Composes the storage pages to the output file, recording the run in the audit log.
+pub(crate) async fn compose_with_storage(
+    storage: &Storage,
+    output_path: &str,
+    options: ComposeOptions,
+    llm_builder: Option<LLMBuilder>,
+) -> Result<(), Error> {
+    let model = match llm_builder {
+        Some(llm_builder) => Some(
+            llm_builder
+                .build()
+                .map_err(|e| Error::from(e).context("Failed to build LLM model"))?,
+        ),
+        None if options.section_overviews || options.describe => {
+            return Err(Error::Config(
+                "Section overviews and site description require an LLM model.".into(),
+            ));
+        }
+        None => None,
+    };
+    if is_stdio(output_path)
+        && (options.existing_output == ExistingOutput::Merge
+            || options.backup == OutputBackup::Kept)
+    {
+        return Err(Error::Config(
+            "Output written to stdout can't be merged into an existing file or backed up".into(),
+        ));
+    }
 
-    let mut processed_count = 0;
-    let mut file = OpenOptions::new()
-        .create(true)
-        .truncate(true)
-        .write(true)
-        .open(output_path)?;
+    let run_id = storage.start_run(
+        "compose",
+        &format!(
+            "output={output_path} source={:?} filter={:?} noindex={:?} sort={:?} max_bytes={:?} max_entries={:?} format={:?} existing_output={:?} backup={:?} markdown_export={:?} redaction={:?} section_overviews={} describe={} keywords={} updated={:?} byline={:?} media={:?} sections={:?} archived={:?}",
+            options.source,
+            options.filter,
+            options.noindex,
+            options.sort,
+            options.max_bytes,
+            options.max_entries,
+            options.format,
+            options.existing_output,
+            options.backup,
+            options.markdown_export,
+            options.redaction,
+            options.section_overviews,
+            options.describe,
+            options.keywords,
+            options.updated,
+            options.byline,
+            options.media,
+            options.sections,
+            options.archived
+        ),
+    )?;
 
-    for url in &urls {
-        let page = match storage.get_page(url)? {
-            Some(page) => page,
-            None => continue,
-        };
+    let result = compose_pages(storage, output_path, &options, model.as_deref()).await;
+    storage.finish_run(
+        run_id,
+        result.as_ref().ok().copied(),
+        result.as_ref().err().map(|error| error.to_string()),
+    )?;
+    if let Ok(composed) = &result {
+        options.progress.stage_complete("compose", *composed);
+    }
+
+    result.map(|_| ())
+}
+
+/// A composed page: its link, content and notes.
+struct ComposeEntry {
+    url: String,
+    /// Markdown export of the page linked instead of the page, see [`MarkdownExport`]
+    link: Option<String>,
+    title: Option<String>,
+    content: String,
+    /// Keywords of a structured summary, listed after the content
+    keywords: Vec<String>,
+    /// Last modification date of the page, noted after the content
+    updated: Option<String>,
+    /// Authors of the article declared by its structured data, noted after the content
+    author: Option<String>,
+    /// Publication date of the article declared by its structured data or news sitemap, noted after the content
+    published: Option<String>,
+    /// Video duration and image captions listed by the sitemap, noted after the content
+    media: Vec<(&'static str, String)>,
+    /// Section of the page by its breadcrumb trail, grouping it in the spec format
+    section: Option<String>,
+    /// Sections parse split the page into, linked under the content
+    sections: Vec<PageSection>,
+}
+
+impl ComposeEntry {
+    /// Returns the URL the entry links to: its markdown export if any, the page otherwise.
+    fn linked_url(&self) -> &str {
+        self.link.as_deref().unwrap_or(&self.url)
+    }
 
-        let value = match pick_composable_value(&page, &source) {
-            Some(content) => content,
-            None => continue,
+    /// Returns the labeled notes written after the content: keywords, byline, media and last modification date.
+    fn notes(&self) -> Vec<(&'static str, String)> {
+        let mut notes = Vec::new();
+        if !self.keywords.is_empty() {
+            notes.push(("Keywords", self.keywords.join(", ")));
+        }
+        if let Some(author) = &self.author {
+            notes.push(("By", author.clone()));
+        }
+        if let Some(published) = &self.published {
+            notes.push(("Published", published.clone()));
+        }
+        notes.extend(self.media.iter().cloned());
+        if let Some(updated) = &self.updated {
+            notes.push(("Updated", updated.clone()));
+        }
+        notes
+    }
+
+    /// Renders the links to the sections of the page with their summaries, one per line.
+    fn render_sections(&self, indent: &str) -> String {
+        self.sections
+            .iter()
+            .map(|section| {
+                let summary = section
+                    .summary
+                    .as_deref()
+                    .map(|summary| {
+                        format!(
+                            ": {}",
+                            summary.split_whitespace().collect::<Vec<_>>().join(" ")
+                        )
+                    })
+                    .unwrap_or_default();
+                format!(
+                    "{indent}- [{}]({}#{}){summary}\n",
+                    section.heading, self.url, section.anchor
+                )
+            })
+            .collect()
+    }
+
+    /// Renders the entry as written to the output file in the given format.
+    fn render(&self, format: &ComposeFormat) -> String {
+        let notes = self.notes();
+        match format {
+            ComposeFormat::Pages => {
+                let heading = match (&self.title, &self.link) {
+                    (Some(title), _) => format!("[{title}]({})", self.linked_url()),
+                    (None, Some(link)) => format!("[{}]({link})", self.url),
+                    (None, None) => self.url.clone(),
+                };
+                let notes = if notes.is_empty() {
+                    String::new()
+                } else {
+                    let lines: Vec<String> = notes
+                        .iter()
+                        .map(|(label, note)| format!("{label}: {note}"))
+                        .collect();
+                    format!("\n{}\n", lines.join("\n"))
+                };
+                let sections = if self.sections.is_empty() {
+                    String::new()
+                } else {
+                    format!("\n{}", self.render_sections(""))
+                };
+                format!("## {heading}\n{}\n{sections}{notes}\n", self.content)
+            }
+            ComposeFormat::Spec => {
+                let notes = if notes.is_empty() {
+                    String::new()
+                } else {
+                    let parts: Vec<String> = notes
+                        .iter()
+                        .map(|(label, note)| format!("{}: {note}", label.to_lowercase()))
+                        .collect();
+                    format!(" ({})", parts.join("; "))
+                };
+                format!(
+                    "- [{}]({}): {}{notes}\n{}",
+                    self.title.as_deref().unwrap_or(&self.url),
+                    self.linked_url(),
+                    self.content,
+                    self.render_sections("  ")
+                )
+            }
+        }
+    }
+}
+
+/// Writes composable pages to the output file and returns the number of composed pages.
+/// Pages are written as they are read, unless the size budget, the spec format or the markdown
+/// export needs all of them first.
+async fn compose_pages(
+    storage: &Storage,
+    output_path: &str,
+    options: &ComposeOptions,
+    model: Option<&dyn LLMProvider>,
+) -> Result<usize> {
+    let buffered = options.max_bytes.is_some()
+        || options.max_entries.is_some()
+        || options.format == ComposeFormat::Spec
+        || options.markdown_export.is_some();
+    let composed_urls = if buffered {
+        compose_buffered(storage, output_path, options, model).await?
+    } else {
+        compose_streamed(storage, output_path, options)?
+    };
+    for url in &composed_urls {
+        storage.update_page_status(url, &PageStatus::Composed)?;
+    }
+
+    info!("Composed {} pages to {output_path}", composed_urls.len());
+    Ok(composed_urls.len())
+}
+
+/// Writes the entries of the pages one by one as they are read, so that memory doesn't grow
+/// with the number of pages, and returns the URLs of the composed pages.
+fn compose_streamed(
+    storage: &Storage,
+    output_path: &str,
+    options: &ComposeOptions,
+) -> Result<Vec<String>> {
+    let mut output = open_output(output_path, options)?;
+    let mut composed_urls = Vec::new();
+    for page in sorted_pages(storage, options)? {
+        let Some(entry) = read_entry(storage, page?, options)? else {
+            continue;
         };
+        output.write(&entry.render(&options.format))?;
+        composed_urls.push(entry.url);
+    }
+    if options.archived == ArchivedPages::Listed {
+        output.write(&render_archived(storage, options)?)?;
+    }
 
-        file.write_all(
-            format!(
-                "## {}\n{}\n\n",
-                page.title
-                    .map(|title| format!("[{}]({})", title, page.url))
-                    .unwrap_or(page.url.to_string()),
-                value,
-            )
-            .as_bytes(),
+    output.finish()?;
+    Ok(composed_urls)
+}
+
+/// Reads the entries of all pages before writing them, fitting them in the size budget,
+/// exporting them as markdown or grouping them in sections, and returns the URLs of the
+/// composed pages.
+async fn compose_buffered(
+    storage: &Storage,
+    output_path: &str,
+    options: &ComposeOptions,
+    model: Option<&dyn LLMProvider>,
+) -> Result<Vec<String>> {
+    let mut entries = Vec::new();
+    for page in sorted_pages(storage, options)? {
+        entries.extend(read_entry(storage, page?, options)?);
+    }
+
+    if options.max_bytes.is_some() || options.max_entries.is_some() {
+        let ranking = storage.list_sorted_urls(
+            PageSelection::Composable,
+            &options.page_filter(),
+            &PageSort::Priority,
         )?;
+        entries = budget::fit_budget(entries, &ranking, options);
+    }
+    let composed_urls: Vec<String> = entries.iter().map(|entry| entry.url.clone()).collect();
+    if let Some(export) = &options.markdown_export {
+        export::export_pages(
+            storage,
+            &mut entries,
+            export,
+            output_path,
+            &options.redaction,
+        )?;
+    }
+
+    let mut output = match options.format {
+        ComposeFormat::Pages => entries
+            .iter()
+            .map(|entry| entry.render(&options.format))
+            .collect(),
+        ComposeFormat::Spec => {
+            let mut sections = spec::group_sections(entries);
+            if let Some(model) = model.filter(|_| options.section_overviews) {
+                spec::write_overviews(&mut sections, model, &options.redaction).await?;
+            }
+            let description = match model.filter(|_| options.describe) {
+                Some(model) => {
+                    spec::describe_site(storage, &sections, model, &options.redaction).await?
+                }
+                None => None,
+            };
+            spec::render(&sections, description.as_deref())
+        }
+    };
+    if options.archived == ArchivedPages::Listed {
+        output.push_str(&render_archived(storage, options)?);
+    }
+
+    let mut writer = open_output(output_path, options)?;
+    writer.write(&output)?;
+    writer.finish()?;
+    Ok(composed_urls)
+}
+
+/// Opens the output file, written into the managed region of the existing file when merging.
+/// The file is replaced atomically once finished, so a failed run leaves the previous version
+/// in place. The output is written to stdout if the path is "-".
+fn open_output(output_path: &str, options: &ComposeOptions) -> Result<output::OutputWriter> {
+    if is_stdio(output_path) {
+        return Ok(output::OutputWriter::stdout());
+    }
+
+    output::OutputWriter::file(output_path, options.existing_output.clone(), options.backup)
+}
+
+/// Returns the composable pages in the output order, read one at a time. Pages ordered by URL
+/// are read in batches, the URLs of pages in other orders are listed first.
+fn sorted_pages<'a>(
+    storage: &'a Storage,
+    options: &ComposeOptions,
+) -> Result<Box<dyn Iterator<Item = crate::Result<Page>> + 'a>> {
+    if options.sort == PageSort::Url {
+        return Ok(Box::new(
+            storage
+                .iter_pages_where(PageSelection::Composable)
+                .matching(options.page_filter()),
+        ));
+    }
+
+    if options.sort == PageSort::Importance {
+        storage.update_importance()?;
+    }
+    let urls = storage.list_sorted_urls(
+        PageSelection::Composable,
+        &options.page_filter(),
+        &options.sort,
+    )?;
+    Ok(Box::new(
+        urls.into_iter()
+            .filter_map(move |url| storage.get_page(&url).transpose()),
+    ))
+}
+
+/// Reads the entry of a composable page, None if it has no content to compose from the source.
+fn read_entry(
+    storage: &Storage,
+    mut page: Page,
+    options: &ComposeOptions,
+) -> Result<Option<ComposeEntry>> {
+    let url = page.url.clone();
+    if let Some(language) = options.language {
+        page.summary = storage.get_page_translation(&url, language.code())?;
+    }
+
+    let value = match pick_composable_value(&page, &options.source) {
+        Some(content) => options.redaction.scrub(&content),
+        None => return Ok(None),
+    };
 
-        processed_count += 1;
+    let content = match options.format {
+        ComposeFormat::Pages => value,
+        // Spec format lists a page on a single line
+        ComposeFormat::Spec => value.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+    let keywords = if options.keywords {
+        storage.get_page_keywords(&url)?
+    } else {
+        Vec::new()
+    };
+    let updated = (options.updated == UpdatedDate::Shown
+        && page.lastmod.timestamp() != UNKNOWN_TIMESTAMP)
+        .then(|| page.lastmod.format("%Y-%m-%d").to_string());
+    let notes = read_page_notes(storage, &url, options)?;
+    let sections = match options.sections {
+        SectionLinks::Hidden => Vec::new(),
+        SectionLinks::Listed => redact_sections(storage.list_page_sections(&url)?, options),
+    };
+    let section = match options.format {
+        ComposeFormat::Pages => None,
+        ComposeFormat::Spec => {
+            spec::breadcrumb_section(&storage.get_page_breadcrumbs(&url)?, page.title.as_deref())
+        }
+    };
+
+    Ok(Some(ComposeEntry {
+        link: None,
+        title: page.title.or(notes.headline),
+        content,
+        url,
+        keywords,
+        updated,
+        author: notes.author,
+        published: notes.published,
+        media: notes.media,
+        section,
+        sections,
+    }))
+}
+
+/// Renders the "Archived" section listing the pages scrape archived as their sitemap no longer
+/// listed them, empty without any.
+fn render_archived(storage: &Storage, options: &ComposeOptions) -> Result<String> {
+    let entries: String = storage
+        .list_archived_pages()?
+        .into_iter()
+        .filter_map(|page| archived_entry(page, options))
+        .map(|entry| entry.render(&options.format))
+        .collect();
+    if entries.is_empty() {
+        return Ok(entries);
+    }
+
+    Ok(match options.format {
+        ComposeFormat::Pages => format!("# Archived\n\n{entries}"),
+        ComposeFormat::Spec => format!("\n## Archived\n\n{entries}"),
+    })
+}
+
+/// Returns the entry of an archived page, None if it has no content to compose from the source.
+fn archived_entry(page: ArchivedPage, options: &ComposeOptions) -> Option<ComposeEntry> {
+    let value = match options.source {
+        ComposeSource::Summary => page.summary,
+        ComposeSource::Text => page.text,
+        ComposeSource::Best => page.summary.or(page.text),
+    }
+    .map(|value| options.redaction.scrub(&value))?;
+    let content = match options.format {
+        ComposeFormat::Pages => value,
+        ComposeFormat::Spec => value.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+
+    Some(ComposeEntry {
+        url: page.url,
+        link: None,
+        title: page.title,
+        content,
+        keywords: Vec::new(),
+        updated: None,
+        author: None,
+        published: None,
+        media: Vec::new(),
+        section: None,
+        sections: Vec::new(),
+    })
+}
+
+/// Scrubs the summaries of the sections of a page.
+fn redact_sections(sections: Vec<PageSection>, options: &ComposeOptions) -> Vec<PageSection> {
+    sections
+        .into_iter()
+        .map(|section| PageSection {
+            summary: section
+                .summary
+                .map(|summary| options.redaction.scrub(&summary)),
+            ..section
+        })
+        .collect()
+}
+
+/// Byline and media of a page read from its structured data and sitemap extensions
+#[derive(Default)]
+struct PageNotes {
+    /// Headline of the article or title of the news article, the title of a page falls back to
+    headline: Option<String>,
+    /// Authors of the article, if the byline is noted
+    author: Option<String>,
+    /// Publication date of the article, if the byline is noted
+    published: Option<String>,
+    /// Labeled video duration and image captions, if media are noted
+    media: Vec<(&'static str, String)>,
+}
+
+/// Reads the byline and media of a page noted as the options ask.
+fn read_page_notes(storage: &Storage, url: &str, options: &ComposeOptions) -> Result<PageNotes> {
+    let structured_data = StructuredData::of_blocks(&storage.get_page_structured_data(url)?);
+    let extensions = storage.get_sitemap_extensions(url)?.unwrap_or_default();
+    let mut notes = PageNotes {
+        headline: structured_data.headline.or(extensions.news_title),
+        ..PageNotes::default()
+    };
+
+    if options.byline == Byline::Shown {
+        notes.author = structured_data.author;
+        notes.published = structured_data.date_published.or_else(|| {
+            extensions
+                .publication_date
+                .map(|publication_date| publication_date.format("%Y-%m-%d").to_string())
+        });
+    }
+    if options.media == MediaNotes::Shown {
+        if let Some(duration) = extensions.video_duration {
+            notes
+                .media
+                .push(("Video", format!("{}:{:02}", duration / 60, duration % 60)));
+        }
+        if !extensions.image_captions.is_empty() {
+            notes
+                .media
+                .push(("Images", extensions.image_captions.join("; ")));
+        }
     }
 
-    info!("Composed {processed_count} pages to {output_path}");
-    Ok(())
+    Ok(notes)
 }
 
 /// Selects content from a page based on the specified source
@@ -81,10 +633,10 @@ pub async fn compose(db_path: &str, output_path: &str, source: super::ComposeSou
 /// # Returns
 ///
 /// Returns Some(String) with the selected content, or None if content is not available based on source
-fn pick_composable_value(page: &Page, source: &super::ComposeSource) -> Option<String> {
+fn pick_composable_value(page: &Page, source: &ComposeSource) -> Option<String> {
     match source {
-        super::ComposeSource::Summary => page.summary.clone(),
-        super::ComposeSource::Text => page.text.clone(),
-        super::ComposeSource::Best => page.summary.clone().or_else(|| page.text.clone()),
+        ComposeSource::Summary => page.summary.clone(),
+        ComposeSource::Text => page.text.clone(),
+        ComposeSource::Best => page.summary.clone().or_else(|| page.text.clone()),
     }
 }
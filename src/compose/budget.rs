@@ -0,0 +1,111 @@
+//! Fitting composed pages into size limits of llms.txt consumers.
+
+use log::warn;
+use std::collections::HashMap;
+
+use super::{ComposeEntry, ComposeOptions};
+use crate::ComposeFormat;
+
+/// Entries whose content would be cut below this many bytes are dropped rather than shortened.
+const MIN_SHORTENED_CONTENT: usize = 200;
+
+/// Marker appended to shortened content.
+const ELLIPSIS: &str = "…";
+
+/// Keeps the most important entries fitting `max_bytes` and `max_entries` of the options,
+/// in their original order.
+///
+/// Entries are taken in the order of `ranking` (URLs from the most to the least important),
+/// an entry not fitting the remaining bytes is shortened if enough of its content can be kept,
+/// and dropped otherwise. Shortened and omitted pages are listed in a warning.
+pub(super) fn fit_budget(
+    entries: Vec<ComposeEntry>,
+    ranking: &[String],
+    options: &ComposeOptions,
+) -> Vec<ComposeEntry> {
+    let rank_by_url: HashMap<&str, usize> = ranking
+        .iter()
+        .enumerate()
+        .map(|(rank, url)| (url.as_str(), rank))
+        .collect();
+    let mut ranked: Vec<(usize, usize, ComposeEntry)> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(position, entry)| {
+            let rank = rank_by_url
+                .get(entry.url.as_str())
+                .copied()
+                .unwrap_or(usize::MAX);
+            (rank, position, entry)
+        })
+        .collect();
+    ranked.sort_by_key(|(rank, position, _)| (*rank, *position));
+
+    let mut remaining_bytes = options.max_bytes.unwrap_or(usize::MAX);
+    let mut kept = Vec::new();
+    let mut shortened = Vec::new();
+    let mut omitted = Vec::new();
+    for (_, position, mut entry) in ranked {
+        if options
+            .max_entries
+            .is_some_and(|max_entries| kept.len() >= max_entries)
+        {
+            omitted.push(entry.url);
+            continue;
+        }
+
+        let size = entry.render(&options.format).len();
+        if size > remaining_bytes {
+            if !shorten(&mut entry, remaining_bytes, &options.format) {
+                omitted.push(entry.url);
+                continue;
+            }
+            shortened.push(entry.url.clone());
+        }
+        remaining_bytes -= entry.render(&options.format).len();
+        kept.push((position, entry));
+    }
+
+    if !shortened.is_empty() {
+        warn!(
+            "Shortened {} pages to fit the size limit: {}",
+            shortened.len(),
+            shortened.join(", ")
+        );
+    }
+    if !omitted.is_empty() {
+        warn!(
+            "Omitted {} pages exceeding the size limits: {}",
+            omitted.len(),
+            omitted.join(", ")
+        );
+    }
+
+    kept.sort_by_key(|(position, _)| *position);
+    kept.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Cuts the entry content at a word boundary so that the rendered entry takes at most
+/// `max_bytes`. Returns `false` if less than `MIN_SHORTENED_CONTENT` bytes of content would be kept.
+fn shorten(entry: &mut ComposeEntry, max_bytes: usize, format: &ComposeFormat) -> bool {
+    let overhead = entry.render(format).len() - entry.content.len() + ELLIPSIS.len();
+    let Some(content_bytes) = max_bytes.checked_sub(overhead) else {
+        return false;
+    };
+    if content_bytes < MIN_SHORTENED_CONTENT {
+        return false;
+    }
+
+    let mut cut = content_bytes;
+    while !entry.content.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let kept = entry.content.get(..cut).unwrap_or_default();
+    let kept = kept
+        .rfind(char::is_whitespace)
+        .and_then(|space| kept.get(..space))
+        .unwrap_or(kept)
+        .trim_end();
+    entry.content = format!("{kept}{ELLIPSIS}");
+    true
+}
@@ -0,0 +1,221 @@
+//! Markdown exports of composed pages: the full text of every page written to its own
+//! `.md` file, which the llms.txt entries link to instead of the HTML page, following the
+//! llms.txt convention of linking markdown versions of pages. Links between exported pages
+//! point at their files, so the bundle can be browsed offline and followed by agents.
+
+use log::info;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use url::Url;
+
+use super::ComposeEntry;
+use super::front_matter::{FrontMatter, PageMetadata};
+use super::output::write_atomically;
+use super::redact::Redaction;
+use super::slug::{PathSlugger, Slugger, unique_slug};
+use crate::canonical::normalize_url;
+use crate::error::{Context, Result};
+use crate::storage::{Page, Storage};
+use crate::{Error, OutputBackup, TrailingSlash};
+
+/// Matches the targets of markdown links, with their optional title: `](target "title")`.
+static MARKDOWN_LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\]\(<?([^()\s<>]+)>?((?:\s+"[^"]*")?)\)"#)
+        .expect("Failed to compile markdown link regex")
+});
+
+/// Where the markdown exports of composed pages are written and published
+#[derive(Clone)]
+pub struct MarkdownExport {
+    /// Directory the `<slug>.md` files are written to, created if missing
+    pub dir: String,
+    /// URL the directory is published at; entries link to the files relative to the output
+    /// file if None, for a bundle published along with it
+    pub base_url: Option<Url>,
+    /// Names the files of the pages, see [`crate::compose::SlugStrategy`] for the built-in ones
+    pub slugger: Arc<dyn Slugger>,
+    /// Front matter written at the top of the files for static site generators
+    pub front_matter: FrontMatter,
+}
+
+impl MarkdownExport {
+    /// Creates an export to the given directory, linked relative to the output file
+    /// and named by the paths of the pages.
+    pub fn new(dir: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            base_url: None,
+            slugger: Arc::new(PathSlugger),
+            front_matter: FrontMatter::default(),
+        }
+    }
+
+    /// Returns the link to an exported file from the output file.
+    fn link(&self, file_name: &str, output_path: &str) -> Result<String> {
+        if let Some(base_url) = &self.base_url {
+            return Ok(base_url
+                .join(file_name)
+                .map_err(|error| {
+                    Error::Config(error.into())
+                        .context(format!("Invalid markdown export URL for {file_name}"))
+                })?
+                .to_string());
+        }
+
+        let dir = Path::new(&self.dir);
+        let relative_dir = Path::new(output_path)
+            .parent()
+            .and_then(|output_dir| dir.strip_prefix(output_dir).ok())
+            .unwrap_or(dir);
+        Ok(relative_dir.join(file_name).to_string_lossy().into_owned())
+    }
+}
+
+impl std::fmt::Debug for MarkdownExport {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("MarkdownExport")
+            .field("dir", &self.dir)
+            .field("base_url", &self.base_url)
+            .field("front_matter", &self.front_matter)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writes the full text of every entry's page, scrubbed by the redaction, to its markdown file
+/// and links the entry to it. Pages without text keep linking to the page itself.
+///
+/// Links of the text to other exported pages are rewritten to their files, and other
+/// internal links recorded by parse are made absolute, so that none is left dangling.
+pub(super) fn export_pages(
+    storage: &Storage,
+    entries: &mut [ComposeEntry],
+    export: &MarkdownExport,
+    output_path: &str,
+    redaction: &Redaction,
+) -> Result<()> {
+    std::fs::create_dir_all(&export.dir).with_context(|| {
+        format!(
+            "Unable to create the markdown export directory {}",
+            export.dir
+        )
+    })?;
+
+    // Files are named first, so that links to pages exported later can be rewritten too
+    let mut slugs = HashSet::new();
+    let mut files = HashMap::new();
+    let mut pages = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(page) = storage
+            .get_page(&entry.url)?
+            .filter(|page| page.text.is_some())
+        else {
+            continue;
+        };
+        let slug = export.slugger.slug(&entry.url, entry.title.as_deref());
+        let slug = unique_slug(slug, &entry.url, &mut slugs);
+        files.insert(entry.url.clone(), format!("{slug}.md"));
+        pages.push((index, page));
+    }
+
+    for (index, page) in pages {
+        let Some(entry) = entries.get_mut(index) else {
+            continue;
+        };
+        let file_name = files.get(&entry.url).cloned().unwrap_or_default();
+        let targets: HashSet<String> = storage.list_page_links(&entry.url)?.into_iter().collect();
+        let text = redaction.scrub(page.text.as_deref().unwrap_or_default());
+        let text = match Url::parse(&entry.url) {
+            Ok(page_url) => rewrite_links(&text, &page_url, &targets, &files),
+            Err(_) => text,
+        };
+        let front_matter =
+            render_front_matter(storage, entry, &page, export.front_matter, redaction)?;
+        write_atomically(
+            &Path::new(&export.dir).join(&file_name).to_string_lossy(),
+            &render_markdown(entry, &text, front_matter.as_deref()),
+            OutputBackup::Discarded,
+        )?;
+        entry.link = Some(export.link(&file_name, output_path)?);
+    }
+
+    info!(
+        "Exported {} pages as markdown to {}",
+        files.len(),
+        export.dir
+    );
+    Ok(())
+}
+
+/// Points the links of a page text at the files of the exported pages they link to, and at
+/// the absolute URL of the other internal pages. Only links to the given targets, the internal
+/// links recorded by parse, are rewritten.
+fn rewrite_links(
+    text: &str,
+    page_url: &Url,
+    targets: &HashSet<String>,
+    files: &HashMap<String, String>,
+) -> String {
+    MARKDOWN_LINK_REGEX
+        .replace_all(text, |link: &Captures| {
+            let original = link.get(0).map_or("", |original| original.as_str());
+            let href = link.get(1).map_or("", |href| href.as_str());
+            let title = link.get(2).map_or("", |title| title.as_str());
+            let Ok(target) = page_url.join(href) else {
+                return original.to_string();
+            };
+            let fragment = target
+                .fragment()
+                .map(|fragment| format!("#{fragment}"))
+                .unwrap_or_default();
+            let target = normalize_url(&target, &TrailingSlash::Keep).to_string();
+            if !targets.contains(&target) {
+                return original.to_string();
+            }
+
+            match files.get(&target) {
+                Some(file_name) => format!("]({file_name}{fragment}{title})"),
+                None => format!("]({target}{fragment}{title})"),
+            }
+        })
+        .into_owned()
+}
+
+/// Renders the front matter of an exported page with its scrubbed summary, None without front matter.
+fn render_front_matter(
+    storage: &Storage,
+    entry: &ComposeEntry,
+    page: &Page,
+    front_matter: FrontMatter,
+    redaction: &Redaction,
+) -> Result<Option<String>> {
+    if front_matter == FrontMatter::Omitted {
+        return Ok(None);
+    }
+
+    let tags = storage.get_page_tags(&entry.url)?;
+    let summary = page
+        .summary
+        .as_deref()
+        .map(|summary| redaction.scrub(summary));
+    Ok(front_matter.render(&PageMetadata {
+        title: entry.title.as_deref().unwrap_or(&entry.url),
+        url: &entry.url,
+        date: page.lastmod,
+        summary: summary.as_deref(),
+        tags: &tags,
+    }))
+}
+
+/// Renders the markdown file of a page: its front matter or else its title as a heading,
+/// a pointer to the page and its full text.
+fn render_markdown(entry: &ComposeEntry, text: &str, front_matter: Option<&str>) -> String {
+    let header = match front_matter {
+        Some(front_matter) => front_matter.to_string(),
+        None => format!("# {}", entry.title.as_deref().unwrap_or(&entry.url)),
+    };
+    format!("{header}\n\n> Source: {}\n\n{}\n", entry.url, text.trim())
+}
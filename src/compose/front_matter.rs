@@ -0,0 +1,65 @@
+//! Front matter of markdown exports, read by static site generators like Hugo and Jekyll,
+//! so that the exported pages can be republished with their metadata.
+
+use chrono::{DateTime, Utc};
+use url::Url;
+
+/// Front matter written at the top of exported markdown pages
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum FrontMatter {
+    /// Start pages with their title as a heading instead
+    #[default]
+    #[value(name = "none")]
+    Omitted,
+    /// YAML front matter between `---` lines, with the title, url, date, summary and tags of the page
+    Yaml,
+}
+
+/// Metadata of an exported page written to its front matter
+pub(super) struct PageMetadata<'a> {
+    pub(super) title: &'a str,
+    pub(super) url: &'a str,
+    /// Last modification date of the page
+    pub(super) date: DateTime<Utc>,
+    pub(super) summary: Option<&'a str>,
+    pub(super) tags: &'a [String],
+}
+
+impl FrontMatter {
+    /// Returns the front matter of a page, None if pages start with a heading.
+    ///
+    /// The `url` is the path of the page, so that a site generator republishes it at the same path.
+    pub(super) fn render(self, metadata: &PageMetadata<'_>) -> Option<String> {
+        match self {
+            Self::Omitted => None,
+            Self::Yaml => {
+                let path = Url::parse(metadata.url).map_or_else(
+                    |_| metadata.url.to_string(),
+                    |url| match url.query() {
+                        Some(query) => format!("{}?{query}", url.path()),
+                        None => url.path().to_string(),
+                    },
+                );
+                let mut lines = vec![
+                    "---".to_string(),
+                    format!("title: {}", yaml_string(metadata.title)),
+                    format!("url: {}", yaml_string(&path)),
+                    format!("date: {}", metadata.date.to_rfc3339()),
+                ];
+                if let Some(summary) = metadata.summary {
+                    lines.push(format!("summary: {}", yaml_string(summary)));
+                }
+                let tags: Vec<String> = metadata.tags.iter().map(|tag| yaml_string(tag)).collect();
+                lines.push(format!("tags: [{}]", tags.join(", ")));
+                lines.push("---".to_string());
+                Some(lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// Quotes a text as a single line YAML string.
+fn yaml_string(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
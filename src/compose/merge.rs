@@ -0,0 +1,50 @@
+//! Merging composed pages into an existing llms.txt, keeping its hand-written parts.
+
+use crate::{Error, Result};
+
+/// Comment opening the region of the file written by compose.
+pub const MANAGED_BEGIN: &str = "<!-- llamap:begin -->";
+
+/// Comment closing the region of the file written by compose.
+pub const MANAGED_END: &str = "<!-- llamap:end -->";
+
+/// Splits the existing file content around its managed region, returning the content written
+/// before the region and after it, so that the composed output replaces the region only.
+///
+/// Content before and after the region, e.g. a curated "Optional" section, is kept as is.
+/// The region is appended to a file without one, and is the whole file when there's no file yet.
+///
+/// # Errors
+///
+/// Returns an error if only one of the markers is found, or the closing one comes first,
+/// so that hand-written content is never overwritten.
+pub(super) fn split_managed_region(existing: Option<&str>) -> Result<(String, String)> {
+    let Some(existing) = existing else {
+        return Ok((String::new(), "\n".to_string()));
+    };
+
+    let managed_region = existing
+        .split_once(MANAGED_BEGIN)
+        .map(|(before, rest)| (before, rest.split_once(MANAGED_END)));
+    match managed_region {
+        Some((before, Some((_, after)))) if !before.contains(MANAGED_END) => {
+            Ok((before.to_string(), after.to_string()))
+        }
+        None if !existing.contains(MANAGED_END) => {
+            let separator = if existing.is_empty() || existing.ends_with("\n\n") {
+                ""
+            } else if existing.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            Ok((format!("{existing}{separator}"), "\n".to_string()))
+        }
+        _ => Err(Error::Config(
+            format!(
+                "Unable to merge: the file must contain {MANAGED_BEGIN} followed by {MANAGED_END}"
+            )
+            .into(),
+        )),
+    }
+}
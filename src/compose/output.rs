@@ -0,0 +1,189 @@
+//! Writing the output file atomically, so that a crash mid-write never leaves a truncated
+//! llms.txt behind, and keeping the replaced version if asked.
+
+use crate::error::{Context, Result};
+use log::info;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::merge::{self, MANAGED_BEGIN, MANAGED_END};
+use crate::{ExistingOutput, OutputBackup};
+
+/// Writes the contents to a temporary file next to the output file, then renames it over
+/// the output file, which readers see either whole or not at all. The replaced file is
+/// copied aside first if it's kept.
+pub(super) fn write_atomically(
+    output_path: &str,
+    contents: &str,
+    backup: OutputBackup,
+) -> Result<()> {
+    let mut output = OutputWriter::file(output_path, ExistingOutput::Overwrite, backup)?;
+    output.write(contents)?;
+    output.finish()
+}
+
+/// Where the composed output is written
+enum Sink {
+    Stdout(std::io::Stdout),
+    /// Temporary file renamed over the output file once the output is complete
+    File {
+        file: BufWriter<File>,
+        temp_path: String,
+        output_path: String,
+    },
+}
+
+/// Output written piece by piece as it's composed, so that it never has to be held in memory
+/// whole. A file output is written to a temporary file replacing the output file when finished,
+/// and the temporary file is removed if the writer is dropped unfinished.
+pub(super) struct OutputWriter {
+    sink: Option<Sink>,
+    /// Content of the existing file after the managed region, written when finished if merging
+    suffix: Option<String>,
+    /// Last byte written to the managed region, if any
+    last_byte: Option<u8>,
+}
+
+impl OutputWriter {
+    /// Opens stdout for the output.
+    pub(super) fn stdout() -> Self {
+        Self {
+            sink: Some(Sink::Stdout(std::io::stdout())),
+            suffix: None,
+            last_byte: None,
+        }
+    }
+
+    /// Opens a temporary file next to the output file, copying the existing output file aside
+    /// first if it's kept. When merging, the part of the existing file before the managed region
+    /// is written right away and the part after it when finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the existing file can't be read, backed up or merged into,
+    /// or the temporary file can't be created
+    pub(super) fn file(
+        output_path: &str,
+        existing_output: ExistingOutput,
+        backup: OutputBackup,
+    ) -> Result<Self> {
+        let merged = match existing_output {
+            ExistingOutput::Overwrite => None,
+            ExistingOutput::Merge => {
+                let existing = match fs::read_to_string(output_path) {
+                    Ok(existing) => Some(existing),
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(error) => return Err(error.into()),
+                };
+                Some(merge::split_managed_region(existing.as_deref())?)
+            }
+        };
+
+        if backup == OutputBackup::Kept && Path::new(output_path).exists() {
+            let backup_path = format!(
+                "{output_path}.{}",
+                chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+            );
+            fs::copy(output_path, &backup_path)
+                .with_context(|| format!("Unable to back up {output_path} to {backup_path}"))?;
+            info!("Kept the previous {output_path} as {backup_path}");
+        }
+
+        let temp_path = format!("{output_path}.tmp");
+        let file =
+            File::create(&temp_path).with_context(|| format!("Unable to write {output_path}"))?;
+        let mut output = Self {
+            sink: Some(Sink::File {
+                file: BufWriter::new(file),
+                temp_path,
+                output_path: output_path.to_string(),
+            }),
+            suffix: None,
+            last_byte: None,
+        };
+        if let Some((prefix, suffix)) = merged {
+            output.write_raw(&format!("{prefix}{MANAGED_BEGIN}\n"))?;
+            output.suffix = Some(suffix);
+        }
+
+        Ok(output)
+    }
+
+    /// Appends the contents to the output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contents can't be written
+    pub(super) fn write(&mut self, contents: &str) -> Result<()> {
+        if let Some(&last_byte) = contents.as_bytes().last() {
+            self.last_byte = Some(last_byte);
+        }
+        self.write_raw(contents)
+    }
+
+    /// Completes the output: closes the managed region when merging, then flushes stdout,
+    /// or syncs the temporary file and renames it over the output file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output can't be written, the output file is left untouched then
+    pub(super) fn finish(mut self) -> Result<()> {
+        if let Some(suffix) = self.suffix.take() {
+            let newline = match self.last_byte {
+                Some(last_byte) if last_byte != b'\n' => "\n",
+                _ => "",
+            };
+            self.write_raw(&format!("{newline}{MANAGED_END}{suffix}"))?;
+        }
+
+        match self.sink.take() {
+            Some(Sink::Stdout(mut stdout)) => stdout.flush().context("Unable to write to stdout"),
+            Some(Sink::File {
+                file,
+                temp_path,
+                output_path,
+            }) => {
+                let written = file
+                    .into_inner()
+                    .map_err(|error| error.into_error())
+                    .and_then(|file| file.sync_all())
+                    .and_then(|()| fs::rename(&temp_path, &output_path));
+                if written.is_err() {
+                    // The output file is left untouched, only the partial temporary file is removed
+                    let _ = fs::remove_file(&temp_path);
+                }
+                written.with_context(|| format!("Unable to write {output_path}"))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the contents to the sink as they are.
+    fn write_raw(&mut self, contents: &str) -> Result<()> {
+        match &mut self.sink {
+            Some(Sink::Stdout(stdout)) => stdout
+                .write_all(contents.as_bytes())
+                .context("Unable to write to stdout"),
+            Some(Sink::File {
+                file, output_path, ..
+            }) => file
+                .write_all(contents.as_bytes())
+                .with_context(|| format!("Unable to write {output_path}")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for OutputWriter {
+    fn drop(&mut self) {
+        if let Some(Sink::File {
+            file, temp_path, ..
+        }) = self.sink.take()
+        {
+            // Unfinished output, e.g. of a failed run, leaves the output file untouched
+            drop(file);
+            let _ = fs::remove_file(temp_path);
+        }
+    }
+}
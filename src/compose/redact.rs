@@ -0,0 +1,104 @@
+//! Redaction of composed texts and summaries. Published llms.txt files are read by anyone,
+//! while crawled pages occasionally show emails, phone numbers or keys which weren't meant
+//! to be republished, so they are scrubbed before being written.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::sync::Arc;
+
+use crate::summarize::PostProcessRule;
+
+/// Matches email addresses.
+static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b")
+        .expect("Failed to compile email regex")
+});
+
+/// Matches phone numbers written with spaces or dashes, e.g. `+1 555 123 4567` or `(555) 123-4567`,
+/// but not dates, version numbers or IP addresses.
+static PHONE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[\s-]?)?(?:\(\d{2,4}\)|\b\d{2,4})[\s-]\d{3,4}[\s-]\d{3,4}\b")
+        .expect("Failed to compile phone regex")
+});
+
+/// Matches keys and tokens of well-known APIs by their prefix: Stripe, OpenAI and Anthropic,
+/// AWS access keys, GitHub, Slack and Google API keys.
+static API_KEY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(?:[spr]k_(?:live|test)_[A-Za-z0-9]{16,}|sk-[A-Za-z0-9_-]{20,}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}|xox[abpr]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})",
+    )
+    .expect("Failed to compile API key regex")
+});
+
+/// Removes data which must not be published from composed texts.
+pub trait Scrubber: Send + Sync {
+    /// Returns the text with the data to hide replaced.
+    fn scrub(&self, text: &str) -> String;
+}
+
+/// Scrubber replacing the matches of regex rules in order, e.g. `[email]` for email addresses
+#[derive(Clone, Debug)]
+pub struct RegexScrubber {
+    rules: Vec<PostProcessRule>,
+}
+
+impl RegexScrubber {
+    /// Creates a scrubber applying the given rules, parsed like summarize post-processing
+    /// rules by [`crate::summarize::parse_rules`].
+    pub fn new(rules: Vec<PostProcessRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Creates the scrubber of the built-in rules, replacing email addresses with `[email]`,
+    /// phone numbers with `[phone]` and API keys with `[secret]`.
+    pub fn builtin() -> Self {
+        Self::new(
+            [
+                (&*API_KEY_REGEX, "[secret]"),
+                (&*EMAIL_REGEX, "[email]"),
+                (&*PHONE_REGEX, "[phone]"),
+            ]
+            .into_iter()
+            .map(|(pattern, replacement)| PostProcessRule {
+                pattern: pattern.clone(),
+                replacement: replacement.to_string(),
+            })
+            .collect(),
+        )
+    }
+}
+
+impl Scrubber for RegexScrubber {
+    fn scrub(&self, text: &str) -> String {
+        self.rules.iter().fold(text.to_string(), |text, rule| {
+            rule.pattern
+                .replace_all(&text, rule.replacement.as_str())
+                .into_owned()
+        })
+    }
+}
+
+/// Scrubbers applied to the texts and summaries of composed pages, none by default
+#[derive(Clone, Default)]
+pub struct Redaction {
+    /// Scrubbers applied in order
+    pub scrubbers: Vec<Arc<dyn Scrubber>>,
+}
+
+impl Redaction {
+    /// Returns the text scrubbed by every scrubber.
+    pub fn scrub(&self, text: &str) -> String {
+        self.scrubbers
+            .iter()
+            .fold(text.to_string(), |text, scrubber| scrubber.scrub(&text))
+    }
+}
+
+impl std::fmt::Debug for Redaction {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("Redaction")
+            .field("scrubbers", &self.scrubbers.len())
+            .finish()
+    }
+}
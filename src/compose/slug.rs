@@ -0,0 +1,110 @@
+//! File names of markdown exports. Published llms.txt files link to the exports, so a page
+//! must keep its file name from one run to the next for the links not to break.
+
+use std::collections::HashSet;
+
+use crate::storage::hash_html;
+
+/// Names the markdown export of a page, i.e. its file name without the `.md` extension.
+///
+/// Slugs should only depend on the page, so that they are the same on every run, and only use
+/// characters which are safe in file names and URLs.
+pub trait Slugger: Send + Sync {
+    /// Returns the slug of the page with the given URL and title.
+    fn slug(&self, url: &str, title: Option<&str>) -> String;
+}
+
+/// Slugs made of the host and path of the page URL, e.g. `example-com-docs-intro`
+/// for `https://example.com/docs/intro`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PathSlugger;
+
+impl Slugger for PathSlugger {
+    fn slug(&self, url: &str, _title: Option<&str>) -> String {
+        slugify(url.split_once("://").map_or(url, |(_, rest)| rest))
+    }
+}
+
+/// Slugs made of the page title, e.g. `getting-started` for "Getting Started", readable but
+/// changing along with the title. Pages without a title are named by their path.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TitleSlugger;
+
+impl Slugger for TitleSlugger {
+    fn slug(&self, url: &str, title: Option<&str>) -> String {
+        match title.map(slugify).filter(|slug| slug != UNNAMED_SLUG) {
+            Some(slug) => slug,
+            None => PathSlugger.slug(url, None),
+        }
+    }
+}
+
+/// Slugs made of a hash of the page URL, e.g. `3f1c2a9b0d4e5f67`, which never change
+/// and never collide whatever the URL looks like
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HashSlugger;
+
+impl Slugger for HashSlugger {
+    fn slug(&self, url: &str, _title: Option<&str>) -> String {
+        url_hash(url)
+    }
+}
+
+/// Built-in slug strategies
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum SlugStrategy {
+    /// Name files by the host and path of the page URL, see [`PathSlugger`]
+    #[default]
+    Path,
+    /// Name files by the page title, see [`TitleSlugger`]
+    Title,
+    /// Name files by a hash of the page URL, see [`HashSlugger`]
+    Hash,
+}
+
+impl SlugStrategy {
+    /// Returns the slugger of the strategy.
+    pub fn slugger(self) -> std::sync::Arc<dyn Slugger> {
+        match self {
+            Self::Path => std::sync::Arc::new(PathSlugger),
+            Self::Title => std::sync::Arc::new(TitleSlugger),
+            Self::Hash => std::sync::Arc::new(HashSlugger),
+        }
+    }
+}
+
+/// Slug of texts without any letter or digit.
+const UNNAMED_SLUG: &str = "page";
+
+/// Lowercases the ASCII letters and digits of a text, joining them with dashes.
+fn slugify(text: &str) -> String {
+    let slug = text
+        .to_lowercase()
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        UNNAMED_SLUG.to_string()
+    } else {
+        slug
+    }
+}
+
+/// Returns the hexadecimal hash of a URL, stable across runs, Rust versions and platforms.
+fn url_hash(url: &str) -> String {
+    let hash = hash_html(url);
+    hash.rsplit(':').next().unwrap_or(&hash).to_string()
+}
+
+/// Returns the slug of a page, suffixed with the hash of its URL if another page of the run
+/// already took it, so that the suffix doesn't depend on the order of the pages.
+pub(super) fn unique_slug(slug: String, url: &str, taken: &mut HashSet<String>) -> String {
+    if taken.insert(slug.clone()) {
+        return slug;
+    }
+
+    let suffixed = format!("{slug}-{}", url_hash(url));
+    taken.insert(suffixed.clone());
+    suffixed
+}
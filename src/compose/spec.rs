@@ -0,0 +1,231 @@
+//! The llms.txt spec layout: an H1 with the site name, then H2 sections
+//! with the site description in a blockquote, then H2 sections of pages grouped by URL prefix,
+//! each listing links to its pages.
+
+use llm::LLMProvider;
+use log::info;
+use std::collections::HashMap;
+use url::Url;
+
+use super::{ComposeEntry, Redaction};
+use crate::constants::{SECTION_OVERVIEW_PROMPT_TEMPLATE, SITE_DESCRIPTION_PROMPT_TEMPLATE};
+use crate::storage::Storage;
+use crate::summarize::{PromptVariables, SummarizeContext, summarize_page};
+use crate::{ComposeFormat, FaqPages, Result};
+
+/// Name of the section of pages at the root of the site.
+const ROOT_SECTION: &str = "Pages";
+
+/// Number of page summaries a section overview is written from.
+const OVERVIEW_SOURCE_PAGES: usize = 5;
+
+/// Number of root page summaries the site description is written from.
+const DESCRIPTION_SOURCE_PAGES: usize = 10;
+
+/// Number of homepage text characters the site description is written from.
+const DESCRIPTION_HOMEPAGE_CHARS: usize = 4000;
+
+/// Pages sharing the first segment of their URL path, or the section of their breadcrumb trail.
+pub(super) struct Section {
+    /// Heading of the section, e.g. "Getting started" for `/getting-started/...`
+    name: String,
+    /// The URL prefix shared by the section pages, e.g. `https://example.com/getting-started/`
+    prefix: String,
+    entries: Vec<ComposeEntry>,
+    /// LLM generated overview of the section pages
+    overview: Option<String>,
+}
+
+/// Groups entries into sections by their breadcrumb section or else the first segment
+/// of their URL path, keeping sections and entries in the order of the entries.
+/// Sections of the same name are merged, so that both kinds of sections of a site match.
+pub(super) fn group_sections(entries: Vec<ComposeEntry>) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut index_by_name: HashMap<String, usize> = HashMap::new();
+
+    for entry in entries {
+        let (path_name, prefix) = section_of(&entry.url);
+        let name = entry.section.clone().unwrap_or(path_name);
+        let index = *index_by_name.entry(name.clone()).or_insert_with(|| {
+            sections.push(Section {
+                name,
+                prefix,
+                entries: Vec::new(),
+                overview: None,
+            });
+            sections.len() - 1
+        });
+        if let Some(section) = sections.get_mut(index) {
+            section.entries.push(entry);
+        }
+    }
+
+    sections
+}
+
+/// Returns the section of a page by its breadcrumb trail: the crumb following the site root,
+/// unless it's the page itself as the last crumb named like the page title.
+pub(super) fn breadcrumb_section(breadcrumbs: &[String], title: Option<&str>) -> Option<String> {
+    let [_root, section, rest @ ..] = breadcrumbs else {
+        return None;
+    };
+    if rest.is_empty() && title.is_some_and(|title| title.contains(section.as_str())) {
+        return None;
+    }
+
+    Some(section.clone())
+}
+
+/// Returns the name and URL prefix of the section a page URL belongs to.
+pub(crate) fn section_of(url: &str) -> (String, String) {
+    let Ok(parsed_url) = Url::parse(url) else {
+        return (ROOT_SECTION.to_string(), String::new());
+    };
+    let origin = parsed_url.origin().ascii_serialization();
+    let segments: Vec<&str> = parsed_url
+        .path_segments()
+        .map(|segments| segments.collect())
+        .unwrap_or_default();
+
+    match segments.as_slice() {
+        [directory, _, ..] if !directory.is_empty() => {
+            let name = directory.replace(['-', '_'], " ");
+            let mut chars = name.chars();
+            let name: String = chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+                .collect();
+            (name, format!("{origin}/{directory}/"))
+        }
+        _ => (ROOT_SECTION.to_string(), format!("{origin}/")),
+    }
+}
+
+/// Asks the model for a short overview of every section based on summaries of its first pages,
+/// scrubbed by the redaction since the model may repeat what the pages show.
+pub(super) async fn write_overviews(
+    sections: &mut [Section],
+    model: &dyn LLMProvider,
+    redaction: &Redaction,
+) -> Result<()> {
+    let ctx = SummarizeContext {
+        model,
+        prompt_template: Some(SECTION_OVERVIEW_PROMPT_TEMPLATE),
+        rate_limiter: None,
+        stop: &[],
+        structured: false,
+        rules: &[],
+        language: None,
+        faq: &FaqPages::Ignored,
+    };
+
+    for section in sections.iter_mut() {
+        let summaries = section
+            .entries
+            .iter()
+            .take(OVERVIEW_SOURCE_PAGES)
+            .map(|entry| entry.render(&ComposeFormat::Spec))
+            .collect::<String>();
+        info!("Writing overview of section {}", section.prefix);
+        let variables = PromptVariables {
+            section: Some(section.name.clone()),
+            ..PromptVariables::default()
+        };
+        let overview = summarize_page(&section.prefix, &summaries, &variables, &ctx).await?;
+        section.overview = Some(redaction.scrub(&overview));
+    }
+
+    Ok(())
+}
+
+/// Asks the model for a one-paragraph description of the site based on its homepage text
+/// and the pages at the root of the site, or the section overviews if written.
+/// Both the homepage text sent to the model and the description are scrubbed by the redaction.
+pub(super) async fn describe_site(
+    storage: &Storage,
+    sections: &[Section],
+    model: &dyn LLMProvider,
+    redaction: &Redaction,
+) -> Result<Option<String>> {
+    let Some(site_url) = site_url(sections) else {
+        return Ok(None);
+    };
+    let homepage_url = format!("{}/", site_url.origin().ascii_serialization());
+
+    let mut text = storage
+        .get_page(&homepage_url)?
+        .and_then(|page| page.text)
+        // Scrubbed whole, so that an email or key at the cut is still matched
+        .map(|text| {
+            redaction
+                .scrub(&text)
+                .chars()
+                .take(DESCRIPTION_HOMEPAGE_CHARS)
+                .collect()
+        })
+        .unwrap_or_default();
+    for section in sections {
+        match (&section.overview, section.prefix == homepage_url) {
+            (Some(overview), _) => text.push_str(&format!("\n\n{}: {overview}", section.name)),
+            (None, true) => {
+                for entry in section.entries.iter().take(DESCRIPTION_SOURCE_PAGES) {
+                    text.push_str(&format!("\n{}", entry.render(&ComposeFormat::Spec)));
+                }
+            }
+            (None, false) => text.push_str(&format!("\n\n{}", section.name)),
+        }
+    }
+
+    let ctx = SummarizeContext {
+        model,
+        prompt_template: Some(SITE_DESCRIPTION_PROMPT_TEMPLATE),
+        rate_limiter: None,
+        stop: &[],
+        structured: false,
+        rules: &[],
+        language: None,
+        faq: &FaqPages::Ignored,
+    };
+    info!("Writing description of site {homepage_url}");
+    let description =
+        summarize_page(&homepage_url, &text, &PromptVariables::default(), &ctx).await?;
+
+    Ok(Some(redaction.scrub(
+        &description.split_whitespace().collect::<Vec<_>>().join(" "),
+    )))
+}
+
+/// Returns the URL of the first page, identifying the site.
+fn site_url(sections: &[Section]) -> Option<Url> {
+    sections
+        .iter()
+        .flat_map(|section| &section.entries)
+        .find_map(|entry| Url::parse(&entry.url).ok())
+}
+
+/// Renders sections under an H1 with the host name of the site and its description as a blockquote,
+/// which the spec requires, so a site without a written description gets a generic one.
+pub(super) fn render(sections: &[Section], description: Option<&str>) -> String {
+    let site = site_url(sections)
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let mut output = format!("# {site}\n");
+    match description {
+        Some(description) => output.push_str(&format!("\n> {description}\n")),
+        None => output.push_str(&format!("\n> Pages of {site}, grouped by section.\n")),
+    }
+    for section in sections {
+        output.push_str(&format!("\n## {}\n\n", section.name));
+        if let Some(overview) = &section.overview {
+            output.push_str(&format!("{overview}\n\n"));
+        }
+        for entry in &section.entries {
+            output.push_str(&entry.render(&ComposeFormat::Spec));
+        }
+    }
+
+    output
+}
@@ -1,6 +1,14 @@
 pub const MODEL_API_KEY_ENV_NAME: &str = "LLAMAP_MODEL_API_KEY";
 
-pub(crate) const THINK_STRIPPER: &str = r"<think>[\s\S]*</think>\s*";
+pub const AUTH_BASIC_ENV_NAME: &str = "LLAMAP_AUTH_BASIC";
+
+pub const AUTH_BEARER_ENV_NAME: &str = "LLAMAP_AUTH_BEARER";
+
+pub(crate) const REASONING_STRIPPER: &str = r"(?:<think>[\s\S]*</think>|<thinking>[\s\S]*</thinking>|<reasoning>[\s\S]*</reasoning>|<\|begin_of_thought\|>[\s\S]*<\|end_of_thought\|>|◁think▷[\s\S]*◁/think▷)\s*";
+
+pub(crate) const CODE_FENCE_STRIPPER: &str = r"^```[\w-]*\n(?P<content>[\s\S]*)\n```$";
+
+pub(crate) const ANSWER_PREFIX_STRIPPER: &str = r"^(?i)(?:\*\*)?(?:summary|tl;dr|here is (?:a|the) (?:concise )?summary[^:\n]*)(?:\*\*)?:(?:\*\*)?\s*";
 
 pub(crate) const DEFAULT_PROMPT_TEMPLATE: &str = r#"
 You will see a webpage content from {url}.
@@ -9,3 +17,54 @@ Your answer should contain only summary, it will be pasted directly into digest.
 Nobody should know it was generated using an LLM.
 Try your best to keep original style and language.
 Webpage content to summarize:"#;
+
+pub(crate) const SECTION_OVERVIEW_PROMPT_TEMPLATE: &str = r#"
+You will see a list of pages from the {url} section of a website with their summaries.
+Write a 2-3 sentence overview of what the section covers for a digest.
+Your answer should contain only the overview, it will be pasted directly into digest.
+Nobody should know it was generated using an LLM.
+Try your best to keep original style and language.
+Section pages:
+{text}"#;
+
+pub(crate) const SITE_DESCRIPTION_PROMPT_TEMPLATE: &str = r#"
+You will see the homepage text of the website {url} followed by its sections and top-level pages with their summaries.
+Write a single paragraph of 2-4 sentences describing what the website is about and who it is for.
+Your answer should contain only the description, it will be pasted directly into the header of the digest.
+Nobody should know it was generated using an LLM.
+Try your best to keep original style and language.
+Website content:
+{text}"#;
+
+pub(crate) const FAQ_PROMPT_TEMPLATE: &str = r#"
+You will see a webpage content from {url}, a page of frequently asked questions.
+Create its concise digest keeping the questions and answers as pairs:
+write every question on its own line in bold, followed by a one or two sentence answer.
+Your answer should contain only the digest, it will be pasted directly into digest.
+Nobody should know it was generated using an LLM.
+Try your best to keep original style and language.
+Webpage content to summarize:"#;
+
+pub(crate) const STRUCTURED_PROMPT_TEMPLATE: &str = r#"
+You will see a webpage content from {url}.
+Create its concise summary for a digest, list its main keywords and tell who the page is written for.
+Answer with a single JSON object and nothing else, in the following form:
+{"summary": "...", "keywords": ["...", "..."], "audience": "..."}
+Nobody should know the summary was generated using an LLM.
+Try your best to keep original style and language.
+Webpage content to summarize:"#;
+
+pub(crate) const AUDIT_PROMPT_TEMPLATE: &str = r#"
+You will see a webpage content from {url} followed by its summary written for a digest.
+Score the summary from 1 (worst) to 5 (best) on:
+- faithfulness: it only states what the webpage says, without made up or wrong facts;
+- length: it's concise and still covers the main points of the webpage;
+- language: it's written in the language of the webpage.
+Answer with a single JSON object and nothing else, in the following form:
+{"faithfulness": 5, "length": 5, "language": 5, "comment": "one sentence explaining the lowest score"}"#;
+
+pub(crate) const ASK_PROMPT_TEMPLATE: &str = r#"
+You will see pages of a website, each starting with its URL, followed by a question.
+Answer the question using only what these pages say, in the language of the question.
+Cite the pages your answer is based on by their URL in square brackets, e.g. [https://example.com/page].
+If the pages don't answer the question, say so instead of guessing."#;
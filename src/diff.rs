@@ -0,0 +1,129 @@
+//! The diff module compares two composed llms.txt files page by page, so that
+//! a regenerated file can be reviewed by the pages it adds, removes and changes.
+
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+use crate::validate::LINK_REGEX;
+
+/// A page of a composed file: its link and the notes or content following it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileEntry {
+    /// The link URL
+    pub url: String,
+    /// The link text, empty for a heading with a bare URL
+    pub title: String,
+    /// Notes after the link of a list item, or content under the link heading
+    pub content: String,
+}
+
+/// Pages added, removed and changed between two composed files
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntryDiff {
+    /// URLs of pages only in the new file, in its order
+    pub added: Vec<String>,
+    /// URLs of pages only in the old file, in its order
+    pub removed: Vec<String>,
+    /// URLs of pages whose title or content changed, in the order of the new file
+    pub changed: Vec<String>,
+    /// Number of pages without changes
+    pub unchanged: usize,
+}
+
+/// Reads page entries of a composed file in both the pages and the spec layout:
+/// `## [title](url)` headings followed by content, and `- [title](url): notes` list items.
+///
+/// # Arguments
+///
+/// * `content` - The content of the composed file
+pub fn parse_entries(content: &str) -> Vec<FileEntry> {
+    let mut entries: Vec<FileEntry> = Vec::new();
+    let mut in_entry = false;
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        if !in_code_block {
+            if let Some(entry) = parse_entry_line(line) {
+                entries.push(entry);
+                in_entry = true;
+                continue;
+            }
+            if line.starts_with('#') {
+                in_entry = false;
+                continue;
+            }
+        }
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        if let Some(entry) = entries.last_mut().filter(|_| in_entry) {
+            entry.content.push('\n');
+            entry.content.push_str(line);
+        }
+    }
+
+    for entry in &mut entries {
+        entry.content = entry.content.trim().to_string();
+    }
+    entries
+}
+
+/// Reads an entry from a link heading or a list item starting with a link.
+fn parse_entry_line(line: &str) -> Option<FileEntry> {
+    if let Some(heading) = line.strip_prefix("## ") {
+        let heading = heading.trim();
+        if Url::parse(heading).is_ok() {
+            return Some(FileEntry {
+                url: heading.to_string(),
+                title: String::new(),
+                content: String::new(),
+            });
+        }
+    }
+
+    let item = ["## ", "- ", "* "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))?;
+    let captures = LINK_REGEX.captures(item)?;
+    let link = captures.get(0).filter(|link| link.start() == 0)?;
+    let notes = item.get(link.end()..).unwrap_or_default();
+
+    Some(FileEntry {
+        url: captures.get(2).map_or("", |url| url.as_str()).to_string(),
+        title: captures
+            .get(1)
+            .map_or("", |title| title.as_str())
+            .to_string(),
+        content: notes.trim_start_matches(':').trim().to_string(),
+    })
+}
+
+/// Compares page entries of two composed files by URL.
+///
+/// # Arguments
+///
+/// * `old` - Entries of the old file
+/// * `new` - Entries of the new file
+pub fn diff_entries(old: &[FileEntry], new: &[FileEntry]) -> EntryDiff {
+    let old_by_url: HashMap<&str, &FileEntry> = old
+        .iter()
+        .map(|entry| (entry.url.as_str(), entry))
+        .collect();
+    let new_urls: HashSet<&str> = new.iter().map(|entry| entry.url.as_str()).collect();
+
+    let mut diff = EntryDiff::default();
+    for entry in new {
+        match old_by_url.get(entry.url.as_str()) {
+            None => diff.added.push(entry.url.clone()),
+            Some(&old_entry) if old_entry != entry => diff.changed.push(entry.url.clone()),
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|entry| !new_urls.contains(entry.url.as_str()))
+        .map(|entry| entry.url.clone())
+        .collect();
+
+    diff
+}
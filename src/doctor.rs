@@ -0,0 +1,143 @@
+//! The doctor module checks the database, sitemap, model and prompt template of a run
+//! before it's started, so that a long run doesn't fail hours in on a problem which could
+//! have been found in seconds.
+
+use std::fmt;
+use std::path::Path;
+use url::Url;
+
+use crate::auth::Auth;
+use crate::sitemap::extract_sitemap_listing;
+use crate::storage::Storage;
+use crate::summarize::{PROMPT_PLACEHOLDERS, SummaryModel, unknown_placeholders};
+
+/// Outcome of a check of [`crate::doctor`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked, e.g. "database"
+    pub subject: String,
+    /// What was found if the check passed, or the problem to fix if it failed
+    pub outcome: Result<String, String>,
+}
+
+impl Check {
+    /// Creates a passed check with what was found.
+    pub fn passed(subject: impl Into<String>, found: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            outcome: Ok(found.into()),
+        }
+    }
+
+    /// Creates a failed check with the problem to fix.
+    pub fn failed(subject: impl Into<String>, problem: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            outcome: Err(problem.into()),
+        }
+    }
+
+    /// Returns whether the check passed.
+    pub fn is_passed(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Ok(found) => write!(formatter, "ok   {}: {found}", self.subject),
+            Err(problem) => write!(formatter, "FAIL {}: {problem}", self.subject),
+        }
+    }
+}
+
+/// Checks the database exists and isn't corrupt.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database file, which isn't created if missing
+pub fn check_database(db_path: &str) -> Check {
+    let subject = format!("database {db_path}");
+    if !Path::new(db_path).exists() {
+        return Check::failed(subject, "doesn't exist, scrape a site into it first");
+    }
+
+    let problems = Storage::new(db_path).and_then(|storage| storage.integrity_check());
+    match problems {
+        Ok(problems) if problems.is_empty() => Check::passed(subject, "integrity check passed"),
+        Ok(problems) => Check::failed(
+            subject,
+            format!(
+                "corrupt, restore it from a backup or scrape it again: {}",
+                problems.join("; ")
+            ),
+        ),
+        Err(error) => Check::failed(subject, format!("can't be opened: {error:#}")),
+    }
+}
+
+/// Checks the sitemap can be fetched and lists pages.
+///
+/// # Arguments
+///
+/// * `sitemap_url` - The sitemap or feed of the site
+/// * `auth` - Credentials of a protected site
+pub async fn check_sitemap(sitemap_url: &Url, auth: Option<&Auth>) -> Check {
+    let subject = format!("sitemap {sitemap_url}");
+    match extract_sitemap_listing(sitemap_url.as_str(), auth).await {
+        Ok(listing) if listing.entries.is_empty() => {
+            Check::failed(subject, "lists no page, is it the sitemap of the site?")
+        }
+        Ok(listing) => Check::passed(subject, format!("lists {} pages", listing.entries.len())),
+        Err(error) => Check::failed(subject, format!("can't be read: {error:#}")),
+    }
+}
+
+/// Checks the model answers a short prompt, which needs its endpoint to be reachable
+/// and to accept the credentials.
+///
+/// # Arguments
+///
+/// * `model_name` - The model URL, naming the check
+/// * `model` - The model to check
+pub async fn check_model(model_name: &str, model: SummaryModel) -> Check {
+    let subject = format!("model {model_name}");
+    match model.check().await {
+        Ok(()) => Check::passed(subject, "answered"),
+        Err(error) => Check::failed(
+            subject,
+            format!("{error:#}, check the endpoint address and the API key"),
+        ),
+    }
+}
+
+/// Checks a prompt template only references known placeholders.
+///
+/// # Arguments
+///
+/// * `template_path` - Path of the template file, naming the check
+/// * `template` - The prompt template
+pub fn check_prompt_template(template_path: &str, template: &str) -> Check {
+    let subject = format!("prompt template {template_path}");
+    let unknown = unknown_placeholders(template);
+    if !unknown.is_empty() {
+        return Check::failed(
+            subject,
+            format!(
+                "unknown placeholders {{{}}}, use {{{}}}",
+                unknown.join("}, {"),
+                PROMPT_PLACEHOLDERS.join("}, {")
+            ),
+        );
+    }
+
+    if template.contains("{text}") {
+        Check::passed(subject, "placeholders are known")
+    } else {
+        Check::passed(
+            subject,
+            "placeholders are known, the page text is sent after the prompt without {text}",
+        )
+    }
+}
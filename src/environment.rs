@@ -0,0 +1,102 @@
+//! The environment module reads settings kept out of command lines and project files:
+//! the API keys of LLM providers under their usual variable names, and `${NAME}` references
+//! to environment variables inside option values.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::constants::MODEL_API_KEY_ENV_NAME;
+use crate::{Error, Result, secrets::Secret};
+
+/// Variables holding the API key of every LLM backend needing one, by backend name.
+const PROVIDER_API_KEY_ENV_NAMES: &[(&str, &str)] = &[
+    ("openai", "OPENAI_API_KEY"),
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("google", "GOOGLE_API_KEY"),
+    ("deepseek", "DEEPSEEK_API_KEY"),
+    ("xai", "XAI_API_KEY"),
+    ("groq", "GROQ_API_KEY"),
+    ("mistral", "MISTRAL_API_KEY"),
+    ("cohere", "COHERE_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+    ("azure-openai", "AZURE_OPENAI_API_KEY"),
+];
+
+/// Matches `${NAME}` references to environment variables.
+static ENV_REFERENCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("Failed to compile env reference regex")
+});
+
+/// Returns the variable holding the API key of an LLM backend, None for backends without one like Ollama.
+pub fn provider_api_key_env_name(backend: &str) -> Option<&'static str> {
+    PROVIDER_API_KEY_ENV_NAMES
+        .iter()
+        .find(|(name, _)| *name == backend)
+        .map(|(_, env_name)| *env_name)
+}
+
+/// Finds the API key of an LLM backend in the environment.
+///
+/// The key is read from `custom_env_name` if given, otherwise from `LLAMAP_MODEL_API_KEY`
+/// or the usual variable of the backend, like `OPENAI_API_KEY`.
+///
+/// # Arguments
+///
+/// * `backend` - The backend name, i.e. the scheme of the model URL
+/// * `custom_env_name` - The variable holding the key in custom setups
+///
+/// # Returns
+///
+/// Returns the name of the variable the key was read from along with the masked key, None if no variable is set
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if the custom variable isn't set
+pub fn find_api_key<'a>(
+    backend: &str,
+    custom_env_name: Option<&'a str>,
+) -> Result<Option<(&'a str, Secret)>> {
+    if let Some(env_name) = custom_env_name {
+        return std::env::var(env_name)
+            .map(|key| Some((env_name, Secret::from(key))))
+            .map_err(|_| {
+                Error::Config(format!("The API key variable {env_name} isn't set").into())
+            });
+    }
+
+    Ok([
+        Some(MODEL_API_KEY_ENV_NAME),
+        provider_api_key_env_name(backend),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|env_name| {
+        std::env::var(env_name)
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| (env_name, Secret::from(key)))
+    }))
+}
+
+/// Replaces `${NAME}` references in a value with the environment variables they name.
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] naming the first referenced variable which isn't set
+pub fn interpolate_env(value: &str) -> Result<String> {
+    let mut missing = None;
+    let interpolated = ENV_REFERENCE_REGEX.replace_all(value, |reference: &Captures| {
+        let name = reference.get(1).map_or("", |name| name.as_str());
+        std::env::var(name).unwrap_or_else(|_| {
+            missing.get_or_insert_with(|| name.to_string());
+            String::new()
+        })
+    });
+
+    match missing {
+        Some(name) => Err(Error::Config(
+            format!("The environment variable {name} referenced by {value} isn't set").into(),
+        )),
+        None => Ok(interpolated.into_owned()),
+    }
+}
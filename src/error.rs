@@ -0,0 +1,127 @@
+//! The error module defines errors of the library grouped by the failing part of the pipeline,
+//! so applications embedding llamap can match on them and recover.
+
+use std::fmt::Display;
+
+/// Cause of a library error: the error of the failing dependency, or a message telling what failed
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Error of a library operation by category, keeping the chain of its causes.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The sitemap, feed, URL list or directory to scrape can't be read
+    #[error(transparent)]
+    Sitemap(BoxError),
+    /// An HTTP request failed
+    #[error(transparent)]
+    Http(BoxError),
+    /// A database operation failed
+    #[error(transparent)]
+    Storage(BoxError),
+    /// Text can't be extracted from a page
+    #[error(transparent)]
+    Extraction(BoxError),
+    /// The LLM model can't be built or doesn't answer as expected
+    #[error(transparent)]
+    Llm(BoxError),
+    /// Options, model URLs or rules are invalid
+    #[error(transparent)]
+    Config(BoxError),
+    /// A file can't be read or written
+    #[error(transparent)]
+    Io(BoxError),
+}
+
+/// Result of a library operation.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Message telling what failed, caused by another error, like anyhow's context.
+/// The alternate format (`{:#}`) appends the messages of the causes.
+#[derive(Debug)]
+struct ContextError {
+    message: String,
+    source: BoxError,
+}
+
+impl Display for ContextError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if formatter.alternate() {
+            write!(formatter, "{}: {:#}", self.message, self.source)
+        } else {
+            formatter.write_str(&self.message)
+        }
+    }
+}
+
+impl std::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl Error {
+    /// Returns the error with a message telling what failed, keeping its category.
+    pub(crate) fn context(self, message: impl Display) -> Error {
+        let (variant, source) = self.into_parts();
+        variant(Box::new(ContextError {
+            message: message.to_string(),
+            source,
+        }))
+    }
+
+    /// Returns the constructor of the variant of the error and its cause.
+    fn into_parts(self) -> (fn(BoxError) -> Error, BoxError) {
+        match self {
+            Error::Sitemap(source) => (Error::Sitemap, source),
+            Error::Http(source) => (Error::Http, source),
+            Error::Storage(source) => (Error::Storage, source),
+            Error::Extraction(source) => (Error::Extraction, source),
+            Error::Llm(source) => (Error::Llm, source),
+            Error::Config(source) => (Error::Config, source),
+            Error::Io(source) => (Error::Io, source),
+        }
+    }
+}
+
+/// Adds a message telling what failed to the error of a result, keeping its category.
+pub(crate) trait Context<T> {
+    /// Wraps the error with the message.
+    fn context(self, message: impl Display) -> Result<T>;
+
+    /// Wraps the error with the message, built only on failure.
+    fn with_context<M: Display>(self, message: impl FnOnce() -> M) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context(self, message: impl Display) -> Result<T> {
+        self.map_err(|error| error.into().context(message))
+    }
+
+    fn with_context<M: Display>(self, message: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|error| error.into().context(message()))
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Storage(error.into())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error.into())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Http(error.into())
+    }
+}
+
+impl From<llm::error::LLMError> for Error {
+    fn from(error: llm::error::LLMError) -> Self {
+        Error::Llm(error.into())
+    }
+}
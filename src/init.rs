@@ -0,0 +1,111 @@
+//! The init module discovers what a project needs to know about a new site: where its sitemap
+//! is and how fast it may be crawled, so that `llamap init` proposes a working project
+//! to first-time users instead of leaving them to learn every subcommand.
+
+use log::{debug, info};
+use std::time::Duration;
+use url::Url;
+
+use crate::error::Context;
+use crate::robots::{RobotsTxt, parse_robots_txt};
+use crate::{Error, Result};
+
+/// Paths sitemaps are usually published at, probed when `robots.txt` declares none.
+const SITEMAP_PATHS: [&str; 3] = ["sitemap.xml", "sitemap_index.xml", "wp-sitemap.xml"];
+
+/// Delay between requests proposed for sites without a crawl delay, in milliseconds
+const DEFAULT_DELAY_MS: u64 = 1000;
+
+/// Concurrent requests proposed for sites without a crawl delay
+const DEFAULT_CONCURRENCY: usize = 2;
+
+/// What was found out about a site before its first scrape
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SiteDiscovery {
+    /// Sitemaps declared by `robots.txt`, or else found at a usual path
+    pub sitemaps: Vec<Url>,
+    /// Crawl delay asked for by `robots.txt`
+    pub crawl_delay: Option<Duration>,
+}
+
+impl SiteDiscovery {
+    /// Returns the proposed delay between requests in milliseconds: the crawl delay of the site,
+    /// or one second.
+    pub fn proposed_delay(&self) -> u64 {
+        self.crawl_delay.map_or(DEFAULT_DELAY_MS, |delay| {
+            u64::try_from(delay.as_millis()).unwrap_or(u64::MAX)
+        })
+    }
+
+    /// Returns the proposed number of concurrent requests: a single one for sites asking
+    /// for a crawl delay, which it applies to, or a couple otherwise.
+    pub fn proposed_concurrency(&self) -> usize {
+        if self.crawl_delay.is_some() {
+            1
+        } else {
+            DEFAULT_CONCURRENCY
+        }
+    }
+}
+
+/// Finds the sitemaps and crawl delay of a site from its `robots.txt`, probing the usual
+/// sitemap paths if it declares none.
+///
+/// # Arguments
+///
+/// * `site_url` - The URL of the site, e.g. `https://example.com/`
+///
+/// # Errors
+///
+/// Returns [`Error::Http`] if the HTTP client can't be built. Unreachable or missing files
+/// are left out of the discovery instead.
+pub async fn discover_site(site_url: &Url) -> Result<SiteDiscovery> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .context("Unable to build the HTTP client")?;
+
+    let robots = match fetch_text(&client, &join(site_url, "/robots.txt")?).await {
+        Some(content) => parse_robots_txt(&content),
+        None => RobotsTxt::default(),
+    };
+    let mut sitemaps = robots.sitemaps;
+    if sitemaps.is_empty() {
+        for path in SITEMAP_PATHS {
+            let sitemap_url = join(site_url, &format!("/{path}"))?;
+            if fetch_text(&client, &sitemap_url).await.is_some() {
+                sitemaps.push(sitemap_url);
+                break;
+            }
+        }
+    }
+
+    info!("Found {} sitemaps of {site_url}", sitemaps.len());
+    Ok(SiteDiscovery {
+        sitemaps,
+        crawl_delay: robots.crawl_delay,
+    })
+}
+
+/// Returns the text of a document, None if it can't be fetched.
+pub(crate) async fn fetch_text(client: &reqwest::Client, url: &Url) -> Option<String> {
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+    match response {
+        Ok(response) => response.text().await.ok(),
+        Err(error) => {
+            debug!("Unable to fetch {url}: {error}");
+            None
+        }
+    }
+}
+
+/// Resolves a path against the site URL.
+fn join(site_url: &Url, path: &str) -> Result<Url> {
+    site_url.join(path).map_err(|error| {
+        Error::Config(error.into()).context(format!("Invalid site URL {site_url}"))
+    })
+}
@@ -1,13 +1,32 @@
 //! The llamap library provides functionality for scraping websites using sitemap.xml
 //! and composing the results into an llms.txt file for AI crawlers.
 
+pub mod auth;
+pub mod canonical;
+pub mod client;
+pub mod compliance;
 pub mod compose;
 pub mod constants;
+pub mod diff;
+pub mod doctor;
+pub mod environment;
+pub mod error;
+pub mod init;
+pub mod ollama;
 pub mod parse;
+pub mod progress;
+pub mod project;
+pub mod robots;
+pub mod sample;
 pub mod scrape;
+pub mod secrets;
+pub mod shutdown;
 pub mod sitemap;
+pub mod stdio;
 pub mod storage;
 pub mod summarize;
+pub mod throttle;
+pub mod validate;
 
 /// Enum representing the text extraction method.
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
@@ -31,6 +50,28 @@ impl std::str::FromStr for TextBy {
     }
 }
 
+/// Enum representing how dom_smoothie selects the article candidate.
+#[derive(Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum CandidateSelect {
+    /// Mozilla Readability.js algorithm
+    Readability,
+    /// dom_smoothie's own algorithm
+    #[default]
+    DomSmoothie,
+}
+
+/// Enum representing the format of text extracted by dom_smoothie.
+#[derive(Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum TextFormat {
+    /// Plain text content of the article
+    Raw,
+    /// Plain text keeping paragraphs and line breaks
+    Formatted,
+    /// Markdown
+    #[default]
+    Markdown,
+}
+
 /// Enum representing the target for summarization.
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub enum SummarizeTarget {
@@ -39,6 +80,8 @@ pub enum SummarizeTarget {
     Unsummarized,
     /// All database pages.
     All,
+    /// Pages with URLs matching a glob pattern, e.g. `https://example.com/blog/*`.
+    Pattern { glob: String },
     /// A page with specified URL.
     Page { url: String },
 }
@@ -48,6 +91,9 @@ impl From<&str> for SummarizeTarget {
         match value {
             "unsummarized" => Self::Unsummarized,
             "all" => Self::All,
+            glob if is_glob(glob) => Self::Pattern {
+                glob: glob.to_string(),
+            },
             url => Self::Page {
                 url: url.to_string(),
             },
@@ -55,12 +101,39 @@ impl From<&str> for SummarizeTarget {
     }
 }
 
+/// Enum representing which pages are summarized with the FAQ prompt, which keeps
+/// their questions and answers as pairs instead of flattening them into prose.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum FaqPages {
+    /// Pages declaring an `FAQPage` in their JSON-LD data, or with several question headings
+    #[default]
+    Detected,
+    /// Pages with the tag
+    Tagged { tag: String },
+    /// No pages
+    Ignored,
+}
+
+impl From<&str> for FaqPages {
+    fn from(value: &str) -> Self {
+        match value {
+            "auto" => Self::Detected,
+            "off" => Self::Ignored,
+            tag => Self::Tagged {
+                tag: tag.to_string(),
+            },
+        }
+    }
+}
+
 /// Enum representing the target for parsing/re-extraction.
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub enum ParseTarget {
     /// All database pages.
     #[default]
     All,
+    /// Pages with URLs matching a glob pattern, e.g. `https://example.com/blog/*`.
+    Pattern { glob: String },
     /// A page with specified URL.
     Page { url: String },
 }
@@ -69,6 +142,9 @@ impl From<&str> for ParseTarget {
     fn from(value: &str) -> Self {
         match value {
             "all" => Self::All,
+            glob if is_glob(glob) => Self::Pattern {
+                glob: glob.to_string(),
+            },
             url => Self::Page {
                 url: url.to_string(),
             },
@@ -76,6 +152,12 @@ impl From<&str> for ParseTarget {
     }
 }
 
+/// Whether a target is a glob pattern rather than a single URL.
+/// `*` and `[` are rarely found unescaped in URLs, while `?` starts every query string.
+pub(crate) fn is_glob(target: &str) -> bool {
+    target.contains(['*', '['])
+}
+
 /// Enum representing the source for composing content.
 #[derive(Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
 pub enum ComposeSource {
@@ -88,7 +170,178 @@ pub enum ComposeSource {
     Best,
 }
 
-pub use compose::compose;
-pub use parse::{extract_article, parse_db_html};
-pub use scrape::process_sitemap;
-pub use summarize::summarize;
+/// Enum representing the order of composed pages.
+#[derive(Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum PageSort {
+    /// By URL
+    #[default]
+    Url,
+    /// By title, case-insensitively, pages without a title by URL
+    Title,
+    /// Most recently modified first
+    Lastmod,
+    /// Highest sitemap priority first, then most recently modified first
+    Priority,
+    /// Most important first by the internal links between pages recorded by parse,
+    /// then highest sitemap priority first
+    Importance,
+}
+
+/// Enum representing the layout of the composed file.
+#[derive(Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum ComposeFormat {
+    /// A heading with the page link followed by the page content, for every page
+    #[default]
+    Pages,
+    /// The llms.txt spec: the site name, then sections of page links with notes, by URL path
+    Spec,
+}
+
+/// Enum representing whether composed pages note their last modification date.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum UpdatedDate {
+    /// Compose pages without it
+    #[default]
+    Hidden,
+    /// Note it as "Updated: YYYY-MM-DD": the sitemap lastmod of the page, or the time it was
+    /// scraped if the sitemap has none
+    Shown,
+}
+
+/// Enum representing whether composed pages note the author and publication date of articles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Byline {
+    /// Compose pages without them
+    #[default]
+    Hidden,
+    /// Note them as "By: ..." and "Published: ..." for pages whose JSON-LD structured data declares them,
+    /// taking the publication date of news articles from the news sitemap otherwise
+    Shown,
+}
+
+/// Enum representing whether composed pages note the videos and images their sitemap lists.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MediaNotes {
+    /// Compose pages without them
+    #[default]
+    Hidden,
+    /// Note them as "Video: m:ss" and "Images: caption; caption" for pages whose video or image
+    /// sitemap entries declare them
+    Shown,
+}
+
+/// Enum representing whether composed pages list the sections parse split them into.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SectionLinks {
+    /// Compose pages without them
+    #[default]
+    Hidden,
+    /// List links to the sections under their page, with the section summaries
+    Listed,
+}
+
+/// Enum representing what compose does with an existing output file.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum ExistingOutput {
+    /// Replace the whole file
+    #[default]
+    Overwrite,
+    /// Regenerate only the region between the `<!-- llamap:begin -->` and `<!-- llamap:end -->`
+    /// comments, keeping hand-written content around it; the region is appended to files without it
+    Merge,
+}
+
+/// Enum representing whether compose keeps the output file it replaces.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OutputBackup {
+    /// Replace the file without keeping it
+    #[default]
+    Discarded,
+    /// Keep a copy of the file next to it, suffixed with the time it was replaced like
+    /// `llms.txt.20261016T120000Z`
+    Kept,
+}
+
+/// Enum representing which listed pages already stored scrape fetches again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StoredPages {
+    /// Fetch pages whose listed lastmod differs from the stored one, or listed without one
+    #[default]
+    RefetchModified,
+    /// Never fetch stored pages again, only new ones, whatever their lastmod
+    Kept,
+}
+
+/// Enum representing what a completed scrape does with stored pages its sitemap no longer lists
+/// or which failed to be fetched.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum UnlistedPages {
+    /// Keep them as they are
+    #[value(name = "keep")]
+    Kept,
+    /// Delete them from the database
+    #[default]
+    #[value(name = "delete")]
+    Deleted,
+    /// Move them to the `archived_pages` table, which compose can list under an "Archived" section
+    #[value(name = "archive")]
+    Archived,
+}
+
+/// Enum representing whether compose lists the pages archived by scrape.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ArchivedPages {
+    /// Compose without them
+    #[default]
+    Omitted,
+    /// List them under an "Archived" section after the composed pages
+    Listed,
+}
+
+/// Enum representing what to do with a trailing slash of page URLs.
+#[derive(Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum TrailingSlash {
+    /// Keep URLs as they are
+    #[default]
+    Keep,
+    /// Remove the trailing slash, so `/page/` is stored as `/page`
+    Strip,
+    /// Add a trailing slash to paths without a file extension, so `/page` is stored as `/page/`
+    Add,
+}
+
+/// Enum representing whether pages flagged noindex are summarized and composed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NoindexPages {
+    /// Leave out pages whose robots meta tag or `X-Robots-Tag` header asks not to index them
+    #[default]
+    Exclude,
+    /// Treat them like any other page
+    Include,
+}
+
+impl NoindexPages {
+    /// Restricts the filter to pages not flagged noindex, unless they are included.
+    pub fn restrict(self, filter: storage::PageFilter) -> storage::PageFilter {
+        match self {
+            NoindexPages::Exclude => filter.noindex(false),
+            NoindexPages::Include => filter,
+        }
+    }
+}
+
+pub use auth::Auth;
+pub use client::Llamap;
+pub use compose::{ComposeOptions, MarkdownExport, compose};
+pub use error::{Error, Result};
+pub use parse::{ParseOptions, ReadabilityOptions, extract_article, parse_db_html};
+pub use progress::{Progress, ProgressReporter};
+pub use project::{Project, Site};
+pub use scrape::{
+    ContentFilter, FailureReason, RetryPolicy, ScrapeOptions, ScrapeReport, SpiderRules,
+    clean_up_sitemaps, process_directory, process_links, process_page_url, process_sitemap,
+    process_sitemaps, process_url_list,
+};
+pub use secrets::Secret;
+pub use summarize::{SummarizeOptions, summarize, summarize_while_scraping};
+pub use tokio_util::sync::CancellationToken;
@@ -8,20 +8,54 @@
 extern crate spider;
 
 use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use clap::{Parser, Subcommand};
 use env_logger::Builder;
 use llm::builder::{LLMBackend, LLMBuilder};
-use log::{LevelFilter, info};
+use log::{LevelFilter, info, warn};
 use spider::tokio;
 use std::str::FromStr;
 use url::Url;
 
 use llamap::{
-    ParseTarget, ComposeSource, SummarizeTarget, TextBy, compose::compose,
-    constants::MODEL_API_KEY_ENV_NAME, parse::parse_db_html, scrape::process_sitemap,
-    summarize::summarize,
+    ArchivedPages, Byline, CandidateSelect, ComposeFormat, ComposeSource, ExistingOutput, FaqPages,
+    MediaNotes, NoindexPages, OutputBackup, PageSort, ParseTarget, Project, SectionLinks, Site,
+    StoredPages, SummarizeTarget, TextBy, TextFormat, TrailingSlash, UnlistedPages, UpdatedDate,
+    auth::Auth,
+    canonical::normalize_url,
+    compliance::compliance_report,
+    compose::{
+        ComposeOptions, FrontMatter, MarkdownExport, Redaction, RegexScrubber, SlugStrategy,
+        compose,
+    },
+    constants::{AUTH_BASIC_ENV_NAME, AUTH_BEARER_ENV_NAME, MODEL_API_KEY_ENV_NAME},
+    diff::{diff_entries, parse_entries},
+    doctor::{Check, check_database, check_model, check_prompt_template, check_sitemap},
+    environment::{find_api_key, interpolate_env, provider_api_key_env_name},
+    init::discover_site,
+    ollama::{self, DEFAULT_OLLAMA_URL, ensure_model_available},
+    parse::{OnPageError, ParseOptions, ReadabilityOptions, parse_db_html},
+    progress::Progress,
+    sample::{DEFAULT_EXCERPT_CHARS, ReviewField, SampleOptions, write_review_bundle},
+    scrape::{
+        ContentFilter, RetryPolicy, ScrapeOptions, ScrapeReport, SpiderRules, clean_up_sitemaps,
+        process_directory, process_links, process_page_url, process_sitemaps, process_url_list,
+    },
+    secrets::Secret,
+    sitemap::{resolve_sitemap_location, write_sitemap},
+    stdio::{is_stdio, read_input, read_url_list},
+    storage::{PageFilter, PageStatus, Storage, plain_search_query},
+    summarize::{
+        AskOptions, AuditOptions, DEFAULT_ASK_PAGE_CHARS, DEFAULT_ASK_PAGES, PromptComparison,
+        Sampling, SummarizeOptions, SummaryLanguage, SummaryModel, ask, audit_summaries,
+        compare_prompts, estimate_summarize, parse_rules, summarize, summarize_while_scraping,
+    },
+    validate::{ValidateOptions, validate},
 };
 use scraper::Selector as ScraperSelector;
 
@@ -40,31 +74,46 @@ struct Cli {
 #[derive(Subcommand)]
 enum Command {
     /// Scrape a website using sitemap and save pages to a local database
+    #[command(allow_missing_positional = true)]
     Scrape {
-        /// The sitemap URL to scrape
-        url: String,
+        #[command(flatten)]
+        source: ScrapeSourceArgs,
         /// Path to database file to store pages data
         db: String,
-        /// Delay between requests in milliseconds (rate limiting)
-        #[arg(long, short, default_value_t = 1000)]
-        delay: u64,
-        /// Number of concurrent requests (default: 1)
-        #[arg(long, short, default_value_t = 1)]
-        concurrency: usize,
+        #[command(flatten)]
+        settings: ScrapeArgs,
+        #[command(flatten)]
+        pipeline: PipelineArgs,
+    },
+    /// Download a single page bypassing the sitemap and store or update it, e.g. to fix one broken page
+    Fetch {
+        /// Path to database file to store the page to
+        db: String,
+        /// URL of the page to fetch
+        url: Url,
+        /// Summarize the page right after parsing it with this LLM model URL
+        #[arg(long, value_name = "MODEL", requires = "parse_on_scrape")]
+        summarize: Option<String>,
+        #[command(flatten)]
+        settings: ScrapeArgs,
+        #[command(flatten)]
+        provider: ProviderArgs,
     },
     /// Parse/re-extract content from HTML in the database
     Parse {
         /// Path to database file to read pages from
         db: String,
-        /// Target to parse: "all" (default) or specify an URL
+        /// Target to parse: "all" (default), an URL glob like "https://example.com/blog/*" or specify an URL
         #[arg(long, short = 't', default_value = "all")]
         target: ParseTarget,
-        /// Text extraction method: "dom_smoothie" (default) or "fast_html2md"
-        #[arg(long, default_value = "dom_smoothie")]
-        text_by: TextBy,
-        /// CSS selector to limit the HTML subset from which content is extracted (optional)
-        #[arg(long, short)]
-        selector: Option<String>,
+        /// Trailing slash policy the pages were scraped with, applied to their canonical URLs:
+        /// "keep" (default), "strip" or "add"
+        #[arg(long, value_enum, default_value_t = TrailingSlash::Keep)]
+        trailing_slash: TrailingSlash,
+        #[command(flatten)]
+        extraction: ParseArgs,
+        #[command(flatten)]
+        filter: PageFilterArgs,
     },
     /// Summarize scraped pages using an LLM model and store the summary in the database
     Summarize {
@@ -72,26 +121,761 @@ enum Command {
         db: String,
         /// URL of the LLM model to use for processing
         model: String,
-        /// Path to the file with a prompt template
-        #[arg(long, short = 'p')]
-        prompt_file: Option<String>,
-        /// Target to summarize: "unsummarized", "all" or specify an URL
-        #[arg(long, short = 't', default_value = "unsummarized")]
-        target: SummarizeTarget,
-        /// Rate limit: requests per minute (default: no limit)
-        #[arg(long, short = 'r')]
-        rpm: Option<u32>,
+        #[command(flatten)]
+        settings: SummarizeArgs,
+        #[command(flatten)]
+        filter: PageFilterArgs,
     },
+    /// Score stored summaries on faithfulness, length and language with an LLM judge and list the worst
+    AuditSummaries(AuditArgs),
+    /// Write a reproducible random sample of stored pages with their text excerpt and summary
+    /// to a markdown review bundle
+    Sample(SampleArgs),
+    /// Search the titles, texts and summaries of stored pages, listing matching URLs with snippets
+    Search(SearchArgs),
+    /// Answer a question from the stored pages best matching it, citing their URLs
+    Ask(AskArgs),
     /// Process scraped pages and composes results to a file
     Compose {
         /// Path to database file to read pages from
         db: String,
-        /// Path to output file to compose results to
+        /// Path to output file to compose results to, "-" for stdout
         output_file: String,
-        /// Source to compose from: "text", "summary", or "best" (default)
-        #[arg(long, value_enum, default_value_t = ComposeSource::Best)]
-        source: ComposeSource,
+        #[command(flatten)]
+        layout: ComposeArgs,
+        #[command(flatten)]
+        filter: PageFilterArgs,
+    },
+    /// Generate a sitemap of stored pages with their lastmod, e.g. for a site ingested from a feed or directory
+    SitemapOut {
+        /// Path to database file to read pages from
+        db: String,
+        /// Path to the sitemap file to write, "-" for stdout
+        output_file: String,
+        /// List pages flagged noindex by their robots meta tag or X-Robots-Tag header too
+        #[arg(long)]
+        include_noindex: bool,
+        #[command(flatten)]
+        filter: PageFilterArgs,
+    },
+    /// Tag pages matching a pattern, remove a tag, or list tags when neither is given
+    Tag {
+        /// Path to database file with pages to tag
+        db: String,
+        /// Tag to add to the matching pages
+        #[arg(long, conflicts_with = "remove")]
+        add: Option<String>,
+        /// Tag to remove from the matching pages
+        #[arg(long)]
+        remove: Option<String>,
+        /// Glob matched against the URL path if it starts with "/" (e.g. "/docs/*"), the whole URL otherwise
+        #[arg(long = "match", default_value = "*")]
+        pattern: String,
+    },
+    /// Exclude pages matching a pattern from scrape and compose, remove an exclusion,
+    /// or list exclusions when neither is given
+    Exclude {
+        /// Path to database file to keep the exclusions in
+        db: String,
+        /// Glob matched against the URL path if it starts with "/" (e.g. "/legal/*"), the whole URL otherwise
+        #[arg(long, conflicts_with = "remove")]
+        pattern: Option<String>,
+        /// Exclusion pattern to remove, so that matching pages are scraped again
+        #[arg(long)]
+        remove: Option<String>,
+    },
+    /// Remove a stored page from the database
+    Rm {
+        /// Path to database file to remove the page from
+        db: String,
+        /// URL of the page to remove, or "-" to remove the pages listed one URL per line on stdin
+        url: String,
+    },
+    /// Check a composed llms.txt file against the spec conventions, failing with a findings report
+    Validate {
+        /// Path to the llms.txt file to check, "-" for stdin
+        file: String,
+        #[command(flatten)]
+        checks: ValidateArgs,
+    },
+    /// Compare two composed files by the pages they add, remove and change
+    Diff {
+        /// Path to the old composed file
+        old_file: String,
+        /// Path to the new composed file
+        new_file: String,
+    },
+    /// Create a project for a site step by step: find its sitemap, propose how fast to crawl it
+    /// and check the model summarizing its pages
+    Init {
+        /// Path to the JSON project file to write
+        #[arg(long, default_value = "llamap.json")]
+        project: String,
+        #[command(flatten)]
+        provider: ProviderArgs,
+    },
+    /// Check the database, sitemap, model and prompt template of a run before starting it
+    Doctor(DoctorArgs),
+    /// Scrape, parse, summarize and compose a site of a project, or all of them at once
+    Run(RunArgs),
+    /// List recent runs recorded in the database
+    Runs {
+        /// Path to database file to read runs from
+        db: String,
+        /// Maximum number of runs to list, newest first
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: u32,
+    },
+    /// Show how far stored pages went through scrape, parse, summarize and compose
+    Status {
+        /// Path to database file to read page statuses from
+        db: String,
+        /// Count pages in every status (scraped, parsed, summarized, composed, failed:<stage>)
+        #[arg(long)]
+        by_stage: bool,
+    },
+    /// Report problems of the stored site
+    Report {
+        #[command(subcommand)]
+        report: ReportCommand,
     },
+    /// Maintain the database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+/// Which sites of a project `run` processes
+#[derive(clap::Args)]
+struct RunArgs {
+    /// Path to the JSON project listing the sites with their sitemap, database and output file
+    #[arg(long, default_value = "llamap.json")]
+    project: String,
+    /// Run every site of the project concurrently, sharing the rate limit of the model
+    #[arg(long, conflicts_with = "site", required_unless_present = "site")]
+    all: bool,
+    /// Name of the site to run
+    site: Option<String>,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// Maintenance commands of `db`
+#[derive(Subcommand)]
+enum DbCommand {
+    /// List page rows with an unparsable URL, invalid timestamps or empty HTML, which break runs
+    Check {
+        /// Path to database file to check
+        db: String,
+        /// Reset invalid timestamps and move rows which can't be fixed to the quarantined_pages table
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Forget the lastmod of pages, e.g. one normalized from an invalid value, so that the next
+    /// scrape fetches them again and stores the lastmod of the sitemap
+    ResetLastmod {
+        /// Path to database file with the pages
+        db: String,
+        #[command(flatten)]
+        filter: PageFilterArgs,
+    },
+    /// Clean up stored pages the sitemaps no longer list without crawling them, like a completed scrape
+    Cleanup(DbCleanupArgs),
+}
+
+/// What `db cleanup` reconciles the database with
+#[derive(clap::Args)]
+struct DbCleanupArgs {
+    /// Path to database file with the pages
+    db: String,
+    /// Sitemap or feed URL listing the pages to keep (repeatable), fetched with the credentials
+    /// of LLAMAP_AUTH_BASIC or LLAMAP_AUTH_BEARER
+    #[arg(long = "sitemap", required = true)]
+    sitemaps: Vec<Url>,
+    /// What to do with the unlisted pages: "delete" them (default) or "archive" them for compose --archived
+    #[arg(long, value_enum, default_value_t = UnlistedPages::Deleted)]
+    cleanup: UnlistedPages,
+    /// Trailing slash policy the pages were scraped with: "keep" (default), "strip" or "add"
+    #[arg(long, value_enum, default_value_t = TrailingSlash::Keep)]
+    trailing_slash: TrailingSlash,
+    /// Extensions of listed URLs which the pages were scraped without, comma separated
+    #[arg(long, value_delimiter = ',', default_values_t = ContentFilter::default().skip_extensions)]
+    skip_extensions: Vec<String>,
+}
+
+/// What `doctor` checks
+#[derive(clap::Args)]
+struct DoctorArgs {
+    /// Path to the database file to check the integrity of
+    db: String,
+    /// Sitemap or feed URL to fetch, with the credentials of LLAMAP_AUTH_BASIC or LLAMAP_AUTH_BEARER
+    #[arg(long)]
+    sitemap: Option<Url>,
+    /// LLM model URL to send a short prompt to
+    #[arg(long)]
+    model: Option<String>,
+    /// Prompt template file whose placeholders are checked
+    #[arg(long)]
+    prompt_file: Option<String>,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// Reports of `report`
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// List internal links of stored pages pointing at pages which failed to be scraped or are missing
+    BrokenLinks {
+        /// Path to database file with parsed pages
+        db: String,
+    },
+    /// Summarize per host the robots.txt rules, pages skipped as disallowed, noindex pages and
+    /// stored pages robots.txt disallows, fetching the current robots.txt of every host
+    Compliance {
+        /// Path to database file with scraped pages
+        db: String,
+    },
+    /// List URLs added to, removed from and with a bumped lastmod in the sitemaps since the
+    /// scrape run before, explaining why a run scraped the pages it did
+    SitemapDiff {
+        /// Path to database file with scraped pages
+        db: String,
+        /// Scrape run to compare with the one before it (see runs), the latest one by default
+        #[arg(long)]
+        run: Option<i64>,
+    },
+}
+
+/// Where `scrape` takes the pages from
+#[derive(clap::Args)]
+struct ScrapeSourceArgs {
+    /// The sitemap or RSS/Atom feed URL (http(s):// or file://) or local path to scrape,
+    /// or the start page with --crawl-depth
+    #[arg(
+        required_unless_present_any = ["from_dir", "url_list", "sitemap"],
+        conflicts_with_all = ["from_dir", "url_list"]
+    )]
+    url: Option<String>,
+    /// Another sitemap or feed of the site to scrape along with the URL, repeatable,
+    /// for sites splitting sitemaps by type without a sitemap index
+    #[arg(long, conflicts_with_all = ["from_dir", "url_list", "crawl_depth"])]
+    sitemap: Vec<String>,
+    /// Follow same-host links from the URL up to this depth instead of reading it as a sitemap
+    #[arg(long, requires = "url", value_parser = clap::value_parser!(u16).range(1..))]
+    crawl_depth: Option<u16>,
+    /// Scrape URLs listed one per line in this file ("-" for stdin) instead of a sitemap
+    #[arg(long, conflicts_with = "from_dir")]
+    url_list: Option<String>,
+    /// Ingest a static site build from this directory instead of crawling a sitemap
+    #[arg(long, requires = "base_url")]
+    from_dir: Option<String>,
+    /// The URL the --from-dir directory is served at, used to map file paths to page URLs
+    #[arg(long, requires = "from_dir")]
+    base_url: Option<String>,
+}
+
+/// How `scrape` crawls and stores pages
+#[derive(clap::Args)]
+struct ScrapeArgs {
+    /// Delay between requests to the same host in milliseconds (rate limiting)
+    #[arg(long, short, default_value_t = 1000)]
+    delay: u64,
+    /// Number of concurrent requests per host (default: 1)
+    #[arg(long, short, default_value_t = 1)]
+    concurrency: usize,
+    /// Stop the crawl once this many pages are stored
+    #[arg(long)]
+    max_pages: Option<usize>,
+    /// Stop the crawl once this many bytes of HTML are stored
+    #[arg(long)]
+    max_bytes: Option<u64>,
+    /// Trailing slash policy of stored page URLs: "keep" (default), "strip" or "add"
+    #[arg(long, value_enum, default_value_t = TrailingSlash::Keep)]
+    trailing_slash: TrailingSlash,
+    /// Extensions of listed URLs which aren't fetched, comma separated ("" to fetch all)
+    #[arg(long, value_delimiter = ',', default_values_t = ContentFilter::default().skip_extensions)]
+    skip_extensions: Vec<String>,
+    /// Content type prefixes of fetched documents which aren't stored, comma separated ("" to store all)
+    #[arg(long, value_delimiter = ',', default_values_t = ContentFilter::default().skip_content_types)]
+    skip_content_types: Vec<String>,
+    /// Regex patterns of URLs the crawler never requests, comma separated (e.g. "/search\?")
+    #[arg(long, value_delimiter = ',')]
+    spider_blacklist: Vec<String>,
+    /// Regex patterns of URLs the crawler only requests, comma separated
+    #[arg(long, value_delimiter = ',')]
+    spider_whitelist: Vec<String>,
+    /// Maximum number of pages the crawler requests under a path as path=pages, repeatable
+    /// ("*=100" limits the whole site)
+    #[arg(long = "spider-budget", value_parser = parse_budget)]
+    spider_budget: Vec<(String, u32)>,
+    /// Maximum duration of a page request in milliseconds (0 waits as long as the site takes)
+    #[arg(long, default_value_t = 30_000)]
+    timeout: u64,
+    /// Number of times a page request timing out or failing with a server error is retried
+    #[arg(long, default_value_t = 1)]
+    retries: u8,
+    /// Pause before the first retry of a page in milliseconds, doubled on every next retry
+    #[arg(long, default_value_t = 1000)]
+    retry_backoff: u64,
+    /// Extract text of every stored page right away with the parse options below,
+    /// instead of in a separate parse run (boilerplate is only stripped by parse)
+    #[arg(long)]
+    parse_on_scrape: bool,
+    /// Only fetch pages which aren't stored yet, never stored ones whatever their sitemap lastmod
+    #[arg(long)]
+    only_new: bool,
+    /// Fetch the pages with this URL, or an URL glob like "https://example.com/blog/*", again even
+    /// if their sitemap lastmod is unchanged (repeatable)
+    #[arg(long = "force-url", value_name = "URL|GLOB")]
+    force_urls: Vec<String>,
+    /// What a completed scrape does with stored pages no longer listed or which failed to be
+    /// fetched: "delete" them (default), "keep" them, or "archive" them for compose --archived
+    #[arg(long, value_enum, default_value_t = UnlistedPages::Deleted)]
+    cleanup: UnlistedPages,
+    /// Store only a hash of page HTML instead of the HTML itself to save space,
+    /// stored pages can't be parsed again without scraping them again
+    #[arg(long, requires = "parse_on_scrape")]
+    no_store_html: bool,
+    /// HTTP Basic credentials of a protected site as user:pass, sent with sitemap and page
+    /// requests (or set LLAMAP_AUTH_BASIC)
+    #[arg(long, conflicts_with = "auth_bearer")]
+    auth_basic: Option<Secret>,
+    /// Bearer token of a protected site, sent with sitemap and page requests
+    /// (or set LLAMAP_AUTH_BEARER)
+    #[arg(long)]
+    auth_bearer: Option<Secret>,
+    #[command(flatten)]
+    extraction: ParseArgs,
+}
+
+/// How `scrape` summarizes pages while crawling
+#[derive(clap::Args)]
+struct PipelineArgs {
+    /// Summarize unsummarized pages with this LLM model URL as soon as they are parsed,
+    /// while the crawl goes on, instead of in a separate summarize run
+    #[arg(
+        long,
+        value_name = "MODEL",
+        requires = "parse_on_scrape",
+        conflicts_with_all = ["from_dir", "url_list", "crawl_depth"]
+    )]
+    summarize_while_scraping: Option<String>,
+    /// Rate limit of the model: requests per minute (default: no limit)
+    #[arg(long, requires = "summarize_while_scraping")]
+    rpm: Option<u32>,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// How `parse` extracts text from page HTML
+#[derive(clap::Args)]
+struct ParseArgs {
+    /// Text extraction method: "dom_smoothie" (default) or "fast_html2md"
+    #[arg(long, default_value = "dom_smoothie")]
+    text_by: TextBy,
+    /// CSS selector to limit the HTML subset from which content is extracted (optional)
+    #[arg(long, short)]
+    selector: Option<String>,
+    /// Keep image alt text as ![alt](src) and figure captions as italicized text
+    #[arg(long)]
+    images: bool,
+    /// Keep <pre> blocks as fenced code blocks with language hints taken from class names
+    #[arg(long)]
+    code: bool,
+    /// Convert simple tables to markdown tables instead of run-on text
+    #[arg(long)]
+    tables: bool,
+    /// dom_smoothie: minimal number of characters an article must have
+    #[arg(long)]
+    char_threshold: Option<usize>,
+    /// dom_smoothie: number of top candidates compared when looking for the article element
+    #[arg(long)]
+    n_top_candidates: Option<usize>,
+    /// dom_smoothie: article selection algorithm, "dom-smoothie" (default) or "readability"
+    #[arg(long, value_enum, default_value_t = CandidateSelect::DomSmoothie)]
+    candidate_select: CandidateSelect,
+    /// dom_smoothie: extracted text format, "markdown" (default), "formatted" or "raw"
+    #[arg(long, value_enum, default_value_t = TextFormat::Markdown)]
+    text_format: TextFormat,
+    /// Flag pages with less extracted characters as too short, so they aren't summarized
+    #[arg(long)]
+    min_text_chars: Option<usize>,
+    /// Strip text blocks (footers, CTAs) repeated verbatim in at least this many pages
+    #[arg(long)]
+    boilerplate_min_pages: Option<usize>,
+    /// Split pages with more extracted characters into sections at their headings with anchors,
+    /// which summarize processes one by one and compose --sections lists under the page
+    #[arg(long)]
+    split_sections: Option<usize>,
+    /// Number of pages extracted in parallel (default: number of CPUs)
+    #[arg(long, short = 'j')]
+    jobs: Option<usize>,
+    /// Stop at the first page failing to be extracted instead of recording it and continuing
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+/// Which pages `summarize` processes and how
+#[derive(clap::Args)]
+struct SummarizeArgs {
+    /// Path to the file with a prompt template, which can reference {url}, {text}, {title}, {lang}, {section}, {lastmod},
+    /// and {headline}, {published} and {author} of JSON-LD structured data
+    #[arg(long, short = 'p')]
+    prompt_file: Option<String>,
+    /// Target to summarize: "unsummarized", "all", an URL glob like "https://example.com/blog/*" or specify an URL
+    #[arg(long, short = 't', default_value = "unsummarized")]
+    target: SummarizeTarget,
+    /// Rate limit: requests per minute (default: no limit)
+    #[arg(long, short = 'r')]
+    rpm: Option<u32>,
+    /// Also summarize pages flagged by parse --min-text-chars as too short
+    #[arg(long)]
+    include_short: bool,
+    /// Sampling temperature, e.g. 0 for deterministic summaries (default: the model's)
+    #[arg(long)]
+    temperature: Option<f32>,
+    /// Nucleus sampling probability mass (default: the model's)
+    #[arg(long)]
+    top_p: Option<f32>,
+    /// Maximal number of tokens of a summary (default: the model's)
+    #[arg(long)]
+    max_tokens: Option<u32>,
+    /// Cut summaries at this sequence, can be repeated
+    #[arg(long)]
+    stop: Vec<String>,
+    /// Instead of summarizing, compare two prompt files on a sample of pages and print a markdown report
+    #[arg(long, num_args = 2, value_names = ["PROMPT_A", "PROMPT_B"], conflicts_with_all = ["prompt_file", "target"])]
+    compare: Vec<String>,
+    /// Number of randomly sampled pages the prompts are compared on
+    #[arg(long, default_value_t = 20)]
+    sample: usize,
+    /// File with regex rules rewriting the answers, one "pattern => replacement" or "pattern" to remove per line
+    #[arg(long)]
+    post_process_file: Option<String>,
+    /// Ask for JSON summaries with keywords and audience, retrying malformed answers
+    #[arg(long, conflicts_with = "compare")]
+    structured: bool,
+    /// Only report the pages, tokens, rate-limited time and cost the run would take, without calling the model
+    #[arg(long, conflicts_with = "compare")]
+    estimate: bool,
+    /// Continue after the last page summarized by an interrupted or killed run of the same target and filters
+    #[arg(long, conflicts_with = "compare")]
+    resume: bool,
+    /// Summarize at most this many pages, the most linked to by other pages first
+    #[arg(long, conflicts_with_all = ["compare", "resume"])]
+    max_pages: Option<usize>,
+    /// Write summaries in this language whatever the page language is, as an ISO 639-3 code (e.g. "deu")
+    /// or an English name (e.g. "German"), stored along with the summaries in the page language
+    #[arg(long, conflicts_with = "structured")]
+    summary_lang: Option<SummaryLanguage>,
+    /// Also summarize pages whose robots meta tag or X-Robots-Tag header asks not to index them
+    #[arg(long)]
+    include_noindex: bool,
+    /// Pages summarized with a prompt keeping their questions and answers: "auto" (default) for pages
+    /// declaring an FAQPage in their JSON-LD or with several question headings, "off", or a tag name
+    #[arg(long, default_value = "auto")]
+    faq: FaqPages,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// How `audit-summaries` judges summaries and which of them it lists
+#[derive(clap::Args)]
+struct AuditArgs {
+    /// Path to database file with summarized pages
+    db: String,
+    /// URL of the LLM model judging the summaries
+    #[arg(long)]
+    model: String,
+    /// Rate limit: requests per minute (default: no limit)
+    #[arg(long, short = 'r')]
+    rpm: Option<u32>,
+    /// Number of the worst summaries listed
+    #[arg(long, default_value_t = 20)]
+    worst: usize,
+    /// Only list the URLs of the worst summaries, e.g. to summarize them again with --urls-from -
+    #[arg(long)]
+    urls_only: bool,
+    #[command(flatten)]
+    filter: PageFilterArgs,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// Which pages `sample` picks and what it shows of them
+#[derive(clap::Args)]
+struct SampleArgs {
+    /// Path to database file to read pages from
+    db: String,
+    /// Path to the markdown file to write, "-" for stdout
+    #[arg(long, short = 'o', default_value = "-")]
+    output: String,
+    /// Number of sampled pages
+    #[arg(long, short = 'n', default_value_t = 25)]
+    size: usize,
+    /// Seed of the sample, the same seed picks the same pages again
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Show this besides the text excerpt of every page: "summary", "keywords" or "sections", can be repeated
+    #[arg(long = "with", value_delimiter = ',')]
+    fields: Vec<ReviewField>,
+    /// Number of characters of the text excerpts
+    #[arg(long, default_value_t = DEFAULT_EXCERPT_CHARS)]
+    excerpt_chars: usize,
+    #[command(flatten)]
+    filter: PageFilterArgs,
+}
+
+/// What `search` looks for and how many pages it lists
+#[derive(clap::Args)]
+struct SearchArgs {
+    /// Path to database file to search
+    db: String,
+    /// Words the pages must all contain, e.g. "sitemap index"
+    query: String,
+    /// Maximum number of listed pages, best matches first
+    #[arg(long, short = 'n', default_value_t = 10)]
+    limit: usize,
+    /// Read the query as SQLite FTS5 syntax, e.g. "sitemap OR robots", "index*" or "title:faq"
+    #[arg(long)]
+    fts: bool,
+    #[command(flatten)]
+    filter: PageFilterArgs,
+}
+
+/// Which model `ask` asks and which pages it answers from
+#[derive(clap::Args)]
+struct AskArgs {
+    /// Path to database file with the pages to answer from
+    db: String,
+    /// The question to answer
+    question: String,
+    /// URL of the LLM model answering the question
+    #[arg(long)]
+    model: String,
+    /// Number of the best matching pages the answer is based on
+    #[arg(long, short = 'k', default_value_t = DEFAULT_ASK_PAGES)]
+    pages: usize,
+    /// Number of characters of the text of every page given to the model
+    #[arg(long, default_value_t = DEFAULT_ASK_PAGE_CHARS)]
+    page_chars: usize,
+    #[command(flatten)]
+    filter: PageFilterArgs,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// What `compose` writes and in which order
+#[derive(clap::Args)]
+struct ComposeArgs {
+    /// Source to compose from: "text", "summary", or "best" (default)
+    #[arg(long, value_enum, default_value_t = ComposeSource::Best)]
+    source: ComposeSource,
+    /// Order of composed pages: "url" (default), "title", "lastmod", "priority" or "importance"
+    /// by internal links, ties broken by URL
+    #[arg(long, value_enum, default_value_t = PageSort::Url)]
+    sort: PageSort,
+    /// Keep the output under this many bytes, shortening or omitting the least important pages
+    #[arg(long)]
+    max_bytes: Option<usize>,
+    /// Compose at most this many pages, omitting the least important ones
+    #[arg(long)]
+    max_entries: Option<usize>,
+    /// Layout of the output file: "pages" (default) or "spec" for the llms.txt spec sections
+    #[arg(long, value_enum, default_value_t = ComposeFormat::Pages)]
+    format: ComposeFormat,
+    /// URL of the LLM model writing section overviews and site description
+    #[arg(long)]
+    model: Option<String>,
+    /// Write an LLM generated overview under each section of the spec format
+    #[arg(long, requires = "model")]
+    section_overviews: bool,
+    /// Write an LLM generated site description from the homepage and top-level pages under the spec format heading
+    #[arg(long, requires = "model")]
+    describe: bool,
+    /// List keywords of pages summarized with summarize --structured
+    #[arg(long)]
+    keywords: bool,
+    /// Note the sitemap lastmod date of pages as "Updated: YYYY-MM-DD"
+    #[arg(long)]
+    updated: bool,
+    /// Note the author and publication date declared by the JSON-LD data of articles as "By: ..." and "Published: ..."
+    #[arg(long)]
+    byline: bool,
+    /// Note the video duration and image captions video and image sitemaps declare as "Video: m:ss" and "Images: ..."
+    #[arg(long)]
+    media: bool,
+    /// List the sections parse --split-sections split pages into under them, with their summaries
+    #[arg(long)]
+    sections: bool,
+    /// List the pages archived by scrape --cleanup archive under an "Archived" section after the others
+    #[arg(long)]
+    archived: bool,
+    /// Replace email addresses, phone numbers and API keys in composed texts and summaries
+    /// with [email], [phone] and [secret]
+    #[arg(long)]
+    redact: bool,
+    /// File with regex rules scrubbing composed texts and summaries, in the format of summarize
+    /// --post-process-file, applied after the --redact ones
+    #[arg(long)]
+    redact_file: Option<String>,
+    /// Compose summaries written in this language by summarize --summary-lang instead of the page language ones
+    #[arg(long)]
+    summary_lang: Option<SummaryLanguage>,
+    /// Also compose pages whose robots meta tag or X-Robots-Tag header asks not to index them
+    #[arg(long)]
+    include_noindex: bool,
+    /// Regenerate only the region of an existing output file between <!-- llamap:begin --> and
+    /// <!-- llamap:end --> comments, keeping hand-written sections around it
+    #[arg(long)]
+    merge: bool,
+    /// Keep the replaced output file next to it, suffixed with the time it was replaced
+    #[arg(long)]
+    backup: bool,
+    /// Write the full text of every composed page to <DIR>/<slug>.md and link the entries to
+    /// these markdown files instead of the pages
+    #[arg(long, value_name = "DIR")]
+    markdown_dir: Option<String>,
+    /// URL the --markdown-dir directory is published at, entries link to the files relative
+    /// to the output file otherwise
+    #[arg(long, requires = "markdown_dir")]
+    markdown_base_url: Option<Url>,
+    /// How the --markdown-dir files are named: by the "path" of the page URL (default),
+    /// the page "title", or a "hash" of the URL which never changes
+    #[arg(long, value_enum, default_value_t = SlugStrategy::Path, requires = "markdown_dir")]
+    markdown_slug: SlugStrategy,
+    /// Front matter of the --markdown-dir files for static site generators like Hugo and Jekyll:
+    /// "none" (default) or "yaml" with the title, url, date, summary and tags of the page
+    #[arg(long, value_enum, default_value_t = FrontMatter::Omitted, requires = "markdown_dir")]
+    front_matter: FrontMatter,
+    #[command(flatten)]
+    provider: ProviderArgs,
+}
+
+/// Where the LLM provider is served when it isn't at the default address of its backend
+#[derive(clap::Args)]
+struct ProviderArgs {
+    /// Base URL of the provider API, e.g. "http://localhost:8000/" for a self-hosted OpenAI-compatible server
+    #[arg(long)]
+    api_base: Option<Url>,
+    /// Path the API endpoints are served under, relative to --api-base, e.g. "openai/v1/"
+    #[arg(long, requires = "api_base")]
+    api_path: Option<String>,
+    /// Extra provider option as key=value: "api_version", "deployment_id" or "timeout_seconds",
+    /// values may reference environment variables as ${NAME}
+    #[arg(long = "provider-option", value_parser = parse_key_value)]
+    provider_options: Vec<(String, String)>,
+    /// Environment variable holding the API key, instead of LLAMAP_MODEL_API_KEY or the
+    /// variable of the provider (OPENAI_API_KEY, ANTHROPIC_API_KEY, ...)
+    #[arg(long)]
+    api_key_env: Option<String>,
+}
+
+/// What `validate` checks besides the structure of the file
+#[derive(clap::Args)]
+struct ValidateArgs {
+    /// URL relative links are resolved against; without it relative links are reported
+    #[arg(long)]
+    base_url: Option<Url>,
+    /// Report the file if it takes more than this many bytes
+    #[arg(long)]
+    max_bytes: Option<usize>,
+    /// Report the file if it has more than this many links
+    #[arg(long)]
+    max_entries: Option<usize>,
+}
+
+/// Which stored pages `parse`, `summarize` and `compose` process
+#[derive(clap::Args)]
+struct PageFilterArgs {
+    /// Only process pages with ("true") or without ("false") a summary
+    #[arg(long)]
+    has_summary: Option<bool>,
+    /// Only process pages whose text contains this string
+    #[arg(long)]
+    text_contains: Option<String>,
+    /// Only process pages whose URL matches this glob, e.g. "https://example.com/docs/*"
+    #[arg(long)]
+    url_glob: Option<String>,
+    /// Only process pages listed one URL per line in this file ("-" for stdin),
+    /// e.g. the output of another tool
+    #[arg(long, value_name = "FILE")]
+    urls_from: Option<String>,
+    /// Only process pages modified at or after this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    lastmod_after: Option<DateTime<Utc>>,
+    /// Only process pages modified before this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    lastmod_before: Option<DateTime<Utc>>,
+    /// Only process pages stored at or after this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    added_after: Option<DateTime<Utc>>,
+    /// Only process pages tagged with this tag (see the tag command)
+    #[arg(long)]
+    tag: Option<String>,
+}
+
+impl TryFrom<PageFilterArgs> for PageFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(args: PageFilterArgs) -> Result<Self> {
+        let mut filter = PageFilter::new();
+        if let Some(has_summary) = args.has_summary {
+            filter = filter.has_summary(has_summary);
+        }
+        if let Some(needle) = args.text_contains {
+            filter = filter.text_contains(needle);
+        }
+        if let Some(glob) = args.url_glob {
+            filter = filter.url_glob(glob);
+        }
+        if let Some(list_path) = args.urls_from {
+            filter = filter.urls(read_url_list(&list_path)?);
+        }
+        filter = match (args.lastmod_after, args.lastmod_before) {
+            (Some(after), Some(before)) => filter.lastmod_range(after..before),
+            (Some(after), None) => filter.lastmod_range(after..),
+            (None, Some(before)) => filter.lastmod_range(..before),
+            (None, None) => filter,
+        };
+        if let Some(since) = args.added_after {
+            filter = filter.added_after(since);
+        }
+        if let Some(tag) = args.tag {
+            filter = filter.tagged(tag);
+        }
+        Ok(filter)
+    }
+}
+
+/// Parses a "key=value" pair.
+fn parse_key_value(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| format!("Invalid option {value}, expected key=value"))
+}
+
+/// Parses a "path=pages" crawl budget.
+fn parse_budget(value: &str) -> Result<(String, u32), String> {
+    let (path, pages) = parse_key_value(value)?;
+    let pages = pages
+        .parse()
+        .map_err(|_| format!("Invalid budget {value}, expected path=pages"))?;
+
+    Ok((path, pages))
+}
+
+/// Parses a date given either as YYYY-MM-DD (midnight UTC) or as an RFC 3339 timestamp.
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|moment| moment.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid date {value}: {e}"))
 }
 
 #[tokio::main]
@@ -108,104 +892,1260 @@ async fn main() -> Result<()> {
         })
         .init();
 
-    match cli.command {
+    run_command(cli.command).await
+}
+
+async fn run_command(command: Command) -> Result<()> {
+    match command {
         Command::Scrape {
+            source,
+            db,
+            settings,
+            pipeline,
+        } => handle_scrape_command(db, source, scrape_options(settings)?, pipeline).await,
+        Command::Fetch {
             db,
             url,
-            delay,
-            concurrency,
-        } => {
-            process_sitemap(
-                Url::parse(&url).map_err(|e| anyhow::anyhow!("Invalid sitemap url: {}", e))?,
-                &db,
-                delay,
-                concurrency,
-            )
-            .await
-        }
+            summarize,
+            settings,
+            provider,
+        } => handle_fetch_command(db, url, scrape_options(settings)?, summarize, provider).await,
         Command::Parse {
             db,
             target,
-            text_by,
-            selector,
-        } => handle_parse_command(db, target, text_by, selector).await,
+            trailing_slash,
+            extraction,
+            filter,
+        } => handle_parse_command(db, target, trailing_slash, extraction, filter.try_into()?).await,
         Command::Summarize {
             db,
             model,
-            prompt_file,
-            target,
-            rpm,
-        } => handle_summarize_command(db, model, prompt_file, target, rpm).await,
+            settings,
+            filter,
+        } => handle_summarize_command(db, model, settings, filter.try_into()?).await,
+        Command::AuditSummaries(settings) => handle_audit_summaries_command(settings).await,
+        Command::Sample(settings) => handle_sample_command(settings),
+        Command::Search(settings) => handle_search_command(settings),
+        Command::Ask(settings) => handle_ask_command(settings).await,
         Command::Compose {
             db,
             output_file,
-            source,
-        } => compose(&db, &output_file, source).await,
+            layout,
+            filter,
+        } => handle_compose_command(db, output_file, layout, filter.try_into()?).await,
+        Command::SitemapOut {
+            db,
+            output_file,
+            include_noindex,
+            filter,
+        } => handle_sitemap_out_command(db, output_file, include_noindex, filter.try_into()?),
+        Command::Tag {
+            db,
+            add,
+            remove,
+            pattern,
+        } => handle_tag_command(db, add, remove, pattern),
+        Command::Exclude {
+            db,
+            pattern,
+            remove,
+        } => handle_exclude_command(db, pattern, remove),
+        Command::Rm { db, url } => handle_rm_command(db, url),
+        Command::Validate { file, checks } => handle_validate_command(file, checks),
+        Command::Diff { old_file, new_file } => handle_diff_command(old_file, new_file),
+        Command::Init { project, provider } => handle_init_command(project, provider).await,
+        Command::Doctor(checks) => handle_doctor_command(checks).await,
+        Command::Run(settings) => handle_run_command(settings).await,
+        Command::Runs { db, limit } => handle_runs_command(db, limit),
+        Command::Status { db, by_stage } => handle_status_command(db, by_stage),
+        Command::Report { report } => handle_report_command(report).await,
+        Command::Db { command } => handle_db_command(command).await,
     }
 }
 
-async fn handle_parse_command(
+async fn handle_report_command(report: ReportCommand) -> Result<()> {
+    match report {
+        ReportCommand::BrokenLinks { db } => handle_broken_links_command(db),
+        ReportCommand::Compliance { db } => handle_compliance_command(db).await,
+        ReportCommand::SitemapDiff { db, run } => handle_sitemap_diff_command(db, run),
+    }
+}
+
+async fn handle_db_command(command: DbCommand) -> Result<()> {
+    match command {
+        DbCommand::Check { db, repair } => handle_db_check_command(db, repair),
+        DbCommand::ResetLastmod { db, filter } => {
+            handle_reset_lastmod_command(db, filter.try_into()?)
+        }
+        DbCommand::Cleanup(args) => handle_db_cleanup_command(args).await,
+    }
+}
+
+async fn handle_scrape_command(
     db: String,
-    target: ParseTarget,
-    text_by: TextBy,
-    selector_query: Option<String>,
+    source: ScrapeSourceArgs,
+    options: ScrapeOptions,
+    pipeline: PipelineArgs,
+) -> Result<()> {
+    if let (Some(dir), Some(base_url)) = (source.from_dir, source.base_url) {
+        let base_url =
+            Url::parse(&base_url).map_err(|e| anyhow::anyhow!("Invalid base URL: {}", e))?;
+        return Ok(process_directory(Path::new(&dir), &base_url, &db, options).await?);
+    }
+
+    if let Some(list_path) = source.url_list {
+        log_scrape_report(&process_url_list(&list_path, &db, options).await?);
+        return Ok(());
+    }
+
+    if let (Some(url), Some(depth)) = (&source.url, source.crawl_depth) {
+        let start_url = Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid start URL: {}", e))?;
+        process_links(&start_url, depth.into(), &db, options).await?;
+        return Ok(());
+    }
+
+    let sitemap_urls = source
+        .url
+        .into_iter()
+        .chain(source.sitemap)
+        .map(|url| resolve_sitemap_location(&url))
+        .collect::<Result<Vec<_>, _>>()?;
+    if sitemap_urls.is_empty() {
+        anyhow::bail!("Specify a sitemap URL, --url-list or --from-dir with --base-url");
+    }
+    let report = match pipeline.summarize_while_scraping {
+        Some(model_name) => {
+            let model = summary_model(&model_name, &pipeline.provider).await?;
+            let summarize_options = SummarizeOptions {
+                model_name,
+                rpm: pipeline.rpm,
+                ..SummarizeOptions::default()
+            };
+            summarize_while_scraping(sitemap_urls, &db, options, model, summarize_options).await?
+        }
+        None => process_sitemaps(sitemap_urls, &db, options).await?,
+    };
+    log_scrape_report(&report);
+
+    Ok(())
+}
+
+/// Logs the page counts of a scrape run
+fn log_scrape_report(report: &ScrapeReport) {
+    info!(
+        "Fetched {} pages, skipped {} unmodified, {} by extension and {} excluded, {} failed, {} dropped unstored, removed {}; made {} requests downloading {} bytes",
+        report.fetched,
+        report.skipped_unmodified,
+        report.skipped_filtered,
+        report.skipped_excluded,
+        report.failed.len(),
+        report.dropped,
+        report.removed,
+        report.requests,
+        report.bytes_downloaded
+    );
+}
+
+/// Fetches a single page into the database, then summarizes it with the given model if any.
+async fn handle_fetch_command(
+    db: String,
+    url: Url,
+    options: ScrapeOptions,
+    summary_model_name: Option<String>,
+    provider: ProviderArgs,
 ) -> Result<()> {
-    let selector = match selector_query {
+    let stored_url = normalize_url(&url, &options.trailing_slash);
+    let report = process_page_url(&url, &db, options).await?;
+    log_scrape_report(&report);
+    if let Some((failed_url, reason)) = report.failed.first() {
+        anyhow::bail!("Unable to fetch {failed_url}: {reason}");
+    }
+
+    let Some(model_name) = summary_model_name.filter(|_| report.fetched > 0) else {
+        return Ok(());
+    };
+    let model = summary_model(&model_name, &provider).await?;
+    let options = SummarizeOptions {
+        model_name,
+        target: SummarizeTarget::Page {
+            url: stored_url.to_string(),
+        },
+        ..SummarizeOptions::default()
+    };
+    Ok(summarize(&db, model, options).await?)
+}
+
+/// Builds the scrape options from the command line settings.
+fn scrape_options(settings: ScrapeArgs) -> Result<ScrapeOptions> {
+    Ok(ScrapeOptions {
+        delay: settings.delay,
+        concurrency: settings.concurrency,
+        max_pages: settings.max_pages,
+        max_bytes: settings.max_bytes,
+        trailing_slash: settings.trailing_slash,
+        content_filter: ContentFilter {
+            skip_extensions: settings.skip_extensions,
+            skip_content_types: settings.skip_content_types,
+        },
+        spider: SpiderRules {
+            blacklist: settings.spider_blacklist,
+            whitelist: settings.spider_whitelist,
+            budget: settings.spider_budget.into_iter().collect(),
+        },
+        retry: RetryPolicy {
+            timeout: (settings.timeout > 0).then_some(settings.timeout),
+            retries: settings.retries,
+            backoff: settings.retry_backoff,
+        },
+        stored_pages: if settings.only_new {
+            StoredPages::Kept
+        } else {
+            StoredPages::RefetchModified
+        },
+        forced_urls: settings.force_urls,
+        unlisted_pages: settings.cleanup,
+        store_html: !settings.no_store_html,
+        auth: scrape_auth(settings.auth_basic, settings.auth_bearer)?,
+        parse: if settings.parse_on_scrape {
+            Some(parse_options(settings.extraction, PageFilter::new())?)
+        } else {
+            None
+        },
+        cancel: None,
+        progress: Progress::default(),
+        parsed_pages: None,
+    })
+}
+
+/// Reads the credentials of a protected site from the command line,
+/// or from the environment if none are given there.
+fn scrape_auth(basic: Option<Secret>, bearer: Option<Secret>) -> Result<Option<Auth>> {
+    let (basic, bearer) = if basic.is_none() && bearer.is_none() {
+        (
+            std::env::var(AUTH_BASIC_ENV_NAME).ok().map(Secret::from),
+            std::env::var(AUTH_BEARER_ENV_NAME).ok().map(Secret::from),
+        )
+    } else {
+        (basic, bearer)
+    };
+
+    match (basic, bearer) {
+        (Some(credentials), _) => Ok(Some(Auth::basic(credentials.expose())?)),
+        (None, token) => Ok(token.map(Auth::Bearer)),
+    }
+}
+
+/// Builds the parse options from the command line settings.
+fn parse_options(extraction: ParseArgs, filter: PageFilter) -> Result<ParseOptions> {
+    let selector = match extraction.selector {
         Some(selector_query) => Some(
             ScraperSelector::parse(&selector_query)
                 .map_err(|e| anyhow::anyhow!("Invalid CSS selector: {}", e))?,
         ),
         None => None,
     };
-    parse_db_html(&db, target, text_by, &selector).await
+    Ok(ParseOptions {
+        text_by: extraction.text_by,
+        selector,
+        images: extraction.images,
+        code: extraction.code,
+        tables: extraction.tables,
+        readability: ReadabilityOptions {
+            char_threshold: extraction.char_threshold,
+            n_top_candidates: extraction.n_top_candidates,
+            candidate_select: extraction.candidate_select,
+            text_format: extraction.text_format,
+        },
+        min_text_chars: extraction.min_text_chars,
+        boilerplate_min_pages: extraction.boilerplate_min_pages,
+        split_sections_chars: extraction.split_sections,
+        jobs: extraction.jobs.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        }),
+        on_page_error: if extraction.fail_fast {
+            OnPageError::FailFast
+        } else {
+            OnPageError::Continue
+        },
+        filter,
+        trailing_slash: TrailingSlash::default(),
+        cancel: None,
+        progress: Progress::default(),
+    })
+}
+
+async fn handle_parse_command(
+    db: String,
+    target: ParseTarget,
+    trailing_slash: TrailingSlash,
+    extraction: ParseArgs,
+    filter: PageFilter,
+) -> Result<()> {
+    let options = ParseOptions {
+        trailing_slash,
+        ..parse_options(extraction, filter)?
+    };
+    parse_db_html(&db, target, &options).await?;
+
+    Ok(())
 }
 
 async fn handle_summarize_command(
     db: String,
     model: String,
-    prompt_file: Option<String>,
-    target: SummarizeTarget,
-    rpm: Option<u32>,
+    settings: SummarizeArgs,
+    filter: PageFilter,
 ) -> Result<()> {
-    let model_url = Url::parse(&model).map_err(|e| anyhow::anyhow!("Invalid model URL: {}", e))?;
-    let llm_builder = LLMBuilder::new()
+    let prompt_template = match settings.prompt_file {
+        Some(file) => Some(read_prompt_file(&file)?),
+        None => None,
+    };
+    let post_process = match settings.post_process_file {
+        Some(file) => parse_rules(
+            &fs::read_to_string(&file)
+                .context(format!("Failed to read post-processing rules file: {file}"))?,
+        )?,
+        None => Vec::new(),
+    };
+    let options = SummarizeOptions {
+        model_name: model,
+        prompt_template,
+        target: settings.target,
+        rpm: settings.rpm,
+        rate_limiter: None,
+        include_short: settings.include_short,
+        filter,
+        sampling: Sampling {
+            temperature: settings.temperature,
+            top_p: settings.top_p,
+            max_tokens: settings.max_tokens,
+            stop: settings.stop,
+        },
+        structured: settings.structured,
+        post_process,
+        resume: settings.resume,
+        max_pages: settings.max_pages,
+        language: settings.summary_lang,
+        cancel: None,
+        noindex: noindex_pages(settings.include_noindex),
+        faq: settings.faq,
+        progress: Progress::default(),
+    };
+
+    if settings.estimate {
+        println!("{}", estimate_summarize(&db, &options)?);
+        return Ok(());
+    }
+
+    let model = summary_model(&options.model_name, &settings.provider).await?;
+    if settings.compare.is_empty() {
+        return Ok(summarize(&db, model, options).await?);
+    }
+    let SummaryModel::Llm(llm_builder) = model else {
+        anyhow::bail!("Prompts can only be compared with an LLM, local summarizers ignore them");
+    };
+
+    let comparison = PromptComparison {
+        prompts: settings
+            .compare
+            .into_iter()
+            .map(|file| read_prompt_file(&file).map(|template| (file, template)))
+            .collect::<Result<_>>()?,
+        sample: settings.sample,
+    };
+    let report = compare_prompts(&db, llm_builder, &options, &comparison).await?;
+    println!("{report}");
+
+    Ok(())
+}
+
+async fn handle_audit_summaries_command(settings: AuditArgs) -> Result<()> {
+    let llm_builder = prepare_llm(&settings.model, &settings.provider).await?;
+    let options = AuditOptions {
+        model_name: settings.model,
+        rpm: settings.rpm,
+        rate_limiter: None,
+        filter: settings.filter.try_into()?,
+    };
+    audit_summaries(&settings.db, llm_builder, &options).await?;
+
+    for audit in Storage::new(&settings.db)?.list_worst_audits(settings.worst)? {
+        if settings.urls_only {
+            println!("{}", audit.url);
+            continue;
+        }
+        let scores = audit.scores;
+        println!(
+            "{}/5 {} (faithfulness {}, length {}, language {})",
+            scores.lowest(),
+            audit.url,
+            scores.faithfulness,
+            scores.length,
+            scores.language
+        );
+        if !audit.comment.is_empty() {
+            println!("  {}", audit.comment);
+        }
+    }
+    Ok(())
+}
+
+fn handle_sample_command(settings: SampleArgs) -> Result<()> {
+    let options = SampleOptions {
+        size: settings.size,
+        seed: settings.seed,
+        fields: settings.fields,
+        excerpt_chars: settings.excerpt_chars,
+        filter: settings.filter.try_into()?,
+    };
+    write_review_bundle(&settings.db, &settings.output, &options)?;
+    Ok(())
+}
+
+fn handle_search_command(settings: SearchArgs) -> Result<()> {
+    let query = if settings.fts {
+        settings.query
+    } else {
+        plain_search_query(&settings.query)
+    };
+    let hits = Storage::new(&settings.db)?.search_pages(
+        &query,
+        &settings.filter.try_into()?,
+        settings.limit,
+    )?;
+    if hits.is_empty() {
+        println!("No pages match {query}");
+    }
+    for hit in hits {
+        println!("{}", hit.title.as_deref().unwrap_or(&hit.url));
+        println!("  {}", hit.url);
+        println!(
+            "  {}",
+            hit.snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+    }
+    Ok(())
+}
+
+async fn handle_ask_command(settings: AskArgs) -> Result<()> {
+    let llm_builder = prepare_llm(&settings.model, &settings.provider).await?;
+    let options = AskOptions {
+        pages: settings.pages,
+        page_chars: settings.page_chars,
+        filter: settings.filter.try_into()?,
+    };
+    let Some(answer) = ask(&settings.db, llm_builder, &settings.question, &options).await? else {
+        println!("No stored page matches the question");
+        return Ok(());
+    };
+
+    println!("{}", answer.text);
+    if !answer.sources.is_empty() {
+        println!("\nSources:");
+        for url in answer.sources {
+            println!("- {url}");
+        }
+    }
+    Ok(())
+}
+
+/// Creates a local summarizer for its model name, or an LLM for a model URL.
+async fn summary_model(model: &str, provider: &ProviderArgs) -> Result<SummaryModel> {
+    Ok(match SummaryModel::local(model)? {
+        Some(local_model) => local_model,
+        None => SummaryModel::Llm(prepare_llm(model, provider).await?),
+    })
+}
+
+fn read_prompt_file(file: &str) -> Result<String> {
+    fs::read_to_string(file).context(format!("Failed to read prompt file: {file}"))
+}
+
+/// Creates the LLM builder for a model URL like "ollama://8b@qwen3", or "ollama://qwen3:8b@localhost:11434"
+/// for a model of the Ollama server at the given address, with the API key from the environment if it is set
+/// and the provider settings. Checks an Ollama server has the model before it is used.
+async fn prepare_llm(model: &str, provider: &ProviderArgs) -> Result<LLMBuilder> {
+    let model_url = Url::parse(model).map_err(|e| anyhow::anyhow!("Invalid model URL: {}", e))?;
+    let model_name = model_name(&model_url)?;
+    let base_url = match provider_base_url(provider)? {
+        Some(base_url) => Some(base_url),
+        None => ollama::server_url(&model_url)?,
+    };
+
+    let llm_builder = build_llm(&model_url, &model_name, base_url.as_ref(), provider)?;
+
+    if model_url.scheme() == "ollama" {
+        let server_url = match base_url {
+            Some(base_url) => base_url,
+            None => Url::parse(DEFAULT_OLLAMA_URL)?,
+        };
+        ensure_model_available(&server_url, &model_name).await?;
+    }
+
+    Ok(llm_builder)
+}
+
+/// Creates the LLM builder for the model at the given provider API address.
+fn build_llm(
+    model_url: &Url,
+    model_name: &str,
+    base_url: Option<&Url>,
+    provider: &ProviderArgs,
+) -> Result<LLMBuilder> {
+    let mut llm_builder = LLMBuilder::new()
         .backend(
             LLMBackend::from_str(model_url.scheme())
                 .map_err(|e| anyhow::anyhow!("Invalid LLM backend: {}", e))?,
         )
-        .model(
-            [
-                model_url
-                    .host_str()
-                    .context("Specify model name as host URL.")?,
-                model_url.username(),
-            ]
-            .iter()
-            .filter(|x| !x.is_empty())
-            .cloned()
-            .collect::<Vec<_>>()
-            .join(":"),
-        );
+        .model(model_name);
+
+    llm_builder = with_api_key(
+        llm_builder,
+        model_url.scheme(),
+        base_url.is_some(),
+        provider,
+    )?;
+
+    if let Some(base_url) = base_url {
+        info!("Using LLM provider API at {base_url}");
+        // Ollama appends its endpoint paths to the base URL, while other backends resolve them against it
+        llm_builder = if model_url.scheme() == "ollama" {
+            llm_builder.base_url(base_url.as_str().trim_end_matches('/'))
+        } else {
+            llm_builder.base_url(base_url.as_str())
+        };
+    }
+
+    for (key, value) in &provider.provider_options {
+        let value = interpolate_env(value)?;
+        llm_builder = match key.as_str() {
+            "api_version" => llm_builder.api_version(&value),
+            "deployment_id" => llm_builder.deployment_id(&value),
+            "timeout_seconds" => llm_builder.timeout_seconds(
+                value
+                    .parse()
+                    .context(format!("Invalid timeout_seconds: {value}"))?,
+            ),
+            _ => anyhow::bail!("Unknown provider option: {key}"),
+        };
+    }
+
+    Ok(llm_builder)
+}
+
+/// Returns the model name of a model URL, e.g. "qwen3:8b" for both
+/// "ollama://8b@qwen3" and "ollama://qwen3:8b@localhost:11434".
+fn model_name(model_url: &Url) -> Result<String> {
+    let parts = if ollama::is_server_model_url(model_url) {
+        [
+            model_url.username(),
+            model_url.password().unwrap_or_default(),
+        ]
+    } else {
+        [
+            model_url
+                .host_str()
+                .context("Specify model name as host URL.")?,
+            model_url.username(),
+        ]
+    };
+
+    Ok(parts
+        .iter()
+        .filter(|x| !x.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
+/// Returns the provider API address given by --api-base and --api-path, if any.
+/// Sets the API key of the backend found in the environment, see [`find_api_key`].
+/// Fails before the run starts if a hosted provider needs a key and none is set;
+/// self-hosted servers at `--api-base` may do without one. The key itself is never logged.
+fn with_api_key(
+    llm_builder: LLMBuilder,
+    backend: &str,
+    self_hosted: bool,
+    provider: &ProviderArgs,
+) -> Result<LLMBuilder> {
+    match find_api_key(backend, provider.api_key_env.as_deref())? {
+        Some((env_name, key)) => {
+            info!("Using the API key set in {env_name}");
+            Ok(llm_builder.api_key(key.expose()))
+        }
+        None => match provider_api_key_env_name(backend).filter(|_| !self_hosted) {
+            Some(env_name) => anyhow::bail!(
+                "The {backend} API needs a key, set {env_name} or {MODEL_API_KEY_ENV_NAME}, or name its variable with --api-key-env"
+            ),
+            None => Ok(llm_builder),
+        },
+    }
+}
+
+fn provider_base_url(provider: &ProviderArgs) -> Result<Option<Url>> {
+    let Some(api_base) = &provider.api_base else {
+        return Ok(None);
+    };
+
+    let mut base_url = api_base.clone();
+    if !base_url.path().ends_with('/') {
+        base_url.set_path(&format!("{}/", base_url.path()));
+    }
+    if let Some(api_path) = &provider.api_path {
+        base_url = base_url
+            .join(&format!("{}/", api_path.trim_matches('/')))
+            .map_err(|e| anyhow::anyhow!("Invalid API path: {}", e))?;
+    }
+
+    Ok(Some(base_url))
+}
+
+/// Returns whether noindex pages are summarized or composed given the `--include-noindex` flag.
+fn noindex_pages(include_noindex: bool) -> NoindexPages {
+    if include_noindex {
+        NoindexPages::Include
+    } else {
+        NoindexPages::Exclude
+    }
+}
+
+async fn handle_compose_command(
+    db: String,
+    output_file: String,
+    layout: ComposeArgs,
+    filter: PageFilter,
+) -> Result<()> {
+    let options = ComposeOptions {
+        source: layout.source,
+        filter,
+        sort: layout.sort,
+        max_bytes: layout.max_bytes,
+        max_entries: layout.max_entries,
+        format: layout.format,
+        existing_output: if layout.merge {
+            ExistingOutput::Merge
+        } else {
+            ExistingOutput::Overwrite
+        },
+        backup: if layout.backup {
+            OutputBackup::Kept
+        } else {
+            OutputBackup::Discarded
+        },
+        markdown_export: layout.markdown_dir.map(|dir| MarkdownExport {
+            dir,
+            base_url: layout.markdown_base_url,
+            slugger: layout.markdown_slug.slugger(),
+            front_matter: layout.front_matter,
+        }),
+        section_overviews: layout.section_overviews,
+        describe: layout.describe,
+        keywords: layout.keywords,
+        updated: if layout.updated {
+            UpdatedDate::Shown
+        } else {
+            UpdatedDate::Hidden
+        },
+        byline: if layout.byline {
+            Byline::Shown
+        } else {
+            Byline::Hidden
+        },
+        media: if layout.media {
+            MediaNotes::Shown
+        } else {
+            MediaNotes::Hidden
+        },
+        sections: if layout.sections {
+            SectionLinks::Listed
+        } else {
+            SectionLinks::Hidden
+        },
+        archived: if layout.archived {
+            ArchivedPages::Listed
+        } else {
+            ArchivedPages::Omitted
+        },
+        language: layout.summary_lang,
+        noindex: noindex_pages(layout.include_noindex),
+        redaction: redaction(layout.redact, layout.redact_file.as_deref())?,
+        progress: Progress::default(),
+    };
+    let llm_builder = match &layout.model {
+        Some(model) => Some(prepare_llm(model, &layout.provider).await?),
+        None => None,
+    };
+
+    compose(&db, &output_file, options, llm_builder).await?;
+
+    Ok(())
+}
+
+/// Builds the redaction of composed pages from the built-in rules if asked and a rules file.
+fn redaction(builtin: bool, rules_file: Option<&str>) -> Result<Redaction> {
+    let mut redaction = Redaction::default();
+    if builtin {
+        redaction.scrubbers.push(Arc::new(RegexScrubber::builtin()));
+    }
+    if let Some(file) = rules_file {
+        let rules = parse_rules(
+            &fs::read_to_string(file)
+                .context(format!("Failed to read redaction rules file: {file}"))?,
+        )?;
+        redaction
+            .scrubbers
+            .push(Arc::new(RegexScrubber::new(rules)));
+    }
+    Ok(redaction)
+}
+
+fn handle_sitemap_out_command(
+    db: String,
+    output_file: String,
+    include_noindex: bool,
+    filter: PageFilter,
+) -> Result<()> {
+    write_sitemap(&db, &output_file, filter, noindex_pages(include_noindex))?;
+    Ok(())
+}
+
+fn handle_tag_command(
+    db: String,
+    add: Option<String>,
+    remove: Option<String>,
+    pattern: String,
+) -> Result<()> {
+    let storage = Storage::new(&db)?;
+
+    if let Some(tag) = add {
+        let tagged = storage.tag_pages(&tag, &pattern)?;
+        info!("Tagged {tagged} pages matching {pattern} with {tag}");
+    } else if let Some(tag) = remove {
+        let untagged = storage.untag_pages(&tag, &pattern)?;
+        info!("Removed {tag} from {untagged} pages matching {pattern}");
+    } else {
+        for (tag, pages) in storage.list_tags()? {
+            println!("{tag} | {pages} pages");
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_exclude_command(
+    db: String,
+    pattern: Option<String>,
+    remove: Option<String>,
+) -> Result<()> {
+    let storage = Storage::new(&db)?;
+
+    if let Some(pattern) = pattern {
+        if storage.add_exclusion(&pattern)? {
+            info!("Excluded pages matching {pattern} from scrape and compose");
+        } else {
+            warn!("Pages matching {pattern} are already excluded");
+        }
+    } else if let Some(pattern) = remove {
+        if storage.remove_exclusion(&pattern)? {
+            info!("Removed the exclusion of pages matching {pattern}");
+        } else {
+            warn!("No exclusion of pages matching {pattern}");
+        }
+    } else {
+        for pattern in storage.list_exclusions()? {
+            println!("{pattern}");
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_rm_command(db: String, url: String) -> Result<()> {
+    let storage = Storage::new(&db)?;
+    if is_stdio(&url) {
+        let urls = read_url_list(&url)?;
+        for url in &urls {
+            if storage.get_page(url)?.is_none() {
+                warn!("Page not found: {url}");
+                continue;
+            }
+            storage.remove_page(url)?;
+            info!("Removed {url}");
+        }
+        return Ok(());
+    }
+
+    if storage.get_page(&url)?.is_none() {
+        anyhow::bail!("Page not found: {url}");
+    }
+
+    storage.remove_page(&url)?;
+    info!("Removed {url}");
+    Ok(())
+}
+
+fn handle_validate_command(file: String, checks: ValidateArgs) -> Result<()> {
+    let content = read_input(&file)?;
+    let options = ValidateOptions {
+        base_url: checks.base_url,
+        max_bytes: checks.max_bytes,
+        max_entries: checks.max_entries,
+    };
+
+    let findings = validate(&content, &options);
+    for finding in &findings {
+        println!("{file}: {finding}");
+    }
+    if !findings.is_empty() {
+        anyhow::bail!("{file} has {} findings", findings.len());
+    }
+
+    info!("{file} is valid");
+    Ok(())
+}
 
-    let llm_builder = match std::env::var(MODEL_API_KEY_ENV_NAME) {
-        Ok(model_key) => {
-            info!("API KEY is provided {model_key}");
-            llm_builder.api_key(model_key)
+fn handle_diff_command(old_file: String, new_file: String) -> Result<()> {
+    let old_content =
+        fs::read_to_string(&old_file).context(format!("Failed to read file: {old_file}"))?;
+    let new_content =
+        fs::read_to_string(&new_file).context(format!("Failed to read file: {new_file}"))?;
+
+    let diff = diff_entries(&parse_entries(&old_content), &parse_entries(&new_content));
+    for url in &diff.added {
+        println!("+ {url}");
+    }
+    for url in &diff.removed {
+        println!("- {url}");
+    }
+    for url in &diff.changed {
+        println!("~ {url}");
+    }
+    println!(
+        "{} added, {} removed, {} changed, {} unchanged",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.unchanged
+    );
+
+    Ok(())
+}
+
+/// Runs the named site of a project, or all its sites concurrently with `--all`.
+async fn handle_run_command(settings: RunArgs) -> Result<()> {
+    let project = Project::load(&settings.project)?;
+    let sites: Vec<Site> = match settings.site {
+        Some(name) => vec![project.site(&name)?.clone()],
+        None => project.sites.clone(),
+    };
+
+    // Runs share this thread, as summary models can't be sent across threads
+    let local = tokio::task::LocalSet::new();
+    let mut runs = Vec::with_capacity(sites.len());
+    let summarize_options = project.summarize_options();
+    for site in sites.iter().cloned() {
+        let summary = match summarize_options.clone() {
+            Some(options) => Some((
+                summary_model(&options.model_name, &settings.provider).await?,
+                options,
+            )),
+            None => None,
+        };
+        let name = site.name.clone();
+        runs.push((
+            name,
+            local.spawn_local(async move { site.run(summary).await }),
+        ));
+    }
+
+    let failed = local
+        .run_until(async move {
+            let mut failed = 0;
+            for (name, run) in runs {
+                match run.await {
+                    Ok(Ok(())) => info!("Site {name} is composed"),
+                    Ok(Err(error)) => {
+                        warn!("Site {name} failed: {error:#}");
+                        failed += 1;
+                    }
+                    Err(join_error) => {
+                        warn!("Site {name} failed: {join_error}");
+                        failed += 1;
+                    }
+                }
+            }
+            failed
+        })
+        .await;
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} sites failed", sites.len());
+    }
+
+    Ok(())
+}
+
+async fn handle_doctor_command(checks: DoctorArgs) -> Result<()> {
+    let mut results = vec![check_database(&checks.db)];
+    if let Some(sitemap) = &checks.sitemap {
+        let auth = scrape_auth(None, None)?;
+        results.push(check_sitemap(sitemap, auth.as_ref()).await);
+    }
+    if let Some(model) = &checks.model {
+        results.push(match summary_model(model, &checks.provider).await {
+            Ok(summary_model) => check_model(model, summary_model).await,
+            Err(error) => Check::failed(format!("model {model}"), format!("{error:#}")),
+        });
+    }
+    if let Some(prompt_file) = &checks.prompt_file {
+        results.push(match read_prompt_file(prompt_file) {
+            Ok(template) => check_prompt_template(prompt_file, &template),
+            Err(error) => Check::failed(
+                format!("prompt template {prompt_file}"),
+                format!("{error:#}"),
+            ),
+        });
+    }
+
+    for check in &results {
+        println!("{check}");
+    }
+    let failed = results.iter().filter(|check| !check.is_passed()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} checks failed", results.len());
+    }
+    Ok(())
+}
+
+async fn handle_init_command(project_path: String, provider: ProviderArgs) -> Result<()> {
+    if Path::new(&project_path).exists() && !confirm(&format!("Replace {project_path}?"))? {
+        anyhow::bail!("Kept the existing project {project_path}");
+    }
+
+    let site = ask_site().await?;
+    let model = ask_model(&provider).await?;
+    let rpm = match model {
+        Some(_) => ask_number::<u32>("Requests per minute of the model, 0 for no limit", 0)?,
+        None => 0,
+    };
+    let project = Project {
+        model,
+        rpm: (rpm > 0).then_some(rpm),
+        sites: vec![site],
+    };
+    fs::write(&project_path, format!("{}\n", project.to_json()))
+        .context(format!("Failed to write project: {project_path}"))?;
+
+    let name = project.sites.first().map(|site| site.name.as_str());
+    println!(
+        "Wrote {project_path}, run it with: llamap run --project {project_path} {}",
+        name.unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// Asks for the URL of the site and proposes its sitemap and crawl settings.
+async fn ask_site() -> Result<Site> {
+    let site_url = loop {
+        let answer = ask("Site URL, e.g. https://example.com", None)?;
+        let answer = if answer.contains("://") {
+            answer
+        } else {
+            format!("https://{answer}")
+        };
+        match Url::parse(&answer) {
+            Ok(url) if url.host_str().is_some() => break url,
+            _ => println!("Invalid URL {answer}"),
+        }
+    };
+
+    println!("Looking for the sitemap of {site_url}...");
+    let discovery = discover_site(&site_url).await?;
+    for sitemap in &discovery.sitemaps {
+        println!("Found the sitemap {sitemap}");
+    }
+    if let Some(crawl_delay) = discovery.crawl_delay {
+        println!("robots.txt asks for {crawl_delay:?} between requests");
+    }
+    let default_sitemap = match discovery.sitemaps.first() {
+        Some(sitemap) => sitemap.clone(),
+        None => site_url.join("/sitemap.xml")?,
+    };
+    let sitemap = resolve_sitemap_location(&ask("Sitemap", Some(default_sitemap.as_str()))?)?;
+    let name = ask("Site name", site_url.host_str())?;
+
+    Ok(Site {
+        db: format!("{name}.sqlite"),
+        output: format!("{name}.llms.txt"),
+        delay: Some(ask_number(
+            "Delay between requests in milliseconds",
+            discovery.proposed_delay(),
+        )?),
+        concurrency: Some(ask_number(
+            "Concurrent requests",
+            discovery.proposed_concurrency(),
+        )?),
+        sitemap,
+        name,
+    })
+}
+
+/// Asks for the model summarizing pages and checks that it answers, None for no summaries.
+async fn ask_model(provider: &ProviderArgs) -> Result<Option<String>> {
+    loop {
+        let model = ask(
+            "Model URL summarizing the pages, e.g. ollama://8b@qwen3 (empty for no summaries)",
+            Some(""),
+        )?;
+        if model.is_empty() {
+            return Ok(None);
+        }
+
+        println!("Checking {model}...");
+        let checked = match summary_model(&model, provider).await {
+            Ok(summary_model) => summary_model.check().await.map_err(Into::into),
+            Err(error) => Err(error),
+        };
+        match checked {
+            Ok(()) => {
+                println!("{model} answered");
+                return Ok(Some(model));
+            }
+            Err(error) => {
+                println!("{model} failed: {error:#}");
+                if confirm("Keep it anyway?")? {
+                    return Ok(Some(model));
+                }
+            }
+        }
+    }
+}
+
+/// Asks a question and returns the answer read from stdin, or the default one if empty.
+fn ask(question: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) if !default.is_empty() => print!("{question} [{default}]: "),
+        _ => print!("{question}: "),
+    }
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer)? == 0 {
+        anyhow::bail!("No answer to: {question}");
+    }
+    match (answer.trim(), default) {
+        ("", Some(default)) => Ok(default.to_string()),
+        (answer, _) => Ok(answer.to_string()),
+    }
+}
+
+/// Asks for a number until a valid one is given.
+fn ask_number<T: FromStr + std::fmt::Display>(question: &str, default: T) -> Result<T> {
+    loop {
+        let answer = ask(question, Some(&default.to_string()))?;
+        match answer.parse() {
+            Ok(number) => return Ok(number),
+            Err(_) => println!("Invalid number {answer}"),
         }
-        Err(err) => {
-            info!("{err} while providing api key");
-            llm_builder
+    }
+}
+
+/// Asks a yes or no question, no by default.
+fn confirm(question: &str) -> Result<bool> {
+    Ok(ask(&format!("{question} (y/N)"), Some("n"))?
+        .to_lowercase()
+        .starts_with('y'))
+}
+
+fn handle_db_check_command(db: String, repair: bool) -> Result<()> {
+    let storage = Storage::new(&db)?;
+    let issues = storage.check_rows()?;
+    for issue in &issues {
+        let problems: Vec<String> = issue.problems.iter().map(ToString::to_string).collect();
+        println!("{}: {}", issue.url, problems.join(", "));
+    }
+
+    if repair {
+        let report = storage.repair_rows()?;
+        println!(
+            "Fixed {} rows, quarantined {} rows in quarantined_pages",
+            report.fixed, report.quarantined
+        );
+    } else if !issues.is_empty() {
+        anyhow::bail!(
+            "{} rows have problems, repair them with --repair",
+            issues.len()
+        );
+    }
+    Ok(())
+}
+
+fn handle_reset_lastmod_command(db: String, filter: PageFilter) -> Result<()> {
+    let reset = Storage::new(&db)?.reset_lastmod(&filter)?;
+    println!("Forgot the lastmod of {reset} pages, the next scrape fetches them again");
+    Ok(())
+}
+
+async fn handle_db_cleanup_command(args: DbCleanupArgs) -> Result<()> {
+    let options = ScrapeOptions {
+        trailing_slash: args.trailing_slash,
+        content_filter: ContentFilter {
+            skip_extensions: args.skip_extensions,
+            ..ContentFilter::default()
+        },
+        unlisted_pages: args.cleanup,
+        auth: scrape_auth(None, None)?,
+        ..ScrapeOptions::default()
+    };
+    let removed = clean_up_sitemaps(args.sitemaps, &args.db, &options).await?;
+    match args.cleanup {
+        UnlistedPages::Kept => println!("Kept the unlisted pages"),
+        UnlistedPages::Deleted => println!("Deleted {removed} unlisted pages"),
+        UnlistedPages::Archived => println!("Archived {removed} unlisted pages in archived_pages"),
+    }
+    Ok(())
+}
+
+fn handle_runs_command(db: String, limit: u32) -> Result<()> {
+    let storage = Storage::new(&db)?;
+
+    for run in storage.list_runs(limit)? {
+        let status = match (&run.finished_at, &run.error) {
+            (_, Some(error)) => format!("failed: {error}"),
+            (Some(_), None) => "ok".to_string(),
+            (None, None) => "unfinished".to_string(),
+        };
+        let traffic = match (run.requests, run.bytes_downloaded) {
+            (Some(requests), Some(bytes)) => format!(" | {requests} requests, {bytes} bytes"),
+            _ => String::new(),
+        };
+
+        println!(
+            "#{} {} {} -> {} | {} pages{} | {} | {}",
+            run.id,
+            run.command,
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            run.finished_at
+                .map(|finished_at| finished_at.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            run.pages_affected
+                .map(|count| count.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            traffic,
+            status,
+            run.parameters,
+        );
+    }
+
+    Ok(())
+}
+
+fn handle_status_command(db: String, by_stage: bool) -> Result<()> {
+    let storage = Storage::new(&db)?;
+    let statuses = storage.count_pages_by_status()?;
+
+    if by_stage {
+        for (status, count) in &statuses {
+            println!("{status}: {count}");
         }
+        return Ok(());
+    }
+
+    let total: usize = statuses.iter().map(|(_, count)| count).sum();
+    let count_of = |wanted: &PageStatus| {
+        statuses
+            .iter()
+            .find(|(status, _)| status == wanted)
+            .map_or(0, |(_, count)| *count)
     };
+    let failed: usize = statuses
+        .iter()
+        .filter(|(status, _)| matches!(status, PageStatus::Failed(_)))
+        .map(|(_, count)| count)
+        .sum();
+    println!(
+        "{total} pages: {} composed, {} summarized, {} parsed, {} scraped, {failed} failed",
+        count_of(&PageStatus::Composed),
+        count_of(&PageStatus::Summarized),
+        count_of(&PageStatus::Parsed),
+        count_of(&PageStatus::Scraped),
+    );
+
+    Ok(())
+}
 
-    let prompt_template = match prompt_file {
-        Some(file) => {
-            let content =
-                fs::read_to_string(&file).context(format!("Failed to read prompt file: {file}"))?;
-            Some(content)
+async fn handle_compliance_command(db: String) -> Result<()> {
+    let hosts = compliance_report(&Storage::new(&db)?).await?;
+    for host in &hosts {
+        let robots = match &host.robots {
+            Some(robots) => format!(
+                "robots.txt with {} rules, crawl delay {}",
+                robots.rules.len(),
+                robots
+                    .crawl_delay
+                    .map_or("none".to_string(), |delay| format!("{delay:?}"))
+            ),
+            None => "no robots.txt".to_string(),
+        };
+        println!("{}: {robots}, {} stored pages", host.origin, host.stored);
+        for (label, urls) in [
+            ("skipped as disallowed", &host.skipped),
+            ("noindex", &host.noindex),
+            ("stored but disallowed", &host.disallowed),
+        ] {
+            for url in urls {
+                println!("  {label}: {url}");
+            }
         }
-        None => None,
+    }
+
+    let disallowed: usize = hosts.iter().map(|host| host.disallowed.len()).sum();
+    if disallowed > 0 {
+        anyhow::bail!(
+            "{disallowed} stored pages are disallowed by robots.txt, remove them with rm or exclude them"
+        );
+    }
+    Ok(())
+}
+
+fn handle_sitemap_diff_command(db: String, run: Option<i64>) -> Result<()> {
+    let Some(diff) = Storage::new(&db)?.sitemap_diff(run)? else {
+        println!("No earlier scrape run recorded its sitemap entries, nothing to compare");
+        return Ok(());
     };
 
-    summarize(&db, llm_builder, prompt_template.as_deref(), target, rpm).await
+    println!(
+        "Sitemap changes from run {} to run {}: {} added, {} removed, {} lastmod bumped",
+        diff.previous_run,
+        diff.run,
+        diff.added.len(),
+        diff.removed.len(),
+        diff.bumped.len()
+    );
+    for (label, urls) in [
+        ("added", &diff.added),
+        ("removed", &diff.removed),
+        ("bumped", &diff.bumped),
+    ] {
+        for url in urls {
+            println!("  {label}: {url}");
+        }
+    }
+    Ok(())
+}
+
+fn handle_broken_links_command(db: String) -> Result<()> {
+    let storage = Storage::new(&db)?;
+    let broken_links = storage.list_broken_links()?;
+
+    for link in &broken_links {
+        println!(
+            "{} -> {} | {}",
+            link.source,
+            link.target,
+            link.error.as_deref().unwrap_or("missing")
+        );
+    }
+    info!("Found {} broken internal links", broken_links.len());
+
+    Ok(())
 }
@@ -0,0 +1,110 @@
+//! The ollama module checks a local Ollama server has the model before a long run,
+//! instead of failing at the first page.
+
+use serde_json::Value;
+use url::Url;
+
+use crate::error::Context;
+use crate::{Error, Result};
+
+/// Address of an Ollama server running locally with the default settings.
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434/";
+
+/// Returns whether the model URL names a model of an Ollama server, e.g.
+/// "ollama://qwen3:8b@localhost:11434", rather than a tagged model of the local server
+/// like "ollama://8b@qwen3" or "ollama://chat@llama2".
+///
+/// Only a port marks the server, since the name before "@" may be any tag of a local model.
+/// A server without an explicit port is given with `--api-base` instead.
+///
+/// # Arguments
+///
+/// * `model_url` - The model URL
+pub fn is_server_model_url(model_url: &Url) -> bool {
+    model_url.scheme() == "ollama" && model_url.port().is_some()
+}
+
+/// Returns the address of the Ollama server named by the model URL, if any.
+///
+/// # Arguments
+///
+/// * `model_url` - The model URL, e.g. "ollama://qwen3:8b@gpu-box:11434"
+///
+/// # Returns
+///
+/// Returns the server address, e.g. `http://gpu-box:11434/`, or `None` for a model
+/// of the local server
+///
+/// # Errors
+///
+/// Returns an error if the server address is invalid
+pub fn server_url(model_url: &Url) -> Result<Option<Url>> {
+    let Some(port) = model_url.port().filter(|_| is_server_model_url(model_url)) else {
+        return Ok(None);
+    };
+
+    let host = model_url.host_str().unwrap_or("localhost");
+    Url::parse(&format!("http://{host}:{port}/"))
+        .map(Some)
+        .map_err(|e| Error::Config(e.into()).context("Invalid Ollama server address"))
+}
+
+/// Checks the Ollama server lists the model among the pulled ones.
+///
+/// # Arguments
+///
+/// * `server_url` - The URL of the Ollama server, e.g. `http://localhost:11434/`
+/// * `model` - The model name with an optional tag, e.g. `qwen3:8b`
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the model is available
+///
+/// # Errors
+///
+/// Returns an error if the server can't be reached or the model isn't pulled,
+/// with the command pulling it.
+pub async fn ensure_model_available(server_url: &Url, model: &str) -> Result<()> {
+    let tags_url = server_url
+        .join("api/tags")
+        .map_err(|e| Error::Config(e.into()).context("Invalid Ollama server URL"))?;
+    let tags: Value = reqwest::get(tags_url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| {
+            format!("Failed to list models of the Ollama server at {server_url}, is it running?")
+        })?
+        .json()
+        .await
+        .context("Failed to read models listed by the Ollama server")?;
+
+    if is_model_listed(&tags, model) {
+        return Ok(());
+    }
+
+    Err(Error::Llm(format!(
+        "Model {model} isn't available at the Ollama server {server_url}, pull it with `ollama pull {model}`"
+    ).into()))
+}
+
+/// Returns whether a response of the Ollama `/api/tags` endpoint lists the model.
+/// A model without a tag matches its `latest` tag.
+///
+/// # Arguments
+///
+/// * `tags` - The JSON response listing pulled models
+/// * `model` - The model name with an optional tag
+pub fn is_model_listed(tags: &Value, model: &str) -> bool {
+    let model = if model.contains(':') {
+        model.to_string()
+    } else {
+        format!("{model}:latest")
+    };
+
+    tags.get("models")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|listed| listed.get("name").and_then(Value::as_str))
+        .any(|name| name == model)
+}
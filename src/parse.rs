@@ -1,10 +1,116 @@
-use crate::{ParseTarget, TextBy, storage::Storage};
+use crate::{
+    CandidateSelect, Error, ParseTarget, Result, TextBy, TextFormat, TrailingSlash,
+    canonical::normalize_url,
+    progress::Progress,
+    robots::has_noindex,
+    storage::{Page, PageFilter, PageSection, ParseStatus, Redirect, Storage, WriteBatch},
+};
 
-use anyhow::Result;
 use dom_smoothie::{Article, CandidateSelectMode, Config, Readability, TextMode};
 use html2md;
-use log::{error, info};
+use log::{error, info, warn};
 use scraper::{Html, Selector as ScraperSelector};
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+pub mod boilerplate;
+mod breadcrumbs;
+mod json_ld;
+mod links;
+mod rewrite;
+mod sections;
+
+pub use json_ld::StructuredData;
+
+/// Options controlling how text is extracted from page HTML.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// The method to use for text extraction (dom_smoothie or fast_html2md).
+    pub text_by: TextBy,
+    /// An optional CSS selector to limit the HTML subset from which content is extracted.
+    pub selector: Option<ScraperSelector>,
+    /// Keep image alt text as `![alt](src)` and figure captions as italicized text.
+    pub images: bool,
+    /// Keep `<pre>` blocks as fenced code blocks with language hints taken from class names.
+    pub code: bool,
+    /// Convert simple tables to GitHub-flavored markdown tables.
+    pub tables: bool,
+    /// Readability settings of dom_smoothie extraction.
+    pub readability: ReadabilityOptions,
+    /// Pages with less extracted characters are flagged as too short, so they aren't summarized.
+    pub min_text_chars: Option<usize>,
+    /// Strip text blocks repeated verbatim in at least this many parsed pages, or recorded by
+    /// earlier runs as repeated in as many, from the parsed pages.
+    pub boilerplate_min_pages: Option<usize>,
+    /// Split pages with more extracted characters into sections at their anchored headings.
+    pub split_sections_chars: Option<usize>,
+    /// Number of pages extracted in parallel, 0 or 1 to extract them one by one.
+    pub jobs: usize,
+    /// What to do when a page fails to be extracted while parsing all pages.
+    pub on_page_error: OnPageError,
+    /// Pages parsed with the "all" and pattern targets, every page by default.
+    pub filter: PageFilter,
+    /// Trailing slash policy the pages were scraped with, applied to their canonical URLs.
+    pub trailing_slash: TrailingSlash,
+    /// Token stopping parsing all pages before the next batch once cancelled.
+    pub cancel: Option<CancellationToken>,
+    /// Reporter of parsed pages.
+    pub progress: Progress,
+}
+
+impl ParseOptions {
+    /// Returns `true` once the run is cancelled.
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+/// What parsing all pages does when a page fails to be extracted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnPageError {
+    /// Record the failure and continue with the next page.
+    #[default]
+    Continue,
+    /// Stop parsing with the error.
+    FailFast,
+}
+
+/// Settings of dom_smoothie extraction, dom_smoothie defaults are used when not set.
+#[derive(Clone, Debug, Default)]
+pub struct ReadabilityOptions {
+    /// Minimal number of characters an article must have.
+    pub char_threshold: Option<usize>,
+    /// Number of top candidates compared when looking for the article element.
+    pub n_top_candidates: Option<usize>,
+    /// The algorithm selecting the article element.
+    pub candidate_select: CandidateSelect,
+    /// The format of the extracted text.
+    pub text_format: TextFormat,
+}
+
+impl ReadabilityOptions {
+    /// Builds the dom_smoothie config of the options.
+    fn to_config(&self) -> Config {
+        let defaults = Config::default();
+
+        Config {
+            char_threshold: self.char_threshold.unwrap_or(defaults.char_threshold),
+            n_top_candidates: self.n_top_candidates.unwrap_or(defaults.n_top_candidates),
+            candidate_select_mode: match self.candidate_select {
+                CandidateSelect::Readability => CandidateSelectMode::Readability,
+                CandidateSelect::DomSmoothie => CandidateSelectMode::DomSmoothie,
+            },
+            text_mode: match self.text_format {
+                TextFormat::Raw => TextMode::Raw,
+                TextFormat::Formatted => TextMode::Formatted,
+                TextFormat::Markdown => TextMode::Markdown,
+            },
+            ..defaults
+        }
+    }
+}
 
 /// Represents an article extracted from a webpage.
 ///
@@ -15,6 +121,16 @@ pub struct PageArticle {
     pub title: Option<String>,
     /// The text content of the article.
     pub text: String,
+    /// The canonical URL declared by `<link rel="canonical">`, if any.
+    pub canonical: Option<String>,
+    /// Whether `<meta name="robots">` asks not to index the page.
+    pub noindex: bool,
+    /// Names of the breadcrumb trail of the page from the site root, if it has one.
+    pub breadcrumbs: Vec<String>,
+    /// JSON-LD blocks of articles, products and FAQ pages describing the page, see [`StructuredData`].
+    pub structured_data: Vec<serde_json::Value>,
+    /// Sections of a long page split at its anchored headings, see [`ParseOptions::split_sections_chars`].
+    pub sections: Vec<PageSection>,
 }
 
 /// Extracts an article from the given HTML content.
@@ -24,8 +140,7 @@ pub struct PageArticle {
 /// # Arguments
 ///
 /// * `html` - A string slice that holds the HTML content of the webpage.
-/// * `text_by` - The method to use for text extraction (dom_smoothie or fast_html2md).
-/// * `selector` - An optional CSS selector to limit the HTML subset from which content is extracted.
+/// * `options` - The extraction method, selector and elements to keep.
 ///
 /// # Returns
 ///
@@ -37,13 +152,13 @@ pub struct PageArticle {
 ///
 /// - The HTML content is invalid or cannot be parsed.
 /// - The chosen extraction method fails to extract the article from the HTML content.
-pub fn extract_article(
-    html: &str,
-    text_by: TextBy,
-    selector: &Option<ScraperSelector>,
-) -> Result<PageArticle> {
+pub fn extract_article(html: &str, options: &ParseOptions) -> Result<PageArticle, Error> {
     let title = parse_title(html);
-    let selected_html = if let Some(sel) = selector {
+    let canonical = parse_canonical(html);
+    let noindex = parse_robots_noindex(html);
+    let breadcrumbs = breadcrumbs::parse_breadcrumbs(html);
+    let structured_data = json_ld::parse_structured_data(html);
+    let selected_html = if let Some(sel) = &options.selector {
         let document = Html::parse_document(html);
         let elements = document.select(sel);
         let selected_content: Vec<String> = elements.map(|el| el.html()).collect();
@@ -51,27 +166,123 @@ pub fn extract_article(
     } else {
         html
     };
+    let rewritten = rewrite::rewrite_html(selected_html, options);
+    let selected_html = rewritten
+        .as_ref()
+        .map_or(selected_html, |rewritten| rewritten.html.as_str());
 
-    match text_by {
+    let text = match options.text_by {
         TextBy::DomSmoothie => {
-            let config = Config {
-                text_mode: TextMode::Markdown,
-                candidate_select_mode: CandidateSelectMode::DomSmoothie,
-                ..Default::default()
-            };
+            let config = options.readability.to_config();
+            let mut readability = Readability::new(selected_html, None, Some(config))
+                .map_err(|e| Error::Extraction(e.into()))?;
+            let article: Article = readability
+                .parse()
+                .map_err(|e| Error::Extraction(e.into()))?;
+            article.text_content.to_string()
+        }
+        TextBy::FastHtml2Md => html2md::parse_html(selected_html, false),
+    };
+    let text = match &rewritten {
+        Some(rewritten) => rewritten.restore_blocks(&text),
+        None => text,
+    };
+    let sections = match options.split_sections_chars {
+        Some(min_chars) if text.chars().count() > min_chars => {
+            sections::split_sections(selected_html, &text)
+        }
+        _ => Vec::new(),
+    };
 
-            let mut readability = Readability::new(selected_html, None, Some(config))?;
-            let article: Article = readability.parse()?;
+    Ok(PageArticle {
+        title,
+        text,
+        canonical,
+        noindex,
+        breadcrumbs,
+        structured_data,
+        sections,
+    })
+}
 
-            Ok(PageArticle {
-                title,
-                text: article.text_content.to_string(),
-            })
-        }
-        TextBy::FastHtml2Md => {
-            let text = html2md::parse_html(selected_html, false);
-            Ok(PageArticle { title, text })
+/// Content type of PDF documents.
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+
+/// Returns `true` if a document served with the content type from the URL is a PDF.
+///
+/// The URL extension is checked when the content type is unknown or generic.
+pub fn is_pdf(content_type: Option<&str>, url: &Url) -> bool {
+    match content_type {
+        Some(content_type) if content_type.to_lowercase().starts_with(PDF_CONTENT_TYPE) => true,
+        Some(content_type)
+            if !content_type.starts_with("application/octet-stream")
+                && !content_type.is_empty() =>
+        {
+            false
         }
+        _ => url.path().to_lowercase().ends_with(".pdf"),
+    }
+}
+
+/// Extracts an article from the given PDF document.
+///
+/// # Arguments
+///
+/// * `content` - The raw content of the PDF document.
+///
+/// # Errors
+///
+/// This function will return an error if the document is not a valid PDF
+/// or its text can't be extracted.
+pub fn extract_pdf_article(content: &[u8]) -> Result<PageArticle, Error> {
+    let text = pdf_extract::extract_text_from_mem(content)
+        .map_err(|e| Error::Extraction(e.into()).context("Unable to extract PDF text"))?;
+    let title = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string);
+
+    Ok(PageArticle {
+        title,
+        text: text.trim().to_string(),
+        canonical: None,
+        noindex: false,
+        breadcrumbs: Vec::new(),
+        structured_data: Vec::new(),
+        sections: Vec::new(),
+    })
+}
+
+/// Content types of documents whose text is taken as served instead of extracted from HTML.
+const PLAIN_TEXT_CONTENT_TYPES: [&str; 2] = ["text/plain", "text/markdown"];
+
+/// Returns `true` if a document served with the content type is plain text or markdown.
+fn is_plain_text(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| {
+        let content_type = content_type.to_lowercase();
+        PLAIN_TEXT_CONTENT_TYPES
+            .iter()
+            .any(|plain_text| content_type.starts_with(plain_text))
+    })
+}
+
+/// Takes the article of a plain text or markdown document as is, titled by its first line.
+fn extract_plain_text_article(text: &str) -> PageArticle {
+    let title = text
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string);
+
+    PageArticle {
+        title,
+        text: text.trim().to_string(),
+        canonical: None,
+        noindex: false,
+        breadcrumbs: Vec::new(),
+        structured_data: Vec::new(),
+        sections: Vec::new(),
     }
 }
 
@@ -112,56 +323,392 @@ fn parse_title(html: &str) -> Option<String> {
     None
 }
 
+/// Parses the canonical URL from `<link rel="canonical">` of HTML content
+fn parse_canonical(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let canonical_selector = ScraperSelector::parse(r#"link[rel~="canonical"][href]"#).ok()?;
+
+    document
+        .select(&canonical_selector)
+        .next()
+        .and_then(|element| element.value().attr("href"))
+        .map(|href| href.trim().to_string())
+        .filter(|href| !href.is_empty())
+}
+
+/// Returns `true` if a `<meta name="robots">` tag of HTML content asks not to index the page
+fn parse_robots_noindex(html: &str) -> bool {
+    let document = Html::parse_document(html);
+    let Ok(meta_selector) = ScraperSelector::parse("meta[name][content]") else {
+        return false;
+    };
+
+    document.select(&meta_selector).any(|element| {
+        element
+            .value()
+            .attr("name")
+            .is_some_and(|name| name.trim().eq_ignore_ascii_case("robots"))
+            && element.value().attr("content").is_some_and(has_noindex)
+    })
+}
+
+/// Moves the page to the URL of its canonical page when it's a duplicate of it.
+///
+/// The page is removed if the canonical page is already stored, otherwise
+/// it's stored under the canonical URL. Either way the page URL is recorded as redirecting
+/// to the canonical URL, so that a scrape listing it neither fetches it again nor removes
+/// the canonical page as unlisted. Canonical URLs of other hosts are ignored.
+fn apply_canonical(
+    batch: &WriteBatch<'_>,
+    page: &mut Page,
+    canonical: Option<&str>,
+    trailing_slash: &TrailingSlash,
+) -> Result<()> {
+    let Some(canonical_url) = canonical.and_then(|href| page.url.join(href).ok()) else {
+        return Ok(());
+    };
+    let canonical_url = normalize_url(&canonical_url, trailing_slash);
+    if canonical_url == page.url || canonical_url.origin() != page.url.origin() {
+        return Ok(());
+    }
+
+    let redirect = Redirect {
+        url: page.url.to_string(),
+        target: canonical_url.to_string(),
+    };
+    if batch.get_page(canonical_url.as_str())?.is_some() {
+        info!(
+            "Removed {} as a duplicate of canonical {canonical_url}",
+            page.url
+        );
+    } else {
+        info!("Moved {} to canonical {canonical_url}", page.url);
+        page.url = canonical_url;
+        batch.upsert_page(page)?;
+    }
+    batch.record_redirect(&redirect)?;
+
+    Ok(())
+}
+
+/// Extracts the article of an HTML, PDF or plain text page.
+fn extract_page(page: &Page, options: &ParseOptions) -> Result<PageArticle> {
+    match &page.content {
+        Some(content) if is_pdf(page.content_type.as_deref(), &page.url) => {
+            Ok(extract_pdf_article(content)?)
+        }
+        _ if is_plain_text(page.content_type.as_deref()) => {
+            Ok(extract_plain_text_article(&page.html))
+        }
+        _ => Ok(extract_article(&page.html, options)?),
+    }
+}
+
+/// Extracts articles of pages using up to `jobs` threads, keeping the order of pages.
+fn extract_pages(pages: &[Page], options: &ParseOptions, jobs: usize) -> Vec<Result<PageArticle>> {
+    if jobs <= 1 || pages.len() <= 1 {
+        return pages
+            .iter()
+            .map(|page| extract_page(page, options))
+            .collect();
+    }
+
+    let chunk_size = pages.len().div_ceil(jobs);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = pages
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let worker = scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|page| extract_page(page, options))
+                        .collect::<Vec<_>>()
+                });
+                (chunk.len(), worker)
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|(chunk_len, worker)| {
+                worker.join().unwrap_or_else(|_| {
+                    (0..chunk_len)
+                        .map(|_| Err(Error::Extraction("Parse worker panicked".into())))
+                        .collect()
+                })
+            })
+            .collect()
+    })
+}
+
+/// Stores the extracted article of a page, honoring the canonical URL of the page,
+/// and returns the URL the page is stored at.
+fn store_article(
+    batch: &WriteBatch<'_>,
+    mut page: Page,
+    mut article: PageArticle,
+    options: &ParseOptions,
+) -> Result<String> {
+    let canonical = article.canonical.take();
+    let breadcrumbs = std::mem::take(&mut article.breadcrumbs);
+    let structured_data = std::mem::take(&mut article.structured_data);
+    let sections = std::mem::take(&mut article.sections);
+    let links = links::internal_links(&page.html, &page.url);
+    let noindex = article.noindex;
+    let text_chars = article.text.trim().chars().count();
+    let status = match options.min_text_chars {
+        Some(min_text_chars) if text_chars < min_text_chars => {
+            warn!(
+                "Flagging {} as too short: {text_chars} of {min_text_chars} characters",
+                page.url
+            );
+            ParseStatus::TooShort
+        }
+        _ => ParseStatus::Ok,
+    };
+    page.apply_article(article);
+    batch.upsert_page(&page)?;
+
+    apply_canonical(
+        batch,
+        &mut page,
+        canonical.as_deref(),
+        &options.trailing_slash,
+    )?;
+    batch.update_parse_status(page.url.as_str(), status)?;
+    batch.update_page_breadcrumbs(page.url.as_str(), &breadcrumbs)?;
+    batch.update_page_structured_data(page.url.as_str(), &structured_data)?;
+    batch.replace_page_sections(page.url.as_str(), &sections)?;
+    batch.replace_page_links(page.url.as_str(), &links)?;
+    if noindex {
+        info!("Flagging {} as noindex by its robots meta tag", page.url);
+        batch.update_page_noindex(page.url.as_str(), true)?;
+    }
+    Ok(page.url.into())
+}
+
+/// Extracts the article of a page just stored by a scrape and stores it,
+/// see [`crate::ScrapeOptions::parse`].
+pub(crate) fn parse_scraped_page(
+    storage: &Storage,
+    page: Page,
+    options: &ParseOptions,
+) -> Result<()> {
+    let article = extract_page(&page, options)?;
+    info!("Parsed {}", page.url);
+    let url = storage.write_batch(|batch| store_article(batch, page, article, options))?;
+    options.progress.page_parsed(&url);
+    Ok(())
+}
+
+/// Number of pages read, parsed in parallel and written at once.
+const PARSE_BATCH_SIZE: usize = 100;
+
+/// Parses all stored pages matching the filter in batches and returns the URLs of the parsed pages
+fn parse_all(
+    storage: &Storage,
+    filter: PageFilter,
+    options: &ParseOptions,
+    run_id: i64,
+) -> Result<Vec<String>> {
+    let mut parsed = Vec::new();
+    let mut failed = 0;
+
+    let mut stored_pages = storage.find_pages(filter);
+    loop {
+        if options.is_cancelled() {
+            warn!(
+                "Parsing cancelled after {} pages, parsed pages are saved",
+                parsed.len()
+            );
+            break;
+        }
+        let pages = stored_pages
+            .by_ref()
+            .take(PARSE_BATCH_SIZE)
+            .collect::<crate::Result<Vec<_>>>()?;
+        if pages.is_empty() {
+            break;
+        }
+        let pages = without_discarded_html(pages);
+
+        let (batch_parsed, batch_failed) = parse_batch(storage, pages, options, run_id)?;
+        parsed.extend(batch_parsed);
+        failed += batch_failed;
+    }
+
+    info!("Parsed {} pages, {failed} failed", parsed.len());
+    Ok(parsed)
+}
+
+/// Leaves out pages scraped without storing their HTML, whose text would be lost by parsing them again.
+fn without_discarded_html(pages: Vec<Page>) -> Vec<Page> {
+    pages
+        .into_iter()
+        .filter(|page| {
+            let discarded = page.is_html_discarded();
+            if discarded {
+                warn!(
+                    "Skipping {}: its HTML wasn't stored, scrape it again to parse it",
+                    page.url
+                );
+            }
+            !discarded
+        })
+        .collect()
+}
+
+/// Parses a batch of pages in parallel and stores them in a single transaction,
+/// returning the URLs of the parsed pages and the number of failed pages.
+///
+/// With [`OnPageError::FailFast`], the pages parsed before the failing one are stored
+/// before the failure is returned.
+fn parse_batch(
+    storage: &Storage,
+    pages: Vec<Page>,
+    options: &ParseOptions,
+    run_id: i64,
+) -> Result<(Vec<String>, usize)> {
+    let mut extracted = Vec::with_capacity(pages.len());
+    let mut failed = 0;
+    let mut fail_fast = None;
+
+    let articles = extract_pages(&pages, options, options.jobs.max(1));
+    for (page, article) in pages.into_iter().zip(articles) {
+        match article {
+            Ok(article) => {
+                info!("Parsed {}", page.url);
+                extracted.push((page, article));
+            }
+            Err(error) if options.on_page_error == OnPageError::FailFast => {
+                fail_fast = Some(error.context(format!("Unable to parse {}", page.url)));
+                break;
+            }
+            Err(error) => {
+                error!("Unable to parse {}: {error}", page.url);
+                storage.record_failure(run_id, "parse", page.url.as_str(), &error.to_string())?;
+                failed += 1;
+            }
+        }
+    }
+
+    let parsed = storage.write_batch(|batch| {
+        extracted
+            .into_iter()
+            .map(|(page, article)| store_article(batch, page, article, options))
+            .collect::<Result<Vec<_>>>()
+    })?;
+    for url in &parsed {
+        options.progress.page_parsed(url);
+    }
+    match fail_fast {
+        Some(error) => Err(error),
+        None => Ok((parsed, failed)),
+    }
+}
+
 /// Extract content from HTML stored in the database and updates the text and title field
 ///
 /// # Arguments
 ///
 /// * `db_path` - Path to the database file to read pages from
 /// * `target` - The parse target (all pages or specific page)
-/// * `text_by` - The method to use for text extraction (dom_smoothie or fast_html2md)
-/// * `selector` - An optional CSS selector to limit the HTML subset from which content is extracted.
+/// * `options` - The extraction method, selector and elements to keep
 ///
 /// # Errors
 ///
 /// This function will return an error if:
 /// - Database operations fail
+/// - A page fails to be extracted while parsing a single page, or with [`OnPageError::FailFast`]
 pub async fn parse_db_html(
     db_path: &str,
     target: ParseTarget,
-    text_by: TextBy,
-    selector: &Option<ScraperSelector>,
-) -> Result<()> {
-    let storage = Storage::new(db_path)?;
+    options: &ParseOptions,
+) -> Result<(), Error> {
+    parse_with_storage(&Storage::new(db_path)?, target, options).await
+}
+
+/// Extracts content from HTML of the storage pages, recording the run in the audit log.
+pub(crate) async fn parse_with_storage(
+    storage: &Storage,
+    target: ParseTarget,
+    options: &ParseOptions,
+) -> Result<(), Error> {
+    let run_id = storage.start_run(
+        "parse",
+        &format!(
+            "target={target:?} text_by={:?} selector={} images={} code={} tables={} readability={:?} min_text_chars={:?} boilerplate_min_pages={:?} split_sections_chars={:?} jobs={} on_page_error={:?} trailing_slash={:?} filter={:?}",
+            options.text_by,
+            options.selector.is_some(),
+            options.images,
+            options.code,
+            options.tables,
+            options.readability,
+            options.min_text_chars,
+            options.boilerplate_min_pages,
+            options.split_sections_chars,
+            options.jobs,
+            options.on_page_error,
+            options.trailing_slash,
+            options.filter
+        ),
+    )?;
+
+    let result = parse_target(storage, target, options, run_id).and_then(|parsed| {
+        if let Some(min_pages) = options.boilerplate_min_pages
+            && !options.is_cancelled()
+        {
+            boilerplate::strip_parsed_boilerplate(storage, &parsed, min_pages)?;
+        }
+        Ok(parsed.len())
+    });
+    let error = match &result {
+        Ok(_) if options.is_cancelled() => Some("Interrupted".to_string()),
+        Ok(_) => None,
+        Err(error) => Some(error.to_string()),
+    };
+    storage.finish_run(run_id, result.as_ref().ok().copied(), error)?;
+    if let Ok(parsed) = &result {
+        options.progress.stage_complete("parse", *parsed);
+    }
 
+    result.map(|_| ())
+}
+
+/// Parses the target pages and returns the URLs of the parsed pages
+fn parse_target(
+    storage: &Storage,
+    target: ParseTarget,
+    options: &ParseOptions,
+    run_id: i64,
+) -> Result<Vec<String>> {
     match target {
-        ParseTarget::All => {
-            let urls = storage.list_urls()?;
-            for url in urls {
-                info!("Parsing {url}");
-                let mut page = match storage.get_page(&url)? {
-                    Some(page) => page,
-                    None => continue,
-                };
-
-                let article = extract_article(&page.html, text_by.clone(), selector)?;
-                page.apply_article(article);
-                storage.upsert_page(&page)?;
-            }
+        ParseTarget::All => parse_all(storage, options.filter.clone(), options, run_id),
+        ParseTarget::Pattern { glob } => {
+            info!("Parsing pages matching {glob}");
+            parse_all(
+                storage,
+                options.filter.clone().url_glob(glob),
+                options,
+                run_id,
+            )
         }
         ParseTarget::Page { url } => {
-            let mut page = if let Some(page) = storage.get_page(&url)? {
+            let page = if let Some(page) = storage.get_page(&url)? {
                 page
             } else {
                 error!("Page not found: {url}");
-                return Ok(());
+                return Ok(Vec::new());
             };
+            if page.is_html_discarded() {
+                warn!("HTML of {url} wasn't stored, scrape it again to parse it");
+                return Ok(Vec::new());
+            }
 
-            let article = extract_article(&page.html, text_by, selector)?;
-
-            page.apply_article(article);
-            storage.upsert_page(&page)?;
+            let article = extract_page(&page, options)?;
+            let url = storage.write_batch(|batch| store_article(batch, page, article, options))?;
+            options.progress.page_parsed(&url);
+            Ok(vec![url])
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,107 @@
+//! Detection of boilerplate blocks (footers, calls to action, newsletter prompts)
+//! repeated verbatim across many pages of a site.
+
+use log::info;
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::Storage;
+use crate::{Error, Result};
+
+/// Blocks shorter than this are never considered boilerplate, so short headings survive.
+const MIN_BLOCK_CHARS: usize = 30;
+
+/// Splits extracted text into blocks separated by blank lines.
+fn split_blocks(text: &str) -> impl Iterator<Item = &str> {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+}
+
+/// Counts the texts repeating every block long enough to be boilerplate,
+/// keeping the blocks repeated in at least `min_pages` of them.
+fn count_repeated_blocks<'a, I>(texts: I, min_pages: usize) -> HashMap<String, usize>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut pages_by_block: HashMap<&str, usize> = HashMap::new();
+    for text in texts {
+        let blocks: HashSet<&str> = split_blocks(text)
+            .filter(|block| block.chars().count() >= MIN_BLOCK_CHARS)
+            .collect();
+        for block in blocks {
+            *pages_by_block.entry(block).or_default() += 1;
+        }
+    }
+
+    pages_by_block
+        .into_iter()
+        .filter(|(_, pages)| *pages >= min_pages.max(2))
+        .map(|(block, pages)| (block.to_string(), pages))
+        .collect()
+}
+
+/// Finds blocks repeated verbatim in at least `min_pages` of the given texts.
+///
+/// # Arguments
+///
+/// * `texts` - Extracted texts of the pages
+/// * `min_pages` - The number of pages a block must appear in to be boilerplate
+pub fn find_boilerplate<'a, I>(texts: I, min_pages: usize) -> HashSet<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    count_repeated_blocks(texts, min_pages)
+        .into_keys()
+        .collect()
+}
+
+/// Removes boilerplate blocks from the text.
+pub fn strip_boilerplate(text: &str, boilerplate: &HashSet<String>) -> String {
+    split_blocks(text)
+        .filter(|block| !boilerplate.contains(*block))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Strips boilerplate from the freshly extracted texts of the pages parsed by a run: blocks
+/// repeated in at least `min_pages` of these pages, which are recorded for later runs, and
+/// blocks earlier runs recorded as repeated in as many pages. Returns the number of updated pages.
+pub(super) fn strip_parsed_boilerplate(
+    storage: &Storage,
+    parsed_urls: &[String],
+    min_pages: usize,
+) -> Result<usize> {
+    let mut texts = Vec::new();
+    for url in parsed_urls {
+        if let Some(text) = storage.get_page_text(url)? {
+            texts.push((url, text));
+        }
+    }
+
+    let detected = count_repeated_blocks(texts.iter().map(|(_, text)| text.as_str()), min_pages);
+    storage.save_boilerplate_blocks(&detected)?;
+    let boilerplate = storage.list_boilerplate_blocks(min_pages.max(2))?;
+    if boilerplate.is_empty() {
+        return Ok(0);
+    }
+    info!(
+        "Stripping {} boilerplate blocks repeated in at least {min_pages} pages, {} of them found by this run",
+        boilerplate.len(),
+        detected.len()
+    );
+
+    let mut updated = 0;
+    storage.write_batch(|batch| {
+        texts.iter().try_for_each(|(url, text)| {
+            let stripped = strip_boilerplate(text, &boilerplate);
+            if stripped != text.trim() {
+                batch.update_page_text(url, &stripped)?;
+                updated += 1;
+            }
+            Ok::<_, Error>(())
+        })
+    })?;
+    info!("Stripped boilerplate from {updated} pages");
+
+    Ok(updated)
+}
@@ -0,0 +1,116 @@
+//! Breadcrumb trails of pages, read from JSON-LD `BreadcrumbList` structured data
+//! or from breadcrumb navigation markup, so that compose can group pages by the
+//! sections their site declares instead of by URL path segments.
+
+use scraper::{ElementRef, Html, Selector};
+use serde_json::Value;
+
+use super::json_ld::{has_type, json_ld_data};
+
+/// Elements which may hold a breadcrumb trail as a list.
+const TRAIL_SELECTOR: &str = r#"nav[aria-label], nav[class*="breadcrumb"], ol[class*="breadcrumb"],
+    ul[class*="breadcrumb"], [itemtype$="BreadcrumbList"]"#;
+
+/// Separators sites put between crumbs, trimmed from crumb names.
+const CRUMB_SEPARATORS: [char; 6] = ['/', '>', '›', '»', '|', '→'];
+
+/// Returns the names of the breadcrumb trail of a page from the site root,
+/// empty if the page has none.
+///
+/// JSON-LD `BreadcrumbList` data is preferred, as its crumbs aren't mixed with markup.
+pub(super) fn parse_breadcrumbs(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let crumbs = json_ld_breadcrumbs(&document);
+    if crumbs.is_empty() {
+        markup_breadcrumbs(&document)
+    } else {
+        crumbs
+    }
+}
+
+/// Returns the crumbs of the first JSON-LD `BreadcrumbList` of the document.
+fn json_ld_breadcrumbs(document: &Html) -> Vec<String> {
+    json_ld_data(document)
+        .iter()
+        .find_map(|data| find_breadcrumb_list(data).map(list_item_names))
+        .unwrap_or_default()
+}
+
+/// Finds a `BreadcrumbList` in JSON-LD data, which may list several items or a `@graph` of them.
+fn find_breadcrumb_list(data: &Value) -> Option<&Value> {
+    match data {
+        Value::Array(items) => items.iter().find_map(find_breadcrumb_list),
+        Value::Object(_) if has_type(data, "BreadcrumbList") => Some(data),
+        Value::Object(object) => object.get("@graph").and_then(find_breadcrumb_list),
+        _ => None,
+    }
+}
+
+/// Returns the names of the items of a `BreadcrumbList`, ordered by their position.
+fn list_item_names(list: &Value) -> Vec<String> {
+    let Some(items) = list.get("itemListElement").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    let mut crumbs: Vec<(i64, String)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let name = item
+                .get("name")
+                .or_else(|| item.get("item").and_then(|target| target.get("name")))
+                .and_then(Value::as_str)?;
+            let position = match item.get("position") {
+                Some(Value::Number(position)) => position.as_i64(),
+                Some(Value::String(position)) => position.trim().parse().ok(),
+                _ => None,
+            };
+            Some((position.unwrap_or(index as i64), crumb_name(name)))
+        })
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+    crumbs.sort_by_key(|(position, _)| *position);
+
+    crumbs.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Returns the list items of the first breadcrumb navigation of the document.
+fn markup_breadcrumbs(document: &Html) -> Vec<String> {
+    let (Ok(trail_selector), Ok(item_selector)) =
+        (Selector::parse(TRAIL_SELECTOR), Selector::parse("li"))
+    else {
+        return Vec::new();
+    };
+
+    document
+        .select(&trail_selector)
+        .filter(|trail| {
+            trail
+                .value()
+                .attr("aria-label")
+                .is_none_or(|label| label.to_lowercase().contains("breadcrumb"))
+        })
+        .map(|trail| {
+            trail
+                .select(&item_selector)
+                .map(element_text)
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .find(|crumbs| !crumbs.is_empty())
+        .unwrap_or_default()
+}
+
+/// Returns the text of an element as a crumb name.
+fn element_text(element: ElementRef) -> String {
+    crumb_name(&element.text().collect::<Vec<_>>().join(" "))
+}
+
+/// Collapses whitespace of a crumb name and trims separators around it.
+fn crumb_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c.is_whitespace() || CRUMB_SEPARATORS.contains(&c))
+        .to_string()
+}
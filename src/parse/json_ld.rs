@@ -0,0 +1,146 @@
+//! JSON-LD structured data of pages: the schema.org blocks sites describe their content with,
+//! which are often cleaner than the text extracted from their markup.
+
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+/// Scripts carrying JSON-LD structured data.
+const JSON_LD_SELECTOR: &str = r#"script[type="application/ld+json"]"#;
+
+/// Schema.org types of articles, whose headline, publication date and author are read.
+const ARTICLE_TYPES: [&str; 5] = [
+    "Article",
+    "NewsArticle",
+    "BlogPosting",
+    "TechArticle",
+    "ScholarlyArticle",
+];
+
+/// Schema.org types of the blocks stored by parse besides articles.
+const OTHER_CAPTURED_TYPES: [&str; 2] = ["Product", "FAQPage"];
+
+/// Key fields of the structured data of a page, which compose and summarization prompts can reference.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StructuredData {
+    /// Headline of the article, or name of the product
+    pub headline: Option<String>,
+    /// Date the article was published, as declared by the site
+    pub date_published: Option<String>,
+    /// Names of the authors of the article, comma separated
+    pub author: Option<String>,
+    /// Whether the page declares an `FAQPage`
+    pub faq: bool,
+}
+
+impl StructuredData {
+    /// Reads the key fields from the JSON-LD blocks of a page stored by parse,
+    /// preferring the first article over the first product.
+    pub fn of_blocks(blocks: &[Value]) -> Self {
+        let article = blocks.iter().find(|block| is_article(block));
+        let product = blocks.iter().find(|block| has_type(block, "Product"));
+
+        StructuredData {
+            headline: article
+                .and_then(|article| text_field(article, "headline"))
+                .or_else(|| product.and_then(|product| text_field(product, "name"))),
+            date_published: article.and_then(|article| text_field(article, "datePublished")),
+            author: article
+                .and_then(|article| article.get("author"))
+                .and_then(author_names),
+            faq: blocks.iter().any(|block| has_type(block, "FAQPage")),
+        }
+    }
+}
+
+/// Returns the JSON-LD blocks of articles, products and FAQ pages of a page,
+/// searched in lists of blocks and `@graph` of them.
+pub(super) fn parse_structured_data(html: &str) -> Vec<Value> {
+    let document = Html::parse_document(html);
+    let mut blocks = Vec::new();
+    for data in json_ld_data(&document) {
+        collect_blocks(data, &mut blocks);
+    }
+    blocks
+}
+
+/// Returns the JSON-LD data of every script of the document, leaving out invalid JSON.
+pub(super) fn json_ld_data(document: &Html) -> Vec<Value> {
+    let Ok(script_selector) = Selector::parse(JSON_LD_SELECTOR) else {
+        return Vec::new();
+    };
+
+    document
+        .select(&script_selector)
+        .filter_map(|script| serde_json::from_str(&script.text().collect::<String>()).ok())
+        .collect()
+}
+
+/// Returns `true` if JSON-LD data has the schema.org type, among others or alone.
+pub(super) fn has_type(data: &Value, type_name: &str) -> bool {
+    match data.get("@type") {
+        Some(Value::String(data_type)) => data_type == type_name,
+        Some(Value::Array(data_types)) => data_types
+            .iter()
+            .any(|data_type| data_type.as_str() == Some(type_name)),
+        _ => false,
+    }
+}
+
+/// Adds the blocks of captured types found in JSON-LD data to the list.
+fn collect_blocks(data: Value, blocks: &mut Vec<Value>) {
+    match data {
+        Value::Array(items) => {
+            for item in items {
+                collect_blocks(item, blocks);
+            }
+        }
+        Value::Object(_)
+            if is_article(&data)
+                || OTHER_CAPTURED_TYPES
+                    .iter()
+                    .any(|type_name| has_type(&data, type_name)) =>
+        {
+            blocks.push(data);
+        }
+        Value::Object(mut object) => {
+            if let Some(graph) = object.remove("@graph") {
+                collect_blocks(graph, blocks);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns `true` if JSON-LD data describes an article.
+fn is_article(data: &Value) -> bool {
+    ARTICLE_TYPES
+        .iter()
+        .any(|type_name| has_type(data, type_name))
+}
+
+/// Returns a non-blank text field of JSON-LD data, with collapsed whitespace.
+fn text_field(data: &Value, key: &str) -> Option<String> {
+    data.get(key)
+        .and_then(Value::as_str)
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+}
+
+/// Returns the names of the authors of an article, given as names, persons or a list of them.
+fn author_names(author: &Value) -> Option<String> {
+    let names: Vec<String> = match author {
+        Value::Array(authors) => authors.iter().filter_map(author_name).collect(),
+        author => author_name(author).into_iter().collect(),
+    };
+
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+/// Returns the name of an author given as a name or a person.
+fn author_name(author: &Value) -> Option<String> {
+    match author {
+        Value::String(name) => Some(name.trim().to_string()).filter(|name| !name.is_empty()),
+        Value::Object(_) => text_field(author, "name"),
+        _ => None,
+    }
+}
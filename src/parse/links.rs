@@ -0,0 +1,28 @@
+//! Internal links of pages, recorded by parse to score the importance of pages by the links between them.
+
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::TrailingSlash;
+use crate::canonical::normalize_url;
+
+/// Returns the URLs of the same origin a page links to, without fragments, sorted and deduplicated.
+/// Links of the page to itself, e.g. to its own anchors, are left out.
+pub(super) fn internal_links(html: &str, page_url: &Url) -> Vec<String> {
+    let Ok(selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+
+    let mut targets: Vec<String> = Html::parse_document(html)
+        .select(&selector)
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| page_url.join(href.trim()).ok())
+        .filter(|target| target.origin() == page_url.origin())
+        .map(|target| normalize_url(&target, &TrailingSlash::Keep))
+        .filter(|target| target != page_url)
+        .map(String::from)
+        .collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
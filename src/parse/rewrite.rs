@@ -0,0 +1,246 @@
+//! Rewrites of HTML elements which text extraction would otherwise drop or mangle.
+//!
+//! Elements whose markdown must survive extraction verbatim (e.g. code blocks) are
+//! replaced with placeholders, which are swapped back for the rendered markdown
+//! once the text is extracted.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Html, Node, Selector, node::Text};
+
+use super::ParseOptions;
+
+/// Matches a block placeholder along with a code fence or indentation the extractor put around it.
+static BLOCK_PLACEHOLDER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:`{3,}[^\n]*\n)?[ \t]*LLAMAPBLOCK(\d+)END[ \t]*(?:\n`{3,})?")
+        .expect("Failed to compile block placeholder regex")
+});
+
+/// Tables with more cells are left to the extractor, as they don't make readable markdown.
+const MAX_TABLE_CELLS: usize = 400;
+
+/// Class name prefixes carrying the language of a code block.
+const LANGUAGE_CLASS_PREFIXES: [&str; 2] = ["language-", "lang-"];
+
+/// HTML rewritten for extraction along with the blocks to restore into the extracted text.
+pub(super) struct RewrittenHtml {
+    pub html: String,
+    blocks: Vec<String>,
+}
+
+impl RewrittenHtml {
+    /// Replaces block placeholders in the extracted text with the rendered blocks.
+    pub fn restore_blocks(&self, text: &str) -> String {
+        if self.blocks.is_empty() {
+            return text.to_string();
+        }
+
+        BLOCK_PLACEHOLDER_REGEX
+            .replace_all(text, |captures: &Captures| {
+                captures
+                    .get(1)
+                    .and_then(|index| index.as_str().parse::<usize>().ok())
+                    .and_then(|index| self.blocks.get(index))
+                    .map(|block| format!("\n{block}\n"))
+                    .unwrap_or_default()
+            })
+            .to_string()
+    }
+}
+
+/// Rewrites the HTML according to the extraction options.
+///
+/// Returns `None` if no rewrite is enabled, so the HTML can be used as is.
+pub(super) fn rewrite_html(html: &str, options: &ParseOptions) -> Option<RewrittenHtml> {
+    if !options.images && !options.code && !options.tables {
+        return None;
+    }
+
+    let mut document = Html::parse_document(html);
+    let mut blocks = Vec::new();
+    if options.code {
+        rewrite_code_blocks(&mut document, &mut blocks);
+    }
+    if options.tables {
+        rewrite_tables(&mut document, &mut blocks);
+    }
+    if options.images {
+        rewrite_images(&mut document);
+    }
+
+    Some(RewrittenHtml {
+        html: document.html(),
+        blocks,
+    })
+}
+
+/// Replaces `<pre>` blocks with placeholders of fenced code blocks.
+fn rewrite_code_blocks(document: &mut Html, blocks: &mut Vec<String>) {
+    replace_children(document, "pre", |element| {
+        let code: String = element.text().collect();
+        let code = code.trim_matches('\n');
+        if code.trim().is_empty() {
+            return None;
+        }
+
+        let language = code_language(element).unwrap_or_default();
+        let fence = "`".repeat(longest_backtick_run(code).max(2) + 1);
+        blocks.push(format!("{fence}{language}\n{code}\n{fence}"));
+        Some(format!("LLAMAPBLOCK{}END", blocks.len() - 1))
+    });
+}
+
+/// Finds the language of a code block in class names of the `<pre>` element or its `<code>` child.
+fn code_language(element: ElementRef) -> Option<String> {
+    let code_selector = Selector::parse("code").ok()?;
+    std::iter::once(element)
+        .chain(element.select(&code_selector))
+        .flat_map(|element| element.value().classes())
+        .find_map(|class| {
+            LANGUAGE_CLASS_PREFIXES
+                .iter()
+                .find_map(|prefix| class.strip_prefix(prefix))
+        })
+        .filter(|language| !language.is_empty())
+        .map(str::to_string)
+}
+
+/// Returns the length of the longest run of backticks in the text.
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|character| character != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Replaces simple tables with placeholders of GitHub-flavored markdown tables.
+fn rewrite_tables(document: &mut Html, blocks: &mut Vec<String>) {
+    replace_elements(document, "table", |table| {
+        let table = render_table(table)?;
+        blocks.push(table);
+        Some(format!("LLAMAPBLOCK{}END", blocks.len() - 1))
+    });
+}
+
+/// Renders a table as a GitHub-flavored markdown table, using its first row as the header.
+///
+/// Returns `None` for tables which are not simple: nested, spanning cells or too large.
+fn render_table(table: ElementRef) -> Option<String> {
+    let nested_selector = Selector::parse("table").ok()?;
+    let row_selector = Selector::parse("tr").ok()?;
+    let cell_selector = Selector::parse("th, td").ok()?;
+    if table.select(&nested_selector).next().is_some() {
+        return None;
+    }
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for row in table.select(&row_selector) {
+        let mut cells = Vec::new();
+        for cell in row.select(&cell_selector) {
+            let spans = ["colspan", "rowspan"].iter().any(|span| {
+                cell.value()
+                    .attr(span)
+                    .is_some_and(|value| value.trim() != "1")
+            });
+            if spans {
+                return None;
+            }
+
+            let text = collapse_whitespace(&cell.text().collect::<Vec<_>>().join(" "));
+            cells.push(text.replace('|', "\\|"));
+        }
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+
+    let columns = rows.iter().map(Vec::len).max()?;
+    if rows.len() * columns > MAX_TABLE_CELLS {
+        return None;
+    }
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (index, mut cells) in rows.into_iter().enumerate() {
+        cells.resize(columns, String::new());
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if index == 0 {
+            lines.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+
+    Some(lines.join("\n"))
+}
+
+/// Replaces images with `![alt](src)` and figure captions with italicized text.
+fn rewrite_images(document: &mut Html) {
+    replace_elements(document, "img[alt]", |element| {
+        let alt = collapse_whitespace(element.value().attr("alt").unwrap_or_default());
+        if alt.is_empty() {
+            return None;
+        }
+
+        let src = element.value().attr("src").unwrap_or_default();
+        Some(format!("![{alt}]({src})"))
+    });
+
+    replace_children(document, "figcaption", |element| {
+        let caption = collapse_whitespace(&element.text().collect::<Vec<_>>().join(" "));
+        (!caption.is_empty()).then(|| format!("*{caption}*"))
+    });
+}
+
+/// Replaces elements matching the selector with the text rendered from them.
+/// Elements for which nothing is rendered are kept.
+fn replace_elements<F>(document: &mut Html, selector: &str, mut render: F)
+where
+    F: FnMut(ElementRef) -> Option<String>,
+{
+    let Ok(selector) = Selector::parse(selector) else {
+        return;
+    };
+    let replacements: Vec<_> = document
+        .select(&selector)
+        .filter_map(|element| render(element).map(|text| (element.id(), text)))
+        .collect();
+
+    for (id, text) in replacements {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.insert_before(text_node(&text));
+            node.detach();
+        }
+    }
+}
+
+/// Replaces children of elements matching the selector with the text rendered from them,
+/// keeping the elements themselves. Elements for which nothing is rendered are kept intact.
+fn replace_children<F>(document: &mut Html, selector: &str, mut render: F)
+where
+    F: FnMut(ElementRef) -> Option<String>,
+{
+    let Ok(selector) = Selector::parse(selector) else {
+        return;
+    };
+    let replacements: Vec<_> = document
+        .select(&selector)
+        .filter_map(|element| render(element).map(|text| (element.id(), text)))
+        .collect();
+
+    for (id, text) in replacements {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            while let Some(mut child) = node.first_child() {
+                child.detach();
+            }
+            node.append(text_node(&text));
+        }
+    }
+}
+
+/// Creates a text node with the given content.
+fn text_node(text: &str) -> Node {
+    Node::Text(Text { text: text.into() })
+}
+
+/// Collapses runs of whitespace into single spaces and trims the text.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
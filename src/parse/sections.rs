@@ -0,0 +1,80 @@
+//! Splitting of long reference pages into sections at their anchored headings,
+//! so that every section of a mega-page can be summarized and linked on its own.
+
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+
+use crate::storage::PageSection;
+
+/// Headings starting the sections of a page when they have an `id` to link to.
+const ANCHORED_HEADING_SELECTOR: &str = "h2[id], h3[id], h4[id]";
+
+/// Splits the extracted text of a page at the lines holding its anchored headings.
+///
+/// The text before the first anchored heading belongs to the page only,
+/// and sections without text are left out.
+pub(super) fn split_sections(html: &str, text: &str) -> Vec<PageSection> {
+    let mut anchors = anchored_headings(html);
+    if anchors.is_empty() {
+        return Vec::new();
+    }
+
+    let mut sections: Vec<PageSection> = Vec::new();
+    for line in text.lines() {
+        let heading = heading_text(line.trim_start_matches('#'));
+        if let Some(anchor) = anchors.remove(&heading) {
+            sections.push(PageSection {
+                anchor,
+                heading,
+                text: String::new(),
+                summary: None,
+            });
+        } else if let Some(section) = sections.last_mut() {
+            // Skips the setext underline of the heading
+            let underline = line.trim();
+            if section.text.is_empty()
+                && !underline.is_empty()
+                && underline.chars().all(|c| c == '=' || c == '-')
+            {
+                continue;
+            }
+            section.text.push_str(line);
+            section.text.push('\n');
+        }
+    }
+
+    sections
+        .into_iter()
+        .map(|section| PageSection {
+            text: section.text.trim().to_string(),
+            ..section
+        })
+        .filter(|section| !section.text.is_empty())
+        .collect()
+}
+
+/// Returns the anchors of the headings of a page by their text, the first one of repeated headings.
+fn anchored_headings(html: &str) -> HashMap<String, String> {
+    let Ok(selector) = Selector::parse(ANCHORED_HEADING_SELECTOR) else {
+        return HashMap::new();
+    };
+
+    let mut anchors = HashMap::new();
+    for heading in Html::parse_document(html).select(&selector) {
+        let text = heading_text(&heading.text().collect::<Vec<_>>().join(" "));
+        if let Some(anchor) = heading.value().id().filter(|_| !text.is_empty()) {
+            anchors.entry(text).or_insert_with(|| anchor.to_string());
+        }
+    }
+    anchors
+}
+
+/// Returns the text of a heading with collapsed whitespace, without markdown emphasis.
+fn heading_text(heading: &str) -> String {
+    heading
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == '*' || c == '_' || c.is_whitespace())
+        .to_string()
+}
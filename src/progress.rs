@@ -0,0 +1,65 @@
+//! The progress module lets applications embedding llamap follow runs page by page,
+//! e.g. to show a progress bar, without parsing the log output.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Receives the progress of pipeline runs. Every method does nothing by default.
+///
+/// Methods are called from the tasks of the run, so they should return quickly.
+pub trait ProgressReporter: Send + Sync {
+    /// Called once a page is stored by a scrape run.
+    fn on_page_scraped(&self, _url: &str) {}
+
+    /// Called once the extracted text of a page is stored by a parse run.
+    fn on_page_parsed(&self, _url: &str) {}
+
+    /// Called once the summary of a page is stored by a summarize run.
+    fn on_page_summarized(&self, _url: &str) {}
+
+    /// Called once a `scrape`, `parse`, `summarize` or `compose` run completes or is interrupted,
+    /// with the number of pages the run stored, parsed, summarized or composed.
+    fn on_stage_complete(&self, _stage: &str, _pages: usize) {}
+}
+
+/// The reporter of a run given in its options, reporting nothing by default.
+#[derive(Clone, Default)]
+pub struct Progress(Option<Arc<dyn ProgressReporter>>);
+
+impl Progress {
+    /// Creates a progress reporting to the given reporter.
+    pub fn new(reporter: Arc<dyn ProgressReporter>) -> Self {
+        Progress(Some(reporter))
+    }
+
+    pub(crate) fn page_scraped(&self, url: &str) {
+        if let Some(reporter) = &self.0 {
+            reporter.on_page_scraped(url);
+        }
+    }
+
+    pub(crate) fn page_parsed(&self, url: &str) {
+        if let Some(reporter) = &self.0 {
+            reporter.on_page_parsed(url);
+        }
+    }
+
+    pub(crate) fn page_summarized(&self, url: &str) {
+        if let Some(reporter) = &self.0 {
+            reporter.on_page_summarized(url);
+        }
+    }
+
+    pub(crate) fn stage_complete(&self, stage: &str, pages: usize) {
+        if let Some(reporter) = &self.0 {
+            reporter.on_stage_complete(stage, pages);
+        }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reporter = if self.0.is_some() { "reporter" } else { "none" };
+        write!(formatter, "Progress({reporter})")
+    }
+}
@@ -0,0 +1,239 @@
+//! The project module reads projects listing several sites, each with its own database
+//! and output file, so that agencies maintaining llms.txt files of many client sites
+//! can scrape, parse, summarize and compose all of them in one invocation.
+
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::sync::Arc;
+use url::Url;
+
+use crate::environment::interpolate_env;
+use crate::error::Context;
+use crate::{
+    ComposeOptions, Error, Llamap, ParseOptions, ParseTarget, Result, ScrapeOptions,
+    SummarizeOptions,
+    summarize::{RateLimiter, SummaryModel, TokenBucketLimiter},
+};
+
+/// Sites processed together, sharing the summary model and its rate limit
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Project {
+    /// Model URL summarizing the pages of every site, pages aren't summarized if None
+    pub model: Option<String>,
+    /// Requests per minute of the model across all sites run at once, no limit if None
+    pub rpm: Option<u32>,
+    /// The sites of the project, with unique names
+    pub sites: Vec<Site>,
+}
+
+/// A site of a project
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Site {
+    /// Name selecting the site on the command line
+    pub name: String,
+    /// The sitemap listing the pages of the site
+    pub sitemap: Url,
+    /// Path to the database of the site
+    pub db: String,
+    /// Path to the llms.txt file composed for the site
+    pub output: String,
+    /// Delay between requests to the site in milliseconds, the scrape default if None
+    pub delay: Option<u64>,
+    /// Number of concurrent requests to the site, the scrape default if None
+    pub concurrency: Option<usize>,
+}
+
+impl Project {
+    /// Reads a project from a JSON file, see [`Project::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file can't be read, or [`Error::Config`] if the project is invalid
+    pub fn load(path: &str) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read project {path}"))?;
+        Self::parse(&json)
+    }
+
+    /// Reads a project written as JSON, like
+    /// `{"model": "ollama://8b@qwen3", "rpm": 60, "sites": [{"name": "example",
+    /// "sitemap": "https://example.com/sitemap.xml", "db": "example.sqlite", "output": "example.txt"}]}`.
+    /// Sites may set the `"delay"` between requests in milliseconds and their `"concurrency"`.
+    /// `${NAME}` references to environment variables in the model and sites are replaced by their values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] if the JSON is invalid, a site misses a field or has an invalid
+    /// sitemap URL, two sites share a name, a database or an output file,
+    /// or a referenced environment variable isn't set
+    pub fn parse(json: &str) -> Result<Self> {
+        let project: Value = serde_json::from_str(json)
+            .map_err(|error| Error::Config(error.into()).context("Invalid project"))?;
+        let sites = project
+            .get("sites")
+            .and_then(Value::as_array)
+            .ok_or_else(|| config_error("A project must list its sites under \"sites\""))?
+            .iter()
+            .map(parse_site)
+            .collect::<Result<Vec<Site>>>()?;
+        ensure_unique(&sites)?;
+
+        Ok(Self {
+            model: project
+                .get("model")
+                .and_then(Value::as_str)
+                .map(interpolate_env)
+                .transpose()?,
+            rpm: project
+                .get("rpm")
+                .and_then(Value::as_u64)
+                .map(|rpm| u32::try_from(rpm).unwrap_or(u32::MAX)),
+            sites,
+        })
+    }
+
+    /// Writes the project as JSON, read back by [`Project::parse`].
+    pub fn to_json(&self) -> String {
+        let sites: Vec<Value> = self
+            .sites
+            .iter()
+            .map(|site| {
+                let mut json = json!({
+                    "name": site.name,
+                    "sitemap": site.sitemap.as_str(),
+                    "db": site.db,
+                    "output": site.output,
+                });
+                if let Some(object) = json.as_object_mut() {
+                    if let Some(delay) = site.delay {
+                        object.insert("delay".to_string(), delay.into());
+                    }
+                    if let Some(concurrency) = site.concurrency {
+                        object.insert("concurrency".to_string(), concurrency.into());
+                    }
+                }
+                json
+            })
+            .collect();
+        let mut project = json!({ "sites": sites });
+        if let Some(object) = project.as_object_mut() {
+            if let Some(model) = &self.model {
+                object.insert("model".to_string(), model.as_str().into());
+            }
+            if let Some(rpm) = self.rpm {
+                object.insert("rpm".to_string(), rpm.into());
+            }
+        }
+        serde_json::to_string_pretty(&project).unwrap_or_else(|_| project.to_string())
+    }
+
+    /// Returns the site with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] if the project has no such site
+    pub fn site(&self, name: &str) -> Result<&Site> {
+        self.sites
+            .iter()
+            .find(|site| site.name == name)
+            .ok_or_else(|| config_error(&format!("The project has no site named {name}")))
+    }
+
+    /// Returns the summarize options of the site runs, whose clones share one limiter of the
+    /// rate limit of the model, so that all runs together stay within it. None without a model.
+    pub fn summarize_options(&self) -> Option<SummarizeOptions> {
+        self.model.as_ref().map(|model| SummarizeOptions {
+            model_name: model.clone(),
+            rpm: self.rpm,
+            rate_limiter: self
+                .rpm
+                .and_then(TokenBucketLimiter::per_minute)
+                .map(|limiter| Arc::new(limiter) as Arc<dyn RateLimiter>),
+            ..SummarizeOptions::default()
+        })
+    }
+}
+
+impl Site {
+    /// Scrapes the sitemap of the site, parses its pages, summarizes them if a model is given
+    /// and composes its output file, recording every run in the database of the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `summary` - The model summarizing the pages and the options of the run, if summarized
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first failing stage
+    pub async fn run(&self, summary: Option<(SummaryModel, SummarizeOptions)>) -> Result<()> {
+        let client = Llamap::open(&self.db)?;
+        let defaults = ScrapeOptions::default();
+        let scrape_options = ScrapeOptions {
+            delay: self.delay.unwrap_or(defaults.delay),
+            concurrency: self.concurrency.unwrap_or(defaults.concurrency),
+            ..defaults
+        };
+        client.scrape(self.sitemap.clone(), scrape_options).await?;
+        client
+            .parse(ParseTarget::All, &ParseOptions::default())
+            .await?;
+        if let Some((model, options)) = summary {
+            client.summarize(model, options).await?;
+        }
+        client
+            .compose(&self.output, ComposeOptions::default(), None)
+            .await
+    }
+}
+
+/// Reads a site of a project.
+fn parse_site(site: &Value) -> Result<Site> {
+    let field = |name: &str| {
+        site.get(name)
+            .and_then(Value::as_str)
+            .ok_or_else(|| config_error(&format!("Every site of a project needs a \"{name}\"")))
+            .and_then(interpolate_env)
+    };
+    let name = field("name")?;
+    let sitemap = Url::parse(&field("sitemap")?).map_err(|error| {
+        Error::Config(error.into()).context(format!("Invalid sitemap URL of {name}"))
+    })?;
+
+    Ok(Site {
+        sitemap,
+        db: field("db")?,
+        output: field("output")?,
+        delay: site.get("delay").and_then(Value::as_u64),
+        concurrency: site
+            .get("concurrency")
+            .and_then(Value::as_u64)
+            .map(|concurrency| usize::try_from(concurrency).unwrap_or(usize::MAX)),
+        name,
+    })
+}
+
+/// Checks that no two sites share a name, a database or an output file,
+/// which concurrent runs would overwrite.
+fn ensure_unique(sites: &[Site]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for site in sites {
+        for (kind, value) in [
+            ("name", &site.name),
+            ("database", &site.db),
+            ("output file", &site.output),
+        ] {
+            if !seen.insert((kind, value.as_str())) {
+                return Err(config_error(&format!(
+                    "Two sites of the project share the {kind} {value}"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a configuration error with the given message.
+fn config_error(message: &str) -> Error {
+    Error::Config(message.into())
+}
@@ -0,0 +1,142 @@
+//! The robots module reads indexing directives of `<meta name="robots">` tags and
+//! `X-Robots-Tag` headers, so that pages their site asks not to index are left out
+//! of summaries and llms.txt files, and the sitemaps, crawl delay and path rules `robots.txt` declares.
+
+use std::time::Duration;
+use url::Url;
+
+/// Directives asking search engines and crawlers not to index a page.
+const NOINDEX_DIRECTIVES: [&str; 2] = ["noindex", "none"];
+
+/// Returns `true` if a comma separated list of robots directives contains `noindex`.
+///
+/// Directives scoped to a crawler (`googlebot: noindex`) are honoured as well,
+/// as are several headers joined with commas.
+///
+/// # Arguments
+///
+/// * `directives` - The `content` of a robots meta tag or an `X-Robots-Tag` header value
+pub fn has_noindex(directives: &str) -> bool {
+    directives.split(',').any(|directive| {
+        let directive = directive.rsplit(':').next().unwrap_or_default();
+        NOINDEX_DIRECTIVES.contains(&directive.trim().to_lowercase().as_str())
+    })
+}
+
+/// Sitemaps, crawl delay and path rules declared by the `robots.txt` file of a site
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RobotsTxt {
+    /// URLs of the `Sitemap:` lines, in their order
+    pub sitemaps: Vec<Url>,
+    /// `Crawl-delay:` of the group of every user agent (`User-agent: *`), if any
+    pub crawl_delay: Option<Duration>,
+    /// `Allow:` and `Disallow:` rules of the group of every user agent, in their order
+    pub rules: Vec<RobotsRule>,
+}
+
+/// An `Allow:` or `Disallow:` line of `robots.txt`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RobotsRule {
+    /// Whether the line allows the paths rather than disallows them
+    pub allow: bool,
+    /// Path prefix the rule applies to, with `*` wildcards and an optional `$` end anchor
+    pub path: String,
+}
+
+impl RobotsTxt {
+    /// Returns whether the rules allow crawling a URL: the longest rule matching its path
+    /// and query decides, `Allow:` winning ties, and URLs matching no rule are allowed.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+        self.rules
+            .iter()
+            .filter(|rule| path_matches(&rule.path, &path))
+            .max_by_key(|rule| (rule.path.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// Returns whether a path matches the path pattern of a rule.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        if anchored && index + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        let Some(position) = rest.find(part) else {
+            return false;
+        };
+        rest = rest.get(position + part.len()..).unwrap_or_default();
+    }
+
+    !anchored || rest.is_empty()
+}
+
+/// Reads the sitemaps, crawl delay and path rules of a `robots.txt` file.
+///
+/// Invalid sitemap URLs and crawl delays are skipped, as are crawl delays and rules of groups
+/// of specific user agents, and empty `Disallow:` lines allowing everything.
+///
+/// # Arguments
+///
+/// * `content` - The content of the `robots.txt` file
+pub fn parse_robots_txt(content: &str) -> RobotsTxt {
+    let mut robots = RobotsTxt::default();
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut in_agent_lines = false;
+
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((field, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match field.trim().to_lowercase().as_str() {
+            "user-agent" => {
+                // Consecutive user-agent lines share the rules following them
+                if !in_agent_lines {
+                    group_agents.clear();
+                }
+                group_agents.push(value.to_string());
+                in_agent_lines = true;
+            }
+            "sitemap" => {
+                if let Ok(url) = Url::parse(value) {
+                    robots.sitemaps.push(url);
+                }
+            }
+            "crawl-delay" => {
+                in_agent_lines = false;
+                if group_agents.iter().any(|agent| agent == "*")
+                    && let Ok(seconds) = value.parse::<f64>()
+                    && let Ok(delay) = Duration::try_from_secs_f64(seconds)
+                {
+                    robots.crawl_delay = Some(delay);
+                }
+            }
+            field @ ("allow" | "disallow") => {
+                in_agent_lines = false;
+                if group_agents.iter().any(|agent| agent == "*") && !value.is_empty() {
+                    robots.rules.push(RobotsRule {
+                        allow: field == "allow",
+                        path: value.to_string(),
+                    });
+                }
+            }
+            _ => in_agent_lines = false,
+        }
+    }
+
+    robots
+}
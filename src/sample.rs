@@ -0,0 +1,192 @@
+//! Reproducible samples of stored pages written as a markdown review bundle, so that editors
+//! can check the texts and summaries of a pipeline run without writing SQL, and review the
+//! same pages again after changing a prompt.
+
+use log::info;
+
+use crate::stdio::{is_stdio, write_stdout};
+use crate::storage::{Page, PageFilter, Storage, hash_html};
+use crate::{Error, Result};
+
+/// Default number of characters of the text excerpts of sampled pages.
+pub const DEFAULT_EXCERPT_CHARS: usize = 600;
+
+/// What the review bundle shows of every sampled page besides its URL, title and text excerpt
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum ReviewField {
+    /// The summary of the page
+    Summary,
+    /// The keywords of a structured summary
+    Keywords,
+    /// The headings and summaries of the sections parse split the page into
+    Sections,
+}
+
+/// Pages sampled for a review bundle and what it shows of them
+#[derive(Clone, Debug, Default)]
+pub struct SampleOptions {
+    /// Maximal number of sampled pages
+    pub size: usize,
+    /// Seed of the sample, the same seed samples the same pages of a database
+    pub seed: u64,
+    /// What is shown of every page besides its text excerpt
+    pub fields: Vec<ReviewField>,
+    /// Number of characters of the text excerpts
+    pub excerpt_chars: usize,
+    /// Conditions the sampled pages must match
+    pub filter: PageFilter,
+}
+
+/// Picks up to `size` URLs ranked by the hash of the seed and the URL, and returns them in URL order.
+/// As the rank of a URL doesn't depend on the other URLs, pages stored since an earlier sample
+/// only replace the pages they outrank.
+///
+/// # Arguments
+///
+/// * `urls` - The URLs to sample from
+/// * `size` - Maximal number of sampled URLs
+/// * `seed` - Seed of the sample
+pub fn seeded_sample(mut urls: Vec<String>, size: usize, seed: u64) -> Vec<String> {
+    urls.sort_by_cached_key(|url| hash_html(&format!("{seed}:{url}")));
+    urls.truncate(size);
+    urls.sort();
+    urls
+}
+
+/// Writes a markdown review bundle of a seeded sample of the stored pages matching the filter,
+/// showing the URL, title and text excerpt of every page along with the requested fields,
+/// and records the run in the audit log.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database file to read pages from
+/// * `output_path` - Path to the markdown file to write, "-" for stdout
+/// * `options` - Sample size and seed, and what is shown of the pages
+///
+/// # Returns
+///
+/// The number of sampled pages.
+///
+/// # Errors
+///
+/// This function will return an error if database operations fail or the file can't be written.
+pub fn write_review_bundle(
+    db_path: &str,
+    output_path: &str,
+    options: &SampleOptions,
+) -> Result<usize, Error> {
+    let storage = Storage::new(db_path)?;
+    let run_id = storage.start_run(
+        "sample",
+        &format!(
+            "output={output_path} size={} seed={} fields={:?} filter={:?}",
+            options.size, options.seed, options.fields, options.filter
+        ),
+    )?;
+
+    let result = sample_pages(&storage, output_path, options);
+    storage.finish_run(
+        run_id,
+        result.as_ref().ok().copied(),
+        result.as_ref().err().map(|error| error.to_string()),
+    )?;
+
+    result
+}
+
+/// Writes the review bundle of the sampled pages and returns the number of sampled pages.
+fn sample_pages(storage: &Storage, output_path: &str, options: &SampleOptions) -> Result<usize> {
+    let urls = seeded_sample(
+        storage.find_urls(&options.filter)?,
+        options.size,
+        options.seed,
+    );
+    let mut bundle = format!(
+        "# Review sample of {} pages (seed {})\n",
+        urls.len(),
+        options.seed
+    );
+    for url in &urls {
+        if let Some(page) = storage.get_page(url)? {
+            bundle.push_str(&render_page(storage, &page, options)?);
+        }
+    }
+
+    if is_stdio(output_path) {
+        write_stdout(&bundle)?;
+    } else {
+        std::fs::write(output_path, bundle)?;
+    }
+    info!("Sampled {} pages to {output_path}", urls.len());
+    Ok(urls.len())
+}
+
+/// Renders a sampled page with its text excerpt and the requested fields.
+fn render_page(storage: &Storage, page: &Page, options: &SampleOptions) -> Result<String> {
+    let url = page.url.as_str();
+    let mut rendered = format!(
+        "\n## {}\n\n<{url}>\n\n### Text\n\n{}\n",
+        page.title.as_deref().unwrap_or(url),
+        quote(&excerpt(
+            page.text.as_deref().unwrap_or_default(),
+            options.excerpt_chars
+        ))
+    );
+
+    for field in &options.fields {
+        match field {
+            ReviewField::Summary => rendered.push_str(&format!(
+                "\n### Summary\n\n{}\n",
+                page.summary
+                    .as_deref()
+                    .map_or("_Not summarized_".to_string(), quote)
+            )),
+            ReviewField::Keywords => {
+                let keywords = storage.get_page_keywords(url)?;
+                rendered.push_str(&format!(
+                    "\n### Keywords\n\n{}\n",
+                    if keywords.is_empty() {
+                        "_None_".to_string()
+                    } else {
+                        keywords.join(", ")
+                    }
+                ));
+            }
+            ReviewField::Sections => {
+                for section in storage.list_page_sections(url)? {
+                    rendered.push_str(&format!(
+                        "\n### Section: {}\n\n{}\n",
+                        section.heading,
+                        section
+                            .summary
+                            .as_deref()
+                            .map_or("_Not summarized_".to_string(), quote)
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(rendered)
+}
+
+/// Returns the first characters of a text, ending with an ellipsis if it's cut.
+fn excerpt(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    let mut excerpt: String = text.chars().take(max_chars).collect();
+    if excerpt.len() < text.len() {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+/// Quotes a text as a markdown block quote, so that its headings and lists don't break the bundle.
+fn quote(text: &str) -> String {
+    if text.is_empty() {
+        return "_Empty_".to_string();
+    }
+    text.lines()
+        .map(|line| format!("> {line}").trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
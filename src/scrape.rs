@@ -3,16 +3,201 @@
 
 extern crate spider;
 
-use anyhow::{Context, Result};
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
+use reqwest::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, LAST_MODIFIED, RETRY_AFTER};
+use sitemap::structs::{LastMod, UrlEntry};
 use spider::configuration::Configuration;
 use spider::website::Website;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
 use url::Url;
 
-use crate::sitemap::extract_sitemap_url_entries;
-use crate::storage::Storage;
+use crate::auth::Auth;
+use crate::canonical::{normalize_url, normalize_url_str};
+use crate::error::Context;
+use crate::parse::{ParseOptions, is_pdf, parse_scraped_page};
+use crate::progress::Progress;
+use crate::shutdown::Shutdown;
+use crate::sitemap::{SitemapListing, extract_sitemap_listing, extract_url_list_entries};
+use crate::storage::{PageFilter, Redirect, ResponseHeaders, Storage, hash_html};
+use crate::throttle::{AdaptiveThrottle, is_overload_status, parse_retry_after};
+use crate::{Error, Result, StoredPages, TrailingSlash, UnlistedPages, is_glob};
+use retry::is_transient_status;
+
+mod content_filter;
+mod directory;
+mod fetch;
+mod links;
+mod retry;
+mod spider_rules;
+
+pub use content_filter::ContentFilter;
+pub use directory::{process_directory, resolve_file_url};
+pub(crate) use fetch::fetch_with_storage;
+pub use fetch::process_page_url;
+pub use links::process_links;
+pub use retry::RetryPolicy;
+pub use spider_rules::SpiderRules;
+
+/// Checkpoint stage name holding the start time of an interrupted scrape.
+const SCRAPE_CHECKPOINT: &str = "scrape";
+
+/// Options controlling a scrape run
+#[derive(Clone, Debug)]
+pub struct ScrapeOptions {
+    /// Delay between requests to the same host in milliseconds (rate limiting)
+    pub delay: u64,
+    /// Number of concurrent requests per host
+    pub concurrency: usize,
+    /// Stop the crawl once this many pages are stored
+    pub max_pages: Option<usize>,
+    /// Stop the crawl once this many bytes of HTML are stored
+    pub max_bytes: Option<u64>,
+    /// What to do with a trailing slash of page URLs before they are stored
+    pub trailing_slash: TrailingSlash,
+    /// Extensions and content types of documents which aren't stored, like images and archives
+    pub content_filter: ContentFilter,
+    /// URL patterns and crawl budget applied by the spider crawler itself
+    pub spider: SpiderRules,
+    /// Timeout of page requests and retries of requests timing out or failing with a server error
+    pub retry: RetryPolicy,
+    /// Extraction options of pages parsed as soon as they are stored, instead of by a separate
+    /// parse run; boilerplate stripping and the page filter are ignored
+    pub parse: Option<ParseOptions>,
+    /// Whether listed pages which are already stored are fetched again if modified
+    pub stored_pages: StoredPages,
+    /// URLs or URL globs like `https://example.com/blog/*` of listed pages fetched again even if
+    /// their lastmod is unchanged, for pages known to have changed without a sitemap update
+    pub forced_urls: Vec<String>,
+    /// What a completed scrape does with stored pages no longer listed or which failed to be fetched
+    pub unlisted_pages: UnlistedPages,
+    /// Whether the HTML of pages is stored, otherwise only its hash is, see [`hash_html`];
+    /// pages must then be parsed on scrape as they can't be parsed later
+    pub store_html: bool,
+    /// Credentials sent with every sitemap and page request, for password-protected sites
+    pub auth: Option<Auth>,
+    /// Token stopping the crawl gracefully once cancelled, keeping the stored pages;
+    /// Ctrl-C stops it if None
+    pub cancel: Option<CancellationToken>,
+    /// Reporter of stored pages
+    pub progress: Progress,
+    /// Channel receiving the URL of every page parsed on scrape, so that it can be summarized
+    /// while the crawl goes on, see [`crate::summarize_while_scraping`]
+    pub parsed_pages: Option<mpsc::UnboundedSender<String>>,
+}
+
+impl Default for ScrapeOptions {
+    fn default() -> Self {
+        Self {
+            delay: 1000,
+            concurrency: 1,
+            max_pages: None,
+            max_bytes: None,
+            trailing_slash: TrailingSlash::default(),
+            content_filter: ContentFilter::default(),
+            spider: SpiderRules::default(),
+            retry: RetryPolicy::default(),
+            parse: None,
+            stored_pages: StoredPages::default(),
+            forced_urls: Vec::new(),
+            unlisted_pages: UnlistedPages::default(),
+            store_html: true,
+            auth: None,
+            cancel: None,
+            progress: Progress::default(),
+            parsed_pages: None,
+        }
+    }
+}
+
+impl ScrapeOptions {
+    /// Returns `true` once the run is cancelled by the embedding application.
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Returns `true` if storing one more page would exceed the page limit
+    /// or the given number of bytes exceeds the byte limit.
+    fn exceeds_limits(&self, stored_pages: usize, stored_bytes: u64) -> bool {
+        self.max_pages
+            .is_some_and(|max_pages| stored_pages >= max_pages)
+            || self
+                .max_bytes
+                .is_some_and(|max_bytes| stored_bytes > max_bytes)
+    }
+
+    /// Returns the options of parsing pages as soon as they are stored, moving pages to their
+    /// canonical URL with the trailing slash policy of the scrape.
+    fn parse_options(&self) -> Option<ParseOptions> {
+        self.parse.clone().map(|parse| ParseOptions {
+            trailing_slash: self.trailing_slash.clone(),
+            ..parse
+        })
+    }
+}
+
+/// Results of a scrape run
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScrapeReport {
+    /// Number of pages fetched and stored
+    pub fetched: usize,
+    /// Number of listed pages not fetched as they weren't modified since they were stored
+    pub skipped_unmodified: usize,
+    /// Number of listed URLs not fetched as their extension is skipped
+    pub skipped_filtered: usize,
+    /// Number of listed URLs not fetched as they match an exclusion pattern
+    pub skipped_excluded: usize,
+    /// Pages which failed to be fetched or stored, with the reason
+    pub failed: Vec<(Url, FailureReason)>,
+    /// Number of stored pages removed as they are no longer listed or failed to be fetched
+    pub removed: usize,
+    /// Number of page requests made, including failed and retried ones
+    pub requests: usize,
+    /// Number of bytes of page bodies downloaded
+    pub bytes_downloaded: u64,
+    /// Whether the run was stopped by Ctrl-C or cancelled before all pages were fetched
+    pub interrupted: bool,
+    /// Number of fetched pages dropped unstored as storing fell behind the crawl,
+    /// the dropped listed URLs are among the failed pages
+    pub dropped: usize,
+}
+
+/// Why a page failed to be scraped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureReason {
+    /// The site answered with a non-success HTTP status code
+    Status(u16),
+    /// The site kept answering with 429/503 after all retries
+    Overloaded,
+    /// The page couldn't be stored to the database
+    Storage,
+    /// The page was served with a skipped content type, like an image or an archive
+    SkippedContentType,
+    /// The page was fetched but dropped unstored as storing fell behind the crawl
+    Dropped,
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status(status) => write!(formatter, "HTTP status {status}"),
+            Self::Overloaded => {
+                write!(formatter, "overloaded after {MAX_OVERLOAD_RETRIES} retries")
+            }
+            Self::Storage => write!(formatter, "storage failure"),
+            Self::SkippedContentType => write!(formatter, "skipped content type"),
+            Self::Dropped => write!(formatter, "dropped as storing fell behind"),
+        }
+    }
+}
 
 /// Scrapes a website using its sitemap and saves pages to a local database.
 ///
@@ -20,12 +205,12 @@ use crate::storage::Storage;
 ///
 /// * `sitemap_url` - The URL of the sitemap to scrape
 /// * `db_path` - Path to the database where pages will be stored
-/// * `delay` - Delay between requests in milliseconds (rate limiting)
-/// * `concurrency` - Number of concurrent requests
+/// * `options` - Crawl pace and safety limits
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on success, or an error if any operation fails
+/// Returns the numbers of fetched, skipped and removed pages and the failed ones on success,
+/// or an error if any operation fails
 ///
 /// # Errors
 ///
@@ -41,109 +226,1288 @@ use crate::storage::Storage;
 pub async fn process_sitemap(
     sitemap_url: Url,
     db_path: &str,
-    delay: u64,
-    concurrency: usize,
-) -> Result<()> {
-    let (mut website, storage) =
-        setup_website_and_storage(sitemap_url.as_str(), db_path, delay, concurrency).await?;
-    let (scrape_storage, cleanup_storage) = (Arc::clone(&storage), Arc::clone(&storage));
-    let (failed_url_tx, failed_url_rx) = mpsc::unbounded_channel();
+    options: ScrapeOptions,
+) -> Result<ScrapeReport, Error> {
+    process_sitemaps(vec![sitemap_url], db_path, options).await
+}
 
-    let mut receiver = website
-        .subscribe(888)
-        .context("Unable to create receiver.")?;
+/// Scrapes a website split into several sitemaps without a sitemap index, like [`process_sitemap`].
+///
+/// Entries of all sitemaps are merged before change detection, so that a page listed
+/// by several of them is scraped once and pages listed by any of them are kept.
+///
+/// # Errors
+///
+/// Returns an error if any of the sitemaps can't be read, or network or database operations fail
+pub async fn process_sitemaps(
+    sitemap_urls: Vec<Url>,
+    db_path: &str,
+    options: ScrapeOptions,
+) -> Result<ScrapeReport, Error> {
+    process_source(
+        ScrapeSource::Sitemaps(sitemap_urls),
+        Storage::new(db_path)?,
+        options,
+    )
+    .await
+}
+
+/// Scrapes a website using its sitemap like [`process_sitemap`], discarding the report,
+/// for callers written against the former signature.
+///
+/// # Errors
+///
+/// Returns an error if the sitemap can't be read, or network or database operations fail
+pub async fn scrape_sitemap(
+    sitemap_url: Url,
+    db_path: &str,
+    options: ScrapeOptions,
+) -> Result<(), Error> {
+    process_sitemap(sitemap_url, db_path, options)
+        .await
+        .map(|_| ())
+}
 
-    let handle = tokio::spawn(async move {
-        while let Ok(page) = receiver.recv().await {
-            info!("Scraped {} with {}", page.get_url(), page.status_code);
+/// Scrapes URLs listed one per line in a file and saves pages to a local database.
+///
+/// Empty lines and lines starting with `#` are ignored. The list is read from
+/// stdin if `list_path` is `-`. Listed URLs have no lastmod, so all of them are
+/// re-scraped on every run.
+///
+/// # Arguments
+///
+/// * `list_path` - Path to the file with URLs to scrape, or `-` for stdin
+/// * `db_path` - Path to the database where pages will be stored
+/// * `options` - Crawl pace and safety limits
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The list can't be read
+/// * Network requests fail
+/// * Database operations fail
+pub async fn process_url_list(
+    list_path: &str,
+    db_path: &str,
+    options: ScrapeOptions,
+) -> Result<ScrapeReport, Error> {
+    process_source(
+        ScrapeSource::UrlList(list_path.to_string()),
+        Storage::new(db_path)?,
+        options,
+    )
+    .await
+}
 
-            if !page.status_code.is_success() {
-                warn!("Skipping {} as {}", page.get_url(), page.status_code);
-                if let Err(e) = failed_url_tx.send(page.get_url().to_string()) {
-                    error!("Failed to send failed URL through channel: {}", e);
-                }
-                continue;
+/// Reconciles the database with its sitemaps without crawling: stored pages they no longer
+/// list are deleted, archived or kept as `options.unlisted_pages` asks, like at the end of a scrape.
+///
+/// # Arguments
+///
+/// * `sitemap_urls` - The sitemap or feed URLs listing the pages to keep
+/// * `db_path` - Path to the database with the pages
+/// * `options` - Credentials, trailing slash and content filter of the scrape, and the cleanup policy
+///
+/// # Returns
+///
+/// Returns the number of pages removed from the pages table on success
+///
+/// # Errors
+///
+/// Returns an error if any of the sitemaps can't be read or lists no page, which would
+/// otherwise clean up every stored page, or database operations fail
+pub async fn clean_up_sitemaps(
+    sitemap_urls: Vec<Url>,
+    db_path: &str,
+    options: &ScrapeOptions,
+) -> Result<usize, Error> {
+    let storage = Storage::new(db_path)?;
+    let source = ScrapeSource::Sitemaps(sitemap_urls);
+    let listing = source.extract_entries(options.auth.as_ref()).await?;
+    if listing.entries.is_empty() {
+        return Err(Error::Sitemap(
+            format!("{source} lists no page, nothing is cleaned up").into(),
+        ));
+    }
+
+    let mut entries = normalize_entries(listing.entries, &options.trailing_slash);
+    retain_scrapable(&mut entries, &storage, &options.content_filter)?;
+    clean_up_unlisted(
+        &storage,
+        &[],
+        entries.into_keys().collect(),
+        options.unlisted_pages,
+    )
+}
+
+/// Where the URLs to scrape come from
+#[derive(Debug)]
+pub(crate) enum ScrapeSource {
+    /// Sitemap or feed URLs, whose entries are merged
+    Sitemaps(Vec<Url>),
+    /// A file with newline-delimited URLs, `-` for stdin
+    UrlList(String),
+}
+
+impl std::fmt::Display for ScrapeSource {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sitemaps(sitemap_urls) => {
+                let sitemap_urls: Vec<&str> = sitemap_urls.iter().map(Url::as_str).collect();
+                write!(formatter, "sitemap={}", sitemap_urls.join(","))
             }
+            Self::UrlList(list_path) => write!(formatter, "url_list={list_path}"),
+        }
+    }
+}
 
-            let html = page.get_html();
-            let url = match Url::parse(page.get_url()) {
-                Ok(parsed_url) => parsed_url,
-                Err(parse_error) => {
-                    error!("Error parsing URL {}: {parse_error}", page.get_url());
-                    if let Err(e) = failed_url_tx.send(page.get_url().to_string()) {
-                        error!("Failed to send failed URL through channel: {}", e);
+impl ScrapeSource {
+    /// Reads URL entries of the source and the metadata of their sitemap extensions,
+    /// fetching sitemaps with the given credentials.
+    async fn extract_entries(&self, auth: Option<&Auth>) -> Result<SitemapListing> {
+        match self {
+            Self::Sitemaps(sitemap_urls) => {
+                let mut listing = SitemapListing::default();
+                for sitemap_url in sitemap_urls {
+                    let sitemap_listing =
+                        extract_sitemap_listing(sitemap_url.as_str(), auth).await?;
+                    info!(
+                        "Read {} entries from {sitemap_url}",
+                        sitemap_listing.entries.len()
+                    );
+                    for (url, entry) in sitemap_listing.entries {
+                        listing.entries.entry(url).or_insert(entry);
+                    }
+                    for (url, extensions) in sitemap_listing.extensions {
+                        listing.extensions.entry(url).or_insert(extensions);
                     }
-                    continue;
                 }
-            };
+                Ok(listing)
+            }
+            Self::UrlList(list_path) => {
+                let content = if list_path == "-" {
+                    let mut content = String::new();
+                    tokio::io::stdin()
+                        .read_to_string(&mut content)
+                        .await
+                        .context("Unable to read URL list from stdin")?;
+                    content
+                } else {
+                    tokio::fs::read_to_string(list_path)
+                        .await
+                        .with_context(|| format!("Unable to read URL list {list_path}"))?
+                };
 
-            let metadata = page.get_metadata().as_ref();
+                Ok(SitemapListing {
+                    entries: extract_url_list_entries(&content),
+                    extensions: HashMap::new(),
+                })
+            }
+        }
+    }
+}
+
+/// Scrapes the URLs of a source into the storage, recording the run in the audit log.
+pub(crate) async fn process_source(
+    source: ScrapeSource,
+    storage: Storage,
+    options: ScrapeOptions,
+) -> Result<ScrapeReport, Error> {
+    let storage = Arc::new(storage);
+    let run_id = storage.start_run(
+        "scrape",
+        &format!(
+            "{source} delay={} concurrency={} max_pages={:?} max_bytes={:?} trailing_slash={:?} parse={} store_html={} auth={:?} spider={:?} retry={:?}",
+            options.delay,
+            options.concurrency,
+            options.max_pages,
+            options.max_bytes,
+            options.trailing_slash,
+            options.parse.is_some(),
+            options.store_html,
+            options.auth,
+            options.spider,
+            options.retry
+        ),
+    )?;
+
+    let result = crawl_source(&source, &storage, &options, run_id).await;
+    match &result {
+        Ok(report) => {
+            for (url, reason) in &report.failed {
+                storage.record_failure(run_id, "scrape", url.as_str(), &reason.to_string())?;
+            }
+            storage.finish_run(
+                run_id,
+                Some(report.fetched),
+                report.interrupted.then(|| "Interrupted".to_string()),
+            )?;
+            storage.record_run_traffic(run_id, report.requests, report.bytes_downloaded)?;
+            options.progress.stage_complete("scrape", report.fetched);
+        }
+        Err(error) => storage.finish_run(run_id, None, Some(error.to_string()))?,
+    }
+
+    result
+}
+
+/// Shared state of all host crawls of a scrape run
+#[derive(Clone)]
+struct CrawlContext {
+    storage: Arc<Storage>,
+    shutdown: Shutdown,
+    budget: Arc<ScrapeBudget>,
+    /// Whether pages already stored with the same HTML are left untouched
+    skip_unchanged: bool,
+    /// What to do with a trailing slash of page URLs before they are stored
+    trailing_slash: TrailingSlash,
+    /// Lastmod dates of the listed URLs, stored as the lastmod of their pages
+    lastmods: Arc<HashMap<String, chrono::DateTime<chrono::Utc>>>,
+    /// Content types of fetched documents which aren't stored
+    content_filter: Arc<ContentFilter>,
+    /// URL patterns and crawl budget applied by the spider crawler
+    spider: Arc<SpiderRules>,
+    /// Timeout and retries of page requests
+    retry: RetryPolicy,
+    /// Requests made and bytes downloaded by all host crawls
+    traffic: Arc<Traffic>,
+    /// Pages stored by the page workers, written to the database in batches by the page handler
+    pending: Arc<Mutex<Vec<ScrapedPage>>>,
+    /// Extraction options of pages parsed as soon as they are stored
+    parse: Option<Arc<ParseOptions>>,
+    /// Whether the HTML of pages is stored, otherwise only its hash is
+    store_html: bool,
+    /// Credentials sent with every page request
+    auth: Option<Auth>,
+    progress: Progress,
+    /// Channel receiving the URL of every page parsed on scrape
+    parsed_pages: Option<mpsc::UnboundedSender<String>>,
+}
+
+/// Requests made and bytes downloaded by all host crawls of a scrape run,
+/// so that operators can check crawls stay within the limits agreed with site owners
+#[derive(Debug, Default)]
+struct Traffic {
+    requests: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl Traffic {
+    /// Counts a page received from the crawler, successful or not.
+    fn record(&self, page: &spider::page::Page) {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        self.bytes
+            .fetch_add(page.get_html_bytes_u8().len() as u64, Ordering::SeqCst);
+    }
+
+    /// Returns the number of page requests made so far.
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of bytes of page bodies downloaded so far.
+    fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::SeqCst)
+    }
+}
+
+/// Page and byte limits shared by all host crawls of a scrape run
+#[derive(Debug, Default)]
+struct ScrapeBudget {
+    max_pages: Option<usize>,
+    max_bytes: Option<u64>,
+    pages: AtomicUsize,
+    bytes: AtomicU64,
+    exhausted: AtomicBool,
+}
 
-            let db_page = crate::storage::Page {
-                url,
-                added_at: chrono::Utc::now(),
-                lastmod: chrono::Utc::now(),
-                html,
-                title: metadata.and_then(|meta| meta.title.clone().map(|title| title.to_string())),
-                text: None,
-                summary: None,
+impl ScrapeBudget {
+    /// Reserves the budget for a page of the given size if it fits the limits, leaving
+    /// the budget untouched otherwise. Returns `false` and marks the budget exhausted
+    /// if the page exceeds a limit.
+    fn try_reserve(&self, bytes: u64) -> bool {
+        let page_reserved = self
+            .pages
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pages| {
+                self.max_pages
+                    .is_none_or(|max_pages| pages < max_pages)
+                    .then_some(pages + 1)
+            })
+            .is_ok();
+        let reserved = page_reserved
+            && self
+                .bytes
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |total_bytes| {
+                    let total_bytes = total_bytes.saturating_add(bytes);
+                    self.max_bytes
+                        .is_none_or(|max_bytes| total_bytes <= max_bytes)
+                        .then_some(total_bytes)
+                })
+                .is_ok();
+
+        if !reserved {
+            if page_reserved {
+                self.pages.fetch_sub(1, Ordering::SeqCst);
+            }
+            self.exhausted.store(true, Ordering::SeqCst);
+        }
+        reserved
+    }
+
+    /// Gives back the budget reserved for pages which failed to be stored.
+    fn release(&self, pages: usize, bytes: u64) {
+        self.pages.fetch_sub(pages, Ordering::SeqCst);
+        self.bytes.fetch_sub(bytes, Ordering::SeqCst);
+    }
+
+    /// Returns the number of pages which can still be stored, if limited.
+    fn remaining_pages(&self) -> Option<usize> {
+        self.max_pages
+            .map(|max_pages| max_pages.saturating_sub(self.pages.load(Ordering::SeqCst)))
+    }
+
+    /// Returns `true` once any of the limits is reached.
+    fn is_exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::SeqCst) || self.remaining_pages() == Some(0)
+    }
+}
+
+async fn crawl_source(
+    source: &ScrapeSource,
+    storage: &Arc<Storage>,
+    options: &ScrapeOptions,
+    run_id: i64,
+) -> Result<ScrapeReport> {
+    let started_at = chrono::Utc::now();
+    let (entries, skipped_filtered, skipped_excluded) =
+        read_scrapable_entries(source, storage, options).await?;
+    storage.save_sitemap_snapshot(run_id, &entries)?;
+    let ctx = CrawlContext {
+        storage: Arc::clone(storage),
+        shutdown: Shutdown::for_run(options.cancel.as_ref()),
+        budget: Arc::new(ScrapeBudget {
+            max_pages: options.max_pages,
+            max_bytes: options.max_bytes,
+            ..Default::default()
+        }),
+        skip_unchanged: false,
+        trailing_slash: options.trailing_slash.clone(),
+        lastmods: Arc::new(listed_lastmods(&entries)),
+        content_filter: Arc::new(options.content_filter.clone()),
+        spider: Arc::new(options.spider.clone()),
+        retry: options.retry,
+        traffic: Arc::default(),
+        pending: Arc::default(),
+        parse: options.parse_options().map(Arc::new),
+        store_html: options.store_html,
+        auth: options.auth.clone(),
+        progress: options.progress.clone(),
+        parsed_pages: options.parsed_pages.clone(),
+    };
+    let listed_urls: Vec<String> = entries.keys().cloned().collect();
+    let scrape_urls = resolve_scrape_urls(entries, storage, options)?;
+    let skipped_unmodified = listed_urls.len().saturating_sub(scrape_urls.len());
+    let urls_by_host = group_urls_by_host(scrape_urls);
+
+    info!(
+        "Starting Crawl on {source} across {} hosts",
+        urls_by_host.len()
+    );
+    let state = crawl_hosts(urls_by_host, options, &ctx).await?;
+
+    let truncated = ctx.budget.is_exhausted();
+    if truncated {
+        warn!(
+            "Scrape truncated by --max-pages/--max-bytes limits: {} pages stored, {} URLs left unscraped",
+            state.stored,
+            state.queue.len()
+        );
+    }
+
+    let mut report = ScrapeReport {
+        fetched: state.stored,
+        skipped_unmodified,
+        skipped_filtered,
+        skipped_excluded,
+        failed: state.failed_urls(&options.trailing_slash),
+        removed: 0,
+        interrupted: ctx.shutdown.is_requested(),
+        requests: ctx.traffic.requests(),
+        bytes_downloaded: ctx.traffic.bytes(),
+        dropped: state.dropped,
+    };
+    if report.interrupted || truncated {
+        save_scrape_checkpoint(storage, started_at)?;
+    } else {
+        storage.clear_checkpoint(SCRAPE_CHECKPOINT)?;
+        report.removed =
+            clean_up_unlisted(storage, &report.failed, listed_urls, options.unlisted_pages)?;
+    }
+
+    Ok(report)
+}
+
+/// Crawls every host concurrently, each with its own crawler and throttle, and merges their states.
+async fn crawl_hosts(
+    urls_by_host: BTreeMap<String, Vec<String>>,
+    options: &ScrapeOptions,
+    ctx: &CrawlContext,
+) -> Result<CrawlState> {
+    let mut hosts = JoinSet::new();
+    for (host_url, urls) in urls_by_host {
+        hosts.spawn(crawl_host(
+            host_url,
+            urls,
+            AdaptiveThrottle::new(options.delay, options.concurrency),
+            ctx.clone(),
+        ));
+    }
+
+    let mut state = CrawlState::default();
+    while let Some(host_state) = hosts.join_next().await {
+        state.merge(
+            host_state
+                .map_err(|error| Error::Http(error.into()).context("Host crawl task failed"))??,
+        );
+    }
+
+    Ok(state)
+}
+
+/// Reads the normalized entries of a source which can be scraped, recording their priorities
+/// and the metadata of their sitemap extensions. Returns them along with the numbers of entries
+/// left out by extension and by exclusion pattern.
+async fn read_scrapable_entries(
+    source: &ScrapeSource,
+    storage: &Storage,
+    options: &ScrapeOptions,
+) -> Result<(HashMap<String, UrlEntry>, usize, usize)> {
+    let listing = source.extract_entries(options.auth.as_ref()).await?;
+    let mut entries = normalize_entries(listing.entries, &options.trailing_slash);
+    let (skipped_filtered, skipped_excluded) =
+        retain_scrapable(&mut entries, storage, &options.content_filter)?;
+    storage.save_priorities(&entries)?;
+    storage.save_sitemap_extensions(&normalize_entries(
+        listing.extensions,
+        &options.trailing_slash,
+    ))?;
+
+    Ok((entries, skipped_filtered, skipped_excluded))
+}
+
+/// Leaves out entries with a skipped extension and entries matching an exclusion pattern,
+/// which are never stored again. Returns the numbers of entries left out for each reason.
+fn retain_scrapable(
+    entries: &mut HashMap<String, UrlEntry>,
+    storage: &Storage,
+    content_filter: &ContentFilter,
+) -> Result<(usize, usize)> {
+    let listed_count = entries.len();
+    entries.retain(|url, _| !content_filter.skips_url(url));
+    let filtered_count = entries.len();
+
+    let mut excluded = HashSet::new();
+    for url in entries.keys() {
+        if storage.is_excluded(url)? {
+            info!("Skipping {url} as it's excluded");
+            excluded.insert(url.clone());
+        }
+    }
+    entries.retain(|url, _| !excluded.contains(url));
+
+    Ok((listed_count - filtered_count, excluded.len()))
+}
+
+/// Records the start time of a crawl which stopped early, unless a previous crawl it resumes
+/// already recorded its own.
+fn save_scrape_checkpoint(
+    storage: &Storage,
+    started_at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    if storage.get_checkpoint(SCRAPE_CHECKPOINT)?.is_none() {
+        storage.save_checkpoint(SCRAPE_CHECKPOINT, &started_at.timestamp().to_string())?;
+    }
+    warn!("Scrape stopped early, run the same command again to resume");
+    Ok(())
+}
+
+/// Cleans up stored pages which are no longer listed or failed to be fetched as the policy asks,
+/// keeping the targets of listed redirects and the pages dropped or failing to be stored,
+/// which the site served fine, and returns the number of pages removed from storage.
+fn clean_up_unlisted(
+    storage: &Storage,
+    failed: &[(Url, FailureReason)],
+    listed_urls: Vec<String>,
+    unlisted_pages: UnlistedPages,
+) -> Result<usize> {
+    if storage.is_empty()? {
+        return Ok(0);
+    }
+
+    let failed: HashSet<&str> = failed
+        .iter()
+        .filter(|(_, reason)| !matches!(reason, FailureReason::Dropped | FailureReason::Storage))
+        .map(|(url, _)| url.as_str())
+        .collect();
+    let mut redirects: HashMap<String, String> = storage
+        .list_redirects()?
+        .into_iter()
+        .map(|redirect| (redirect.url, redirect.target))
+        .collect();
+    let kept_urls = listed_urls
+        .into_iter()
+        .filter(|url| !failed.contains(url.as_str()))
+        .map(|url| redirects.remove(&url).unwrap_or(url));
+    let removed = clean_up_unvisited(storage, kept_urls, unlisted_pages)?;
+    info!("Removed {removed} unlisted or failed pages from storage");
+
+    Ok(removed)
+}
+
+/// Deletes, archives or keeps the stored pages which weren't visited as the policy asks,
+/// and returns the number of pages removed from storage.
+fn clean_up_unvisited(
+    storage: &Storage,
+    visited_urls: impl IntoIterator<Item = String>,
+    unlisted_pages: UnlistedPages,
+) -> Result<usize> {
+    Ok(match unlisted_pages {
+        UnlistedPages::Kept => 0,
+        UnlistedPages::Deleted => storage.remove_unvisited_pages(visited_urls)?,
+        UnlistedPages::Archived => storage.archive_unvisited_pages(visited_urls)?,
+    })
+}
+
+/// Crawls URLs of a single host in rounds with its own throttle,
+/// so that delays and backoff apply per host rather than globally.
+async fn crawl_host(
+    host_url: String,
+    urls: Vec<String>,
+    mut throttle: AdaptiveThrottle,
+    ctx: CrawlContext,
+) -> Result<CrawlState> {
+    let mut state = CrawlState {
+        queue: urls.into(),
+        ..Default::default()
+    };
+
+    while !state.queue.is_empty() && !ctx.shutdown.is_requested() && !ctx.budget.is_exhausted() {
+        let round_size = ctx
+            .budget
+            .remaining_pages()
+            .unwrap_or(ROUND_SIZE)
+            .min(ROUND_SIZE)
+            .min(state.queue.len());
+        let round: Vec<String> = state.queue.drain(..round_size).collect();
+        let round_outcome = crawl_round(&round, &throttle, &ctx).await?;
+
+        if let Some(pause) = state.absorb(&host_url, round_outcome, &mut throttle, &ctx.retry) {
+            info!("Pausing {host_url} for {pause:?} before the next requests");
+            tokio::select! {
+                () = tokio::time::sleep(pause) => {},
+                () = ctx.shutdown.requested() => {},
             };
+        }
+    }
+
+    Ok(state)
+}
+
+/// Groups URLs by the root URL of their origin (scheme, host and port).
+fn group_urls_by_host(urls: Vec<String>) -> BTreeMap<String, Vec<String>> {
+    let mut urls_by_host: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for url in urls {
+        match Url::parse(&url) {
+            Ok(parsed_url) => urls_by_host
+                .entry(format!("{}/", parsed_url.origin().ascii_serialization()))
+                .or_default()
+                .push(url),
+            Err(parse_error) => warn!("Skipping invalid URL {url}: {parse_error}"),
+        }
+    }
+
+    urls_by_host
+}
+
+/// Number of URLs crawled in one round, after which the crawl pace is adapted.
+const ROUND_SIZE: usize = 50;
+
+/// Number of times an URL answering with 429/503 is re-queued before it's given up.
+const MAX_OVERLOAD_RETRIES: u8 = 3;
+
+/// Pages received from the crawler during one round
+#[derive(Default)]
+struct RoundOutcome {
+    /// Number of pages stored to the database
+    stored: usize,
+    /// URLs which failed to be fetched or stored, with the reason
+    failed: Vec<(String, FailureReason)>,
+    /// URLs which timed out or answered with a server error, with the reason
+    transient: Vec<(String, FailureReason)>,
+    /// URLs answered with 429/503
+    overloaded: Vec<String>,
+    /// URLs fetched after the scrape limits were reached, so not stored
+    over_budget: Vec<String>,
+    /// The longest Retry-After pause requested by the site
+    retry_after: Option<Duration>,
+    /// URLs of the pages received from the crawler
+    received: HashSet<String>,
+    /// Number of pages the crawler sent while storing fell behind, which were never received
+    dropped: usize,
+}
+
+impl RoundOutcome {
+    /// Records what became of a page processed by a page worker.
+    fn absorb_page(&mut self, page: PageOutcome) {
+        match page {
+            PageOutcome::Stored => self.stored += 1,
+            PageOutcome::Skipped => {}
+            PageOutcome::Failed(url, FailureReason::Status(status))
+                if is_transient_status(status) =>
+            {
+                self.transient.push((url, FailureReason::Status(status)));
+            }
+            PageOutcome::Failed(url, reason) => self.failed.push((url, reason)),
+            PageOutcome::Overloaded(url, retry_after) => {
+                self.retry_after = self.retry_after.max(retry_after);
+                self.overloaded.push(url);
+            }
+            PageOutcome::OverBudget(url) => self.over_budget.push(url),
+        }
+    }
+
+    /// Returns `true` once a page failed to be stored, after which the round stops.
+    fn has_storage_failure(&self) -> bool {
+        self.failed
+            .iter()
+            .any(|(_, reason)| *reason == FailureReason::Storage)
+    }
+}
+
+/// What became of a page received from the crawler
+enum PageOutcome {
+    /// Stored to the database
+    Stored,
+    /// Left untouched as unchanged or with an invalid URL
+    Skipped,
+    /// Failed to be fetched or stored, or served with a skipped content type
+    Failed(String, FailureReason),
+    /// Answered with 429/503, with the pause requested by Retry-After
+    Overloaded(String, Option<Duration>),
+    /// Fetched after the scrape limits were reached, so not stored
+    OverBudget(String),
+}
+
+/// Progress of a crawl across rounds
+#[derive(Default)]
+struct CrawlState {
+    /// URLs to crawl
+    queue: VecDeque<String>,
+    /// Number of 429/503 answers per URL
+    overloads: HashMap<String, u8>,
+    /// Number of retries per URL which timed out or answered with a server error
+    retries: HashMap<String, u8>,
+    /// Number of pages stored to the database
+    stored: usize,
+    /// URLs which failed to be fetched or stored, with the reason
+    failed: Vec<(String, FailureReason)>,
+    /// Number of fetched pages dropped unstored as storing fell behind the crawl
+    dropped: usize,
+}
+
+impl CrawlState {
+    /// Merges the results of another host crawl into this one.
+    fn merge(&mut self, other: CrawlState) {
+        self.queue.extend(other.queue);
+        self.stored += other.stored;
+        self.failed.extend(other.failed);
+        self.dropped += other.dropped;
+    }
+
+    /// Returns the failed URLs normalized as the stored ones.
+    fn failed_urls(&self, trailing_slash: &TrailingSlash) -> Vec<(Url, FailureReason)> {
+        self.failed
+            .iter()
+            .filter_map(|(url, reason)| {
+                Url::parse(url)
+                    .ok()
+                    .map(|url| (normalize_url(&url, trailing_slash), *reason))
+            })
+            .collect()
+    }
+
+    /// Merges a round into the crawl state, re-queues overloaded and failing URLs
+    /// and adapts the throttle. Returns the pause to take before the next round,
+    /// if the host signalled overload or URLs are retried.
+    fn absorb(
+        &mut self,
+        host_url: &str,
+        round: RoundOutcome,
+        throttle: &mut AdaptiveThrottle,
+        policy: &RetryPolicy,
+    ) -> Option<Duration> {
+        self.stored += round.stored;
+        self.failed.extend(round.failed);
+        self.dropped += round.dropped;
+        self.queue.extend(round.over_budget);
+        let backoff = self.requeue_transient(round.transient, policy);
 
-            if let Err(storage_error) = scrape_storage.upsert_page(&db_page) {
-                error!(
-                    "Error storing page {} with minimal data: {storage_error}",
-                    db_page.url.as_str()
+        if round.overloaded.is_empty() {
+            if throttle.is_throttled() {
+                throttle.ramp_up();
+                info!(
+                    "Speeding up {host_url} to {} ms delay and {} concurrent requests",
+                    throttle.delay(),
+                    throttle.concurrency()
                 );
+            }
+            return backoff;
+        }
+
+        throttle.back_off();
+        warn!(
+            "{} pages of {host_url} answered as overloaded, slowing down to {} ms delay and {} concurrent requests",
+            round.overloaded.len(),
+            throttle.delay(),
+            throttle.concurrency()
+        );
 
-                return;
+        for url in round.overloaded {
+            let overloads = self.overloads.entry(url.clone()).or_default();
+            *overloads += 1;
+            if *overloads > MAX_OVERLOAD_RETRIES {
+                warn!("Giving up on {url} after {MAX_OVERLOAD_RETRIES} retries");
+                self.failed.push((url, FailureReason::Overloaded));
+            } else {
+                self.queue.push_front(url);
             }
         }
-    });
 
-    info!("Starting Crawl on {sitemap_url:?}");
+        Some(
+            round
+                .retry_after
+                .unwrap_or(Duration::from_millis(throttle.delay()))
+                .max(backoff.unwrap_or_default()),
+        )
+    }
+
+    /// Re-queues URLs which timed out or answered with a server error until they run out
+    /// of retries. Returns the backoff pause of the most retried URL, if any is re-queued.
+    fn requeue_transient(
+        &mut self,
+        transient: Vec<(String, FailureReason)>,
+        policy: &RetryPolicy,
+    ) -> Option<Duration> {
+        let mut backoff = None;
+        for (url, reason) in transient {
+            let retries = self.retries.entry(url.clone()).or_default();
+            *retries += 1;
+            if *retries > policy.retries {
+                warn!(
+                    "Giving up on {url} after {} retries: {reason}",
+                    policy.retries
+                );
+                self.failed.push((url, reason));
+            } else {
+                info!("Retrying {url} which failed with {reason}");
+                backoff = backoff.max(Some(policy.pause_before(*retries)));
+                self.queue.push_front(url);
+            }
+        }
+        backoff
+    }
+}
+
+/// Crawls a round of URLs with the current throttle settings.
+///
+/// The crawler starts from the first URL of the round rather than from the host root,
+/// so that a round requests its own URLs only.
+async fn crawl_round(
+    urls: &[String],
+    throttle: &AdaptiveThrottle,
+    ctx: &CrawlContext,
+) -> Result<RoundOutcome> {
+    let Some((start_url, other_urls)) = urls.split_first() else {
+        return Ok(RoundOutcome::default());
+    };
+    let mut website = setup_website(start_url, throttle.delay(), throttle.concurrency(), 0, ctx)?;
+    // Failing URLs are re-queued with a backoff rather than retried right away by the crawler
+    website.with_retry(0);
+    website.set_extra_links(
+        other_urls
+            .iter()
+            .map(spider::CaseInsensitiveString::new)
+            .collect::<spider::hashbrown::HashSet<spider::CaseInsensitiveString>>(),
+    );
     website.persist_links();
-    website.crawl().await;
+
+    let receiver = website
+        .subscribe(888)
+        .ok_or_else(|| Error::Http("Unable to create receiver.".into()))?;
+    // Cancelled by the page handler once storing fails, so the crawler stops fetching pages
+    // which would never be stored
+    let stop = CancellationToken::new();
+    let handle = spawn_page_handler(receiver, ctx.clone(), stop.clone());
+
+    tokio::select! {
+        () = website.crawl() => {},
+        () = ctx.shutdown.requested() => {},
+        () = stop.cancelled() => {},
+    };
     website.unsubscribe();
-    handle.await.context("Task failed to complete")?;
+    // Drain pages that were already fetched before the crawl stopped.
+    let mut outcome = handle
+        .await
+        .map_err(|error| Error::Http(error.into()).context("Task failed to complete"))?;
+    let unreceived_reason = if stop.is_cancelled() {
+        Some(FailureReason::Storage)
+    } else if outcome.dropped > 0 && !ctx.shutdown.is_requested() {
+        Some(FailureReason::Dropped)
+    } else {
+        None
+    };
+    if let Some(reason) = unreceived_reason {
+        let unreceived_urls: Vec<(String, FailureReason)> = urls
+            .iter()
+            .filter(|url| !outcome.received.contains(url.as_str()))
+            .map(|url| (url.clone(), reason))
+            .collect();
+        outcome.failed.extend(unreceived_urls);
+    }
 
-    storage
-        .old
-        .then(async || cleanup_unvisited_pages(website, &cleanup_storage, failed_url_rx).await);
-    Ok(())
+    Ok(outcome)
 }
 
-async fn setup_website_and_storage(
-    sitemap_url_str: &str,
-    db_path: &str,
+/// Number of pages received from the crawler which are processed and stored concurrently.
+const PAGE_WORKERS: usize = 4;
+
+/// Spawns a task handing every page received from the crawler subscription
+/// to a pool of `PAGE_WORKERS` workers, which store them until the scrape budget is exhausted.
+///
+/// Pages wait in the subscription channel while all workers are busy, so that
+/// slow storage writes don't pile up pages in memory. Once storing fails, the page at hand
+/// is recorded as failed to be stored and `stop` is cancelled to end the round.
+fn spawn_page_handler(
+    mut receiver: broadcast::Receiver<spider::page::Page>,
+    ctx: CrawlContext,
+    stop: CancellationToken,
+) -> JoinHandle<RoundOutcome> {
+    tokio::spawn(async move {
+        let mut outcome = RoundOutcome::default();
+        let mut workers = JoinSet::new();
+        loop {
+            let page = match receiver.recv().await {
+                Ok(page) => page,
+                Err(broadcast::error::RecvError::Lagged(dropped)) => {
+                    warn!(
+                        "Storing pages fell behind the crawl, {dropped} fetched pages were dropped unstored"
+                    );
+                    outcome.dropped += usize::try_from(dropped).unwrap_or(usize::MAX);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            ctx.traffic.record(&page);
+            outcome.received.insert(page.get_url().to_string());
+
+            while workers.len() >= PAGE_WORKERS {
+                if let Some(worker) = workers.join_next().await {
+                    absorb_worker(&mut outcome, worker);
+                }
+            }
+            write_pending_pages(&ctx, &mut outcome, WRITE_BATCH_SIZE);
+            if outcome.has_storage_failure() {
+                warn!(
+                    "Stopping the round of {} as storing pages failed",
+                    page.get_url()
+                );
+                outcome
+                    .failed
+                    .push((page.get_url().to_string(), FailureReason::Storage));
+                stop.cancel();
+                break;
+            }
+            let ctx = ctx.clone();
+            workers.spawn_blocking(move || process_page(&page, &ctx));
+        }
+        while let Some(worker) = workers.join_next().await {
+            absorb_worker(&mut outcome, worker);
+        }
+
+        write_pending_pages(&ctx, &mut outcome, 1);
+        outcome
+    })
+}
+
+/// Records the outcome of a finished page worker.
+fn absorb_worker(outcome: &mut RoundOutcome, worker: Result<PageOutcome, tokio::task::JoinError>) {
+    match worker {
+        Ok(page) => outcome.absorb_page(page),
+        Err(join_error) => error!("Page worker failed: {join_error}"),
+    }
+}
+
+/// Stores a page received from the crawler unless it failed to be fetched,
+/// is unchanged or exceeds the scrape limits.
+///
+/// A redirected page is stored at its final URL, recording the redirect of the requested one.
+fn process_page(page: &spider::page::Page, ctx: &CrawlContext) -> PageOutcome {
+    let page_url = page.get_url().to_string();
+    info!("Scraped {page_url} with {}", page.status_code);
+
+    if is_overload_status(page.status_code.as_u16()) {
+        warn!("Site is overloaded at {page_url} with {}", page.status_code);
+        let retry_after = header_value(page, RETRY_AFTER.as_str()).and_then(parse_retry_after);
+        return PageOutcome::Overloaded(page_url, retry_after);
+    }
+
+    if !page.status_code.is_success() {
+        warn!("Skipping {page_url} as {}", page.status_code);
+        return PageOutcome::Failed(page_url, FailureReason::Status(page.status_code.as_u16()));
+    }
+
+    if let Some(content_type) = header_value(page, CONTENT_TYPE.as_str())
+        .filter(|content_type| ctx.content_filter.skips_content_type(content_type))
+    {
+        info!("Skipping {page_url} served as {content_type}");
+        return PageOutcome::Failed(page_url, FailureReason::SkippedContentType);
+    }
+
+    let url = match Url::parse(page.get_url_final()) {
+        Ok(parsed_url) => normalize_url(&parsed_url, &ctx.trailing_slash),
+        Err(parse_error) => {
+            error!("Error parsing URL {page_url}: {parse_error}");
+            return PageOutcome::Skipped;
+        }
+    };
+    let html = page.get_html();
+    if ctx.skip_unchanged && is_stored_unchanged(&ctx.storage, url.as_str(), &html) {
+        info!("Skipping {url} as it's unchanged");
+        return PageOutcome::Skipped;
+    }
+    if ctx
+        .storage
+        .is_excluded(url.as_str())
+        .unwrap_or_else(|storage_error| {
+            warn!("Unable to check whether {url} is excluded: {storage_error}");
+            false
+        })
+    {
+        info!("Skipping {url} as it's excluded");
+        return PageOutcome::Skipped;
+    }
+
+    let listed_url = normalize_url_str(&page_url, &ctx.trailing_slash);
+    store_page(page, url, html, &listed_url, ctx)
+}
+
+/// Stores a crawled page at its normalized final URL unless it exceeds the scrape limits,
+/// recording the redirect of the listed URL if they differ. The budget of the page is
+/// given back if it fails to be stored.
+fn store_page(
+    page: &spider::page::Page,
+    url: Url,
+    html: String,
+    listed_url: &str,
+    ctx: &CrawlContext,
+) -> PageOutcome {
+    let bytes = html.len() as u64;
+    if !ctx.budget.try_reserve(bytes) {
+        info!("Not storing {url} as the scrape limits are reached");
+        return PageOutcome::OverBudget(page.get_url().to_string());
+    }
+
+    let html_hash = (!ctx.store_html).then(|| hash_html(&html));
+    let lastmod = ctx.lastmods.get(listed_url).copied();
+    let mut db_page = build_db_page(page, url, html, lastmod);
+    db_page.html_hash = html_hash;
+    let redirect = (listed_url != db_page.url.as_str()).then(|| {
+        info!("{listed_url} redirects to {}", db_page.url);
+        Redirect {
+            url: listed_url.to_string(),
+            target: db_page.url.to_string(),
+        }
+    });
+    let scraped = ScrapedPage {
+        headers: response_headers(page),
+        redirect,
+        page: db_page,
+        bytes,
+    };
+    let url = scraped.page.url.to_string();
+    if ctx.parse.is_none() {
+        ctx.pending
+            .lock()
+            .expect("Pending pages mutex poisoned")
+            .push(scraped);
+        ctx.progress.page_scraped(&url);
+        return PageOutcome::Stored;
+    }
+
+    // Pages parsed on scrape are written right away, so that the summarizer finds them
+    if let Err(storage_error) = write_scraped_pages(&ctx.storage, std::slice::from_ref(&scraped)) {
+        error!("Error storing page {url} with minimal data: {storage_error}");
+        ctx.budget.release(1, bytes);
+        return PageOutcome::Failed(url, FailureReason::Storage);
+    }
+    ctx.progress.page_scraped(&url);
+    parse_on_scrape(
+        &ctx.storage,
+        scraped.page,
+        ctx.parse.as_deref(),
+        ctx.parsed_pages.as_ref(),
+    );
+    PageOutcome::Stored
+}
+
+/// Extracts the text of a stored page right away if the scrape parses pages,
+/// sending its URL to the pipelined summarizer if there is one.
+/// Failures are logged, leaving the page to a parse run.
+fn parse_on_scrape(
+    storage: &Storage,
+    page: crate::storage::Page,
+    parse_options: Option<&ParseOptions>,
+    parsed_pages: Option<&mpsc::UnboundedSender<String>>,
+) {
+    let Some(parse_options) = parse_options else {
+        return;
+    };
+    let url = page.url.to_string();
+    match parse_scraped_page(storage, page, parse_options) {
+        Ok(()) => {
+            if let Some(parsed_pages) = parsed_pages
+                && parsed_pages.send(url).is_err()
+            {
+                debug!("The summarizer stopped, parsed pages are left to a summarize run");
+            }
+        }
+        Err(parse_error) => {
+            error!("Unable to parse {url}, it's left to the parse command: {parse_error}");
+        }
+    }
+}
+
+/// Number of stored pages committed to the database at once.
+const WRITE_BATCH_SIZE: usize = 100;
+
+/// A page stored by a page worker with the data written along with it
+struct ScrapedPage {
+    page: crate::storage::Page,
+    headers: ResponseHeaders,
+    /// Redirect of the listed URL to the URL the page is stored at, if they differ
+    redirect: Option<Redirect>,
+    /// Size of the HTML reserved in the scrape budget
+    bytes: u64,
+}
+
+/// Writes the pages stored by the page workers once at least `min_pages` of them are pending,
+/// so that the page handler alone writes them, each batch in a transaction of its own.
+/// Pages of a failed batch are recorded as failed to be stored and their budget is given back.
+fn write_pending_pages(ctx: &CrawlContext, outcome: &mut RoundOutcome, min_pages: usize) {
+    let pages = {
+        let mut pending = ctx.pending.lock().expect("Pending pages mutex poisoned");
+        if pending.is_empty() || pending.len() < min_pages {
+            return;
+        }
+        std::mem::take(&mut *pending)
+    };
+
+    if let Err(storage_error) = write_scraped_pages(&ctx.storage, &pages) {
+        error!("Error storing {} pages: {storage_error}", pages.len());
+        ctx.budget
+            .release(pages.len(), pages.iter().map(|scraped| scraped.bytes).sum());
+        outcome.stored = outcome.stored.saturating_sub(pages.len());
+        outcome.failed.extend(
+            pages
+                .into_iter()
+                .map(|scraped| (scraped.page.url.to_string(), FailureReason::Storage)),
+        );
+    }
+}
+
+/// Writes scraped pages with their response headers and redirects in a single transaction.
+fn write_scraped_pages(storage: &Storage, pages: &[ScrapedPage]) -> crate::Result<()> {
+    storage.write_batch(|batch| {
+        for scraped in pages {
+            batch.upsert_page(&scraped.page)?;
+            batch.update_page_headers(scraped.page.url.as_str(), &scraped.headers)?;
+            if let Some(redirect) = &scraped.redirect {
+                batch.record_redirect(redirect)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Returns the value of a response header of a crawled page, if it's valid text.
+fn header_value<'a>(page: &'a spider::page::Page, name: &str) -> Option<&'a str> {
+    page.headers
+        .as_ref()
+        .and_then(|headers| headers.get(name))
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Returns the response headers of a crawled page which are stored along with it.
+fn response_headers(page: &spider::page::Page) -> ResponseHeaders {
+    let header = |name: &str| header_value(page, name).map(str::to_string);
+    ResponseHeaders {
+        content_type: header(CONTENT_TYPE.as_str()),
+        last_modified: header(LAST_MODIFIED.as_str()),
+        etag: header(ETAG.as_str()),
+        cache_control: header(CACHE_CONTROL.as_str()),
+    }
+}
+
+/// Header carrying robots directives of non-HTML documents as well as HTML pages.
+const X_ROBOTS_TAG: &str = "x-robots-tag";
+
+/// Builds a database page of a crawled page, keeping the raw content of PDF documents.
+///
+/// The lastmod of the page is its listed one, or the current time if it isn't listed with one.
+fn build_db_page(
+    page: &spider::page::Page,
+    url: Url,
+    html: String,
+    lastmod: Option<chrono::DateTime<chrono::Utc>>,
+) -> crate::storage::Page {
+    let content_type = header_value(page, CONTENT_TYPE.as_str()).map(str::to_string);
+    let robots_tag = page.headers.as_ref().and_then(|headers| {
+        let values: Vec<&str> = headers
+            .get_all(X_ROBOTS_TAG)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        (!values.is_empty()).then(|| values.join(", "))
+    });
+    let metadata = page.get_metadata().as_ref();
+    let mut db_page = crate::storage::Page {
+        added_at: chrono::Utc::now(),
+        lastmod: lastmod.unwrap_or_else(chrono::Utc::now),
+        html,
+        title: metadata.and_then(|meta| meta.title.clone().map(|title| title.to_string())),
+        text: None,
+        summary: None,
+        content: None,
+        content_type,
+        robots_tag,
+        html_hash: None,
+        url,
+    };
+
+    if is_pdf(db_page.content_type.as_deref(), &db_page.url) {
+        db_page.html = String::new();
+        db_page.title = None;
+        db_page.content = Some(page.get_html_bytes_u8().to_vec());
+    }
+
+    db_page
+}
+
+/// Returns `true` if the page is already stored with the same HTML.
+fn is_stored_unchanged(storage: &Storage, url: &str, html: &str) -> bool {
+    match storage.get_page(url) {
+        Ok(Some(stored_page)) => match &stored_page.html_hash {
+            Some(html_hash) => *html_hash == hash_html(html),
+            None => stored_page.html == html,
+        },
+        Ok(None) => false,
+        Err(storage_error) => {
+            warn!("Unable to compare {url} with the stored page: {storage_error}");
+            false
+        }
+    }
+}
+
+fn setup_website(
+    base_url: &str,
     delay: u64,
     concurrency: usize,
-) -> Result<(Website, Arc<Storage>)> {
-    let sitemap_url = Url::parse(sitemap_url_str)?;
-    let base_url = sitemap_url.join("/")?.to_string();
-
-    let config = Configuration::new()
+    depth: usize,
+    ctx: &CrawlContext,
+) -> Result<Website> {
+    let mut config = Configuration::new()
         .with_user_agent(Some("LLaMap Bot"))
         .with_subdomains(false)
         .with_redirect_limit(3)
-        .with_retry(1)
-        .with_depth(0)
+        .with_retry(ctx.retry.retries)
+        .with_request_timeout(ctx.retry.request_timeout())
+        .with_depth(depth)
         .with_respect_robots_txt(true)
+        // Sitemap entries are resolved by llamap itself and fed as extra links.
+        .with_ignore_sitemap(true)
         .with_delay(delay)
         .with_concurrency_limit(Some(concurrency))
+        .with_headers(ctx.auth.as_ref().map(Auth::headers).transpose()?)
         .build();
+    ctx.spider.configure(&mut config);
+
+    let website = Website::new(base_url)
+        .with_config(config)
+        .build()
+        .map_err(|error| Error::Http(error.into()))?;
+
+    Ok(website)
+}
+
+/// Normalizes URLs of sitemap entries or their metadata, so that variants of the same page are scraped once.
+fn normalize_entries<T>(
+    sitemap_entries: HashMap<String, T>,
+    trailing_slash: &TrailingSlash,
+) -> HashMap<String, T> {
+    sitemap_entries
+        .into_iter()
+        .map(|(url, entry)| (normalize_url_str(&url, trailing_slash), entry))
+        .collect()
+}
 
-    let storage = Arc::new(Storage::new(db_path)?);
-    let mut website = Website::new(&base_url)
-        .with_config(config.clone())
-        .build()?;
+/// Returns the lastmod dates of sitemap entries which have a valid one.
+fn listed_lastmods(
+    sitemap_entries: &HashMap<String, UrlEntry>,
+) -> HashMap<String, chrono::DateTime<chrono::Utc>> {
+    sitemap_entries
+        .iter()
+        .filter_map(|(url, entry)| match entry.lastmod {
+            LastMod::DateTime(lastmod) => Some((url.clone(), lastmod.to_utc())),
+            LastMod::None | LastMod::ParseErr(_) => None,
+        })
+        .collect()
+}
 
-    let sitemap_entries = extract_sitemap_url_entries(sitemap_url_str).await?;
+/// Resolves the sitemap URLs which need to be scraped: new or modified ones, or only new ones
+/// if stored pages are kept, except those already scraped by an interrupted run.
+fn resolve_scrape_urls(
+    sitemap_entries: HashMap<String, UrlEntry>,
+    storage: &Storage,
+    options: &ScrapeOptions,
+) -> Result<Vec<String>> {
     let sitemap_entries_count = sitemap_entries.len();
-    let scrape_urls = if storage.new {
+    let forced_urls = resolve_forced_urls(&sitemap_entries, storage, options)?;
+    let mut scrape_urls: Vec<String> = if storage.is_empty()? {
         sitemap_entries.into_keys().collect()
     } else {
-        storage.resolve_modified(sitemap_entries)?
+        match options.stored_pages {
+            StoredPages::RefetchModified => storage.resolve_modified(sitemap_entries)?,
+            StoredPages::Kept => storage.resolve_new(sitemap_entries.into_keys())?,
+        }
     };
+    let resolved: HashSet<String> = scrape_urls.iter().cloned().collect();
+    scrape_urls.extend(
+        forced_urls
+            .into_iter()
+            .filter(|url| !resolved.contains(url)),
+    );
+
+    if let Some(interrupted_at) = resolve_interrupted_scrape(storage)? {
+        let scraped: HashSet<String> = storage
+            .list_urls_added_since(interrupted_at)?
+            .into_iter()
+            .collect();
+        scrape_urls.retain(|url| !scraped.contains(url));
+        info!(
+            "Resuming scrape interrupted at {interrupted_at}, skipping {} already scraped pages",
+            scraped.len()
+        );
+    }
 
     info!(
         "Sitemap entries: {}/{} (modified/all)",
@@ -151,34 +1515,48 @@ async fn setup_website_and_storage(
         sitemap_entries_count
     );
 
-    website.set_extra_links(
-        scrape_urls
-            .into_iter()
-            .map(|url| spider::CaseInsensitiveString::new(&url))
-            .collect::<spider::hashbrown::HashSet<spider::CaseInsensitiveString>>(),
-    );
-
-    Ok((website, storage))
+    Ok(scrape_urls)
 }
 
-async fn cleanup_unvisited_pages(
-    website: Website,
-    cleanup_storage: &Storage,
-    mut failed_url_rx: tokio::sync::mpsc::UnboundedReceiver<String>,
-) {
-    let mut scraped_urls: Vec<String> = website
-        .get_all_links_visited()
-        .await
-        .into_iter()
-        .map(|s| s.to_string())
-        .collect();
-
-    while let Ok(failed_url) = failed_url_rx.try_recv() {
-        scraped_urls.retain(|url| url != &failed_url);
+/// Returns the URLs of the forced pages: the listed ones of the forced URLs, and the stored
+/// listed pages matching one of the forced globs. Unlisted pages aren't fetched, as the scrape
+/// would remove them again.
+fn resolve_forced_urls(
+    sitemap_entries: &HashMap<String, UrlEntry>,
+    storage: &Storage,
+    options: &ScrapeOptions,
+) -> Result<Vec<String>> {
+    let mut forced_urls = Vec::new();
+    for target in &options.forced_urls {
+        let urls = if is_glob(target) {
+            storage.find_urls(&PageFilter::new().url_glob(target))?
+        } else {
+            vec![normalize_url_str(target, &options.trailing_slash)]
+        };
+        let listed: Vec<String> = urls
+            .into_iter()
+            .filter(|url| sitemap_entries.contains_key(url))
+            .collect();
+        if listed.is_empty() {
+            warn!("No listed page matches {target}, nothing is fetched again for it");
+        }
+        forced_urls.extend(listed);
     }
 
-    match cleanup_storage.remove_unvisited_pages(scraped_urls) {
-        Ok(count) => info!("Removed {count} unvisited pages from storage"),
-        Err(error) => error!("Error removing unvisited pages: {error}"),
+    if !forced_urls.is_empty() {
+        info!("Fetching {} forced pages again", forced_urls.len());
     }
+    Ok(forced_urls)
+}
+
+/// Returns the start time of a previously interrupted scrape, if any.
+fn resolve_interrupted_scrape(storage: &Storage) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    let Some(value) = storage.get_checkpoint(SCRAPE_CHECKPOINT)? else {
+        return Ok(None);
+    };
+    let timestamp: i64 = value.parse().map_err(|error: std::num::ParseIntError| {
+        Error::Storage(error.into()).context("Unable to parse scrape checkpoint")
+    })?;
+
+    Ok(chrono::DateTime::from_timestamp(timestamp, 0))
 }
@@ -0,0 +1,69 @@
+//! Skipping images, videos, archives and other binaries listed among pages.
+
+use url::Url;
+
+/// Extensions of documents skipped by default: images, audio, video, archives, fonts and executables.
+const DEFAULT_SKIP_EXTENSIONS: [&str; 31] = [
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "ico", "bmp", "tif", "tiff", "avif", "mp3", "wav",
+    "ogg", "flac", "mp4", "webm", "mov", "avi", "mkv", "zip", "gz", "tgz", "rar", "7z", "tar",
+    "woff", "woff2", "ttf", "exe", "dmg",
+];
+
+/// Content types of documents skipped by default, matched as prefixes.
+const DEFAULT_SKIP_CONTENT_TYPES: [&str; 9] = [
+    "image/",
+    "audio/",
+    "video/",
+    "font/",
+    "application/zip",
+    "application/gzip",
+    "application/x-tar",
+    "application/x-7z-compressed",
+    "application/vnd.rar",
+];
+
+/// Extensions and content types of documents which aren't stored by a scrape.
+#[derive(Clone, Debug)]
+pub struct ContentFilter {
+    /// URL path extensions of documents which aren't fetched, lowercase without the dot
+    pub skip_extensions: Vec<String>,
+    /// Content type prefixes of fetched documents which aren't stored, e.g. `image/`
+    pub skip_content_types: Vec<String>,
+}
+
+impl Default for ContentFilter {
+    fn default() -> Self {
+        Self {
+            skip_extensions: DEFAULT_SKIP_EXTENSIONS.map(str::to_string).to_vec(),
+            skip_content_types: DEFAULT_SKIP_CONTENT_TYPES.map(str::to_string).to_vec(),
+        }
+    }
+}
+
+impl ContentFilter {
+    /// Returns `true` if the URL path ends with a skipped extension.
+    pub fn skips_url(&self, url: &str) -> bool {
+        let Ok(url) = Url::parse(url) else {
+            return false;
+        };
+        let Some((_, extension)) = url.path().rsplit_once('.') else {
+            return false;
+        };
+        let extension = extension.to_lowercase();
+
+        !extension.contains('/')
+            && self
+                .skip_extensions
+                .iter()
+                .any(|skipped| !skipped.is_empty() && skipped.eq_ignore_ascii_case(&extension))
+    }
+
+    /// Returns `true` if the content type starts with a skipped one.
+    pub fn skips_content_type(&self, content_type: &str) -> bool {
+        let content_type = content_type.trim().to_lowercase();
+
+        self.skip_content_types
+            .iter()
+            .any(|skipped| !skipped.is_empty() && content_type.starts_with(&skipped.to_lowercase()))
+    }
+}
@@ -0,0 +1,242 @@
+//! Offline scraping of a static site build read directly from disk.
+
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use super::{ScrapeOptions, clean_up_unvisited, parse_on_scrape};
+use crate::canonical::normalize_url;
+use crate::error::Context;
+use crate::storage::{Page, Storage, hash_html};
+use crate::{Error, Result};
+
+/// File extensions ingested as HTML pages.
+const HTML_EXTENSIONS: [&str; 2] = ["html", "htm"];
+
+/// Scrapes a static site build from a local directory and saves pages to a local database.
+///
+/// Every HTML file is mapped to an URL relative to `base_url`, where `index.html`
+/// files stand for their directory (`docs/index.html` becomes `{base_url}docs/`).
+/// Files are stored with their modification time as lastmod, so re-running the
+/// command only re-stores changed files, and pages whose files were removed are
+/// removed from the database.
+///
+/// # Arguments
+///
+/// * `dir` - Path to the directory containing the site build
+/// * `base_url` - The URL the directory is served at
+/// * `db_path` - Path to the database where pages will be stored
+/// * `options` - Scrape limits and cancellation token, crawl pace options are ignored
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The directory can't be read
+/// * Database operations fail
+pub async fn process_directory(
+    dir: &Path,
+    base_url: &Url,
+    db_path: &str,
+    options: ScrapeOptions,
+) -> Result<(), Error> {
+    let storage = Storage::new(db_path)?;
+    let run_id = storage.start_run(
+        "scrape",
+        &format!(
+            "dir={} base_url={base_url} max_pages={:?} max_bytes={:?} parse={} store_html={}",
+            dir.display(),
+            options.max_pages,
+            options.max_bytes,
+            options.parse.is_some(),
+            options.store_html
+        ),
+    )?;
+
+    let result = ingest_directory(dir, base_url, &storage, &options).await;
+    storage.finish_run(
+        run_id,
+        result.as_ref().ok().copied(),
+        result.as_ref().err().map(|error| error.to_string()),
+    )?;
+    if let Ok(stored) = &result {
+        options.progress.stage_complete("scrape", *stored);
+    }
+
+    result.map(|_| ())
+}
+
+/// Stores changed HTML files of the directory and returns the number of stored pages
+async fn ingest_directory(
+    dir: &Path,
+    base_url: &Url,
+    storage: &Storage,
+    options: &ScrapeOptions,
+) -> Result<usize> {
+    let files = list_html_files(dir)?;
+    info!(
+        "Found {} HTML files in {}, serving them at {base_url}",
+        files.len(),
+        dir.display()
+    );
+
+    let mut stored = 0;
+    let mut stored_bytes: u64 = 0;
+    let mut visited = Vec::with_capacity(files.len());
+    let mut truncated = false;
+    let mut interrupted = false;
+    let parse_options = options.parse_options();
+
+    for file in files {
+        if options.is_cancelled() {
+            interrupted = true;
+            break;
+        }
+        let relative_path = file.strip_prefix(dir).unwrap_or(&file);
+        let url = match resolve_file_url(base_url, relative_path) {
+            Ok(url) => normalize_url(&url, &options.trailing_slash),
+            Err(error) => {
+                warn!("Skipping {}: {error}", file.display());
+                continue;
+            }
+        };
+        if storage.is_excluded(url.as_str())? {
+            info!("Skipping {url} as it's excluded");
+            continue;
+        }
+        visited.push(url.to_string());
+
+        let lastmod = read_lastmod(&file)?;
+        if truncated || storage.get_lastmod(url.as_str())? == Some(lastmod.timestamp()) {
+            continue;
+        }
+
+        let html = tokio::fs::read_to_string(&file)
+            .await
+            .with_context(|| format!("Unable to read {}", file.display()))?;
+        stored_bytes += html.len() as u64;
+        if options.exceeds_limits(stored, stored_bytes) {
+            truncated = true;
+            continue;
+        }
+
+        info!("Stored {url} from {}", file.display());
+        let page = file_page(url, lastmod, html, options.store_html);
+        storage.upsert_page(&page)?;
+        options.progress.page_scraped(page.url.as_str());
+        parse_on_scrape(
+            storage,
+            page,
+            parse_options.as_ref(),
+            options.parsed_pages.as_ref(),
+        );
+        stored += 1;
+    }
+
+    if interrupted {
+        warn!("Scrape cancelled: {stored} pages stored, run the same command again to continue");
+    } else if truncated {
+        warn!(
+            "Scrape truncated by --max-pages/--max-bytes limits: {stored} pages stored, run the same command again to continue"
+        );
+    } else if !storage.is_empty()? {
+        let removed = clean_up_unvisited(storage, visited, options.unlisted_pages)?;
+        info!("Removed {removed} pages missing from {}", dir.display());
+    }
+
+    Ok(stored)
+}
+
+/// Creates the page of an HTML file, keeping only the hash of its HTML unless `store_html`.
+fn file_page(
+    url: Url,
+    lastmod: chrono::DateTime<chrono::Utc>,
+    html: String,
+    store_html: bool,
+) -> Page {
+    let html_hash = (!store_html).then(|| hash_html(&html));
+    Page {
+        url,
+        added_at: chrono::Utc::now(),
+        lastmod,
+        html,
+        title: None,
+        text: None,
+        summary: None,
+        content_type: None,
+        content: None,
+        robots_tag: None,
+        html_hash,
+    }
+}
+
+/// Maps a path of a file relative to the site root to its URL.
+///
+/// `index.html` files are mapped to the URL of their directory.
+///
+/// # Errors
+///
+/// Returns an error if the path can't be joined to the base URL.
+pub fn resolve_file_url(base_url: &Url, relative_path: &Path) -> Result<Url, Error> {
+    let mut segments: Vec<String> = relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if segments
+        .last()
+        .is_some_and(|file_name| file_name == "index.html" || file_name == "index.htm")
+    {
+        segments.pop();
+        segments.push(String::new());
+    }
+
+    let mut base_url = base_url.clone();
+    if !base_url.path().ends_with('/') {
+        base_url.set_path(&format!("{}/", base_url.path()));
+    }
+
+    base_url.join(&segments.join("/")).map_err(|error| {
+        Error::Sitemap(error.into())
+            .context(format!("Unable to map {} to URL", relative_path.display()))
+    })
+}
+
+/// Reads the modification time of a file.
+fn read_lastmod(file: &Path) -> Result<chrono::DateTime<chrono::Utc>> {
+    let modified = std::fs::metadata(file)
+        .and_then(|metadata| metadata.modified())
+        .with_context(|| format!("Unable to read modification time of {}", file.display()))?;
+
+    Ok(modified.into())
+}
+
+/// Recursively lists HTML files of a directory in a stable order.
+fn list_html_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs_to_process = vec![dir.to_path_buf()];
+
+    while let Some(current_dir) = dirs_to_process.pop() {
+        let entries = std::fs::read_dir(&current_dir).map_err(|error| {
+            Error::Sitemap(error.into()).context(format!(
+                "Unable to read directory {}",
+                current_dir.display()
+            ))
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs_to_process.push(path);
+            } else if path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    HTML_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                })
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,133 @@
+//! On-demand fetch of a single page, e.g. to fix one broken page without a full scrape.
+
+use log::{info, warn};
+use std::sync::Arc;
+use url::Url;
+
+use super::{CrawlContext, ScrapeBudget, ScrapeOptions, ScrapeReport, crawl_host};
+use crate::canonical::normalize_url;
+use crate::shutdown::Shutdown;
+use crate::storage::Storage;
+use crate::throttle::AdaptiveThrottle;
+use crate::{Error, Result};
+
+/// Downloads a single page, bypassing the sitemap, and stores or updates it in a local database.
+///
+/// The page is stored with the fetch time as lastmod even if its HTML didn't change,
+/// and no other stored page is removed. It's parsed right away if [`ScrapeOptions::parse`] is set.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page to fetch
+/// * `db_path` - Path to the database where the page will be stored
+/// * `options` - Credentials, content filter and storage options; crawl pace and limits are ignored
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The crawler can't be set up
+/// * Database operations fail
+pub async fn process_page_url(
+    url: &Url,
+    db_path: &str,
+    options: ScrapeOptions,
+) -> Result<ScrapeReport, Error> {
+    fetch_with_storage(url, Storage::new(db_path)?, options).await
+}
+
+/// Fetches a single page into the storage, recording the run in the audit log.
+pub(crate) async fn fetch_with_storage(
+    url: &Url,
+    storage: Storage,
+    options: ScrapeOptions,
+) -> Result<ScrapeReport, Error> {
+    let storage = Arc::new(storage);
+    let run_id = storage.start_run(
+        "fetch",
+        &format!(
+            "url={url} trailing_slash={:?} parse={} store_html={} auth={:?}",
+            options.trailing_slash,
+            options.parse.is_some(),
+            options.store_html,
+            options.auth
+        ),
+    )?;
+
+    let result = fetch_page(url, &storage, &options).await;
+    match &result {
+        Ok(report) => {
+            storage.finish_run(
+                run_id,
+                Some(report.fetched),
+                report.interrupted.then(|| "Interrupted".to_string()),
+            )?;
+            storage.record_run_traffic(run_id, report.requests, report.bytes_downloaded)?;
+            options.progress.stage_complete("fetch", report.fetched);
+        }
+        Err(error) => storage.finish_run(run_id, None, Some(error.to_string()))?,
+    }
+
+    result
+}
+
+/// Fetches and stores the page with a crawler of its host.
+async fn fetch_page(
+    url: &Url,
+    storage: &Arc<Storage>,
+    options: &ScrapeOptions,
+) -> Result<ScrapeReport> {
+    let url = normalize_url(url, &options.trailing_slash);
+    if options.content_filter.skips_url(url.as_str()) {
+        warn!("Skipping {url} by its extension, see --skip-extensions");
+        return Ok(ScrapeReport {
+            skipped_filtered: 1,
+            ..ScrapeReport::default()
+        });
+    }
+    if storage.is_excluded(url.as_str())? {
+        warn!("Skipping {url} as it matches an exclusion pattern");
+        return Ok(ScrapeReport {
+            skipped_excluded: 1,
+            ..ScrapeReport::default()
+        });
+    }
+
+    let ctx = CrawlContext {
+        storage: Arc::clone(storage),
+        shutdown: Shutdown::for_run(options.cancel.as_ref()),
+        budget: Arc::new(ScrapeBudget::default()),
+        skip_unchanged: false,
+        trailing_slash: options.trailing_slash.clone(),
+        lastmods: Arc::default(),
+        content_filter: Arc::new(options.content_filter.clone()),
+        // The page is fetched as asked, whatever the crawler rules
+        spider: Arc::default(),
+        retry: options.retry,
+        traffic: Arc::default(),
+        pending: Arc::default(),
+        parse: options.parse_options().map(Arc::new),
+        store_html: options.store_html,
+        auth: options.auth.clone(),
+        progress: options.progress.clone(),
+        parsed_pages: options.parsed_pages.clone(),
+    };
+
+    info!("Fetching {url}");
+    let state = crawl_host(
+        format!("{}/", url.origin().ascii_serialization()),
+        vec![url.to_string()],
+        AdaptiveThrottle::new(options.delay, 1),
+        ctx.clone(),
+    )
+    .await?;
+
+    Ok(ScrapeReport {
+        fetched: state.stored,
+        failed: state.failed_urls(&options.trailing_slash),
+        interrupted: ctx.shutdown.is_requested(),
+        requests: ctx.traffic.requests(),
+        bytes_downloaded: ctx.traffic.bytes(),
+        dropped: state.dropped,
+        ..ScrapeReport::default()
+    })
+}
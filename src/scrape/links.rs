@@ -0,0 +1,148 @@
+//! Link-following crawl for sites which don't publish a sitemap.
+
+use log::{info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+use super::{CrawlContext, ScrapeBudget, ScrapeOptions, setup_website, spawn_page_handler};
+use crate::shutdown::Shutdown;
+use crate::storage::Storage;
+use crate::{Error, Result};
+
+/// Interval at which a running crawl checks whether the scrape limits are reached.
+const BUDGET_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Scrapes a website by following its links from a start page and saves pages to a local database.
+///
+/// Only links of the start page host are followed, up to `depth` levels deep.
+/// Discovered pages are stored with the fetch time as lastmod, and pages already
+/// stored with the same HTML are left untouched, so their text and summary are kept.
+///
+/// # Arguments
+///
+/// * `start_url` - The URL of the page the crawl starts from
+/// * `depth` - The maximum number of links followed from the start page
+/// * `db_path` - Path to the database where pages will be stored
+/// * `options` - Crawl pace and safety limits
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The crawler can't be set up
+/// * Database operations fail
+pub async fn process_links(
+    start_url: &Url,
+    depth: usize,
+    db_path: &str,
+    options: ScrapeOptions,
+) -> Result<(), Error> {
+    let storage = Arc::new(Storage::new(db_path)?);
+    let run_id = storage.start_run(
+        "scrape",
+        &format!(
+            "start={start_url} depth={depth} delay={} concurrency={} max_pages={:?} max_bytes={:?} trailing_slash={:?} parse={} store_html={} auth={:?} spider={:?} retry={:?}",
+            options.delay,
+            options.concurrency,
+            options.max_pages,
+            options.max_bytes,
+            options.trailing_slash,
+            options.parse.is_some(),
+            options.store_html,
+            options.auth,
+            options.spider,
+            options.retry
+        ),
+    )?;
+
+    let ctx = CrawlContext {
+        storage: Arc::clone(&storage),
+        shutdown: Shutdown::for_run(options.cancel.as_ref()),
+        budget: Arc::new(ScrapeBudget {
+            max_pages: options.max_pages,
+            max_bytes: options.max_bytes,
+            ..Default::default()
+        }),
+        skip_unchanged: true,
+        trailing_slash: options.trailing_slash.clone(),
+        lastmods: Arc::default(),
+        content_filter: Arc::new(options.content_filter.clone()),
+        spider: Arc::new(options.spider.clone()),
+        retry: options.retry,
+        traffic: Arc::default(),
+        pending: Arc::default(),
+        parse: options.parse_options().map(Arc::new),
+        store_html: options.store_html,
+        auth: options.auth.clone(),
+        progress: options.progress.clone(),
+        parsed_pages: options.parsed_pages.clone(),
+    };
+
+    let result = crawl_links(start_url, depth, &options, &ctx).await;
+    let error = match &result {
+        Ok(_) if ctx.shutdown.is_requested() => Some("Interrupted".to_string()),
+        Ok(_) => None,
+        Err(error) => Some(error.to_string()),
+    };
+    storage.finish_run(run_id, result.as_ref().ok().copied(), error)?;
+    storage.record_run_traffic(run_id, ctx.traffic.requests(), ctx.traffic.bytes())?;
+    if let Ok(stored) = &result {
+        options.progress.stage_complete("scrape", *stored);
+    }
+
+    result.map(|_| ())
+}
+
+/// Crawls links from the start page until done, interrupted or out of budget.
+/// Returns the number of stored pages.
+async fn crawl_links(
+    start_url: &Url,
+    depth: usize,
+    options: &ScrapeOptions,
+    ctx: &CrawlContext,
+) -> Result<usize> {
+    let mut website = setup_website(
+        start_url.as_str(),
+        options.delay,
+        options.concurrency.max(1),
+        depth,
+        ctx,
+    )?;
+    let receiver = website
+        .subscribe(888)
+        .ok_or_else(|| Error::Http("Unable to create receiver.".into()))?;
+    let handle = spawn_page_handler(receiver, ctx.clone());
+
+    info!("Starting link crawl from {start_url} up to depth {depth}");
+    tokio::select! {
+        () = website.crawl() => {},
+        () = ctx.shutdown.requested() => {},
+        () = wait_for_exhaustion(&ctx.budget) => {},
+    };
+    website.unsubscribe();
+    let outcome = handle
+        .await
+        .map_err(|error| Error::Http(error.into()).context("Task failed to complete"))?;
+
+    if ctx.budget.is_exhausted() {
+        warn!(
+            "Link crawl truncated by --max-pages/--max-bytes limits: {} pages stored",
+            outcome.stored
+        );
+    }
+    info!(
+        "Link crawl stored {} pages, {} failed, {} dropped unstored",
+        outcome.stored,
+        outcome.failed.len(),
+        outcome.dropped
+    );
+
+    Ok(outcome.stored)
+}
+
+/// Waits until any of the scrape limits is reached.
+async fn wait_for_exhaustion(budget: &ScrapeBudget) {
+    while !budget.is_exhausted() {
+        tokio::time::sleep(BUDGET_CHECK_INTERVAL).await;
+    }
+}
@@ -0,0 +1,49 @@
+//! Timeout and retry policy of page requests, so that slow endpoints don't hang the crawl
+//! and flaky ones don't lose pages to a single failed request.
+
+use std::time::Duration;
+
+use crate::throttle::is_overload_status;
+
+/// Upper bound of the pause before a retry.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// How long page requests may take and how failing requests are retried
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum duration of a page request in milliseconds, `None` to wait as long as the site takes
+    pub timeout: Option<u64>,
+    /// Number of times a request timing out or failing with a server error is retried
+    pub retries: u8,
+    /// Pause before the first retry in milliseconds, doubled on every next retry
+    pub backoff: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Some(30_000),
+            retries: 1,
+            backoff: 1000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the maximum duration of a page request.
+    pub(super) fn request_timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_millis)
+    }
+
+    /// Returns the pause before the given retry, counted from 1, capped to five minutes.
+    pub(super) fn pause_before(&self, retry: u8) -> Duration {
+        let factor = 1_u64 << retry.saturating_sub(1).min(16);
+        Duration::from_millis(self.backoff.saturating_mul(factor)).min(MAX_BACKOFF)
+    }
+}
+
+/// Returns `true` for HTTP status codes of requests worth retrying: timeouts and server errors,
+/// except overload signals which the throttle handles.
+pub(super) fn is_transient_status(status: u16) -> bool {
+    (status == 408 || status >= 500) && !is_overload_status(status)
+}
@@ -0,0 +1,45 @@
+//! URL rules passed through to the spider crawler, so that advanced users can exclude URLs
+//! and limit the pages requested under a path with the engine itself.
+
+use spider::compact_str::CompactString;
+use spider::configuration::Configuration;
+use std::collections::BTreeMap;
+
+/// URL patterns and crawl budget of the spider crawler, on top of llamap's own filters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpiderRules {
+    /// Regex patterns of URLs which are never requested, e.g. `/search\?`
+    pub blacklist: Vec<String>,
+    /// Regex patterns of URLs which are only requested, all URLs if empty
+    pub whitelist: Vec<String>,
+    /// Maximum numbers of pages requested under a path, `*` limiting the whole site
+    pub budget: BTreeMap<String, u32>,
+}
+
+impl SpiderRules {
+    /// Adds the rules to the configuration of a crawler, leaving it untouched if there are none.
+    pub(super) fn configure(&self, config: &mut Configuration) {
+        if !self.blacklist.is_empty() {
+            config.with_blacklist_url(Some(compact_strings(&self.blacklist)));
+        }
+        if !self.whitelist.is_empty() {
+            config.with_whitelist_url(Some(compact_strings(&self.whitelist)));
+        }
+        if !self.budget.is_empty() {
+            config.with_budget(Some(
+                self.budget
+                    .iter()
+                    .map(|(path, pages)| (path.as_str(), *pages))
+                    .collect(),
+            ));
+        }
+    }
+}
+
+/// Converts patterns to the strings spider keeps its URL lists in.
+fn compact_strings(patterns: &[String]) -> Vec<CompactString> {
+    patterns
+        .iter()
+        .map(|pattern| CompactString::from(pattern.as_str()))
+        .collect()
+}
@@ -0,0 +1,47 @@
+//! The secrets module wraps credentials like API keys, passwords and tokens, so that
+//! options and settings holding them can be logged or debug-printed without leaking them.
+
+/// Text shown in place of a secret.
+const MASK: &str = "********";
+
+/// A credential whose `Display` and `Debug` output is masked.
+///
+/// The value is only reachable through [`Secret::expose`], at the place it is sent.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wraps a credential.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the credential, to be sent and never logged.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(MASK)
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "Secret({MASK})")
+    }
+}
@@ -0,0 +1,62 @@
+//! The shutdown module listens for Ctrl-C, or a cancellation token of an embedding application,
+//! so that long-running stages can stop issuing new work and leave the database
+//! in a consistent, resumable state.
+
+use log::warn;
+use tokio_util::sync::CancellationToken;
+
+/// Shutdown is a cloneable handle reporting whether a graceful stop was requested.
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    token: CancellationToken,
+}
+
+impl Shutdown {
+    /// Starts listening for Ctrl-C in a background task.
+    ///
+    /// The first Ctrl-C requests a graceful stop, a second one terminates the
+    /// process immediately.
+    pub fn listen() -> Self {
+        let token = CancellationToken::new();
+        let sender = token.clone();
+
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+
+            warn!("Ctrl-C received, finishing in-flight work. Press Ctrl-C again to abort.");
+            sender.cancel();
+
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        });
+
+        Self { token }
+    }
+
+    /// Creates a handle requesting a graceful stop once the token is cancelled,
+    /// without listening for Ctrl-C.
+    pub fn with_token(token: CancellationToken) -> Self {
+        Self { token }
+    }
+
+    /// Creates a handle for a run: stopped by the token of the embedding application if given,
+    /// by Ctrl-C otherwise.
+    pub fn for_run(cancel: Option<&CancellationToken>) -> Self {
+        cancel.map_or_else(Self::listen, |token| Self::with_token(token.clone()))
+    }
+
+    /// Returns `true` if a graceful stop was requested.
+    pub fn is_requested(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Waits until a graceful stop is requested.
+    ///
+    /// Never resolves if the signal listener could not be installed.
+    pub async fn requested(&self) {
+        self.token.cancelled().await;
+    }
+}
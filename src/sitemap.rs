@@ -1,19 +1,61 @@
 use std::collections::HashMap;
 
-use anyhow::Result;
+use crate::auth::Auth;
+use crate::{Error, Result};
+use log::warn;
+use reqwest::header::CONTENT_TYPE;
 use sitemap::{
     reader::{SiteMapEntity, SiteMapReader},
-    structs::UrlEntry,
+    structs::{LastMod, UrlEntry},
 };
+use url::Url;
+
+use crate::storage::SitemapExtensions;
+
+mod extensions;
+mod writer;
+
+use extensions::extract_sitemap_extensions;
+pub use writer::{render_sitemap, write_sitemap};
+
+/// Resolves a sitemap location given either as an URL or as a local file path.
+///
+/// Local paths are converted to absolute `file://` URLs.
+///
+/// # Arguments
+///
+/// * `location` - An `http(s)://` or `file://` URL, or a path to a local sitemap file.
+///
+/// # Errors
+///
+/// This function will return an error if the location is neither a valid URL nor an existing file.
+pub fn resolve_sitemap_location(location: &str) -> Result<Url, Error> {
+    if let Ok(url) = Url::parse(location) {
+        return Ok(url);
+    }
+
+    let path = std::fs::canonicalize(location).map_err(|error| {
+        Error::Sitemap(error.into()).context(format!(
+            "Sitemap is neither an URL nor an existing file: {location}"
+        ))
+    })?;
+
+    Url::from_file_path(&path).map_err(|()| {
+        Error::Sitemap(format!("Unable to convert {} to file URL", path.display()).into())
+    })
+}
 
 /// Extracts URL entries from a sitemap.
 ///
 /// This function takes a sitemap URL and returns a `HashMap` containing the URL entries found in the sitemap.
 /// It processes the sitemap and any nested sitemaps recursively.
+/// Sitemaps may be fetched over HTTP or read from the local filesystem.
+/// RSS and Atom feeds are accepted in place of a sitemap and detected by their content type.
 ///
 /// # Arguments
 ///
-/// * `sitemap_url` - A string slice that holds the URL of the sitemap to be processed.
+/// * `sitemap_url` - A string slice that holds the URL (`http(s)://` or `file://`) or the local path of the sitemap to be processed.
+/// * `auth` - Credentials sent with every sitemap request, if the site is protected.
 ///
 /// # Returns
 ///
@@ -22,15 +64,52 @@ use sitemap::{
 /// # Errors
 ///
 /// This function will return an error if there is a problem fetching the sitemap or parsing its content.
-pub async fn extract_sitemap_url_entries(sitemap_url: &str) -> Result<HashMap<String, UrlEntry>> {
+pub async fn extract_sitemap_url_entries(
+    sitemap_url: &str,
+    auth: Option<&Auth>,
+) -> Result<HashMap<String, UrlEntry>, Error> {
+    Ok(extract_sitemap_listing(sitemap_url, auth).await?.entries)
+}
+
+/// URL entries of a sitemap along with the metadata of its news, video and image extensions
+#[derive(Debug, Default)]
+pub struct SitemapListing {
+    /// URL entries by their URL
+    pub entries: HashMap<String, UrlEntry>,
+    /// Extension metadata of the entries declaring any, by their URL
+    pub extensions: HashMap<String, SitemapExtensions>,
+}
+
+/// Extracts URL entries from a sitemap like [`extract_sitemap_url_entries`],
+/// along with the metadata of their news, video and image extensions.
+///
+/// Entries without a lastmod take the publication date of their news article as lastmod.
+///
+/// # Errors
+///
+/// This function will return an error if there is a problem fetching the sitemap or parsing its content.
+pub async fn extract_sitemap_listing(
+    sitemap_url: &str,
+    auth: Option<&Auth>,
+) -> Result<SitemapListing, Error> {
     let mut entries = HashMap::new();
+    let mut extensions = HashMap::new();
     let mut sitemaps_to_process = vec![sitemap_url.to_string()];
-    let client = reqwest::Client::new();
+    let client = reqwest::Client::builder()
+        .default_headers(auth.map(Auth::headers).transpose()?.unwrap_or_default())
+        .build()
+        .map_err(|error| Error::Http(error.into()))?;
 
     while let Some(current_sitemap) = sitemaps_to_process.pop() {
-        let response = client.get(&current_sitemap).send().await?;
-        let content = response.bytes().await?;
+        let (content, content_type) = fetch_sitemap(&client, &current_sitemap).await?;
+        if is_feed(content_type.as_deref(), &content) {
+            entries.extend(extract_feed_entries(&content)?);
+            continue;
+        }
 
+        extensions.extend(extract_sitemap_extensions(&String::from_utf8_lossy(
+            &content,
+        )));
         let reader = SiteMapReader::new(&*content);
 
         for entity in reader {
@@ -50,5 +129,155 @@ pub async fn extract_sitemap_url_entries(sitemap_url: &str) -> Result<HashMap<St
         }
     }
 
+    for (url, entry) in &mut entries {
+        let publication_date = extensions
+            .get(url)
+            .and_then(|page_extensions| page_extensions.publication_date);
+        if let (LastMod::None, Some(publication_date)) = (&entry.lastmod, publication_date) {
+            entry.lastmod = LastMod::DateTime(publication_date.fixed_offset());
+        }
+    }
+
+    Ok(SitemapListing {
+        entries,
+        extensions,
+    })
+}
+
+/// Extracts URL entries from an RSS or Atom feed.
+///
+/// Every feed item becomes an URL entry of its first link, with the item's update
+/// (or publication) date used as lastmod.
+///
+/// # Arguments
+///
+/// * `content` - The raw content of the feed.
+///
+/// # Errors
+///
+/// This function will return an error if the content is not a valid RSS or Atom feed.
+pub fn extract_feed_entries(content: &[u8]) -> Result<HashMap<String, UrlEntry>, Error> {
+    let feed = feed_rs::parser::parse(content)
+        .map_err(|error| Error::Sitemap(error.into()).context("Unable to parse feed"))?;
+    let mut entries = HashMap::new();
+
+    for item in feed.entries {
+        let Some(link) = item.links.into_iter().next() else {
+            continue;
+        };
+
+        let builder = UrlEntry::builder().loc(link.href.as_str());
+        let builder = match item.updated.or(item.published) {
+            Some(lastmod) => builder.lastmod(lastmod.fixed_offset()),
+            None => builder,
+        };
+
+        match builder.build() {
+            Ok(url_entry) => {
+                entries.insert(link.href, url_entry);
+            }
+            Err(error) => warn!("Skipping feed item {}: {error:?}", link.href),
+        }
+    }
+
     Ok(entries)
 }
+
+/// Extracts URL entries from a newline-delimited list of URLs.
+///
+/// Empty lines and lines starting with `#` are ignored, invalid URLs are skipped.
+/// Entries have no lastmod, so they are always considered modified.
+///
+/// # Arguments
+///
+/// * `content` - The list of URLs, one per line.
+pub fn extract_url_list_entries(content: &str) -> HashMap<String, UrlEntry> {
+    let mut entries = HashMap::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let url = match Url::parse(line) {
+            Ok(url) => url,
+            Err(parse_error) => {
+                warn!("Skipping invalid listed URL {line}: {parse_error}");
+                continue;
+            }
+        };
+
+        match UrlEntry::builder().loc(url.as_str()).build() {
+            Ok(url_entry) => {
+                entries.insert(url.to_string(), url_entry);
+            }
+            Err(error) => warn!("Skipping listed URL {url}: {error:?}"),
+        }
+    }
+
+    entries
+}
+
+/// Returns `true` if the content is an RSS or Atom feed rather than a sitemap.
+///
+/// The content type is trusted when it names a feed, otherwise the root element is sniffed.
+fn is_feed(content_type: Option<&str>, content: &[u8]) -> bool {
+    if let Some(content_type) = content_type
+        && FEED_CONTENT_TYPES
+            .iter()
+            .any(|feed_type| content_type.starts_with(feed_type))
+    {
+        return true;
+    }
+
+    let head = String::from_utf8_lossy(content.get(..FEED_SNIFF_LENGTH).unwrap_or(content));
+    head.contains("<rss") || head.contains("<feed") || head.contains("<rdf:RDF")
+}
+
+/// Content types identifying RSS and Atom feeds.
+const FEED_CONTENT_TYPES: [&str; 3] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+
+/// Number of leading bytes searched for the root element of a feed.
+const FEED_SNIFF_LENGTH: usize = 1024;
+
+/// Reads sitemap content from a `file://` URL or a local path, or downloads it otherwise.
+/// Returns the content along with its content type, if served over HTTP.
+async fn fetch_sitemap(
+    client: &reqwest::Client,
+    location: &str,
+) -> Result<(Vec<u8>, Option<String>)> {
+    let path = match Url::parse(location) {
+        Ok(url) if url.scheme() == "file" => url
+            .to_file_path()
+            .map_err(|()| Error::Sitemap(format!("Invalid sitemap file URL: {location}").into()))?,
+        Ok(_) => {
+            let response = client
+                .get(location)
+                .send()
+                .await
+                .map_err(|error| Error::Sitemap(error.into()))?;
+            let content_type = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_lowercase());
+            let content = response
+                .bytes()
+                .await
+                .map_err(|error| Error::Sitemap(error.into()))?;
+            return Ok((content.to_vec(), content_type));
+        }
+        Err(_) => location.into(),
+    };
+
+    let content = tokio::fs::read(&path).await.map_err(|error| {
+        Error::Sitemap(error.into())
+            .context(format!("Unable to read sitemap file {}", path.display()))
+    })?;
+
+    Ok((content, None))
+}
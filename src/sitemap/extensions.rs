@@ -0,0 +1,99 @@
+//! Google news, video and image sitemap extensions, which the sitemap reader skips:
+//! publication dates and titles of news articles, durations of videos and captions of images.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::storage::SitemapExtensions;
+
+/// Matches the `<url>` entries of a sitemap, capturing their content.
+static URL_ENTRY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<url\b[^>]*>(.*?)</url>").expect("Failed to compile sitemap url regex")
+});
+
+/// Matches the `<loc>` of an entry, leaving out the `<image:loc>` and `<video:...>` ones.
+static LOC_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<loc>\s*(.*?)\s*</loc>").expect("Failed to compile sitemap loc regex")
+});
+
+/// Matches the extension tags of an entry, capturing their name and content.
+static EXTENSION_TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?s)<(news:publication_date|news:title|video:duration|image:caption|image:title)>(.*?)</(?:news|video|image):\w+>",
+    )
+    .expect("Failed to compile sitemap extension regex")
+});
+
+/// Returns the extension metadata of the entries of a sitemap by their URL,
+/// leaving out entries without any.
+pub(super) fn extract_sitemap_extensions(content: &str) -> HashMap<String, SitemapExtensions> {
+    URL_ENTRY_REGEX
+        .captures_iter(content)
+        .filter_map(|entry| {
+            let entry = entry.get(1)?.as_str();
+            let url = xml_text(LOC_REGEX.captures(entry)?.get(1)?.as_str());
+            let extensions = entry_extensions(entry);
+            (extensions != SitemapExtensions::default()).then_some((url, extensions))
+        })
+        .collect()
+}
+
+/// Reads the extension tags of an entry. Image titles stand for the captions of images without any.
+fn entry_extensions(entry: &str) -> SitemapExtensions {
+    let mut extensions = SitemapExtensions::default();
+    let mut image_titles = Vec::new();
+    for tag in EXTENSION_TAG_REGEX.captures_iter(entry) {
+        let (Some(name), Some(value)) = (tag.get(1), tag.get(2)) else {
+            continue;
+        };
+        let value = xml_text(value.as_str());
+        if value.is_empty() {
+            continue;
+        }
+        match name.as_str() {
+            "news:publication_date" => extensions.publication_date = parse_w3c_date(&value),
+            "news:title" => extensions.news_title = Some(value),
+            "video:duration" => extensions.video_duration = value.parse().ok(),
+            "image:caption" => extensions.image_captions.push(value),
+            _ => image_titles.push(value),
+        }
+    }
+    if extensions.image_captions.is_empty() {
+        extensions.image_captions = image_titles;
+    }
+    extensions
+}
+
+/// Parses a W3C date, either a full timestamp or a day taken at midnight UTC.
+fn parse_w3c_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.to_utc())
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+                .map(|midnight| midnight.and_utc())
+        })
+}
+
+/// Returns the text of an XML element: unwrapped from CDATA, unescaped, with collapsed whitespace.
+fn xml_text(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("<![CDATA[")
+        .and_then(|value| value.strip_suffix("]]>"))
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            value
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        });
+
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
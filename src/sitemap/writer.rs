@@ -0,0 +1,109 @@
+//! Generation of a sitemap from stored pages, for sites ingested from feeds, URL lists
+//! or directories which also need a sitemap of the same pages.
+
+use log::{info, warn};
+
+use crate::stdio::{is_stdio, write_stdout};
+use crate::storage::{PageFilter, SitemapEntry, Storage, UNKNOWN_TIMESTAMP};
+use crate::{Error, NoindexPages, Result};
+
+/// Maximal number of URLs of a sitemap file allowed by the sitemaps protocol.
+const MAX_SITEMAP_URLS: usize = 50_000;
+
+/// Renders a sitemap listing the given pages with their lastmod and priority,
+/// leaving out the lastmod of pages where it's unknown.
+///
+/// # Arguments
+///
+/// * `entries` - The pages to list, in the order they are listed
+pub fn render_sitemap(entries: &[SitemapEntry]) -> String {
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries {
+        sitemap.push_str("  <url>\n");
+        sitemap.push_str(&format!("    <loc>{}</loc>\n", escape_xml(&entry.url)));
+        if entry.lastmod.timestamp() != UNKNOWN_TIMESTAMP {
+            sitemap.push_str(&format!(
+                "    <lastmod>{}</lastmod>\n",
+                entry.lastmod.format("%Y-%m-%dT%H:%M:%SZ")
+            ));
+        }
+        if let Some(priority) = entry.priority {
+            sitemap.push_str(&format!("    <priority>{priority:.1}</priority>\n"));
+        }
+        sitemap.push_str("  </url>\n");
+    }
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
+
+/// Writes a sitemap of the stored pages matching the filter to a file, recording the run in the audit log.
+///
+/// Excluded pages are left out, as are pages flagged noindex unless included.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database file to read pages from
+/// * `output_path` - Path to the sitemap file to write, "-" for stdout
+/// * `filter` - Conditions the listed pages must match
+/// * `noindex` - Whether pages flagged noindex are listed
+///
+/// # Returns
+///
+/// The number of listed pages.
+///
+/// # Errors
+///
+/// This function will return an error if database operations fail or the file can't be written.
+pub fn write_sitemap(
+    db_path: &str,
+    output_path: &str,
+    filter: PageFilter,
+    noindex: NoindexPages,
+) -> Result<usize, Error> {
+    let storage = Storage::new(db_path)?;
+    let filter = noindex.restrict(filter).skip_excluded();
+    let run_id = storage.start_run(
+        "sitemap-out",
+        &format!("output={output_path} filter={filter:?} noindex={noindex:?}"),
+    )?;
+
+    let result = list_pages(&storage, output_path, &filter);
+    storage.finish_run(
+        run_id,
+        result.as_ref().ok().copied(),
+        result.as_ref().err().map(|error| error.to_string()),
+    )?;
+
+    result
+}
+
+/// Writes the sitemap of the stored pages and returns the number of listed pages.
+fn list_pages(storage: &Storage, output_path: &str, filter: &PageFilter) -> Result<usize> {
+    let entries = storage.list_sitemap_entries(filter)?;
+    if entries.len() > MAX_SITEMAP_URLS {
+        warn!(
+            "{output_path} lists {} pages, more than the {MAX_SITEMAP_URLS} crawlers read from a sitemap",
+            entries.len()
+        );
+    }
+
+    let sitemap = render_sitemap(&entries);
+    if is_stdio(output_path) {
+        write_stdout(&sitemap)?;
+    } else {
+        std::fs::write(output_path, sitemap)?;
+    }
+    info!("Listed {} pages in {output_path}", entries.len());
+    Ok(entries.len())
+}
+
+/// Escapes the characters of a text which can't appear as is in XML.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,56 @@
+//! Standard input and output standing in for files given as "-", so that llamap fits in Unix
+//! pipelines like `llamap compose site.sqlite - | gzip > llms.txt.gz`.
+
+use std::io::{Read, Write};
+
+use crate::Result;
+use crate::error::Context;
+use crate::sitemap::extract_url_list_entries;
+
+/// Path of input and output files read from stdin or written to stdout
+pub const STDIO_PATH: &str = "-";
+
+/// Returns whether the path stands for stdin or stdout.
+pub fn is_stdio(path: &str) -> bool {
+    path == STDIO_PATH
+}
+
+/// Reads a file, or stdin if the path is "-".
+///
+/// # Errors
+///
+/// Returns [`crate::Error::Io`] if the file or stdin can't be read
+pub fn read_input(path: &str) -> Result<String> {
+    if is_stdio(path) {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .context("Unable to read stdin")?;
+        return Ok(content);
+    }
+
+    std::fs::read_to_string(path).with_context(|| format!("Unable to read {path}"))
+}
+
+/// Reads the URLs listed one per line in a file, or stdin if the path is "-". Blank lines,
+/// `#` comments and invalid URLs are skipped, URLs are normalized like stored page URLs.
+///
+/// # Errors
+///
+/// Returns [`crate::Error::Io`] if the file or stdin can't be read
+pub fn read_url_list(path: &str) -> Result<Vec<String>> {
+    let mut urls: Vec<String> = extract_url_list_entries(&read_input(path)?)
+        .into_keys()
+        .collect();
+    urls.sort();
+    Ok(urls)
+}
+
+/// Writes the contents to stdout and flushes it, for output files given as "-".
+pub(crate) fn write_stdout(contents: &str) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    stdout
+        .write_all(contents.as_bytes())
+        .and_then(|()| stdout.flush())
+        .context("Unable to write to stdout")
+}
@@ -1,24 +1,65 @@
 //! The storage module provides database operations for storing and retrieving
 //! scraped web page content using SQLite.
 
-use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use log::warn;
 use rusqlite::{Connection, OptionalExtension, params};
-use sitemap::structs::LastMod;
+use sitemap::structs::{LastMod, Priority};
 use std::convert::TryFrom;
 use std::sync::{Arc, Mutex};
 use url::Url;
 
+use crate::error::{Error, Result};
 use crate::parse::PageArticle;
+use crate::robots::has_noindex;
+use crate::summarize::StructuredSummary;
+
+mod archive;
+mod audits;
+mod batch;
+mod boilerplate;
+mod evaluations;
+mod exclusions;
+mod failures;
+mod filter;
+mod headers;
+mod links;
+mod page_iter;
+mod redirects;
+mod repair;
+mod runs;
+mod search;
+mod sections;
+mod sitemap_extensions;
+mod snapshots;
+mod status;
+mod tags;
+mod translations;
+
+pub use archive::ArchivedPage;
+pub use audits::{SummaryAudit, SummaryScores};
+pub use batch::WriteBatch;
+pub use evaluations::Evaluation;
+pub use failures::Failure;
+pub use filter::PageFilter;
+pub use headers::ResponseHeaders;
+pub use links::BrokenLink;
+pub use page_iter::{PageIter, PageSelection, SitemapEntry};
+pub use redirects::Redirect;
+pub use repair::{RepairReport, RowIssue, RowProblem};
+pub use runs::Run;
+pub use search::{SearchHit, any_word_search_query, plain_search_query};
+pub use sections::PageSection;
+pub use sitemap_extensions::SitemapExtensions;
+pub use snapshots::SitemapDiff;
+pub use status::PageStatus;
 
 /// Storage provides database operations for storing and retrieving scraped web page content.
+/// Clones share the same connection.
+#[derive(Clone)]
 pub struct Storage {
     /// The underlying SQLite connection wrapped in Arc<Mutex<>> to make it thread-safe
     conn: Arc<Mutex<Connection>>,
-    /// Indicates whether the database was newly created or already existed
-    pub new: bool,
-    /// Indicates whether the database was newly created or already existed
-    pub old: bool,
 }
 
 impl Storage {
@@ -36,19 +77,80 @@ impl Storage {
     ///
     /// Returns an error if database creation fails
     pub fn new(database_path: &str) -> Result<Self> {
-        let new = std::path::Path::new(database_path).try_exists().is_err();
-        let conn = Connection::open(database_path)?;
+        Self::with_connection(Connection::open(database_path)?)
+    }
 
+    /// Creates a new Storage instance with a database living in memory as long as its clones,
+    /// e.g. to test parse, summarize and compose without touching the filesystem.
+    ///
+    /// # Returns
+    ///
+    /// Returns a new Storage instance on success, or an error if database creation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database creation fails
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    /// Initializes the schema of an opened database and wraps its connection.
+    fn with_connection(conn: Connection) -> Result<Self> {
         Self::init_schema(&conn)?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
-            new,
-            old: !new,
         })
     }
 
-    /// Initializes the database schema with the pages table if it doesn't exist.
+    /// Checks whether no page is stored yet, as in a newly created database.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the pages table is empty, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn is_empty(&self) -> Result<bool> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let has_pages: bool =
+            conn.query_row("SELECT EXISTS (SELECT 1 FROM pages)", [], |row| row.get(0))?;
+
+        Ok(!has_pages)
+    }
+
+    /// Checks the database file isn't corrupt with SQLite's `PRAGMA integrity_check`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the problems found, empty if the database is sound, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let problems: Result<Vec<String>, rusqlite::Error> =
+            stmt.query_map([], |row| row.get(0))?.collect();
+
+        Ok(problems?
+            .into_iter()
+            .filter(|problem| problem != "ok")
+            .collect())
+    }
+
+    /// Initializes the database schema with the pages, redirects, sitemap extensions, page tags, page translations, priorities, checkpoints and exclusions tables and the tables of runs if they don't exist,
+    /// adding columns introduced since the database was created.
     fn init_schema(conn: &Connection) -> Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS pages (
@@ -58,7 +160,140 @@ impl Storage {
                 html TEXT NOT NULL,
                 title TEXT NULL,
                 text TEXT NULL,
-                summary TEXT NULL
+                summary TEXT NULL,
+                content_type TEXT NULL,
+                content BLOB NULL
+            )",
+            params![],
+        )?;
+        Self::add_missing_column(conn, "pages", "content_type", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "content", "BLOB NULL")?;
+        Self::add_missing_column(conn, "pages", "parse_status", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "keywords", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "audience", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "robots_tag", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "html_hash", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "noindex", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::add_missing_column(conn, "pages", "breadcrumbs", "TEXT NULL")?;
+        Self::add_missing_column(conn, "pages", "structured_data", "TEXT NULL")?;
+        Self::init_status_column(conn)?;
+        Self::normalize_timestamps(conn)?;
+        Self::init_quarantine_table(conn)?;
+        Self::init_archive_table(conn)?;
+        Self::init_snapshot_table(conn)?;
+        Self::init_audit_table(conn)?;
+        Self::init_boilerplate_table(conn)?;
+        Self::init_search_index(conn)?;
+
+        Self::init_page_data_schema(conn)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS redirects (
+                url TEXT PRIMARY KEY,
+                target TEXT NOT NULL
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sitemap_extensions (
+                url TEXT PRIMARY KEY,
+                publication_date INTEGER NULL,
+                news_title TEXT NULL,
+                video_duration INTEGER NULL,
+                image_captions TEXT NULL
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS checkpoints (
+                stage TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS exclusions (
+                pattern TEXT PRIMARY KEY,
+                added_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        Self::init_run_schema(conn)
+    }
+
+    /// Initializes the tables of tags, translations, sections, priorities, links, response headers
+    /// and importance of pages if they don't exist.
+    fn init_page_data_schema(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS page_tags (
+                url TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (url, tag)
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS page_translations (
+                url TEXT NOT NULL,
+                lang TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                PRIMARY KEY (url, lang)
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS page_sections (
+                url TEXT NOT NULL,
+                anchor TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                heading TEXT NOT NULL,
+                text TEXT NOT NULL,
+                summary TEXT NULL,
+                PRIMARY KEY (url, anchor)
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS priorities (
+                url TEXT PRIMARY KEY,
+                priority REAL NOT NULL
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS links (
+                source TEXT NOT NULL,
+                target TEXT NOT NULL,
+                PRIMARY KEY (source, target)
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS page_headers (
+                url TEXT PRIMARY KEY,
+                content_type TEXT NULL,
+                last_modified TEXT NULL,
+                etag TEXT NULL,
+                cache_control TEXT NULL,
+                fetched_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS importance (
+                url TEXT PRIMARY KEY,
+                score REAL NOT NULL
             )",
             params![],
         )?;
@@ -66,6 +301,74 @@ impl Storage {
         Ok(())
     }
 
+    /// Initializes the runs, failures and evaluations tables recorded by llamap runs if they don't exist.
+    fn init_run_schema(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL,
+                parameters TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER NULL,
+                pages_affected INTEGER NULL,
+                error TEXT NULL,
+                requests INTEGER NULL,
+                bytes_downloaded INTEGER NULL
+            )",
+            params![],
+        )?;
+        Self::add_missing_column(conn, "runs", "requests", "INTEGER NULL")?;
+        Self::add_missing_column(conn, "runs", "bytes_downloaded", "INTEGER NULL")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS failures (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id INTEGER NOT NULL,
+                stage TEXT NOT NULL,
+                url TEXT NOT NULL,
+                error TEXT NOT NULL,
+                failed_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS evaluations (
+                run_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                PRIMARY KEY (run_id, url, prompt)
+            )",
+            params![],
+        )?;
+
+        Ok(())
+    }
+
+    /// Adds a column missing from a table created by an older llamap version.
+    /// Returns `true` if the column was added.
+    fn add_missing_column(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<bool> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let columns: Result<Vec<String>, rusqlite::Error> =
+            stmt.query_map([], |row| row.get(1))?.collect();
+
+        if columns?.iter().any(|existing| existing == column) {
+            return Ok(false);
+        }
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+            params![],
+        )?;
+
+        Ok(true)
+    }
+
     /// Returns a list of all URLs stored in the database.
     ///
     /// # Returns
@@ -116,6 +419,98 @@ impl Storage {
         urls.map_err(|e| e.into())
     }
 
+    /// Returns a list of URLs of pages that were stored at or after the given moment.
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - The moment from which stored pages are listed
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URL strings on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_urls_added_since(&self, since: DateTime<Utc>) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT url FROM pages WHERE added_at >= ?1")?;
+        let urls: Result<Vec<String>, rusqlite::Error> = stmt
+            .query_map([since.timestamp()], |row| row.get(0))?
+            .collect();
+
+        urls.map_err(|e| e.into())
+    }
+
+    /// Returns the URLs the database knows of without storing their page: the ones listed by
+    /// sitemaps with a priority, linked to by stored pages or which failed to be scraped.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URL strings ordered by URL on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_unstored_urls(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT url FROM priorities
+             UNION SELECT target FROM links
+             UNION SELECT url FROM failures WHERE stage = 'scrape'
+             EXCEPT SELECT url FROM pages
+             ORDER BY 1",
+        )?;
+        let urls: Result<Vec<String>, rusqlite::Error> =
+            stmt.query_map([], |row| row.get(0))?.collect();
+
+        urls.map_err(|e| e.into())
+    }
+
+    /// Flags a page its site asks not to index, or clears the flag.
+    ///
+    /// Pages are flagged from their `X-Robots-Tag` header when they are stored,
+    /// this also flags pages whose robots meta tag is found when they are parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page to update
+    /// * `noindex` - Whether the page asks not to be indexed
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_page_noindex(&self, url: &str, noindex: bool) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::write_page_noindex(&conn, url, noindex)
+    }
+
+    /// Flags a page on the connection, see [`Storage::update_page_noindex`].
+    pub(super) fn write_page_noindex(conn: &Connection, url: &str, noindex: bool) -> Result<()> {
+        conn.execute(
+            "UPDATE pages SET noindex = ?1 WHERE url = ?2",
+            params![noindex, url],
+        )?;
+
+        Ok(())
+    }
+
     /// Gets the content for a specific URL from the database.
     ///
     /// # Arguments
@@ -161,8 +556,13 @@ impl Storage {
     /// Panics if the mutex is poisoned
     pub fn get_page(&self, url: &str) -> Result<Option<Page>> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::read_page(&conn, url)
+    }
+
+    /// Reads all page data for a URL on the connection, see [`Storage::get_page`].
+    pub(super) fn read_page(conn: &Connection, url: &str) -> Result<Option<Page>> {
         let mut stmt = conn.prepare(
-            "SELECT url, added_at, lastmod, html, title, text, summary FROM pages WHERE url = ?1",
+            "SELECT url, added_at, lastmod, html, title, text, summary, content_type, content, robots_tag, html_hash FROM pages WHERE url = ?1",
         )?;
         let page_row: Result<Option<PageRow>, rusqlite::Error> = stmt
             .query_row([url], |row| {
@@ -174,12 +574,16 @@ impl Storage {
                     title: row.get(4)?,
                     text: row.get(5)?,
                     summary: row.get(6)?,
+                    content_type: row.get(7)?,
+                    content: row.get(8)?,
+                    robots_tag: row.get(9)?,
+                    html_hash: row.get(10)?,
                 })
             })
             .optional();
 
         let page_row: Option<PageRow> =
-            page_row.map_err(|e| anyhow::anyhow!("Unable to fetch page row: {e}"))?;
+            page_row.map_err(|e| Error::from(e).context("Unable to fetch page row"))?;
 
         let page_row = match page_row {
             Some(page_row) => page_row,
@@ -191,6 +595,11 @@ impl Storage {
 
     /// Adds or updates a page in the database.
     ///
+    /// HTML and raw content of pages with a `html_hash` aren't stored, only the hash is.
+    /// The page is moved to the status its text and summary tell, see [`PageStatus`].
+    /// Columns which aren't part of [`Page`], like keywords, breadcrumbs and the parse status,
+    /// are kept when the page is already stored.
+    ///
     /// # Arguments
     ///
     /// * `page` - The Page struct containing all the page data
@@ -208,16 +617,41 @@ impl Storage {
     /// Panics if the mutex is poisoned
     pub fn upsert_page(&self, page: &Page) -> Result<()> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::write_page(&conn, page)
+    }
+
+    /// Adds or updates a page on the connection, see [`Storage::upsert_page`].
+    pub(super) fn write_page(conn: &Connection, page: &Page) -> Result<()> {
         conn.execute(
-            "INSERT OR REPLACE INTO pages (url, added_at, lastmod, html, title, text, summary) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO pages (url, added_at, lastmod, html, title, text, summary, content_type, content, robots_tag, noindex, html_hash, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+             ON CONFLICT(url) DO UPDATE SET
+                added_at = excluded.added_at,
+                lastmod = excluded.lastmod,
+                html = excluded.html,
+                title = excluded.title,
+                text = excluded.text,
+                summary = excluded.summary,
+                content_type = excluded.content_type,
+                content = excluded.content,
+                robots_tag = excluded.robots_tag,
+                noindex = excluded.noindex,
+                html_hash = excluded.html_hash,
+                status = excluded.status",
             params![
                 page.url.as_str(),
                 page.added_at.timestamp(),
                 page.lastmod.timestamp(),
-                page.html,
+                if page.html_hash.is_some() { "" } else { page.html.as_str() },
                 page.title,
                 page.text.as_deref().unwrap_or_default(),
-                page.summary.as_deref()
+                page.summary.as_deref(),
+                page.content_type.as_deref(),
+                page.content.as_deref().filter(|_| page.html_hash.is_none()),
+                page.robots_tag.as_deref(),
+                page.robots_tag.as_deref().is_some_and(has_noindex),
+                page.html_hash.as_deref(),
+                PageStatus::of_page(page).as_db_value()
             ],
         )?;
 
@@ -244,6 +678,11 @@ impl Storage {
     /// Panics if the mutex is poisoned
     pub fn update_page_text(&self, url: &str, text: &str) -> Result<()> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::write_page_text(&conn, url, text)
+    }
+
+    /// Updates the text of a page on the connection, see [`Storage::update_page_text`].
+    pub(super) fn write_page_text(conn: &Connection, url: &str, text: &str) -> Result<()> {
         conn.execute(
             "UPDATE pages SET text = ?1 WHERE url = ?2",
             params![text, url],
@@ -252,7 +691,7 @@ impl Storage {
         Ok(())
     }
 
-    /// Updates the summary for a page in the database.
+    /// Updates the summary for a page in the database, moving it to [`PageStatus::Summarized`].
     ///
     /// # Arguments
     ///
@@ -273,18 +712,20 @@ impl Storage {
     pub fn update_page_summary(&self, url: &str, summary: &str) -> Result<()> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
         conn.execute(
-            "UPDATE pages SET summary = ?1 WHERE url = ?2",
+            "UPDATE pages SET summary = ?1, keywords = NULL, audience = NULL, status = 'summarized' WHERE url = ?2",
             params![summary, url],
         )?;
 
         Ok(())
     }
 
-    /// Removes a page from the database.
+    /// Updates the summary for a page in the database along with its keywords and audience,
+    /// moving it to [`PageStatus::Summarized`].
     ///
     /// # Arguments
     ///
-    /// * `url` - The URL of the page to remove
+    /// * `url` - The URL of the page to update
+    /// * `summary` - The structured summary to store
     ///
     /// # Returns
     ///
@@ -297,86 +738,278 @@ impl Storage {
     /// # Panics
     ///
     /// Panics if the mutex is poisoned
-    pub fn remove_page(&self, url: &str) -> Result<()> {
+    pub fn update_page_structured_summary(
+        &self,
+        url: &str,
+        summary: &StructuredSummary,
+    ) -> Result<()> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
-        conn.execute("DELETE FROM pages WHERE url = ?1", params![url])?;
+        conn.execute(
+            "UPDATE pages SET summary = ?1, keywords = ?2, audience = ?3, status = 'summarized' WHERE url = ?4",
+            params![
+                summary.summary,
+                serde_json::to_string(&summary.keywords).map_err(|e| Error::Storage(e.into()))?,
+                summary.audience,
+                url
+            ],
+        )?;
+
         Ok(())
     }
 
-    /// Gets a limited number of pages that have not been summarized yet.
-    /// This helps manage memory usage when dealing with large databases.
+    /// Returns the keywords of a page stored with its structured summary.
     ///
     /// # Arguments
     ///
-    /// * `limit` - The maximum number of pages to retrieve
+    /// * `url` - The URL of the page
     ///
     /// # Returns
     ///
-    /// Returns a vector of (url, text) tuples for pages that need summarization on success,
+    /// Returns the keywords, empty if the page has no structured summary,
     /// or an error if database operation fails
     ///
     /// # Errors
     ///
+    /// Returns an error if database operation fails or the stored keywords are malformed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_keywords(&self, url: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let keywords: Option<String> = conn
+            .query_row(
+                "SELECT keywords FROM pages WHERE url = ?1",
+                params![url],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        match keywords {
+            Some(keywords) => serde_json::from_str(&keywords).map_err(|e| Error::Storage(e.into())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Stores the breadcrumb trail of a page, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page to update
+    /// * `breadcrumbs` - Names of the crumbs from the site root, empty if the page has none
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
     /// Returns an error if database operation fails
     ///
     /// # Panics
     ///
     /// Panics if the mutex is poisoned
-    pub fn fetch_unsummarized_pages(&self, limit: u32) -> Result<Vec<(String, String)>> {
+    pub fn update_page_breadcrumbs(&self, url: &str, breadcrumbs: &[String]) -> Result<()> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
-        let mut stmt = conn.prepare(
-            "
-                SELECT url, text FROM pages
-                WHERE text is not NULL and text != ''
-                  and (summary is NULL or summary = '')
-                ORDER BY added_at ASC
-                LIMIT ?1
-            ",
+        Self::write_page_breadcrumbs(&conn, url, breadcrumbs)
+    }
+
+    /// Stores the breadcrumb trail of a page on the connection, see [`Storage::update_page_breadcrumbs`].
+    pub(super) fn write_page_breadcrumbs(
+        conn: &Connection,
+        url: &str,
+        breadcrumbs: &[String],
+    ) -> Result<()> {
+        let breadcrumbs = if breadcrumbs.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(breadcrumbs).map_err(|e| Error::Storage(e.into()))?)
+        };
+        conn.execute(
+            "UPDATE pages SET breadcrumbs = ?1 WHERE url = ?2",
+            params![breadcrumbs, url],
         )?;
-        let rows = stmt.query_map([limit], |row| Ok((row.get(0)?, row.get(1)?)))?;
-        let pages: Vec<(String, String)> = rows.flatten().collect();
 
-        Ok(pages)
+        Ok(())
     }
 
-    /// Gets a limited number of all pages that can be summarized from the
-    /// database with an offset.
+    /// Returns the breadcrumb trail of a page stored by parse.
     ///
     /// # Arguments
     ///
-    /// * `limit` - The maximum number of pages to retrieve
-    /// * `offset` - The offset from which to start retrieving pages
+    /// * `url` - The URL of the page
     ///
     /// # Returns
     ///
-    /// Returns a vector of (url, text) tuples for all pages on success,
+    /// Returns the names of the crumbs from the site root, empty if the page has none,
     /// or an error if database operation fails
     ///
     /// # Errors
     ///
+    /// Returns an error if database operation fails or the stored breadcrumbs are malformed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_breadcrumbs(&self, url: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let breadcrumbs: Option<String> = conn
+            .query_row(
+                "SELECT breadcrumbs FROM pages WHERE url = ?1",
+                params![url],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        match breadcrumbs {
+            Some(breadcrumbs) => {
+                serde_json::from_str(&breadcrumbs).map_err(|e| Error::Storage(e.into()))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Stores the JSON-LD blocks of a page, replacing the previous ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page to update
+    /// * `blocks` - JSON-LD blocks of articles, products and FAQ pages, empty if the page has none
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
     /// Returns an error if database operation fails
     ///
     /// # Panics
     ///
     /// Panics if the mutex is poisoned
-    pub fn fetch_summarizable_pages(
+    pub fn update_page_structured_data(
         &self,
-        limit: u32,
-        offset: u32,
-    ) -> Result<Vec<(String, String)>> {
+        url: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<()> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
-        let mut stmt = conn.prepare(
-            "
-                SELECT url, text FROM pages
-                WHERE text is not NULL and text != ''
-                ORDER BY added_at ASC
-                LIMIT ?1 OFFSET ?2
-            ",
+        Self::write_page_structured_data(&conn, url, blocks)
+    }
+
+    /// Stores the JSON-LD blocks of a page on the connection, see [`Storage::update_page_structured_data`].
+    pub(super) fn write_page_structured_data(
+        conn: &Connection,
+        url: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<()> {
+        let blocks = if blocks.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(blocks).map_err(|e| Error::Storage(e.into()))?)
+        };
+        conn.execute(
+            "UPDATE pages SET structured_data = ?1 WHERE url = ?2",
+            params![blocks, url],
         )?;
-        let rows = stmt.query_map([limit, offset], |row| Ok((row.get(0)?, row.get(1)?)))?;
-        let pages: Vec<(String, String)> = rows.flatten().collect();
 
-        Ok(pages)
+        Ok(())
+    }
+
+    /// Returns the JSON-LD blocks of a page stored by parse.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns the blocks in the order of the page, empty if the page has none,
+    /// or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails or the stored blocks are malformed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_structured_data(&self, url: &str) -> Result<Vec<serde_json::Value>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let blocks: Option<String> = conn
+            .query_row(
+                "SELECT structured_data FROM pages WHERE url = ?1",
+                params![url],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        match blocks {
+            Some(blocks) => serde_json::from_str(&blocks).map_err(|e| Error::Storage(e.into())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Removes a page from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page to remove
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn remove_page(&self, url: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute("DELETE FROM pages WHERE url = ?1", params![url])?;
+        Ok(())
+    }
+
+    /// Records the outcome of parsing a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page to update
+    /// * `status` - The outcome of parsing the page
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_parse_status(&self, url: &str, status: ParseStatus) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::write_parse_status(&conn, url, status)
+    }
+
+    /// Records the outcome of parsing a page on the connection, see [`Storage::update_parse_status`].
+    pub(super) fn write_parse_status(
+        conn: &Connection,
+        url: &str,
+        status: ParseStatus,
+    ) -> Result<()> {
+        conn.execute(
+            "UPDATE pages SET parse_status = ?1 WHERE url = ?2",
+            params![status.as_str(), url],
+        )?;
+
+        Ok(())
     }
 
     /// Gets the content for a specific URL from the database.
@@ -441,6 +1074,72 @@ impl Storage {
         Ok(scrapable_urls)
     }
 
+    /// Filters and returns URLs which aren't stored yet, whatever the lastmod of the stored ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - The listed URLs
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URLs not in the database on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn resolve_new(&self, urls: impl IntoIterator<Item = String>) -> Result<Vec<String>> {
+        let mut new_urls = Vec::new();
+        for url in urls {
+            if self.get_lastmod(&url)?.is_none() {
+                new_urls.push(url);
+            }
+        }
+
+        Ok(new_urls)
+    }
+
+    /// Records priorities of sitemap entries, kept apart from pages so that they
+    /// survive pages being re-scraped.
+    ///
+    /// # Arguments
+    ///
+    /// * `sitemap_entries` - A map of URLs to their sitemap entries containing priority information
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_priorities(
+        &self,
+        sitemap_entries: &std::collections::HashMap<String, sitemap::structs::UrlEntry>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        {
+            let mut stmt = transaction
+                .prepare("INSERT OR REPLACE INTO priorities (url, priority) VALUES (?1, ?2)")?;
+            for (url, sitemap_entry) in sitemap_entries {
+                if let Priority::Value(priority) = sitemap_entry.priority {
+                    stmt.execute(params![url, priority])?;
+                }
+            }
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
     /// Determines if a URL should be scraped based on its lastmod timestamp.
     ///
     /// # Arguments
@@ -462,17 +1161,12 @@ impl Storage {
     fn should_scrape(&self, url: &str, lastmod: LastMod) -> Result<bool> {
         Ok(match lastmod {
             LastMod::DateTime(lastmod) => {
-                let db_lastmod = match self.get_lastmod(url)? {
-                    Some(db_lastmod) => db_lastmod,
+                match self.get_lastmod(url)? {
+                    // Pages are stored with the sitemap lastmod, truncated to seconds
+                    Some(db_lastmod) => lastmod.timestamp() != db_lastmod,
                     // No record in DB, should scrape
-                    None => return Ok(true),
-                };
-
-                let db_lastmod_datetime = DateTime::from_timestamp(db_lastmod, 0)
-                    .ok_or("Can't convert timestamp to DateTime")
-                    .map_err(|e| anyhow::anyhow!("{e}"))?;
-
-                lastmod != db_lastmod_datetime
+                    None => true,
+                }
             }
             // No lastmod in sitemap, should scrape.
             LastMod::None => true,
@@ -481,7 +1175,8 @@ impl Storage {
         })
     }
 
-    /// Gets the lastmod timestamp for a specific URL from the database.
+    /// Gets the lastmod timestamp for a specific URL from the database,
+    /// the one of the page it redirects to if it does.
     ///
     /// # Arguments
     ///
@@ -500,7 +1195,10 @@ impl Storage {
     /// Panics if the mutex is poisoned
     pub fn get_lastmod(&self, url: &str) -> Result<Option<i64>> {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
-        let mut stmt = conn.prepare("SELECT lastmod FROM pages WHERE url = ?1")?;
+        let mut stmt = conn.prepare(
+            "SELECT lastmod FROM pages
+            WHERE url = coalesce((SELECT target FROM redirects WHERE url = ?1), ?1)",
+        )?;
         let lastmod: Result<Option<i64>, rusqlite::Error> =
             stmt.query_row([url], |row| row.get(0)).optional();
 
@@ -530,7 +1228,22 @@ impl Storage {
         I: IntoIterator<Item = String>,
     {
         let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::record_visited_urls(&conn, visited_urls)?;
+
+        let deleted_count = conn.execute(
+            "DELETE FROM pages WHERE url NOT IN (SELECT url FROM temp_visited_urls)",
+            [],
+        )?;
 
+        Ok(deleted_count)
+    }
+
+    /// Fills the `temp_visited_urls` temporary table with the visited URLs, replacing the
+    /// ones of a previous run.
+    fn record_visited_urls<I>(conn: &Connection, visited_urls: I) -> Result<()>
+    where
+        I: IntoIterator<Item = String>,
+    {
         conn.execute_batch(
             r#"
                 DROP TABLE IF EXISTS temp_visited_urls;
@@ -552,12 +1265,104 @@ impl Storage {
             conn.execute(&sql, rusqlite::params_from_iter(params))?;
         }
 
-        let deleted_count = conn.execute(
-            "DELETE FROM pages WHERE url NOT IN (SELECT url FROM temp_visited_urls)",
-            [],
+        Ok(())
+    }
+
+    /// Gets the checkpoint recorded for a processing stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The name of the stage (e.g. "scrape" or "summarize")
+    ///
+    /// # Returns
+    ///
+    /// Returns the checkpoint value if one was recorded, None otherwise, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_checkpoint(&self, stage: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT value FROM checkpoints WHERE stage = ?1")?;
+        let value: Result<Option<String>, rusqlite::Error> =
+            stmt.query_row([stage], |row| row.get(0)).optional();
+
+        value.map_err(|e| e.into())
+    }
+
+    /// Records a checkpoint for a processing stage, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The name of the stage (e.g. "scrape" or "summarize")
+    /// * `value` - The stage-specific position to resume from
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_checkpoint(&self, stage: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "INSERT OR REPLACE INTO checkpoints (stage, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![stage, value, Utc::now().timestamp()],
         )?;
 
-        Ok(deleted_count)
+        Ok(())
+    }
+
+    /// Removes the checkpoint of a processing stage once it has completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The name of the stage (e.g. "scrape" or "summarize")
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn clear_checkpoint(&self, stage: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute("DELETE FROM checkpoints WHERE stage = ?1", params![stage])?;
+
+        Ok(())
+    }
+}
+
+/// Outcome of parsing a page, stored in the `parse_status` column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseStatus {
+    /// The text was extracted successfully
+    Ok,
+    /// The extracted text is shorter than `--min-text-chars`, e.g. a "JavaScript required" stub
+    TooShort,
+}
+
+impl ParseStatus {
+    /// Returns the value stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::TooShort => "too_short",
+        }
     }
 }
 
@@ -571,6 +1376,10 @@ pub struct PageRow {
     pub title: Option<String>,
     pub text: Option<String>,
     pub summary: Option<String>,
+    pub content_type: Option<String>,
+    pub content: Option<Vec<u8>>,
+    pub robots_tag: Option<String>,
+    pub html_hash: Option<String>,
 }
 
 /// Represents domain Page
@@ -583,9 +1392,38 @@ pub struct Page {
     pub title: Option<String>,
     pub text: Option<String>,
     pub summary: Option<String>,
+    /// The content type the page was served with, if known
+    pub content_type: Option<String>,
+    /// Raw content of non-HTML documents (e.g. PDF), whose `html` is empty
+    pub content: Option<Vec<u8>>,
+    /// The `X-Robots-Tag` header the page was served with, if any
+    pub robots_tag: Option<String>,
+    /// Hash of the HTML of a page scraped without storing it, see [`hash_html`];
+    /// its `html` and `content` are kept in memory only and read back empty
+    pub html_hash: Option<String>,
+}
+
+/// Timestamp of pages whose lastmod or storage date isn't known, the epoch. Pages with an unknown
+/// lastmod are scraped again and listed without a date.
+pub const UNKNOWN_TIMESTAMP: i64 = 0;
+
+/// Returns a hash identifying the HTML of a page, stored instead of the HTML
+/// when scraping without storing it, so that unchanged pages are still detected.
+///
+/// The 64-bit FNV-1a hash is used, which is stable across Rust versions and platforms.
+pub fn hash_html(html: &str) -> String {
+    let hash = html.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("fnv1a64:{hash:016x}")
 }
 
 impl Page {
+    /// Returns `true` if the HTML of the page isn't stored, so the page can't be parsed again.
+    pub fn is_html_discarded(&self) -> bool {
+        self.html_hash.is_some() && self.html.is_empty() && self.content.is_none()
+    }
+
     /// Applies content from a PageArticle to the page.
     ///
     /// Updates the text field with the article text, and the title field with
@@ -599,19 +1437,31 @@ impl Page {
 }
 
 impl TryFrom<PageRow> for Page {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(page_row: PageRow) -> Result<Self> {
+        // Out of range timestamps are read as unknown, so one bad row can't fail a whole run
+        let timestamp = |column: &str, timestamp: i64| {
+            DateTime::from_timestamp_secs(timestamp).unwrap_or_else(|| {
+                warn!(
+                    "Reading the invalid {column} {timestamp} of {} as unknown",
+                    page_row.url
+                );
+                DateTime::UNIX_EPOCH
+            })
+        };
         Ok(Page {
-            url: Url::parse(&page_row.url)?,
-            added_at: DateTime::from_timestamp_secs(page_row.added_at)
-                .context("Unable to initialize added_at from database")?,
-            lastmod: DateTime::from_timestamp_secs(page_row.lastmod)
-                .context("Unable to initialize lastmod from database")?,
+            added_at: timestamp("added_at", page_row.added_at),
+            lastmod: timestamp("lastmod", page_row.lastmod),
+            url: Url::parse(&page_row.url).map_err(|e| Error::Storage(e.into()))?,
             html: page_row.html,
             title: page_row.title,
             text: page_row.text,
             summary: page_row.summary,
+            content_type: page_row.content_type,
+            content: page_row.content,
+            robots_tag: page_row.robots_tag,
+            html_hash: page_row.html_hash,
         })
     }
 }
@@ -0,0 +1,114 @@
+//! Pages a scrape no longer found, moved out of the pages table instead of deleted, so that
+//! content which disappeared from the sitemap can still be listed as archived.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, params};
+
+use super::Storage;
+use crate::error::Result;
+
+/// Represents a page archived as its sitemap no longer listed it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedPage {
+    pub url: String,
+    pub title: Option<String>,
+    pub text: Option<String>,
+    pub summary: Option<String>,
+    /// When the page was archived
+    pub archived_at: DateTime<Utc>,
+}
+
+impl Storage {
+    /// Creates the table of archived pages if it doesn't exist.
+    pub(super) fn init_archive_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS archived_pages (
+                url TEXT PRIMARY KEY,
+                archived_at INTEGER NOT NULL,
+                lastmod INTEGER NOT NULL,
+                title TEXT NULL,
+                text TEXT NULL,
+                summary TEXT NULL
+            )",
+            params![],
+        )?;
+
+        Ok(())
+    }
+
+    /// Moves all pages that are not present in the provided list of visited URLs to the
+    /// `archived_pages` table, replacing previously archived versions of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `visited_urls` - A collection of URLs that were visited during scraping
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of pages archived on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn archive_unvisited_pages<I>(&self, visited_urls: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::record_visited_urls(&conn, visited_urls)?;
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO archived_pages (url, archived_at, lastmod, title, text, summary)
+             SELECT url, ?1, lastmod, title, text, summary FROM pages
+             WHERE url NOT IN (SELECT url FROM temp_visited_urls)",
+            params![Utc::now().timestamp()],
+        )?;
+        let archived_count = tx.execute(
+            "DELETE FROM pages WHERE url NOT IN (SELECT url FROM temp_visited_urls)",
+            [],
+        )?;
+        tx.commit()?;
+
+        Ok(archived_count)
+    }
+
+    /// Returns the archived pages which weren't stored again since, ordered by URL.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of archived pages on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_archived_pages(&self) -> Result<Vec<ArchivedPage>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT url, title, text, summary, archived_at FROM archived_pages
+             WHERE url NOT IN (SELECT url FROM pages)
+             ORDER BY url",
+        )?;
+        let pages: Result<Vec<ArchivedPage>, rusqlite::Error> = stmt
+            .query_map([], |row| {
+                Ok(ArchivedPage {
+                    url: row.get(0)?,
+                    title: row.get(1)?,
+                    text: row.get(2)?,
+                    summary: row.get(3)?,
+                    archived_at: DateTime::from_timestamp(row.get(4)?, 0).unwrap_or_default(),
+                })
+            })?
+            .collect();
+
+        Ok(pages?)
+    }
+}
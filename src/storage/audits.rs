@@ -0,0 +1,187 @@
+//! Scores a judge model gave to the stored summaries, so that the worst ones can be
+//! reviewed or summarized again.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, params, params_from_iter};
+
+use super::{PageFilter, Storage};
+use crate::error::Result;
+
+/// Scores of a summary from 1 (worst) to 5 (best)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SummaryScores {
+    /// Whether the summary only states what the page text says
+    pub faithfulness: u8,
+    /// Whether the summary is concise without leaving out the main points of the page
+    pub length: u8,
+    /// Whether the summary is written in the language of the page
+    pub language: u8,
+}
+
+impl SummaryScores {
+    /// Returns the lowest of the scores, which ranks the summaries.
+    pub fn lowest(self) -> u8 {
+        self.faithfulness.min(self.length).min(self.language)
+    }
+}
+
+/// Represents the audit of the current summary of a page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SummaryAudit {
+    pub url: String,
+    pub scores: SummaryScores,
+    /// Why the judge gave these scores
+    pub comment: String,
+    /// When the summary was audited
+    pub audited_at: DateTime<Utc>,
+}
+
+impl Storage {
+    /// Creates the table of summary audits if it doesn't exist.
+    pub(super) fn init_audit_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS summary_audits (
+                url TEXT PRIMARY KEY,
+                run_id INTEGER NOT NULL,
+                summary TEXT NOT NULL,
+                faithfulness INTEGER NOT NULL,
+                length INTEGER NOT NULL,
+                language INTEGER NOT NULL,
+                comment TEXT NOT NULL,
+                audited_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns URLs of the summarized pages matching the filter whose current summary
+    /// wasn't audited yet, ordered by URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Conditions the pages must match
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URL strings on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_unaudited_urls(&self, filter: &PageFilter) -> Result<Vec<String>> {
+        let (filter_conditions, values) = filter.conditions();
+        let mut conditions = vec![
+            "summary IS NOT NULL",
+            "NOT EXISTS (SELECT 1 FROM summary_audits
+                         WHERE summary_audits.url = pages.url AND summary_audits.summary = pages.summary)",
+        ];
+        conditions.extend(filter_conditions);
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT url FROM pages WHERE ({}) ORDER BY url",
+            conditions.join(") and (")
+        ))?;
+        let urls: Result<Vec<String>, rusqlite::Error> = stmt
+            .query_map(params_from_iter(values), |row| row.get(0))?
+            .collect();
+
+        Ok(urls?)
+    }
+
+    /// Stores the audit of a summary, replacing the previous audit of the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the run returned by [`Storage::start_run`]
+    /// * `summary` - The audited summary, so that the audit is dropped once the page is summarized again
+    /// * `audit` - The scores of the summary
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_summary_audit(
+        &self,
+        run_id: i64,
+        summary: &str,
+        audit: &SummaryAudit,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "INSERT OR REPLACE INTO summary_audits
+             (url, run_id, summary, faithfulness, length, language, comment, audited_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                audit.url,
+                run_id,
+                summary,
+                audit.scores.faithfulness,
+                audit.scores.length,
+                audit.scores.language,
+                audit.comment,
+                audit.audited_at.timestamp()
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the audits of the current summaries of stored pages, the worst first: by their
+    /// lowest score, then by the sum of their scores.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximal number of returned audits
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of audits on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_worst_audits(&self, limit: usize) -> Result<Vec<SummaryAudit>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT audits.url, faithfulness, length, language, comment, audited_at
+             FROM summary_audits AS audits
+             JOIN pages ON pages.url = audits.url AND pages.summary = audits.summary
+             ORDER BY MIN(faithfulness, length, language), faithfulness + length + language, audits.url
+             LIMIT ?1",
+        )?;
+        let audits: Result<Vec<SummaryAudit>, rusqlite::Error> = stmt
+            .query_map([i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+                Ok(SummaryAudit {
+                    url: row.get(0)?,
+                    scores: SummaryScores {
+                        faithfulness: row.get(1)?,
+                        length: row.get(2)?,
+                        language: row.get(3)?,
+                    },
+                    comment: row.get(4)?,
+                    audited_at: DateTime::from_timestamp(row.get(5)?, 0).unwrap_or_default(),
+                })
+            })?
+            .collect();
+
+        Ok(audits?)
+    }
+}
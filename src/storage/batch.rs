@@ -0,0 +1,161 @@
+//! Batched writes grouping many page updates into a single transaction.
+
+use rusqlite::Transaction;
+
+use super::{Page, PageSection, ParseStatus, Redirect, ResponseHeaders, Storage};
+use crate::error::{Error, Result};
+
+/// Writes of a batch, applied in a transaction of its own, see [`Storage::write_batch`].
+///
+/// The batch holds the database connection until it's committed, so that writes of other
+/// threads wait for it instead of joining its transaction.
+pub struct WriteBatch<'a> {
+    transaction: Transaction<'a>,
+}
+
+impl Storage {
+    /// Runs the writes of a batch in a single transaction instead of one implicit transaction
+    /// each, committing them if they all succeed and rolling them all back otherwise.
+    ///
+    /// The connection is held while the batch runs, the writes can't call other methods of
+    /// the storage.
+    ///
+    /// The batch is scoped to a closure rather than opened and committed by separate calls,
+    /// so that a batch can't be left open holding the connection: it's committed when the
+    /// writes succeed and rolled back when they fail, return early or panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `write` - Writes the batch
+    ///
+    /// # Returns
+    ///
+    /// Returns what the writes return on success, or an error if they or the commit fail
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a write or database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn write_batch<T, E: From<Error>>(
+        &self,
+        write: impl FnOnce(&WriteBatch<'_>) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        let mut conn = self.conn.lock().expect("Storage mutex poisoned");
+        let batch = WriteBatch {
+            transaction: conn.transaction().map_err(Error::from)?,
+        };
+        let written = write(&batch)?;
+        batch.transaction.commit().map_err(Error::from)?;
+
+        Ok(written)
+    }
+}
+
+impl WriteBatch<'_> {
+    /// Gets all page data for a URL, including pages written by the batch,
+    /// see [`Storage::get_page`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn get_page(&self, url: &str) -> Result<Option<Page>> {
+        Storage::read_page(&self.transaction, url)
+    }
+
+    /// Adds or updates a page, see [`Storage::upsert_page`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn upsert_page(&self, page: &Page) -> Result<()> {
+        Storage::write_page(&self.transaction, page)
+    }
+
+    /// Updates the text of a page, see [`Storage::update_page_text`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn update_page_text(&self, url: &str, text: &str) -> Result<()> {
+        Storage::write_page_text(&self.transaction, url, text)
+    }
+
+    /// Records whether a page asks not to be indexed, see [`Storage::update_page_noindex`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn update_page_noindex(&self, url: &str, noindex: bool) -> Result<()> {
+        Storage::write_page_noindex(&self.transaction, url, noindex)
+    }
+
+    /// Records the outcome of parsing a page, see [`Storage::update_parse_status`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn update_parse_status(&self, url: &str, status: ParseStatus) -> Result<()> {
+        Storage::write_parse_status(&self.transaction, url, status)
+    }
+
+    /// Stores the breadcrumb trail of a page, see [`Storage::update_page_breadcrumbs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn update_page_breadcrumbs(&self, url: &str, breadcrumbs: &[String]) -> Result<()> {
+        Storage::write_page_breadcrumbs(&self.transaction, url, breadcrumbs)
+    }
+
+    /// Stores the JSON-LD blocks of a page, see [`Storage::update_page_structured_data`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn update_page_structured_data(
+        &self,
+        url: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<()> {
+        Storage::write_page_structured_data(&self.transaction, url, blocks)
+    }
+
+    /// Replaces the sections of a page, see [`Storage::replace_page_sections`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn replace_page_sections(&self, url: &str, sections: &[PageSection]) -> Result<()> {
+        Storage::write_page_sections(&self.transaction, url, sections)
+    }
+
+    /// Replaces the links of a page, see [`Storage::replace_page_links`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn replace_page_links(&self, source: &str, targets: &[String]) -> Result<()> {
+        Storage::write_page_links(&self.transaction, source, targets)
+    }
+
+    /// Stores the response headers of a page, see [`Storage::update_page_headers`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn update_page_headers(&self, url: &str, headers: &ResponseHeaders) -> Result<()> {
+        Storage::write_page_headers(&self.transaction, url, headers)
+    }
+
+    /// Records a redirect, see [`Storage::record_redirect`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    pub fn record_redirect(&self, redirect: &Redirect) -> Result<()> {
+        Storage::write_redirect(&self.transaction, redirect)
+    }
+}
@@ -0,0 +1,94 @@
+//! Boilerplate blocks detected by parse runs, so that pages parsed by later runs, e.g. of a
+//! single URL, are stripped of them too although their texts alone don't repeat them.
+
+use rusqlite::{Connection, params};
+use std::collections::{HashMap, HashSet};
+
+use super::Storage;
+use crate::error::Result;
+
+impl Storage {
+    /// Creates the table of boilerplate blocks if it doesn't exist.
+    pub(super) fn init_boilerplate_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS boilerplate_blocks (
+                block TEXT PRIMARY KEY,
+                pages INTEGER NOT NULL,
+                detected_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records detected boilerplate blocks with the number of pages they were found in,
+    /// keeping the highest number of a block detected before.
+    ///
+    /// # Arguments
+    ///
+    /// * `blocks` - A map of blocks to the number of parsed pages repeating them
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_boilerplate_blocks(&self, blocks: &HashMap<String, usize>) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        let detected_at = chrono::Utc::now().timestamp();
+        for (block, pages) in blocks {
+            transaction.execute(
+                "INSERT INTO boilerplate_blocks (block, pages, detected_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(block) DO UPDATE SET
+                    pages = max(pages, excluded.pages),
+                    detected_at = excluded.detected_at",
+                params![
+                    block,
+                    i64::try_from(*pages).unwrap_or(i64::MAX),
+                    detected_at
+                ],
+            )?;
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the recorded boilerplate blocks found in at least `min_pages` pages.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_pages` - The number of pages a block must have been found in
+    ///
+    /// # Returns
+    ///
+    /// Returns the blocks on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_boilerplate_blocks(&self, min_pages: usize) -> Result<HashSet<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT block FROM boilerplate_blocks WHERE pages >= ?1")?;
+        let blocks: Result<HashSet<String>, rusqlite::Error> = stmt
+            .query_map(
+                params![i64::try_from(min_pages).unwrap_or(i64::MAX)],
+                |row| row.get(0),
+            )?
+            .collect();
+
+        Ok(blocks?)
+    }
+}
@@ -0,0 +1,140 @@
+//! Summaries written by prompt comparison runs, kept apart from the page summaries.
+
+use crate::error::Result;
+use rusqlite::{params, params_from_iter};
+
+use super::{PageFilter, PageSelection, Storage};
+
+/// Represents a summary of a page written with one of the compared prompts.
+#[derive(Debug)]
+pub struct Evaluation {
+    pub url: String,
+    /// Name of the prompt the summary was written with
+    pub prompt: String,
+    pub summary: String,
+}
+
+impl Storage {
+    /// Returns URLs of a random sample of the selected stored pages.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - Which pages to sample
+    /// * `filter` - Conditions the pages must also match
+    /// * `size` - Maximal number of sampled pages
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URL strings on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn sample_urls(
+        &self,
+        selection: PageSelection,
+        filter: &PageFilter,
+        size: usize,
+    ) -> Result<Vec<String>> {
+        let (filter_conditions, mut values) = filter.conditions();
+        let mut conditions = vec![selection.condition()];
+        conditions.extend(filter_conditions);
+        values.push(i64::try_from(size).unwrap_or(i64::MAX).into());
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "
+                SELECT url FROM pages
+                WHERE ({})
+                ORDER BY random()
+                LIMIT ?
+            ",
+            conditions.join(") and (")
+        ))?;
+        let urls: Result<Vec<String>, rusqlite::Error> = stmt
+            .query_map(params_from_iter(values), |row| row.get(0))?
+            .collect();
+
+        urls.map_err(|e| e.into())
+    }
+
+    /// Stores a summary written by a prompt comparison run.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the run returned by [`Storage::start_run`]
+    /// * `url` - The URL of the summarized page
+    /// * `prompt` - Name of the prompt the summary was written with
+    /// * `summary` - The summary
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_evaluation(
+        &self,
+        run_id: i64,
+        url: &str,
+        prompt: &str,
+        summary: &str,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "INSERT OR REPLACE INTO evaluations (run_id, url, prompt, summary) VALUES (?1, ?2, ?3, ?4)",
+            params![run_id, url, prompt, summary],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns summaries written by a prompt comparison run, in the order they were written.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the run
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of evaluations on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_evaluations(&self, run_id: i64) -> Result<Vec<Evaluation>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "
+                SELECT url, prompt, summary
+                FROM evaluations
+                WHERE run_id = ?1
+                ORDER BY rowid ASC
+            ",
+        )?;
+        let evaluations: Result<Vec<Evaluation>, rusqlite::Error> = stmt
+            .query_map([run_id], |row| {
+                Ok(Evaluation {
+                    url: row.get(0)?,
+                    prompt: row.get(1)?,
+                    summary: row.get(2)?,
+                })
+            })?
+            .collect();
+
+        evaluations.map_err(|e| e.into())
+    }
+}
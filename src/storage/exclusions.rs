@@ -0,0 +1,124 @@
+//! Persistent blocklist of URL patterns which scrape never stores again and compose leaves out.
+
+use crate::error::Result;
+use rusqlite::params;
+
+use super::Storage;
+
+/// SQL condition matching a `url` no exclusion pattern matches. Patterns starting with `/`
+/// are matched against the URL path (with the query), others against the whole URL.
+pub(super) const NOT_EXCLUDED_SQL: &str = "NOT EXISTS (
+    SELECT 1 FROM exclusions
+    WHERE (CASE WHEN exclusions.pattern GLOB '/*'
+        THEN substr(url, instr(url, '://') + 2 + instr(substr(url, instr(url, '://') + 3), '/'))
+        ELSE url END) GLOB exclusions.pattern
+)";
+
+impl Storage {
+    /// Adds a glob pattern to the exclusions.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Glob matched against the URL path if it starts with `/` (e.g. `/legal/*`),
+    ///   against the whole URL otherwise
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the pattern wasn't excluded yet, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn add_exclusion(&self, pattern: &str) -> Result<bool> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let added = conn.execute(
+            "INSERT OR IGNORE INTO exclusions (pattern, added_at) VALUES (?1, ?2)",
+            params![pattern, chrono::Utc::now().timestamp()],
+        )?;
+
+        Ok(added > 0)
+    }
+
+    /// Removes a glob pattern from the exclusions.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The pattern as it was added
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the pattern was excluded, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn remove_exclusion(&self, pattern: &str) -> Result<bool> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let removed = conn.execute(
+            "DELETE FROM exclusions WHERE pattern = ?1",
+            params![pattern],
+        )?;
+
+        Ok(removed > 0)
+    }
+
+    /// Returns all exclusion patterns, ordered by pattern.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of patterns on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_exclusions(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT pattern FROM exclusions ORDER BY pattern ASC")?;
+        let patterns: Result<Vec<String>, rusqlite::Error> =
+            stmt.query_map([], |row| row.get(0))?.collect();
+
+        patterns.map_err(|e| e.into())
+    }
+
+    /// Checks whether an URL matches any exclusion pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to check
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the URL is excluded, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn is_excluded(&self, url: &str) -> Result<bool> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let excluded = conn.query_row(
+            &format!(
+                "WITH candidate(url) AS (SELECT ?1) SELECT NOT ({NOT_EXCLUDED_SQL}) FROM candidate"
+            ),
+            params![url],
+            |row| row.get(0),
+        )?;
+
+        Ok(excluded)
+    }
+}
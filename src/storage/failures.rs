@@ -0,0 +1,99 @@
+//! Per-page failures recorded by llamap runs.
+
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+use super::{PageStatus, Storage};
+
+/// Represents a page which failed to be processed by a run.
+#[derive(Debug)]
+pub struct Failure {
+    pub id: i64,
+    /// The run the failure happened in
+    pub run_id: i64,
+    /// The stage that failed (e.g. "parse")
+    pub stage: String,
+    pub url: String,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl Storage {
+    /// Records a page which failed to be processed, moving it to [`PageStatus::Failed`] if it's stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the run returned by [`Storage::start_run`]
+    /// * `stage` - The stage that failed (e.g. "parse")
+    /// * `url` - The URL of the failed page
+    /// * `error` - The error the page failed with
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn record_failure(&self, run_id: i64, stage: &str, url: &str, error: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "INSERT INTO failures (run_id, stage, url, error, failed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![run_id, stage, url, error, Utc::now().timestamp()],
+        )?;
+        conn.execute(
+            "UPDATE pages SET status = ?1 WHERE url = ?2",
+            params![PageStatus::Failed(stage.to_string()).as_db_value(), url],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns failures recorded by a run, in the order they happened.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the run
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of failures on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_failures(&self, run_id: i64) -> Result<Vec<Failure>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "
+                SELECT id, run_id, stage, url, error, failed_at
+                FROM failures
+                WHERE run_id = ?1
+                ORDER BY id ASC
+            ",
+        )?;
+        let failures: Result<Vec<Failure>, rusqlite::Error> = stmt
+            .query_map([run_id], |row| {
+                Ok(Failure {
+                    id: row.get(0)?,
+                    run_id: row.get(1)?,
+                    stage: row.get(2)?,
+                    url: row.get(3)?,
+                    error: row.get(4)?,
+                    failed_at: DateTime::from_timestamp_secs(row.get(5)?).unwrap_or_default(),
+                })
+            })?
+            .collect();
+
+        failures.map_err(|e| e.into())
+    }
+}
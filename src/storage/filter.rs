@@ -0,0 +1,228 @@
+//! Selection of stored pages by their content, URL and dates.
+
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use std::ops::{Bound, RangeBounds};
+
+use super::exclusions::NOT_EXCLUDED_SQL;
+use super::{PageIter, Storage};
+
+/// Conditions stored pages must match, all of them when several are set.
+#[derive(Clone, Debug)]
+pub struct PageFilter {
+    has_summary: Option<bool>,
+    text_contains: Option<String>,
+    url_glob: Option<String>,
+    urls: Option<Vec<String>>,
+    lastmod_from: Bound<DateTime<Utc>>,
+    lastmod_to: Bound<DateTime<Utc>>,
+    added_after: Option<DateTime<Utc>>,
+    tag: Option<String>,
+    untranslated: Option<String>,
+    noindex: Option<bool>,
+    skip_excluded: bool,
+}
+
+impl Default for PageFilter {
+    fn default() -> Self {
+        Self {
+            has_summary: None,
+            text_contains: None,
+            url_glob: None,
+            urls: None,
+            lastmod_from: Bound::Unbounded,
+            lastmod_to: Bound::Unbounded,
+            added_after: None,
+            tag: None,
+            untranslated: None,
+            noindex: None,
+            skip_excluded: false,
+        }
+    }
+}
+
+impl PageFilter {
+    /// Creates a filter matching every page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches pages with a summary if `has_summary`, or pages without one otherwise.
+    #[must_use]
+    pub fn has_summary(mut self, has_summary: bool) -> Self {
+        self.has_summary = Some(has_summary);
+        self
+    }
+
+    /// Matches pages whose extracted text contains the given string, case-sensitively.
+    #[must_use]
+    pub fn text_contains(mut self, needle: impl Into<String>) -> Self {
+        self.text_contains = Some(needle.into());
+        self
+    }
+
+    /// Matches pages whose URL matches the glob pattern, e.g. `https://example.com/docs/*`.
+    #[must_use]
+    pub fn url_glob(mut self, glob: impl Into<String>) -> Self {
+        self.url_glob = Some(glob.into());
+        self
+    }
+
+    /// Matches pages with one of the given URLs, e.g. a list read by [`crate::stdio::read_url_list`].
+    #[must_use]
+    pub fn urls(mut self, urls: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.urls = Some(urls.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Matches pages whose last modification date is within the range.
+    #[must_use]
+    pub fn lastmod_range(mut self, range: impl RangeBounds<DateTime<Utc>>) -> Self {
+        self.lastmod_from = range.start_bound().cloned();
+        self.lastmod_to = range.end_bound().cloned();
+        self
+    }
+
+    /// Matches pages stored at or after the given moment.
+    #[must_use]
+    pub fn added_after(mut self, since: DateTime<Utc>) -> Self {
+        self.added_after = Some(since);
+        self
+    }
+
+    /// Matches pages tagged with the given tag.
+    #[must_use]
+    pub fn tagged(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Matches pages without a summary in the language of the given ISO 639-3 code.
+    #[must_use]
+    pub fn untranslated(mut self, lang: impl Into<String>) -> Self {
+        self.untranslated = Some(lang.into());
+        self
+    }
+
+    /// Matches pages flagged noindex by their robots meta tag or `X-Robots-Tag` header
+    /// if `noindex`, or the other pages otherwise.
+    #[must_use]
+    pub fn noindex(mut self, noindex: bool) -> Self {
+        self.noindex = Some(noindex);
+        self
+    }
+
+    /// Leaves out pages matching a pattern added by [`Storage::add_exclusion`].
+    #[must_use]
+    pub fn skip_excluded(mut self) -> Self {
+        self.skip_excluded = true;
+        self
+    }
+
+    /// Returns the SQL conditions of the filter with the values of their `?` placeholders.
+    pub(super) fn conditions(&self) -> (Vec<&'static str>, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        match self.has_summary {
+            Some(true) => conditions.push("(summary is not NULL and summary != '')"),
+            Some(false) => conditions.push("(summary is NULL or summary = '')"),
+            None => {}
+        }
+        if let Some(needle) = &self.text_contains {
+            conditions.push("instr(text, ?) > 0");
+            values.push(Value::Text(needle.clone()));
+        }
+        if let Some(glob) = &self.url_glob {
+            conditions.push("url GLOB ?");
+            values.push(Value::Text(glob.clone()));
+        }
+        if let Some(urls) = &self.urls {
+            conditions.push("url IN (SELECT value FROM json_each(?))");
+            values.push(Value::Text(
+                serde_json::Value::from(urls.clone()).to_string(),
+            ));
+        }
+        let lastmod_bounds = [
+            (self.lastmod_from, "lastmod >= ?", "lastmod > ?"),
+            (self.lastmod_to, "lastmod <= ?", "lastmod < ?"),
+        ];
+        for (bound, included, excluded) in lastmod_bounds {
+            match bound {
+                Bound::Included(moment) => {
+                    conditions.push(included);
+                    values.push(Value::Integer(moment.timestamp()));
+                }
+                Bound::Excluded(moment) => {
+                    conditions.push(excluded);
+                    values.push(Value::Integer(moment.timestamp()));
+                }
+                Bound::Unbounded => {}
+            }
+        }
+        if let Some(since) = self.added_after {
+            conditions.push("added_at >= ?");
+            values.push(Value::Integer(since.timestamp()));
+        }
+        if let Some(tag) = &self.tag {
+            conditions.push("url IN (SELECT url FROM page_tags WHERE tag = ?)");
+            values.push(Value::Text(tag.clone()));
+        }
+        if let Some(lang) = &self.untranslated {
+            conditions.push("url NOT IN (SELECT url FROM page_translations WHERE lang = ?)");
+            values.push(Value::Text(lang.clone()));
+        }
+        match self.noindex {
+            Some(true) => conditions.push("noindex = 1"),
+            Some(false) => conditions.push("noindex = 0"),
+            None => {}
+        }
+        if self.skip_excluded {
+            conditions.push(NOT_EXCLUDED_SQL);
+        }
+
+        (conditions, values)
+    }
+}
+
+impl Storage {
+    /// Returns an iterator over stored pages matching the filter, ordered by URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Conditions the pages must match
+    pub fn find_pages(&self, filter: PageFilter) -> PageIter<'_> {
+        self.iter_pages().matching(filter)
+    }
+
+    /// Returns the URLs of stored pages matching the filter, ordered by URL, without reading
+    /// the pages themselves.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Conditions the pages must match
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn find_urls(&self, filter: &PageFilter) -> crate::Result<Vec<String>> {
+        let (filter_conditions, values) = filter.conditions();
+        let mut conditions = vec!["1"];
+        conditions.extend(filter_conditions);
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT url FROM pages WHERE ({}) ORDER BY url",
+            conditions.join(") and (")
+        ))?;
+        let urls: Result<Vec<String>, rusqlite::Error> = stmt
+            .query_map(rusqlite::params_from_iter(values), |row| row.get(0))?
+            .collect();
+
+        Ok(urls?)
+    }
+}
@@ -0,0 +1,108 @@
+//! Response headers of scraped pages, kept for conditional refetching
+//! and for picking the extraction of a page by its content type.
+
+use crate::error::Result;
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use super::Storage;
+
+/// Selected response headers of a scraped page.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResponseHeaders {
+    /// The `Content-Type` header
+    pub content_type: Option<String>,
+    /// The `Last-Modified` header, as sent by the site
+    pub last_modified: Option<String>,
+    /// The `ETag` header
+    pub etag: Option<String>,
+    /// The `Cache-Control` header
+    pub cache_control: Option<String>,
+}
+
+impl Storage {
+    /// Stores the response headers of a page, replacing the previous ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    /// * `headers` - The headers of the last response of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_page_headers(&self, url: &str, headers: &ResponseHeaders) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        Self::write_page_headers(&conn, url, headers)
+    }
+
+    /// Stores the response headers of a page on the connection, see [`Storage::update_page_headers`].
+    pub(super) fn write_page_headers(
+        conn: &Connection,
+        url: &str,
+        headers: &ResponseHeaders,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO page_headers
+            (url, content_type, last_modified, etag, cache_control, fetched_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                url,
+                headers.content_type,
+                headers.last_modified,
+                headers.etag,
+                headers.cache_control,
+                Utc::now().timestamp()
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the response headers of a page stored by scrape.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns the headers, `None` if the page wasn't scraped since headers are stored,
+    /// or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_headers(&self, url: &str) -> Result<Option<ResponseHeaders>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let headers = conn
+            .query_row(
+                "SELECT content_type, last_modified, etag, cache_control
+                FROM page_headers WHERE url = ?1",
+                params![url],
+                |row| {
+                    Ok(ResponseHeaders {
+                        content_type: row.get(0)?,
+                        last_modified: row.get(1)?,
+                        etag: row.get(2)?,
+                        cache_control: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(headers)
+    }
+}
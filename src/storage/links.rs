@@ -0,0 +1,232 @@
+//! Internal links between stored pages recorded by parse, and the importance of pages
+//! scored from them like PageRank, so that the most linked to pages come first.
+
+use crate::error::Result;
+use rusqlite::{Connection, params};
+use std::collections::HashMap;
+
+use super::Storage;
+use super::exclusions::NOT_EXCLUDED_SQL;
+
+/// Probability of following a link rather than jumping to any page, as in PageRank.
+const DAMPING: f64 = 0.85;
+
+/// Number of iterations refining the importance scores.
+const ITERATIONS: usize = 20;
+
+/// An internal link of a stored page pointing at a page which isn't stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The URL of the linking page
+    pub source: String,
+    /// The URL the page links to
+    pub target: String,
+    /// Why the target failed to be scraped the last time, `None` if it was never scraped
+    pub error: Option<String>,
+}
+
+impl Storage {
+    /// Stores the internal links of a page, replacing the previous ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The URL of the linking page
+    /// * `targets` - The URLs the page links to, stored or not
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn replace_page_links(&self, source: &str, targets: &[String]) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        Self::write_page_links(&transaction, source, targets)?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Replaces the links of a page on the connection, see [`Storage::replace_page_links`].
+    pub(super) fn write_page_links(
+        conn: &Connection,
+        source: &str,
+        targets: &[String],
+    ) -> Result<()> {
+        conn.execute("DELETE FROM links WHERE source = ?1", params![source])?;
+        let mut stmt =
+            conn.prepare("INSERT OR IGNORE INTO links (source, target) VALUES (?1, ?2)")?;
+        for target in targets {
+            stmt.execute(params![source, target])?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the URLs a page links to, ordered by URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The URL of the linking page
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URLs on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_page_links(&self, source: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt =
+            conn.prepare("SELECT target FROM links WHERE source = ?1 ORDER BY target ASC")?;
+        let targets: Result<Vec<String>, rusqlite::Error> =
+            stmt.query_map(params![source], |row| row.get(0))?.collect();
+
+        targets.map_err(|e| e.into())
+    }
+
+    /// Returns the internal links of stored pages pointing at pages which aren't stored,
+    /// as they failed to be scraped or were never listed, ordered by target and source.
+    ///
+    /// Links to excluded pages aren't broken, as these pages are left out on purpose,
+    /// nor are links to URLs redirecting to a stored page.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of broken links on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_broken_links(&self) -> Result<Vec<BrokenLink>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT source, url, (
+                SELECT error FROM failures
+                WHERE failures.url = broken.url AND stage = 'scrape'
+                ORDER BY id DESC LIMIT 1
+            )
+            FROM (
+                SELECT source, target AS url FROM links
+                WHERE source IN (SELECT url FROM pages)
+                    AND target NOT IN (SELECT url FROM pages)
+                    AND target NOT IN (SELECT url FROM redirects)
+            ) AS broken
+            WHERE {NOT_EXCLUDED_SQL}
+            ORDER BY url ASC, source ASC"
+        ))?;
+        let links: Result<Vec<BrokenLink>, rusqlite::Error> = stmt
+            .query_map([], |row| {
+                Ok(BrokenLink {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                    error: row.get(2)?,
+                })
+            })?
+            .collect();
+
+        links.map_err(|e| e.into())
+    }
+
+    /// Scores the importance of every stored page from the links between stored pages,
+    /// replacing the previous scores.
+    ///
+    /// A page of average importance scores 1, pages linked to by many important pages score more.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of scored pages, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_importance(&self) -> Result<usize> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let urls: Vec<String> = conn
+            .prepare("SELECT url FROM pages ORDER BY url ASC")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, rusqlite::Error>>()?;
+        let index: HashMap<&str, usize> = urls
+            .iter()
+            .enumerate()
+            .map(|(position, url)| (url.as_str(), position))
+            .collect();
+        let links: Vec<(usize, usize)> = conn
+            .prepare("SELECT source, target FROM links WHERE source != target")?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?
+            .iter()
+            .filter_map(|(source, target)| {
+                Some((*index.get(source.as_str())?, *index.get(target.as_str())?))
+            })
+            .collect();
+
+        let transaction = conn.unchecked_transaction()?;
+        transaction.execute("DELETE FROM importance", params![])?;
+        {
+            let mut stmt =
+                transaction.prepare("INSERT INTO importance (url, score) VALUES (?1, ?2)")?;
+            for (url, score) in urls.iter().zip(page_rank(urls.len(), &links)) {
+                stmt.execute(params![url, score])?;
+            }
+        }
+        transaction.commit()?;
+
+        Ok(urls.len())
+    }
+}
+
+/// Scores pages linked to by the given `(source, target)` index pairs like PageRank,
+/// scaled so that scores average to 1. Pages without links share their score with every page.
+fn page_rank(pages: usize, links: &[(usize, usize)]) -> Vec<f64> {
+    let mut out_degrees = vec![0_usize; pages];
+    for (source, _) in links {
+        if let Some(out_degree) = out_degrees.get_mut(*source) {
+            *out_degree += 1;
+        }
+    }
+
+    let mut scores = vec![1.0; pages];
+    for _ in 0..ITERATIONS {
+        let dangling: f64 = scores
+            .iter()
+            .zip(&out_degrees)
+            .filter(|(_, out_degree)| **out_degree == 0)
+            .map(|(score, _)| score)
+            .sum();
+        let base = 1.0 - DAMPING + DAMPING * dangling / pages as f64;
+        let mut next = vec![base; pages];
+        for (source, target) in links {
+            if let (Some(score), Some(out_degree), Some(next_score)) = (
+                scores.get(*source),
+                out_degrees.get(*source),
+                next.get_mut(*target),
+            ) {
+                *next_score += DAMPING * score / *out_degree as f64;
+            }
+        }
+        scores = next;
+    }
+    scores
+}
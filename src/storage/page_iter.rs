@@ -0,0 +1,307 @@
+//! Streaming iteration over stored pages in constant memory.
+
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+
+use super::{Page, PageFilter, PageRow, Storage};
+use crate::PageSort;
+
+/// Number of pages read from the database at once while iterating.
+const PAGE_ITER_BATCH_SIZE: u32 = 100;
+
+/// Which pages a [`PageIter`] yields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageSelection {
+    /// Every stored page
+    #[default]
+    All,
+    /// Pages parsed or further along, unless scrape or parse failed on them since
+    Composable,
+    /// Pages parsed or further along, flagged pages (e.g. too short) only if `include_flagged`
+    Summarizable { include_flagged: bool },
+    /// Pages parsed but not summarized yet, or which summarize failed on,
+    /// flagged pages only if `include_flagged`
+    Unsummarized { include_flagged: bool },
+}
+
+impl PageSelection {
+    /// Returns the SQL condition matching the selected pages by their status.
+    pub(super) fn condition(self) -> &'static str {
+        match self {
+            Self::All => "1",
+            Self::Composable
+            | Self::Summarizable {
+                include_flagged: true,
+            } => {
+                "status in ('parsed', 'summarized', 'composed', 'failed:summarize', 'failed:compose')"
+            }
+            Self::Summarizable {
+                include_flagged: false,
+            } => {
+                "status in ('parsed', 'summarized', 'composed', 'failed:summarize', 'failed:compose')
+                  and (parse_status is NULL or parse_status = 'ok')"
+            }
+            Self::Unsummarized {
+                include_flagged: true,
+            } => "status in ('parsed', 'failed:summarize')",
+            Self::Unsummarized {
+                include_flagged: false,
+            } => {
+                "status in ('parsed', 'failed:summarize')
+                  and (parse_status is NULL or parse_status = 'ok')"
+            }
+        }
+    }
+}
+
+/// A stored page as listed by a generated sitemap
+#[derive(Clone, Debug, PartialEq)]
+pub struct SitemapEntry {
+    pub url: String,
+    pub lastmod: DateTime<Utc>,
+    /// Priority of the page in the sitemap it was scraped from, if it had one
+    pub priority: Option<f64>,
+}
+
+/// Iterator over stored pages ordered by URL, reading them in batches.
+///
+/// The database isn't locked between batches, so pages can be updated while iterating.
+/// Pages are resumed after the last yielded URL, so updated pages aren't yielded twice
+/// and pages added after the current position are yielded too.
+pub struct PageIter<'a> {
+    storage: &'a Storage,
+    selection: PageSelection,
+    filter: PageFilter,
+    /// URL of the last read page, the next batch starts after it
+    after: String,
+    batch: std::vec::IntoIter<PageRow>,
+    exhausted: bool,
+}
+
+impl PageIter<'_> {
+    /// Restricts the iterated pages to the ones also matching the filter.
+    #[must_use]
+    pub fn matching(mut self, filter: PageFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Starts the iteration after the page with the given URL, e.g. one saved in a checkpoint.
+    /// An empty URL starts from the first page.
+    #[must_use]
+    pub fn after(mut self, url: impl Into<String>) -> Self {
+        self.after = url.into();
+        self
+    }
+}
+
+impl Iterator for PageIter<'_> {
+    type Item = Result<Page>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(row) = self.batch.next() {
+            return Some(row.try_into());
+        }
+        if self.exhausted {
+            return None;
+        }
+
+        match self
+            .storage
+            .fetch_page_rows_after(self.selection, &self.filter, &self.after)
+        {
+            Ok(rows) => {
+                self.exhausted = rows.len() < PAGE_ITER_BATCH_SIZE as usize;
+                if let Some(last) = rows.last() {
+                    self.after.clone_from(&last.url);
+                }
+                self.batch = rows.into_iter();
+                self.next()
+            }
+            Err(error) => {
+                self.exhausted = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl Storage {
+    /// Returns an iterator over all stored pages ordered by URL.
+    ///
+    /// Pages are read in batches, so memory usage doesn't grow with the size of the database.
+    pub fn iter_pages(&self) -> PageIter<'_> {
+        self.iter_pages_where(PageSelection::All)
+    }
+
+    /// Returns an iterator over the selected stored pages ordered by URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - Which pages to yield
+    pub fn iter_pages_where(&self, selection: PageSelection) -> PageIter<'_> {
+        PageIter {
+            storage: self,
+            selection,
+            filter: PageFilter::default(),
+            after: String::new(),
+            batch: Vec::new().into_iter(),
+            exhausted: false,
+        }
+    }
+
+    /// Reads the next batch of selected page rows with URLs after the given one.
+    fn fetch_page_rows_after(
+        &self,
+        selection: PageSelection,
+        filter: &PageFilter,
+        after: &str,
+    ) -> Result<Vec<PageRow>> {
+        let (filter_conditions, filter_values) = filter.conditions();
+        let mut conditions = vec!["url > ?", selection.condition()];
+        conditions.extend(filter_conditions);
+        let mut values = vec![Value::Text(after.to_string())];
+        values.extend(filter_values);
+        values.push(Value::Integer(PAGE_ITER_BATCH_SIZE.into()));
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "
+                SELECT url, added_at, lastmod, html, title, text, summary, content_type, content, robots_tag, html_hash
+                FROM pages
+                WHERE ({})
+                ORDER BY url ASC
+                LIMIT ?
+            ",
+            conditions.join(") and (")
+        ))?;
+        let rows: Result<Vec<PageRow>, rusqlite::Error> = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(PageRow {
+                    url: row.get(0)?,
+                    added_at: row.get(1)?,
+                    lastmod: row.get(2)?,
+                    html: row.get(3)?,
+                    title: row.get(4)?,
+                    text: row.get(5)?,
+                    summary: row.get(6)?,
+                    content_type: row.get(7)?,
+                    content: row.get(8)?,
+                    robots_tag: row.get(9)?,
+                    html_hash: row.get(10)?,
+                })
+            })?
+            .collect();
+
+        rows.map_err(|e| e.into())
+    }
+
+    /// Returns URLs of the selected stored pages in the given order, ties broken by URL,
+    /// so that the order is the same from run to run.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - Which pages to list
+    /// * `filter` - Conditions the pages must also match
+    /// * `sort` - The order of the pages
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of URL strings on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_sorted_urls(
+        &self,
+        selection: PageSelection,
+        filter: &PageFilter,
+        sort: &PageSort,
+    ) -> Result<Vec<String>> {
+        let (filter_conditions, values) = filter.conditions();
+        let mut conditions = vec![selection.condition()];
+        conditions.extend(filter_conditions);
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "
+                SELECT url FROM pages
+                LEFT JOIN priorities USING (url)
+                LEFT JOIN importance USING (url)
+                WHERE ({})
+                ORDER BY {}, url ASC
+            ",
+            conditions.join(") and ("),
+            sort_order(sort)
+        ))?;
+        let urls: Result<Vec<String>, rusqlite::Error> = stmt
+            .query_map(params_from_iter(values), |row| row.get(0))?
+            .collect();
+
+        urls.map_err(|e| e.into())
+    }
+
+    /// Lists stored pages matching the filter with their lastmod and priority, ordered by URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Conditions the pages must match
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of sitemap entries on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_sitemap_entries(&self, filter: &PageFilter) -> Result<Vec<SitemapEntry>> {
+        let (filter_conditions, values) = filter.conditions();
+        let mut conditions = vec![PageSelection::All.condition()];
+        conditions.extend(filter_conditions);
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "
+                SELECT url, lastmod, priority FROM pages
+                LEFT JOIN priorities USING (url)
+                WHERE ({})
+                ORDER BY url ASC
+            ",
+            conditions.join(") and (")
+        ))?;
+        let entries: Result<Vec<SitemapEntry>, rusqlite::Error> = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(SitemapEntry {
+                    url: row.get(0)?,
+                    lastmod: DateTime::from_timestamp_secs(row.get(1)?).unwrap_or_default(),
+                    priority: row.get(2)?,
+                })
+            })?
+            .collect();
+
+        entries.map_err(|e| e.into())
+    }
+}
+
+/// Returns the ORDER BY expression of a sort, pages without a title
+/// are sorted by URL and pages without a priority have the sitemap default 0.5,
+/// the most recently modified first among pages of the same priority.
+fn sort_order(sort: &PageSort) -> &'static str {
+    match sort {
+        PageSort::Url => "url ASC",
+        PageSort::Title => "coalesce(nullif(title, ''), url) COLLATE NOCASE ASC",
+        PageSort::Lastmod => "lastmod DESC",
+        PageSort::Priority => "coalesce(priority, 0.5) DESC, lastmod DESC",
+        PageSort::Importance => "coalesce(score, 0) DESC, coalesce(priority, 0.5) DESC",
+    }
+}
@@ -0,0 +1,94 @@
+//! Redirects of listed URLs to the final URLs their pages are stored at,
+//! so that variants of a page (http and https, with and without a trailing slash) are stored once.
+
+use crate::error::Result;
+use rusqlite::{Connection, params};
+
+use super::Storage;
+
+/// A listed URL answered with a redirect, whose page is stored at the final URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Redirect {
+    /// The requested URL
+    pub url: String,
+    /// The final URL the redirects lead to
+    pub target: String,
+}
+
+impl Storage {
+    /// Records a redirect, removing the page stored at the redirecting URL
+    /// and carrying its sitemap priority over to the target.
+    ///
+    /// # Arguments
+    ///
+    /// * `redirect` - The redirect of a listed URL to the URL its page is stored at
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn record_redirect(&self, redirect: &Redirect) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        Self::write_redirect(&transaction, redirect)?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Records a redirect on the connection, see [`Storage::record_redirect`].
+    pub(super) fn write_redirect(conn: &Connection, redirect: &Redirect) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO redirects (url, target) VALUES (?1, ?2)",
+            params![redirect.url, redirect.target],
+        )?;
+        // The target no longer redirects if it was recorded doing so before
+        conn.execute(
+            "DELETE FROM redirects WHERE url = ?1",
+            params![redirect.target],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO priorities (url, priority)
+            SELECT ?2, priority FROM priorities WHERE url = ?1",
+            params![redirect.url, redirect.target],
+        )?;
+        conn.execute("DELETE FROM pages WHERE url = ?1", params![redirect.url])?;
+
+        Ok(())
+    }
+
+    /// Returns the recorded redirects, ordered by the redirecting URL.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of redirects on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_redirects(&self) -> Result<Vec<Redirect>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT url, target FROM redirects ORDER BY url ASC")?;
+        let redirects: Result<Vec<Redirect>, rusqlite::Error> = stmt
+            .query_map([], |row| {
+                Ok(Redirect {
+                    url: row.get(0)?,
+                    target: row.get(1)?,
+                })
+            })?
+            .collect();
+
+        redirects.map_err(|e| e.into())
+    }
+}
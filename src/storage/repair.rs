@@ -0,0 +1,251 @@
+//! Checks and repairs of page rows written by older versions or interrupted runs, whose
+//! unreadable values would otherwise make every run reading them fail.
+
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{Connection, params};
+use url::Url;
+
+use super::{PageFilter, Storage, UNKNOWN_TIMESTAMP};
+use crate::error::Result;
+
+/// Latest timestamp taken as valid, the end of year 9999; later ones were written by bugs.
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+
+/// Problem of a stored page row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowProblem {
+    /// The URL can't be parsed, the page is quarantined on repair
+    InvalidUrl,
+    /// The storage date isn't a valid timestamp, reset to the repair time
+    InvalidAddedAt,
+    /// The lastmod isn't a valid timestamp, reset to [`UNKNOWN_TIMESTAMP`] so the page is scraped again
+    InvalidLastmod,
+    /// Neither HTML, content nor HTML hash is stored, the page is quarantined on repair
+    EmptyHtml,
+}
+
+impl RowProblem {
+    /// Returns whether the row is moved out of the pages table on repair, as it can't be fixed.
+    pub fn is_quarantined(self) -> bool {
+        matches!(self, Self::InvalidUrl | Self::EmptyHtml)
+    }
+}
+
+impl std::fmt::Display for RowProblem {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(match self {
+            Self::InvalidUrl => "invalid URL",
+            Self::InvalidAddedAt => "invalid added_at timestamp",
+            Self::InvalidLastmod => "invalid lastmod timestamp",
+            Self::EmptyHtml => "empty HTML",
+        })
+    }
+}
+
+/// A stored page row with problems
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowIssue {
+    /// The URL as stored, which may not be a valid URL
+    pub url: String,
+    /// The problems of the row
+    pub problems: Vec<RowProblem>,
+}
+
+/// Rows a repair fixed and quarantined
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Number of rows whose timestamps were reset
+    pub fixed: usize,
+    /// Number of rows moved to the `quarantined_pages` table
+    pub quarantined: usize,
+}
+
+impl Storage {
+    /// Creates the table of page rows quarantined by [`Storage::repair_rows`] if it doesn't exist.
+    /// Its columns are untyped, so that any value of the broken row is kept as is.
+    pub(super) fn init_quarantine_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS quarantined_pages (
+                url,
+                reason TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL,
+                added_at,
+                lastmod,
+                html,
+                title,
+                text,
+                summary
+            )",
+            params![],
+        )?;
+
+        Ok(())
+    }
+
+    /// Normalizes the timestamps of pages written by older versions which aren't integers,
+    /// are before the epoch or are out of range, replacing them with [`UNKNOWN_TIMESTAMP`],
+    /// so that reading these pages doesn't fail.
+    pub(super) fn normalize_timestamps(conn: &Connection) -> Result<()> {
+        for column in ["added_at", "lastmod"] {
+            conn.execute(
+                &format!(
+                    "UPDATE pages SET {column} = ?1
+                     WHERE typeof({column}) != 'integer' OR {column} < 0 OR {column} > ?2"
+                ),
+                params![UNKNOWN_TIMESTAMP, MAX_TIMESTAMP],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Forgets the lastmod of the pages matching the filter, so that the next scrape fetches
+    /// them again and stores the lastmod their sitemap lists, e.g. after lastmod values were
+    /// normalized to [`UNKNOWN_TIMESTAMP`] or stored wrong by an older version.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Conditions the pages must match
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of updated pages on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn reset_lastmod(&self, filter: &PageFilter) -> Result<usize> {
+        let (filter_conditions, mut values) = filter.conditions();
+        let mut conditions = vec!["1"];
+        conditions.extend(filter_conditions);
+        values.insert(0, UNKNOWN_TIMESTAMP.into());
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let updated = conn.execute(
+            &format!(
+                "UPDATE pages SET lastmod = ? WHERE ({})",
+                conditions.join(") and (")
+            ),
+            rusqlite::params_from_iter(values),
+        )?;
+
+        Ok(updated)
+    }
+
+    /// Finds stored page rows with an unparsable URL, invalid timestamps or empty HTML,
+    /// which fail to be read as pages.
+    ///
+    /// # Returns
+    ///
+    /// Returns the rows with problems in URL order on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn check_rows(&self) -> Result<Vec<RowIssue>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT url, added_at, lastmod,
+                    (html IS NULL OR html = '') AND content IS NULL AND html_hash IS NULL
+             FROM pages ORDER BY url",
+        )?;
+        let rows: Result<Vec<(String, Value, Value, bool)>, rusqlite::Error> = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect();
+
+        Ok(rows?
+            .into_iter()
+            .filter_map(|(url, added_at, lastmod, empty_html)| {
+                let problems: Vec<RowProblem> = [
+                    (Url::parse(&url).is_err(), RowProblem::InvalidUrl),
+                    (!is_valid_timestamp(&added_at), RowProblem::InvalidAddedAt),
+                    (!is_valid_timestamp(&lastmod), RowProblem::InvalidLastmod),
+                    (empty_html, RowProblem::EmptyHtml),
+                ]
+                .into_iter()
+                .filter_map(|(found, problem)| found.then_some(problem))
+                .collect();
+                (!problems.is_empty()).then_some(RowIssue { url, problems })
+            })
+            .collect())
+    }
+
+    /// Repairs the rows found by [`Storage::check_rows`]: invalid timestamps are reset, while rows
+    /// with an invalid URL or empty HTML are moved to the `quarantined_pages` table, so that
+    /// one bad row can't break runs reading the whole database.
+    ///
+    /// # Returns
+    ///
+    /// Returns the numbers of fixed and quarantined rows on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn repair_rows(&self) -> Result<RepairReport> {
+        let issues = self.check_rows()?;
+        let mut conn = self.conn.lock().expect("Storage mutex poisoned");
+        let tx = conn.transaction()?;
+        let mut report = RepairReport::default();
+        let now = Utc::now().timestamp();
+
+        for issue in issues {
+            let quarantined: Vec<String> = issue
+                .problems
+                .iter()
+                .filter(|problem| problem.is_quarantined())
+                .map(ToString::to_string)
+                .collect();
+            if !quarantined.is_empty() {
+                tx.execute(
+                    "INSERT INTO quarantined_pages
+                     SELECT url, ?2, ?3, added_at, lastmod, html, title, text, summary
+                     FROM pages WHERE url = ?1",
+                    params![issue.url, quarantined.join(", "), now],
+                )?;
+                tx.execute("DELETE FROM pages WHERE url = ?1", params![issue.url])?;
+                report.quarantined += 1;
+                continue;
+            }
+
+            if issue.problems.contains(&RowProblem::InvalidAddedAt) {
+                tx.execute(
+                    "UPDATE pages SET added_at = ?2 WHERE url = ?1",
+                    params![issue.url, now],
+                )?;
+            }
+            if issue.problems.contains(&RowProblem::InvalidLastmod) {
+                tx.execute(
+                    "UPDATE pages SET lastmod = ?2 WHERE url = ?1",
+                    params![issue.url, UNKNOWN_TIMESTAMP],
+                )?;
+            }
+            report.fixed += 1;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+}
+
+/// Returns whether a stored value is a timestamp pages can be read with.
+fn is_valid_timestamp(value: &Value) -> bool {
+    match value {
+        Value::Integer(timestamp) => DateTime::from_timestamp_secs(*timestamp).is_some(),
+        _ => false,
+    }
+}
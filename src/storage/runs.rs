@@ -0,0 +1,180 @@
+//! Audit log of llamap runs performed against a database.
+
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+use super::Storage;
+
+/// Represents a single recorded run of a llamap command.
+#[derive(Debug)]
+pub struct Run {
+    pub id: i64,
+    /// The command that was run (e.g. "scrape" or "summarize")
+    pub command: String,
+    /// Human-readable parameters the command was run with
+    pub parameters: String,
+    pub started_at: DateTime<Utc>,
+    /// None if the run is still in progress or was killed
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Number of pages stored, parsed, summarized or composed by the run
+    pub pages_affected: Option<i64>,
+    /// The error the run failed with, if any
+    pub error: Option<String>,
+    /// Number of page requests made by a scrape run
+    pub requests: Option<i64>,
+    /// Number of bytes downloaded by a scrape run
+    pub bytes_downloaded: Option<i64>,
+}
+
+impl Storage {
+    /// Records the start of a run in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command being run
+    /// * `parameters` - Human-readable parameters the command is run with
+    ///
+    /// # Returns
+    ///
+    /// Returns the id of the recorded run on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn start_run(&self, command: &str, parameters: &str) -> Result<i64> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "INSERT INTO runs (command, parameters, started_at) VALUES (?1, ?2, ?3)",
+            params![command, parameters, Utc::now().timestamp()],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Records the end of a run in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id returned by [`Storage::start_run`]
+    /// * `pages_affected` - Number of pages affected by the run, if known
+    /// * `error` - The error the run failed with, if any
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn finish_run(
+        &self,
+        run_id: i64,
+        pages_affected: Option<usize>,
+        error: Option<String>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "UPDATE runs SET finished_at = ?1, pages_affected = ?2, error = ?3 WHERE id = ?4",
+            params![
+                Utc::now().timestamp(),
+                pages_affected.map(|count| count as i64),
+                error,
+                run_id
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records the network traffic of a scrape run in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id returned by [`Storage::start_run`]
+    /// * `requests` - Number of page requests made by the run
+    /// * `bytes_downloaded` - Number of bytes downloaded by the run
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn record_run_traffic(
+        &self,
+        run_id: i64,
+        requests: usize,
+        bytes_downloaded: u64,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "UPDATE runs SET requests = ?1, bytes_downloaded = ?2 WHERE id = ?3",
+            params![requests as i64, bytes_downloaded as i64, run_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the most recent runs, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of runs to return
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of runs on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_runs(&self, limit: u32) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "
+                SELECT id, command, parameters, started_at, finished_at, pages_affected, error,
+                    requests, bytes_downloaded
+                FROM runs
+                ORDER BY id DESC
+                LIMIT ?1
+            ",
+        )?;
+        let runs: Result<Vec<Run>, rusqlite::Error> = stmt
+            .query_map([limit], |row| {
+                Ok(Run {
+                    id: row.get(0)?,
+                    command: row.get(1)?,
+                    parameters: row.get(2)?,
+                    started_at: DateTime::from_timestamp_secs(row.get(3)?).unwrap_or_default(),
+                    finished_at: row
+                        .get::<_, Option<i64>>(4)?
+                        .and_then(DateTime::from_timestamp_secs),
+                    pages_affected: row.get(5)?,
+                    error: row.get(6)?,
+                    requests: row.get(7)?,
+                    bytes_downloaded: row.get(8)?,
+                })
+            })?
+            .collect();
+
+        runs.map_err(|e| e.into())
+    }
+}
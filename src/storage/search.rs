@@ -0,0 +1,146 @@
+//! Full-text index of the titles, texts and summaries of stored pages, kept up to date by
+//! triggers on the pages table, so that the database can be searched as a local knowledge base.
+
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+
+use super::{PageFilter, Storage};
+use crate::error::Result;
+
+/// Represents a page matching a search query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub url: String,
+    pub title: Option<String>,
+    /// Excerpt of the best matching column, the matched terms between `**`
+    pub snippet: String,
+}
+
+/// Returns an FTS5 query matching the pages containing every word of a plain text, each word
+/// quoted so that punctuation like in `sitemap.xml` isn't read as query syntax.
+///
+/// # Arguments
+///
+/// * `text` - Words to search for
+pub fn plain_search_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns an FTS5 query matching the pages containing any word of a plain text, e.g. a question,
+/// leaving out words shorter than three characters; the best matches contain most of the words.
+///
+/// # Arguments
+///
+/// * `text` - Words to search for
+pub fn any_word_search_query(text: &str) -> String {
+    text.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(|word| format!("\"{word}\""))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+impl Storage {
+    /// Creates the full-text index of pages and the triggers updating it along with the pages
+    /// if they don't exist, indexing the pages of databases created before the index.
+    pub(super) fn init_search_index(conn: &Connection) -> Result<()> {
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'pages_fts'",
+                params![],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if exists {
+            // Pages used to be stored with INSERT OR REPLACE, whose replaced row was removed
+            // from the index by this trigger. Upserts fire the update trigger instead, and
+            // removing the row twice would corrupt the index.
+            conn.execute("DROP TRIGGER IF EXISTS pages_fts_replace", params![])?;
+            return Ok(());
+        }
+
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE pages_fts USING fts5(
+                url UNINDEXED, title, text, summary,
+                content = 'pages', content_rowid = 'rowid', tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER pages_fts_insert AFTER INSERT ON pages BEGIN
+                INSERT INTO pages_fts (rowid, url, title, text, summary)
+                VALUES (new.rowid, new.url, new.title, new.text, new.summary);
+            END;
+            CREATE TRIGGER pages_fts_update AFTER UPDATE OF url, title, text, summary ON pages BEGIN
+                INSERT INTO pages_fts (pages_fts, rowid, url, title, text, summary)
+                VALUES ('delete', old.rowid, old.url, old.title, old.text, old.summary);
+                INSERT INTO pages_fts (rowid, url, title, text, summary)
+                VALUES (new.rowid, new.url, new.title, new.text, new.summary);
+            END;
+            CREATE TRIGGER pages_fts_delete AFTER DELETE ON pages BEGIN
+                INSERT INTO pages_fts (pages_fts, rowid, url, title, text, summary)
+                VALUES ('delete', old.rowid, old.url, old.title, old.text, old.summary);
+            END;
+            INSERT INTO pages_fts (pages_fts) VALUES ('rebuild');",
+        )?;
+
+        Ok(())
+    }
+
+    /// Searches the titles, texts and summaries of the stored pages matching the filter,
+    /// best matches first, titles weighing the most and summaries more than texts.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - An FTS5 query, see [`plain_search_query`] for plain words
+    /// * `filter` - Conditions the pages must match
+    /// * `limit` - Maximal number of returned pages
+    ///
+    /// # Returns
+    ///
+    /// Returns the matching pages with a snippet on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query isn't valid FTS5 syntax or database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn search_pages(
+        &self,
+        query: &str,
+        filter: &PageFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let (filter_conditions, filter_values) = filter.conditions();
+        let mut conditions = vec!["1"];
+        conditions.extend(filter_conditions);
+        let mut values: Vec<Value> = vec![query.to_string().into()];
+        values.extend(filter_values);
+        values.push(i64::try_from(limit).unwrap_or(i64::MAX).into());
+
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT url, title, snippet(pages_fts, -1, '**', '**', '…', 16)
+             FROM pages_fts
+             WHERE pages_fts MATCH ?
+               AND rowid IN (SELECT rowid FROM pages WHERE ({}))
+             ORDER BY bm25(pages_fts, 0.0, 5.0, 1.0, 2.0)
+             LIMIT ?",
+            conditions.join(") and (")
+        ))?;
+        let hits: Result<Vec<SearchHit>, rusqlite::Error> = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(SearchHit {
+                    url: row.get(0)?,
+                    title: row.get(1)?,
+                    snippet: row.get(2)?,
+                })
+            })?
+            .collect();
+
+        Ok(hits?)
+    }
+}
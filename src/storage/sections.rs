@@ -0,0 +1,160 @@
+//! Sections of long pages split by parse at their anchored headings, summarized one by one
+//! and listed by compose under their page.
+
+use crate::error::Result;
+use rusqlite::{Connection, params};
+
+use super::Storage;
+
+/// A section of a page starting at a heading with an anchor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageSection {
+    /// The `id` of the heading, linked to as `url#anchor`
+    pub anchor: String,
+    /// Text of the heading
+    pub heading: String,
+    /// Extracted text of the section, up to the next anchored heading
+    pub text: String,
+    /// Summary of the section, if summarized
+    pub summary: Option<String>,
+}
+
+impl Storage {
+    /// Stores the sections of a page, replacing the previous ones.
+    ///
+    /// Summaries of sections whose anchor and text didn't change are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    /// * `sections` - The sections in page order, empty if the page isn't split
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn replace_page_sections(&self, url: &str, sections: &[PageSection]) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        Self::write_page_sections(&transaction, url, sections)?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Replaces the sections of a page on the connection, see [`Storage::replace_page_sections`].
+    pub(super) fn write_page_sections(
+        conn: &Connection,
+        url: &str,
+        sections: &[PageSection],
+    ) -> Result<()> {
+        conn.execute(
+            "CREATE TEMP TABLE IF NOT EXISTS temp_section_anchors (anchor TEXT PRIMARY KEY)",
+            params![],
+        )?;
+        conn.execute("DELETE FROM temp_section_anchors", params![])?;
+        {
+            let mut upsert = conn.prepare(
+                "INSERT INTO page_sections (url, anchor, position, heading, text, summary)
+                VALUES (?1, ?2, ?3, ?4, ?5, NULL)
+                ON CONFLICT (url, anchor) DO UPDATE SET
+                    position = excluded.position,
+                    heading = excluded.heading,
+                    summary = CASE WHEN text = excluded.text THEN summary ELSE NULL END,
+                    text = excluded.text",
+            )?;
+            let mut keep =
+                conn.prepare("INSERT OR IGNORE INTO temp_section_anchors VALUES (?1)")?;
+            for (position, section) in sections.iter().enumerate() {
+                upsert.execute(params![
+                    url,
+                    section.anchor,
+                    position,
+                    section.heading,
+                    section.text
+                ])?;
+                keep.execute(params![section.anchor])?;
+            }
+        }
+        conn.execute(
+            "DELETE FROM page_sections
+            WHERE url = ?1 AND anchor NOT IN (SELECT anchor FROM temp_section_anchors)",
+            params![url],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the sections of a page in page order.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns the sections, empty if the page isn't split, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_page_sections(&self, url: &str) -> Result<Vec<PageSection>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT anchor, heading, text, summary FROM page_sections
+            WHERE url = ?1 ORDER BY position ASC",
+        )?;
+        let sections: Result<Vec<PageSection>, rusqlite::Error> = stmt
+            .query_map(params![url], |row| {
+                Ok(PageSection {
+                    anchor: row.get(0)?,
+                    heading: row.get(1)?,
+                    text: row.get(2)?,
+                    summary: row.get(3)?,
+                })
+            })?
+            .collect();
+
+        sections.map_err(|e| e.into())
+    }
+
+    /// Stores the summary of a page section.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    /// * `anchor` - The anchor of the section
+    /// * `summary` - The summary
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_section_summary(&self, url: &str, anchor: &str, summary: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "UPDATE page_sections SET summary = ?1 WHERE url = ?2 AND anchor = ?3",
+            params![summary, url, anchor],
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+//! Metadata of pages listed by the news, video and image extensions of sitemaps,
+//! kept apart from pages like priorities so that it survives pages being re-scraped.
+
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, params};
+use std::collections::HashMap;
+
+use super::Storage;
+
+/// Metadata of a page declared by the Google news, video and image sitemap extensions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SitemapExtensions {
+    /// Publication date of a news article, `<news:publication_date>`
+    pub publication_date: Option<DateTime<Utc>>,
+    /// Title of a news article, `<news:title>`
+    pub news_title: Option<String>,
+    /// Duration of the video of the page in seconds, `<video:duration>`
+    pub video_duration: Option<u32>,
+    /// Captions of the images of the page, `<image:caption>` or `<image:title>`
+    pub image_captions: Vec<String>,
+}
+
+impl Storage {
+    /// Records the sitemap extension metadata of listed pages, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `extensions` - A map of URLs to the metadata their sitemap entries declare
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_sitemap_extensions(
+        &self,
+        extensions: &HashMap<String, SitemapExtensions>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        {
+            let mut stmt = transaction.prepare(
+                "INSERT OR REPLACE INTO sitemap_extensions
+                (url, publication_date, news_title, video_duration, image_captions)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (url, page_extensions) in extensions {
+                let image_captions = if page_extensions.image_captions.is_empty() {
+                    None
+                } else {
+                    Some(
+                        serde_json::to_string(&page_extensions.image_captions)
+                            .map_err(|e| Error::Storage(e.into()))?,
+                    )
+                };
+                stmt.execute(params![
+                    url,
+                    page_extensions
+                        .publication_date
+                        .map(|publication_date| publication_date.timestamp()),
+                    page_extensions.news_title,
+                    page_extensions.video_duration,
+                    image_captions
+                ])?;
+            }
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the sitemap extension metadata of a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns the metadata, `None` if the sitemap declares none,
+    /// or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails or the stored captions are malformed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_sitemap_extensions(&self, url: &str) -> Result<Option<SitemapExtensions>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let row = conn
+            .query_row(
+                "SELECT publication_date, news_title, video_duration, image_captions
+                FROM sitemap_extensions WHERE url = ?1",
+                params![url],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<i64>>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<u32>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((publication_date, news_title, video_duration, image_captions)) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(SitemapExtensions {
+            publication_date: publication_date.and_then(DateTime::from_timestamp_secs),
+            news_title,
+            video_duration,
+            image_captions: match image_captions {
+                Some(image_captions) => {
+                    serde_json::from_str(&image_captions).map_err(|e| Error::Storage(e.into()))?
+                }
+                None => Vec::new(),
+            },
+        }))
+    }
+}
@@ -0,0 +1,154 @@
+//! Snapshots of the URLs and lastmod dates sitemaps listed on every scrape run, so that
+//! the changes between runs explain why a run scraped the pages it did.
+
+use rusqlite::{Connection, OptionalExtension, params};
+use sitemap::structs::{LastMod, UrlEntry};
+use std::collections::HashMap;
+
+use super::Storage;
+use crate::error::Result;
+
+/// Number of latest snapshots kept, older ones are removed when a snapshot is saved.
+const SNAPSHOTS_KEPT: i64 = 10;
+
+/// Changes of the listed URLs between the snapshots of two scrape runs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SitemapDiff {
+    /// The run of the earlier snapshot
+    pub previous_run: i64,
+    /// The run of the later snapshot
+    pub run: i64,
+    /// URLs listed by the later snapshot only
+    pub added: Vec<String>,
+    /// URLs listed by the earlier snapshot only
+    pub removed: Vec<String>,
+    /// URLs listed by both snapshots with a different lastmod
+    pub bumped: Vec<String>,
+}
+
+impl Storage {
+    /// Creates the table of sitemap snapshots if it doesn't exist.
+    pub(super) fn init_snapshot_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sitemap_snapshots (
+                run_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                lastmod INTEGER NULL,
+                PRIMARY KEY (run_id, url)
+            )",
+            params![],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records the entries a scrape run read from its sitemaps, removing all but the latest snapshots.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the scrape run
+    /// * `sitemap_entries` - A map of URLs to their sitemap entries
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn save_sitemap_snapshot(
+        &self,
+        run_id: i64,
+        sitemap_entries: &HashMap<String, UrlEntry>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let transaction = conn.unchecked_transaction()?;
+        {
+            let mut stmt = transaction.prepare(
+                "INSERT OR REPLACE INTO sitemap_snapshots (run_id, url, lastmod) VALUES (?1, ?2, ?3)",
+            )?;
+            for (url, sitemap_entry) in sitemap_entries {
+                let lastmod = match sitemap_entry.lastmod {
+                    LastMod::DateTime(lastmod) => Some(lastmod.to_utc().timestamp()),
+                    LastMod::None | LastMod::ParseErr(_) => None,
+                };
+                stmt.execute(params![run_id, url, lastmod])?;
+            }
+        }
+        transaction.execute(
+            "DELETE FROM sitemap_snapshots WHERE run_id NOT IN (
+                SELECT DISTINCT run_id FROM sitemap_snapshots ORDER BY run_id DESC LIMIT ?1
+            )",
+            params![SNAPSHOTS_KEPT],
+        )?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Compares the sitemap snapshot of a scrape run with the one of the scrape run before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The id of the later run, the latest run with a snapshot if None
+    ///
+    /// # Returns
+    ///
+    /// Returns the changes between the snapshots, or None if there aren't two snapshots to compare
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn sitemap_diff(&self, run_id: Option<i64>) -> Result<Option<SitemapDiff>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let runs: Option<(i64, Option<i64>)> = conn
+            .query_row(
+                "SELECT run_id, (SELECT MAX(run_id) FROM sitemap_snapshots AS previous
+                                 WHERE previous.run_id < latest.run_id)
+                 FROM sitemap_snapshots AS latest
+                 WHERE ?1 IS NULL OR run_id = ?1
+                 ORDER BY run_id DESC LIMIT 1",
+                params![run_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((run, Some(previous_run))) = runs else {
+            return Ok(None);
+        };
+
+        let list = |sql: &str| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(sql)?;
+            let urls: Result<Vec<String>, rusqlite::Error> = stmt
+                .query_map(params![run, previous_run], |row| row.get(0))?
+                .collect();
+            Ok(urls?)
+        };
+        Ok(Some(SitemapDiff {
+            previous_run,
+            run,
+            added: list(
+                "SELECT url FROM sitemap_snapshots WHERE run_id = ?1 AND url NOT IN
+                 (SELECT url FROM sitemap_snapshots WHERE run_id = ?2) ORDER BY url",
+            )?,
+            removed: list(
+                "SELECT url FROM sitemap_snapshots WHERE run_id = ?2 AND url NOT IN
+                 (SELECT url FROM sitemap_snapshots WHERE run_id = ?1) ORDER BY url",
+            )?,
+            bumped: list(
+                "SELECT latest.url FROM sitemap_snapshots AS latest
+                 JOIN sitemap_snapshots AS previous ON previous.url = latest.url
+                 WHERE latest.run_id = ?1 AND previous.run_id = ?2
+                   AND latest.lastmod IS NOT previous.lastmod
+                 ORDER BY latest.url",
+            )?,
+        }))
+    }
+}
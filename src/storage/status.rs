@@ -0,0 +1,180 @@
+//! Processing status of pages, moved along scraped → parsed → summarized → composed by the
+//! stages themselves, or to `failed:<stage>`, so that work is selected by status and the
+//! progress of a database can be broken down by stage.
+
+use crate::error::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use super::{Page, Storage};
+
+/// Status of a page whose stored content tells how far it went, used for pages written whole
+/// and for databases created before statuses were recorded.
+const CONTENT_STATUS_SQL: &str = "CASE
+    WHEN summary IS NOT NULL AND summary != '' THEN 'summarized'
+    WHEN text IS NOT NULL AND text != '' THEN 'parsed'
+    ELSE 'scraped'
+END";
+
+/// How far a page went through the pipeline
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PageStatus {
+    /// Stored by scrape or fetch, without text yet
+    Scraped,
+    /// Text extracted by parse
+    Parsed,
+    /// Summarized by summarize
+    Summarized,
+    /// Written to an output file by compose
+    Composed,
+    /// The named stage failed on the page, e.g. `parse`
+    Failed(String),
+}
+
+impl PageStatus {
+    /// Returns the status of a page written whole from its content.
+    pub(super) fn of_page(page: &Page) -> Self {
+        if page
+            .summary
+            .as_deref()
+            .is_some_and(|summary| !summary.is_empty())
+        {
+            Self::Summarized
+        } else if page.text.as_deref().is_some_and(|text| !text.is_empty()) {
+            Self::Parsed
+        } else {
+            Self::Scraped
+        }
+    }
+
+    /// Returns the value stored in the database.
+    pub fn as_db_value(&self) -> String {
+        match self {
+            Self::Scraped => "scraped".to_string(),
+            Self::Parsed => "parsed".to_string(),
+            Self::Summarized => "summarized".to_string(),
+            Self::Composed => "composed".to_string(),
+            Self::Failed(stage) => format!("failed:{stage}"),
+        }
+    }
+
+    /// Reads a value stored in the database, unknown values being taken as scraped.
+    pub fn from_db_value(value: &str) -> Self {
+        match value {
+            "parsed" => Self::Parsed,
+            "summarized" => Self::Summarized,
+            "composed" => Self::Composed,
+            _ => match value.strip_prefix("failed:") {
+                Some(stage) => Self::Failed(stage.to_string()),
+                None => Self::Scraped,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for PageStatus {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(&self.as_db_value())
+    }
+}
+
+impl Storage {
+    /// Adds the status column to databases created before statuses were recorded,
+    /// taking the status of every page from its stored content.
+    pub(super) fn init_status_column(conn: &Connection) -> Result<()> {
+        if Self::add_missing_column(conn, "pages", "status", "TEXT NOT NULL DEFAULT 'scraped'")? {
+            conn.execute(
+                &format!("UPDATE pages SET status = {CONTENT_STATUS_SQL}"),
+                params![],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves a page to a status.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page to update
+    /// * `status` - The new status of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_page_status(&self, url: &str, status: &PageStatus) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "UPDATE pages SET status = ?1 WHERE url = ?2",
+            params![status.as_db_value(), url],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the status of a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns the status if the page is stored, None if not, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_status(&self, url: &str) -> Result<Option<PageStatus>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let status: Option<String> = conn
+            .query_row(
+                "SELECT status FROM pages WHERE url = ?1",
+                params![url],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(status.map(|status| PageStatus::from_db_value(&status)))
+    }
+
+    /// Returns the number of pages in every status, ordered along the pipeline
+    /// with failures last, leaving out statuses without pages.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of statuses and page counts on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn count_pages_by_status(&self) -> Result<Vec<(PageStatus, usize)>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT status, COUNT(*) FROM pages GROUP BY status")?;
+        let counts: Result<Vec<(String, i64)>, rusqlite::Error> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect();
+
+        let mut counts: Vec<(PageStatus, usize)> = counts?
+            .into_iter()
+            .map(|(status, pages)| (PageStatus::from_db_value(&status), pages as usize))
+            .collect();
+        counts.sort();
+        Ok(counts)
+    }
+}
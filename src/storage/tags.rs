@@ -0,0 +1,144 @@
+//! Tags grouping stored pages independently of their URL structure.
+
+use crate::error::Result;
+use rusqlite::params;
+
+use super::Storage;
+
+/// SQL expression extracting the path (with the query) of the `url` column,
+/// e.g. `/docs/intro` of `https://example.com/docs/intro`.
+const URL_PATH_SQL: &str =
+    "substr(url, instr(url, '://') + 2 + instr(substr(url, instr(url, '://') + 3), '/'))";
+
+/// Returns the SQL expression a tagging pattern is matched against:
+/// the URL path for patterns starting with `/`, the whole URL otherwise.
+fn pattern_subject(pattern: &str) -> &'static str {
+    if pattern.starts_with('/') {
+        URL_PATH_SQL
+    } else {
+        "url"
+    }
+}
+
+impl Storage {
+    /// Tags stored pages matching a glob pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to add
+    /// * `pattern` - Glob matched against the URL path if it starts with `/` (e.g. `/docs/*`),
+    ///   against the whole URL otherwise
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of newly tagged pages on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn tag_pages(&self, tag: &str, pattern: &str) -> Result<usize> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let tagged = conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO page_tags (url, tag)
+                 SELECT url, ?1 FROM pages WHERE {} GLOB ?2",
+                pattern_subject(pattern)
+            ),
+            params![tag, pattern],
+        )?;
+
+        Ok(tagged)
+    }
+
+    /// Removes a tag from stored pages matching a glob pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to remove
+    /// * `pattern` - Glob matched the same way as by [`Storage::tag_pages`]
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of untagged pages on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn untag_pages(&self, tag: &str, pattern: &str) -> Result<usize> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let untagged = conn.execute(
+            &format!(
+                "DELETE FROM page_tags WHERE tag = ?1 and {} GLOB ?2",
+                pattern_subject(pattern)
+            ),
+            params![tag, pattern],
+        )?;
+
+        Ok(untagged)
+    }
+
+    /// Returns all tags with the number of stored pages tagged with them, ordered by tag.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of (tag, pages count) tuples on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare(
+            "
+                SELECT page_tags.tag, count(*)
+                FROM page_tags
+                JOIN pages ON pages.url = page_tags.url
+                GROUP BY page_tags.tag
+                ORDER BY page_tags.tag ASC
+            ",
+        )?;
+        let tags: Result<Vec<(String, usize)>, rusqlite::Error> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect();
+
+        tags.map_err(|e| e.into())
+    }
+
+    /// Returns the tags of a stored page, ordered by tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of tags on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_tags(&self, url: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let mut stmt = conn.prepare("SELECT tag FROM page_tags WHERE url = ?1 ORDER BY tag ASC")?;
+        let tags: Result<Vec<String>, rusqlite::Error> =
+            stmt.query_map([url], |row| row.get(0))?.collect();
+
+        tags.map_err(|e| e.into())
+    }
+}
@@ -0,0 +1,68 @@
+//! Summaries of pages in other languages, stored along with the summary of every page.
+
+use crate::error::Result;
+use rusqlite::{OptionalExtension, params};
+
+use super::Storage;
+
+impl Storage {
+    /// Stores the summary of a page in a language, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    /// * `lang` - The ISO 639-3 code of the language of the summary
+    /// * `summary` - The summary
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success, or an error if database operation fails
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn update_page_translation(&self, url: &str, lang: &str, summary: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        conn.execute(
+            "INSERT OR REPLACE INTO page_translations (url, lang, summary) VALUES (?1, ?2, ?3)",
+            params![url, lang, summary],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the summary of a page in a language, if written.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page
+    /// * `lang` - The ISO 639-3 code of the language of the summary
+    ///
+    /// # Returns
+    ///
+    /// Returns the summary on success, None if the page has no summary in the language
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if database operation fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex is poisoned
+    pub fn get_page_translation(&self, url: &str, lang: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().expect("Storage mutex poisoned");
+        let summary = conn
+            .query_row(
+                "SELECT summary FROM page_translations WHERE url = ?1 AND lang = ?2",
+                params![url, lang],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(summary)
+    }
+}
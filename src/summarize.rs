@@ -3,23 +3,49 @@
 
 extern crate spider;
 
-use anyhow::Result;
 use llm::builder::LLMBuilder;
 use llm::chat::{ChatMessage, ChatMessageBuilder, ChatProvider};
-use log::{debug, info};
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::cell::RefCell;
+use log::{debug, info, warn};
 
-use crate::SummarizeTarget;
-use crate::constants::{DEFAULT_PROMPT_TEMPLATE, THINK_STRIPPER};
-use crate::storage::Storage;
+use crate::constants::{DEFAULT_PROMPT_TEMPLATE, FAQ_PROMPT_TEMPLATE, STRUCTURED_PROMPT_TEMPLATE};
+use crate::parse::StructuredData;
+use crate::progress::Progress;
+use crate::shutdown::Shutdown;
+use crate::storage::{Page, PageFilter, PageIter, PageSelection, PageStatus, Storage};
+use crate::throttle::is_rate_limit_error;
+use crate::{Error, FaqPages, NoindexPages, PageSort, Result, SummarizeTarget};
 
-use rate_guard::{RateLimit, StdTokenBucket, TokenBucketBuilder};
+use std::sync::Arc;
 use std::time::Duration;
-
-static THINK_STRIPPER_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(THINK_STRIPPER).expect("Failed to compile THINK_STRIPPER regex"));
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use url::Url;
+
+mod ask;
+mod audit;
+mod compare;
+mod estimate;
+mod extractive;
+mod faq;
+mod language;
+mod pipeline;
+mod postprocess;
+mod prompt;
+mod rate_limit;
+mod structured;
+
+pub use ask::{Answer, AskOptions, DEFAULT_ASK_PAGE_CHARS, DEFAULT_ASK_PAGES, ask};
+pub use audit::{AuditOptions, audit_summaries};
+pub use compare::{PromptComparison, compare_prompts};
+pub use estimate::{Estimate, estimate_summarize};
+pub use extractive::{LOCAL_MODEL_SCHEME, TEXTRANK_MODEL, TextRankSummarizer, textrank};
+pub use language::SummaryLanguage;
+pub(crate) use pipeline::scrape_and_summarize;
+pub use pipeline::summarize_while_scraping;
+pub use postprocess::{PostProcessRule, parse_rules, post_process};
+pub use prompt::{PROMPT_PLACEHOLDERS, PromptVariables, unknown_placeholders};
+pub use rate_limit::{Acquire, RateLimiter, TokenBucketLimiter};
+pub use structured::{StructuredSummary, parse_structured_summary, summarize_page_structured};
 
 /// Configuration containing shared data for summarization operations
 pub struct SummarizeContext<'a> {
@@ -28,7 +54,210 @@ pub struct SummarizeContext<'a> {
     /// Prompt template to use
     pub prompt_template: Option<&'a str>,
     /// Rate limiter for controlling request frequency
-    pub rate_limiter: Option<&'a StdTokenBucket>,
+    pub rate_limiter: Option<&'a dyn RateLimiter>,
+    /// Sequences the summary is cut at, the first of them found ends it
+    pub stop: &'a [String],
+    /// Whether pages are summarized as JSON with keywords and audience, see [`summarize_page_structured`]
+    pub structured: bool,
+    /// User rules rewriting the answers, see [`post_process`]
+    pub rules: &'a [PostProcessRule],
+    /// Language the summaries are written in, the language of the page if None
+    pub language: Option<SummaryLanguage>,
+    /// Pages summarized with the FAQ prompt instead of the default one
+    pub faq: &'a FaqPages,
+}
+
+impl<'a> SummarizeContext<'a> {
+    /// Creates the context of a summarization run with the given options.
+    fn for_options(
+        model: &'a dyn ChatProvider,
+        rate_limiter: Option<&'a dyn RateLimiter>,
+        options: &'a SummarizeOptions,
+    ) -> Self {
+        SummarizeContext {
+            model,
+            prompt_template: options
+                .prompt_template
+                .as_deref()
+                .or(options.structured.then_some(STRUCTURED_PROMPT_TEMPLATE)),
+            rate_limiter,
+            stop: &options.sampling.stop,
+            structured: options.structured,
+            rules: &options.post_process,
+            language: options.language,
+            faq: &options.faq,
+        }
+    }
+}
+
+/// Generation parameters of the model, its defaults if not set
+#[derive(Clone, Debug, Default)]
+pub struct Sampling {
+    /// Sampling temperature, lower values give more deterministic summaries
+    pub temperature: Option<f32>,
+    /// Nucleus sampling: only tokens within this cumulative probability are considered
+    pub top_p: Option<f32>,
+    /// Maximal number of tokens of a summary
+    pub max_tokens: Option<u32>,
+    /// Sequences the summary is cut at
+    pub stop: Vec<String>,
+}
+
+impl Sampling {
+    /// Passes the set generation parameters to the LLM builder.
+    /// Stop sequences are applied to the responses, see [`SummarizeContext::stop`].
+    pub fn apply(&self, mut llm_builder: LLMBuilder) -> LLMBuilder {
+        if let Some(temperature) = self.temperature {
+            llm_builder = llm_builder.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            llm_builder = llm_builder.top_p(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            llm_builder = llm_builder.max_tokens(max_tokens);
+        }
+        llm_builder
+    }
+}
+
+/// Model writing the summaries
+pub enum SummaryModel {
+    /// LLM, built with the generation parameters of the run
+    Llm(LLMBuilder),
+    /// Local extractive summarizer, which needs no API key and ignores prompts
+    TextRank(TextRankSummarizer),
+}
+
+impl SummaryModel {
+    /// Returns the local summarizer of a `local://` model URL like `local://textrank`,
+    /// None for URLs of LLMs.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model URL
+    ///
+    /// # Returns
+    ///
+    /// Returns the local summarizer, or None if the URL isn't a local one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL names an unknown local summarizer
+    pub fn local(model: &str) -> Result<Option<Self>, Error> {
+        let Some(model_url) = Url::parse(model)
+            .ok()
+            .filter(|model_url| model_url.scheme() == LOCAL_MODEL_SCHEME)
+        else {
+            return Ok(None);
+        };
+
+        match model_url.host_str() {
+            Some(TEXTRANK_MODEL) => Ok(Some(SummaryModel::TextRank(TextRankSummarizer::default()))),
+            _ => Err(Error::Config(
+                format!(
+                    "Unknown local summarizer {model}, use {LOCAL_MODEL_SCHEME}://{TEXTRANK_MODEL}"
+                )
+                .into(),
+            )),
+        }
+    }
+
+    /// Sends a short prompt to the model, checking that it's reachable and accepts the credentials
+    /// before a long run. Local summarizers always pass.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Llm`] if the model can't be built or fails to answer
+    pub async fn check(self) -> Result<(), Error> {
+        let model = self.build(&Sampling::default())?;
+        model
+            .chat(&[ChatMessage::user().content("Reply with OK.").build()])
+            .await
+            .map_err(|e| Error::from(e).context("The model failed to answer"))?;
+        Ok(())
+    }
+
+    /// Builds the model with the generation parameters of the run.
+    fn build(self, sampling: &Sampling) -> Result<Box<dyn ChatProvider>> {
+        match self {
+            SummaryModel::Llm(llm_builder) => sampling
+                .apply(llm_builder)
+                .build()
+                .map_err(|e| Error::from(e).context("Failed to build LLM model")),
+            SummaryModel::TextRank(summarizer) => Ok(Box::new(summarizer)),
+        }
+    }
+}
+
+/// Options controlling a summarization run
+#[derive(Clone, Debug, Default)]
+pub struct SummarizeOptions {
+    /// Name of the model, recorded in the runs log
+    pub model_name: String,
+    /// Prompt template to use, the default one if None
+    pub prompt_template: Option<String>,
+    /// Pages to summarize
+    pub target: SummarizeTarget,
+    /// Rate limit: requests per minute (default: no limit)
+    pub rpm: Option<u32>,
+    /// Limiter shared with other runs, e.g. of the other sites of a project, used instead of `rpm`
+    pub rate_limiter: Option<Arc<dyn RateLimiter>>,
+    /// Whether to summarize pages flagged by parse as too short
+    pub include_short: bool,
+    /// Pages summarized with the "unsummarized" and "all" targets, every page by default
+    pub filter: PageFilter,
+    /// Generation parameters of the model
+    pub sampling: Sampling,
+    /// Whether to ask for JSON summaries with keywords and audience, stored in their own columns
+    pub structured: bool,
+    /// User rules rewriting the answers before they are stored
+    pub post_process: Vec<PostProcessRule>,
+    /// Whether to continue after the last page summarized by an interrupted run
+    pub resume: bool,
+    /// Maximal number of summarized pages, the most important by internal links first
+    pub max_pages: Option<usize>,
+    /// Language the summaries are written in and stored under, along with the summary of the page,
+    /// the language of the page if None
+    pub language: Option<SummaryLanguage>,
+    /// Token stopping the run gracefully before the next page once cancelled,
+    /// keeping the stored summaries; Ctrl-C stops it if None
+    pub cancel: Option<CancellationToken>,
+    /// Whether pages flagged noindex are summarized by the "unsummarized", "all" and pattern targets
+    pub noindex: NoindexPages,
+    /// Pages summarized with the FAQ prompt keeping their questions and answers,
+    /// unless a prompt template is given or summaries are structured
+    pub faq: FaqPages,
+    /// Reporter of summarized pages
+    pub progress: Progress,
+}
+
+impl SummarizeOptions {
+    /// Returns the filter of the summarized pages, leaving out noindex pages unless included.
+    fn page_filter(&self) -> PageFilter {
+        self.noindex.restrict(self.filter.clone())
+    }
+
+    /// Returns the checkpoint stage of the run, naming its target and filter so that runs
+    /// of other pages neither resume after its last page nor clear its checkpoint.
+    fn checkpoint_stage(&self) -> String {
+        format!(
+            "summarize target={:?} language={:?} include_short={} max_pages={:?} filter={:?}",
+            self.target,
+            self.language,
+            self.include_short,
+            self.max_pages,
+            self.page_filter()
+        )
+    }
+
+    /// Returns the shared limiter of the run, or a limiter of `rpm` requests per minute.
+    fn rate_limiter(&self) -> Option<Arc<dyn RateLimiter>> {
+        self.rate_limiter.clone().or_else(|| {
+            self.rpm
+                .and_then(TokenBucketLimiter::per_minute)
+                .map(|limiter| Arc::new(limiter) as Arc<dyn RateLimiter>)
+        })
+    }
 }
 
 /// Summarizes pages from the database that have not been summarized yet
@@ -38,9 +267,8 @@ pub struct SummarizeContext<'a> {
 /// # Arguments
 ///
 /// * `db_path` - Path to the database containing scraped pages
-/// * `llm_builder` - The LLM builder to create the model for processing
-/// * `prompt` - Optional prompt template to use for summarization
-/// * `rpm` - Rate limit: requests per minute (default: no limit)
+/// * `model` - The LLM builder to create the model for processing, or a local summarizer
+/// * `options` - Prompt template, target and rate limit of the run
 ///
 /// # Returns
 ///
@@ -50,105 +278,276 @@ pub struct SummarizeContext<'a> {
 ///
 /// Returns an error if:
 /// * The LLM model fails to build
+/// * A local summarizer is given a prompt or asked for structured summaries
 /// * Database operations fail
 /// * File operations fail
 pub async fn summarize(
     db_path: &str,
-    llm_builder: LLMBuilder,
-    prompt_template: Option<&str>,
-    target: SummarizeTarget,
-    rpm: Option<u32>,
-) -> Result<()> {
-    let model = llm_builder
-        .build()
-        .map_err(|e| anyhow::anyhow!("Failed to build LLM model: {}", e))?;
-
-    let rate_limiter: Option<StdTokenBucket> = rpm.and_then(|rpm| {
-        let capacity = rpm.max(1) as u64;
-        let refill_interval = Duration::from_secs_f64(60.0 / capacity as f64);
-
-        TokenBucketBuilder::builder()
-            .capacity(capacity)
-            .refill_amount(1_u64)
-            .refill_every(refill_interval)
-            .with_time(rate_guard::StdTimeSource::new())
-            .with_precision::<rate_guard::Nanos>()
-            .build()
-            .ok()
-    });
+    model: SummaryModel,
+    options: SummarizeOptions,
+) -> Result<(), Error> {
+    info!("Summarizing pages from database {db_path}...");
+    summarize_with_storage(&Storage::new(db_path)?, model, options).await
+}
 
-    let storage = Storage::new(db_path)?;
+/// Summarizes the storage pages, recording the run in the audit log.
+pub(crate) async fn summarize_with_storage(
+    storage: &Storage,
+    model: SummaryModel,
+    options: SummarizeOptions,
+) -> Result<(), Error> {
+    record_summarize_run(storage, model, options, None).await
+}
 
-    let ctx = SummarizeContext {
-        model: model.as_ref(),
-        prompt_template,
-        rate_limiter: rate_limiter.as_ref(),
+/// Summarizes the storage pages, recording the run in the audit log. Unsummarized pages are
+/// summarized as their URLs are received if a scrape sends the pages it parses.
+async fn record_summarize_run(
+    storage: &Storage,
+    model: SummaryModel,
+    options: SummarizeOptions,
+    parsed_pages: Option<mpsc::UnboundedReceiver<String>>,
+) -> Result<(), Error> {
+    if matches!(model, SummaryModel::TextRank(_))
+        && (options.prompt_template.is_some() || options.structured || options.language.is_some())
+    {
+        return Err(Error::Config("The local extractive summarizer takes no prompt and writes no structured or translated summaries".into()));
+    }
+    if options.resume && options.max_pages.is_some() {
+        return Err(Error::Config(
+            "Runs with a page budget can't be resumed, as they follow the importance of pages"
+                .into(),
+        ));
+    }
+    if options.structured && options.language.is_some() {
+        return Err(Error::Config(
+            "Structured summaries can't be written in another language".into(),
+        ));
+    }
+
+    let shutdown = Shutdown::for_run(options.cancel.as_ref());
+    let run_id = storage.start_run(
+        "summarize",
+        &format!(
+            "model={} target={:?} rpm={:?} include_short={} filter={:?} noindex={:?} sampling={:?} structured={} rules={} resume={} max_pages={:?} language={} faq={:?} prompt={} while_scraping={}",
+            options.model_name,
+            options.target,
+            options.rpm,
+            options.include_short,
+            options.filter,
+            options.noindex,
+            options.sampling,
+            options.structured,
+            options.post_process.len(),
+            options.resume,
+            options.max_pages,
+            options
+                .language
+                .map_or("page".to_string(), |language| language.to_string()),
+            options.faq,
+            if options.prompt_template.is_some() {
+                "custom"
+            } else {
+                "default"
+            },
+            parsed_pages.is_some()
+        ),
+    )?;
+
+    let result = summarize_target(storage, model, &options, &shutdown, parsed_pages).await;
+    let error = match &result {
+        Ok(_) if shutdown.is_requested() => Some("Interrupted".to_string()),
+        Ok(_) => None,
+        Err(error) => Some(error.to_string()),
     };
+    storage.finish_run(run_id, result.as_ref().ok().copied(), error)?;
+    if let Ok(summarized) = &result {
+        options.progress.stage_complete("summarize", *summarized);
+    }
 
-    let total_processed = match &target {
-        SummarizeTarget::Unsummarized => {
-            info!("Summarizing pages from database {db_path} that haven't been summarized yet...");
-            summarize_unsummarized_pages(&ctx, &storage).await?
-        }
+    result.map(|_| ())
+}
+
+/// Summarizes the target pages and returns the number of summarized pages
+async fn summarize_target(
+    storage: &Storage,
+    model: SummaryModel,
+    options: &SummarizeOptions,
+    shutdown: &Shutdown,
+    parsed_pages: Option<mpsc::UnboundedReceiver<String>>,
+) -> Result<usize> {
+    let extractive = matches!(model, SummaryModel::TextRank(_));
+    let model = model.build(&options.sampling)?;
+
+    let rate_limiter = options.rate_limiter();
+
+    let mut ctx = SummarizeContext::for_options(model.as_ref(), rate_limiter.as_deref(), options);
+    if extractive {
+        ctx.prompt_template = Some(extractive::EXTRACTIVE_PROMPT_TEMPLATE);
+    }
+
+    let summarizable = PageSelection::Summarizable {
+        include_flagged: options.include_short,
+    };
+    let total_processed = match &options.target {
+        SummarizeTarget::Unsummarized => match parsed_pages {
+            Some(mut parsed_pages) => {
+                info!("Summarizing pages as they are scraped...");
+                pipeline::summarize_parsed_pages(
+                    &ctx,
+                    storage,
+                    shutdown,
+                    options,
+                    &mut parsed_pages,
+                )
+                .await?
+            }
+            None => {
+                info!("Summarizing pages that haven't been summarized yet...");
+                let selection = unsummarized_selection(options);
+                summarize_selection(&ctx, storage, shutdown, options, selection).await?
+            }
+        },
         SummarizeTarget::All => {
-            info!("Summarizing ALL pages from database {db_path}...");
-            summarize_all_pages(&ctx, &storage).await?
+            info!("Summarizing ALL pages...");
+            let selection = (summarizable, options.page_filter());
+            summarize_selection(&ctx, storage, shutdown, options, selection).await?
+        }
+        SummarizeTarget::Pattern { glob } => {
+            info!("Summarizing pages matching {glob}...");
+            let selection = (summarizable, options.page_filter().url_glob(glob));
+            summarize_selection(&ctx, storage, shutdown, options, selection).await?
         }
         SummarizeTarget::Page { url } => {
-            info!("Summarizing page {url} from database {db_path}...");
-            summarize_single_page(&ctx, &storage, url).await?
+            info!("Summarizing page {url}...");
+            summarize_single_page(&ctx, storage, &options.progress, url).await?
         }
     };
 
-    if total_processed == 0 {
-        match &target {
-            SummarizeTarget::Unsummarized => {
-                info!("No pages to summarize. All pages already have summaries.");
-            }
-            SummarizeTarget::All => {
-                info!("No pages in the database.");
-            }
-            SummarizeTarget::Page { url } => {
-                info!("Page {url} not found in the database.");
-            }
-        }
-    } else {
-        info!("Summarized {total_processed} pages");
+    if shutdown.is_requested() {
+        warn!(
+            "Summarization interrupted after {total_processed} pages, completed summaries are saved, run the same command with --resume to continue"
+        );
+        return Ok(total_processed as usize);
     }
 
-    Ok(())
-}
+    storage.clear_checkpoint(&options.checkpoint_stage())?;
 
-const FETCH_BATCH_SIZE: u32 = 100;
+    log_summarized(&options.target, total_processed);
+    Ok(total_processed as usize)
+}
 
-/// Summarizes pages from the database that have not been summarized yet
-async fn summarize_unsummarized_pages(
+/// Summarizes the selected pages in URL order from the checkpoint of a resumed run,
+/// or only the most important of them by internal links if the run has a page budget
+async fn summarize_selection(
     ctx: &SummarizeContext<'_>,
     storage: &Storage,
+    shutdown: &Shutdown,
+    options: &SummarizeOptions,
+    (selection, filter): (PageSelection, PageFilter),
 ) -> Result<u32> {
-    summarize_fetched_pages(ctx, storage, || {
-        storage.fetch_unsummarized_pages(FETCH_BATCH_SIZE)
-    })
-    .await
+    if let Some(max_pages) = options.max_pages {
+        storage.update_importance()?;
+        let mut urls = storage.list_sorted_urls(selection, &filter, &PageSort::Importance)?;
+        urls.truncate(max_pages);
+        info!(
+            "Summarizing the {} most important pages by links",
+            urls.len()
+        );
+
+        let mut urls = urls.into_iter();
+        return summarize_fetched_pages(ctx, storage, shutdown, options, || {
+            Ok(urls
+                .by_ref()
+                .take(FETCH_BATCH_SIZE)
+                .filter_map(|url| storage.get_page(&url).transpose())
+                .collect::<crate::Result<_>>()?)
+        })
+        .await;
+    }
+
+    let resume_after = resume_checkpoint(storage, options)?;
+    let pages = storage
+        .iter_pages_where(selection)
+        .matching(filter)
+        .after(resume_after.as_str());
+    summarize_selected_pages(ctx, storage, shutdown, options, pages).await
 }
 
-/// Summarizes ALL pages from the database, regardless of whether they're already summarized
-async fn summarize_all_pages(ctx: &SummarizeContext<'_>, storage: &Storage) -> Result<u32> {
-    let offset = RefCell::new(0);
-    let has_more = RefCell::new(true);
-    summarize_fetched_pages(ctx, storage, || {
-        if !*has_more.borrow() {
-            return Ok(Vec::new());
+/// Returns the selection and filter of pages without a summary, in the language of the run if set
+fn unsummarized_selection(options: &SummarizeOptions) -> (PageSelection, PageFilter) {
+    match options.language {
+        Some(language) => (
+            PageSelection::Summarizable {
+                include_flagged: options.include_short,
+            },
+            options.page_filter().untranslated(language.code()),
+        ),
+        None => (
+            PageSelection::Unsummarized {
+                include_flagged: options.include_short,
+            },
+            options.page_filter(),
+        ),
+    }
+}
+
+/// Returns the URL of the last page summarized by an interrupted run of the same target
+/// and filter if resuming, an empty string to start from the first page otherwise
+fn resume_checkpoint(storage: &Storage, options: &SummarizeOptions) -> Result<String> {
+    if !options.resume {
+        return Ok(String::new());
+    }
+
+    match storage.get_checkpoint(&options.checkpoint_stage())? {
+        Some(url) => {
+            info!("Resuming summarization after {url}");
+            Ok(url)
+        }
+        None => {
+            info!("No interrupted summarization to resume, starting from the first page");
+            Ok(String::new())
         }
+    }
+}
+
+/// Logs the number of pages summarized for the target, or why there were none
+fn log_summarized(target: &SummarizeTarget, total_processed: u32) {
+    if total_processed > 0 {
+        info!("Summarized {total_processed} pages");
+        return;
+    }
 
-        let batch = storage.fetch_summarizable_pages(FETCH_BATCH_SIZE, *offset.borrow())?;
-        let batch_size = batch.len();
-        *offset.borrow_mut() += FETCH_BATCH_SIZE;
-        if batch_size < FETCH_BATCH_SIZE as usize {
-            *has_more.borrow_mut() = false;
+    match target {
+        SummarizeTarget::Unsummarized => {
+            info!("No pages to summarize. All pages already have summaries.");
+        }
+        SummarizeTarget::All => {
+            info!("No pages in the database.");
         }
+        SummarizeTarget::Page { url } => {
+            info!("Page {url} not found in the database.");
+        }
+        SummarizeTarget::Pattern { glob } => {
+            info!("No pages matching {glob} in the database.");
+        }
+    }
+}
 
-        Ok(batch)
+const FETCH_BATCH_SIZE: usize = 100;
+
+/// Summarizes the iterated pages from the database, reading them in batches
+async fn summarize_selected_pages(
+    ctx: &SummarizeContext<'_>,
+    storage: &Storage,
+    shutdown: &Shutdown,
+    options: &SummarizeOptions,
+    mut pages: PageIter<'_>,
+) -> Result<u32> {
+    summarize_fetched_pages(ctx, storage, shutdown, options, || {
+        Ok(pages
+            .by_ref()
+            .take(FETCH_BATCH_SIZE)
+            .collect::<crate::Result<_>>()?)
     })
     .await
 }
@@ -157,28 +556,109 @@ async fn summarize_all_pages(ctx: &SummarizeContext<'_>, storage: &Storage) -> R
 async fn summarize_single_page(
     ctx: &SummarizeContext<'_>,
     storage: &Storage,
+    progress: &Progress,
     url: &str,
 ) -> Result<u32> {
-    let content = match storage.fetch_page_content(url)? {
-        None => return Ok(0),
-        Some(content) => content,
+    let Some(page) = storage.get_page(url)? else {
+        return Ok(0);
     };
-    let summary = summarize_page(url, &content, ctx).await?;
-    storage.update_page_summary(url, &summary)?;
+    summarize_and_store(ctx, storage, &page).await?;
+    progress.page_summarized(url);
     debug!("Summarized page: {url}");
     Ok(1)
 }
 
-/// Generalized function to summarize pages using a fetcher callback
+/// Summarizes a page and stores its summary, moving the page to `failed:summarize` if it fails
+async fn summarize_and_store(
+    ctx: &SummarizeContext<'_>,
+    storage: &Storage,
+    page: &Page,
+) -> Result<()> {
+    let result = store_page_summary(ctx, storage, page).await;
+    if result.is_err() {
+        storage.update_page_status(
+            page.url.as_str(),
+            &PageStatus::Failed("summarize".to_string()),
+        )?;
+    }
+    result
+}
+
+/// Summarizes a page and stores its summary, structured or in another language if the context asks so
+async fn store_page_summary(
+    ctx: &SummarizeContext<'_>,
+    storage: &Storage,
+    page: &Page,
+) -> Result<()> {
+    let url = page.url.as_str();
+    let content = page.text.as_deref().unwrap_or_default();
+    let structured_data = StructuredData::of_blocks(&storage.get_page_structured_data(url)?);
+    let faq_ctx;
+    let ctx = if ctx.prompt_template.is_none()
+        && faq::is_faq_page(ctx.faq, storage, page, &structured_data)?
+    {
+        debug!("Summarizing {url} as an FAQ page");
+        faq_ctx = SummarizeContext {
+            prompt_template: Some(FAQ_PROMPT_TEMPLATE),
+            ..*ctx
+        };
+        &faq_ctx
+    } else {
+        ctx
+    };
+    let variables = PromptVariables::of_page(page).with_structured_data(structured_data);
+    if let Some(language) = ctx.language {
+        let summary = summarize_page(url, content, &variables, ctx).await?;
+        storage.update_page_translation(url, language.code(), &summary)?;
+    } else if ctx.structured {
+        let summary = summarize_page_structured(url, content, &variables, ctx).await?;
+        storage.update_page_structured_summary(url, &summary)?;
+    } else {
+        let summary = summarize_page(url, content, &variables, ctx).await?;
+        storage.update_page_summary(url, &summary)?;
+        summarize_sections(ctx, storage, url, &variables).await?;
+    }
+
+    Ok(())
+}
+
+/// Summarizes the sections parse split a page into, titled by their headings
+async fn summarize_sections(
+    ctx: &SummarizeContext<'_>,
+    storage: &Storage,
+    url: &str,
+    variables: &PromptVariables,
+) -> Result<()> {
+    for section in storage.list_page_sections(url)? {
+        let variables = PromptVariables {
+            title: Some(section.heading.clone()),
+            ..variables.clone()
+        };
+        let section_url = format!("{url}#{}", section.anchor);
+        let summary = summarize_page(&section_url, &section.text, &variables, ctx).await?;
+        storage.update_section_summary(url, &section.anchor, &summary)?;
+        debug!("Summarized section: {section_url}");
+    }
+
+    Ok(())
+}
+
+/// Generalized function to summarize pages using a fetcher callback.
+/// Stops before the next page once a graceful shutdown is requested,
+/// saving the URL of the last summarized page in the checkpoint of the run.
 async fn summarize_fetched_pages<F>(
     ctx: &SummarizeContext<'_>,
     storage: &Storage,
+    shutdown: &Shutdown,
+    options: &SummarizeOptions,
     mut fetcher: F,
 ) -> Result<u32>
 where
-    F: FnMut() -> Result<Vec<(String, String)>>,
+    F: FnMut() -> Result<Vec<Page>>,
 {
+    let checkpoint_stage = options.checkpoint_stage();
     let mut processed = 0;
+    let mut last_url: Option<String> = None;
 
     loop {
         let batch = fetcher()?;
@@ -186,11 +666,24 @@ where
             break;
         }
 
-        for (url, content) in batch {
-            let summary = summarize_page(&url, &content, ctx).await?;
-            storage.update_page_summary(&url, &summary)?;
+        for page in batch {
+            if shutdown.is_requested() {
+                if let Some(last_url) = &last_url {
+                    storage.save_checkpoint(&checkpoint_stage, last_url)?;
+                }
+                return Ok(processed);
+            }
+
+            summarize_and_store(ctx, storage, &page).await?;
+            options.progress.page_summarized(page.url.as_str());
             processed += 1;
-            debug!("Summarized page: {url}");
+            debug!("Summarized page: {}", page.url);
+            last_url = Some(page.url.to_string());
+        }
+
+        // Saved after every batch, so that a killed run can be resumed too
+        if let Some(last_url) = &last_url {
+            storage.save_checkpoint(&checkpoint_stage, last_url)?;
         }
     }
 
@@ -203,7 +696,8 @@ where
 ///
 /// * `url` - The URL of the page
 /// * `content` - The content of the page
-/// * `ctx` - Context containing model, prompt template, and rate limiter
+/// * `variables` - Metadata of the page replacing the `{title}`, `{lang}`, `{section}` and `{lastmod}` placeholders
+/// * `ctx` - Context containing model, prompt template, rate limiter and stop sequences
 ///
 /// # Returns
 ///
@@ -217,10 +711,13 @@ where
 pub async fn summarize_page(
     url: &str,
     text: &str,
+    variables: &PromptVariables,
     ctx: &SummarizeContext<'_>,
-) -> Result<String, anyhow::Error> {
+) -> Result<String, Error> {
     let prompt_template = ctx.prompt_template.unwrap_or(DEFAULT_PROMPT_TEMPLATE);
-    let prompt = prompt_template
+    // Variables are replaced before the text, so placeholders quoted by the page are kept
+    let prompt = variables
+        .apply(prompt_template)
         .replace("{url}", url)
         .replace("{text}", text);
 
@@ -229,35 +726,63 @@ pub async fn summarize_page(
     if !prompt_template.contains("{text}") {
         messages.push(ChatMessage::user().content(text));
     }
+    if let Some(language) = ctx.language {
+        messages.push(ChatMessage::user().content(language.instruction()));
+    }
 
     let messages: Vec<ChatMessage> = messages
         .into_iter()
         .map(|message| message.build())
         .collect();
 
-    if let Some(limiter) = ctx.rate_limiter {
-        loop {
-            match limiter.try_acquire(1) {
-                Ok(()) => break,
-                Err(_) => {
-                    tokio::time::sleep(Duration::from_millis(100)).await;
+    let mut attempt = 1;
+    loop {
+        let response = chat_with_backoff(ctx, &messages).await?;
+        let summary = post_process(&response, ctx.stop, ctx.rules);
+        match ctx.language {
+            Some(language) if !language.is_used_by(&summary) => {
+                warn!(
+                    "Summary of {url} isn't written in {} (attempt {attempt})",
+                    language.name()
+                );
+                if attempt >= language::MAX_LANGUAGE_ATTEMPTS {
+                    return Ok(summary);
                 }
+                attempt += 1;
             }
+            _ => return Ok(summary),
         }
     }
+}
+
+/// Number of times a request rejected by the provider as rate limited is retried.
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
 
-    let response = ctx
-        .model
-        .chat(&messages)
-        .await
-        .map_err(|err| anyhow::anyhow!("LLM error: {err}."))?
-        .to_string();
+/// Pause after the first rate limited request, doubled on every next one.
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(5);
 
-    let summary = THINK_STRIPPER_REGEX
-        .replace_all(&response, "")
-        .to_string()
-        .trim()
-        .to_owned();
+/// Sends messages to the model respecting the rate limiter, backing off
+/// exponentially while the provider answers with 429.
+async fn chat_with_backoff(ctx: &SummarizeContext<'_>, messages: &[ChatMessage]) -> Result<String> {
+    let mut backoff = RATE_LIMIT_BACKOFF;
+    let mut retries = 0;
+
+    loop {
+        if let Some(limiter) = ctx.rate_limiter {
+            limiter.acquire().await;
+        }
 
-    Ok(summary)
+        match ctx.model.chat(messages).await {
+            Ok(response) => return Ok(response.to_string()),
+            Err(err)
+                if retries < MAX_RATE_LIMIT_RETRIES && is_rate_limit_error(&err.to_string()) =>
+            {
+                warn!("LLM provider is rate limiting, retrying in {backoff:?}: {err}");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            Err(err) => return Err(Error::from(err).context("LLM error")),
+        }
+    }
 }
@@ -0,0 +1,127 @@
+//! Questions answered from the stored pages: the pages best matching the question in the
+//! full-text index are given to the model, which answers citing their URLs.
+
+use llm::builder::LLMBuilder;
+use llm::chat::ChatMessage;
+use log::debug;
+
+use super::{SummarizeContext, chat_with_backoff, post_process};
+use crate::constants::ASK_PROMPT_TEMPLATE;
+use crate::storage::{PageFilter, Storage, any_word_search_query};
+use crate::{Error, FaqPages, Result};
+
+/// Default number of pages the model answers from.
+pub const DEFAULT_ASK_PAGES: usize = 5;
+
+/// Default number of characters of the text of every page given to the model.
+pub const DEFAULT_ASK_PAGE_CHARS: usize = 4000;
+
+/// Pages a question is answered from
+#[derive(Clone, Debug, Default)]
+pub struct AskOptions {
+    /// Number of best matching pages given to the model
+    pub pages: usize,
+    /// Number of characters of the text of every page given to the model
+    pub page_chars: usize,
+    /// Conditions the pages must match
+    pub filter: PageFilter,
+}
+
+/// Answer of the model with the pages it cites
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Answer {
+    pub text: String,
+    /// URLs of the given pages the answer cites, in the order they were given
+    pub sources: Vec<String>,
+}
+
+/// Answers a question from the stored pages best matching its words, asking the model
+/// to cite the URLs of the pages it used.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database containing the pages
+/// * `llm_builder` - The LLM builder to create the model answering
+/// * `question` - The question
+/// * `options` - Number and length of the pages given to the model
+///
+/// # Returns
+///
+/// Returns the answer, or None without asking the model if no stored page matches the question
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The LLM model fails to build
+/// * Database operations fail
+/// * LLM chat operation fails
+pub async fn ask(
+    db_path: &str,
+    llm_builder: LLMBuilder,
+    question: &str,
+    options: &AskOptions,
+) -> Result<Option<Answer>, Error> {
+    let storage = Storage::new(db_path)?;
+    let hits = storage.search_pages(
+        &any_word_search_query(question),
+        &options.filter,
+        options.pages,
+    )?;
+    if hits.is_empty() {
+        return Ok(None);
+    }
+
+    let mut messages = vec![ASK_PROMPT_TEMPLATE.to_string()];
+    for hit in &hits {
+        let Some(page) = storage.get_page(&hit.url)? else {
+            continue;
+        };
+        debug!("Answering from {}", hit.url);
+        let text: String = page
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .take(options.page_chars)
+            .collect();
+        messages.push(format!(
+            "URL: {}\nTitle: {}\nSummary: {}\n\n{text}",
+            hit.url,
+            page.title.as_deref().unwrap_or_default(),
+            page.summary.as_deref().unwrap_or_default()
+        ));
+    }
+    messages.push(format!("Question: {question}"));
+
+    let text = answer(llm_builder, &messages).await?;
+    let sources = hits
+        .into_iter()
+        .map(|hit| hit.url)
+        .filter(|url| text.contains(url.as_str()))
+        .collect();
+    Ok(Some(Answer { text, sources }))
+}
+
+/// Sends the prompt, pages and question to the model and returns its cleaned up answer
+async fn answer(llm_builder: LLMBuilder, messages: &[String]) -> Result<String> {
+    let model = llm_builder
+        .build()
+        .map_err(|e| Error::from(e).context("Failed to build LLM model"))?;
+    let ctx = SummarizeContext {
+        model: model.as_ref(),
+        prompt_template: None,
+        rate_limiter: None,
+        stop: &[],
+        structured: false,
+        rules: &[],
+        language: None,
+        faq: &FaqPages::Ignored,
+    };
+    let messages: Vec<ChatMessage> = messages
+        .iter()
+        .map(|content| ChatMessage::user().content(content).build())
+        .collect();
+
+    let response = chat_with_backoff(&ctx, &messages).await?;
+    Ok(post_process(&response, &[], &[]))
+}
@@ -0,0 +1,200 @@
+//! Audit of stored summaries by a judge model, scoring their faithfulness to the page text,
+//! their length and their language, so that bad summaries are found without reading them all.
+
+use chrono::Utc;
+use llm::builder::LLMBuilder;
+use llm::chat::ChatMessage;
+use log::{debug, info, warn};
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::{RateLimiter, SummarizeContext, TokenBucketLimiter, chat_with_backoff};
+use crate::constants::AUDIT_PROMPT_TEMPLATE;
+use crate::storage::{PageFilter, Storage, SummaryAudit, SummaryScores};
+use crate::{Error, FaqPages, Result};
+
+/// Number of times a summary is audited before a malformed answer skips it.
+const MAX_AUDIT_ATTEMPTS: u32 = 3;
+
+/// Options controlling an audit run
+#[derive(Clone, Debug, Default)]
+pub struct AuditOptions {
+    /// Name of the judge model, recorded in the runs log
+    pub model_name: String,
+    /// Rate limit: requests per minute (default: no limit)
+    pub rpm: Option<u32>,
+    /// Limiter shared with other runs of the judge model, used instead of `rpm`
+    pub rate_limiter: Option<Arc<dyn RateLimiter>>,
+    /// Pages whose summaries are audited, every summarized page by default
+    pub filter: PageFilter,
+}
+
+impl AuditOptions {
+    /// Returns the shared limiter of the run, or a limiter of `rpm` requests per minute.
+    fn rate_limiter(&self) -> Option<Arc<dyn RateLimiter>> {
+        self.rate_limiter.clone().or_else(|| {
+            self.rpm
+                .and_then(TokenBucketLimiter::per_minute)
+                .map(|limiter| Arc::new(limiter) as Arc<dyn RateLimiter>)
+        })
+    }
+}
+
+/// Scores the summaries of pages which weren't audited since they were last summarized,
+/// asking the judge model to compare them with the page text, and stores the scores,
+/// see [`Storage::list_worst_audits`]. Summaries the judge keeps answering malformed
+/// scores for are skipped.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database containing summarized pages
+/// * `llm_builder` - The LLM builder to create the judge model
+/// * `options` - Pages to audit and rate limit of the run
+///
+/// # Returns
+///
+/// Returns the number of audited summaries on success, or an error if any operation fails
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The LLM model fails to build
+/// * Database operations fail
+/// * LLM chat operation fails
+pub async fn audit_summaries(
+    db_path: &str,
+    llm_builder: LLMBuilder,
+    options: &AuditOptions,
+) -> Result<usize, Error> {
+    let storage = Storage::new(db_path)?;
+    let run_id = storage.start_run(
+        "audit",
+        &format!("model={} filter={:?}", options.model_name, options.filter),
+    )?;
+
+    let result = audit_pages(&storage, llm_builder, options, run_id).await;
+    storage.finish_run(
+        run_id,
+        result.as_ref().ok().copied(),
+        result.as_ref().err().map(|error| error.to_string()),
+    )?;
+
+    result
+}
+
+/// Audits the summaries of the pages and returns the number of audited ones
+async fn audit_pages(
+    storage: &Storage,
+    llm_builder: LLMBuilder,
+    options: &AuditOptions,
+    run_id: i64,
+) -> Result<usize> {
+    let model = llm_builder
+        .build()
+        .map_err(|e| Error::from(e).context("Failed to build LLM model"))?;
+    let rate_limiter = options.rate_limiter();
+    let ctx = SummarizeContext {
+        model: model.as_ref(),
+        prompt_template: None,
+        rate_limiter: rate_limiter.as_deref(),
+        stop: &[],
+        structured: false,
+        rules: &[],
+        language: None,
+        faq: &FaqPages::Ignored,
+    };
+
+    let urls = storage.list_unaudited_urls(&options.filter)?;
+    info!("Auditing summaries of {} pages...", urls.len());
+    let mut audited = 0;
+    for url in &urls {
+        let Some(page) = storage.get_page(url)? else {
+            continue;
+        };
+        let Some(summary) = page.summary.as_deref() else {
+            continue;
+        };
+        let text = page.text.as_deref().unwrap_or_default();
+        let Some(audit) = audit_summary(&ctx, url, text, summary).await? else {
+            continue;
+        };
+        storage.save_summary_audit(run_id, summary, &audit)?;
+        debug!("Audited summary of {url}: {:?}", audit.scores);
+        audited += 1;
+    }
+
+    info!("Audited {audited} summaries");
+    Ok(audited)
+}
+
+/// Asks the judge to score the summary of a page, None if every answer is malformed
+async fn audit_summary(
+    ctx: &SummarizeContext<'_>,
+    url: &str,
+    text: &str,
+    summary: &str,
+) -> Result<Option<SummaryAudit>> {
+    // The page text and summary are sent apart from the prompt, so that they can't alter it
+    let messages: Vec<ChatMessage> = [
+        AUDIT_PROMPT_TEMPLATE.replace("{url}", url),
+        format!("Webpage content:\n{text}"),
+        format!("Summary:\n{summary}"),
+    ]
+    .into_iter()
+    .map(|content| ChatMessage::user().content(content).build())
+    .collect();
+
+    for attempt in 1..=MAX_AUDIT_ATTEMPTS {
+        let response = chat_with_backoff(ctx, &messages).await?;
+        match read_audit(&response) {
+            Ok((scores, comment)) => {
+                return Ok(Some(SummaryAudit {
+                    url: url.to_string(),
+                    scores,
+                    comment,
+                    audited_at: Utc::now(),
+                }));
+            }
+            Err(error) => {
+                warn!("Malformed audit of the summary of {url} (attempt {attempt}): {error:#}");
+            }
+        }
+    }
+
+    warn!("Skipped the summary of {url}, the judge answered no valid scores");
+    Ok(None)
+}
+
+/// Parses the scores and comment of a judge answer: a JSON object, possibly in a code fence,
+/// with "faithfulness", "length" and "language" scores from 1 to 5 and a "comment" string.
+fn read_audit(response: &str) -> Result<(SummaryScores, String)> {
+    let json = response
+        .find('{')
+        .zip(response.rfind('}'))
+        .and_then(|(start, end)| response.get(start..=end))
+        .ok_or_else(|| Error::Llm("The answer has no JSON object".into()))?;
+    let value: Value = serde_json::from_str(json)
+        .map_err(|error| Error::Llm(error.into()).context("The answer isn't valid JSON"))?;
+
+    let score = |name: &str| -> Result<u8> {
+        value
+            .get(name)
+            .and_then(Value::as_u64)
+            .filter(|score| (1..=5).contains(score))
+            .and_then(|score| u8::try_from(score).ok())
+            .ok_or_else(|| Error::Llm(format!("\"{name}\" must be a score from 1 to 5").into()))
+    };
+    let scores = SummaryScores {
+        faithfulness: score("faithfulness")?,
+        length: score("length")?,
+        language: score("language")?,
+    };
+    let comment = value
+        .get("comment")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    Ok((scores, comment))
+}
@@ -0,0 +1,158 @@
+//! Comparison of prompts on a random sample of pages, to pick one before summarizing the whole site.
+
+use llm::builder::LLMBuilder;
+use log::{debug, info};
+
+use super::{PromptVariables, SummarizeContext, SummarizeOptions, summarize_page};
+use crate::parse::StructuredData;
+use crate::storage::{Evaluation, PageSelection, Storage};
+use crate::{Error, FaqPages, Result};
+
+/// Prompts to compare and the number of pages to compare them on
+#[derive(Clone, Debug, Default)]
+pub struct PromptComparison {
+    /// Names (e.g. file names) and templates of the compared prompts
+    pub prompts: Vec<(String, String)>,
+    /// Number of randomly sampled pages summarized with every prompt
+    pub sample: usize,
+}
+
+/// Summarizes a random sample of pages with every compared prompt, stores the summaries
+/// in the evaluations table instead of the pages and returns a markdown report
+/// listing them side by side.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database containing scraped pages
+/// * `llm_builder` - The LLM builder to create the model for processing
+/// * `options` - Pages to sample from, rate limit and generation parameters of the run
+/// * `comparison` - Prompts to compare and the sample size
+///
+/// # Returns
+///
+/// Returns the markdown report on success, or an error if any operation fails
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The LLM model fails to build
+/// * Database operations fail
+/// * LLM chat operation fails
+pub async fn compare_prompts(
+    db_path: &str,
+    llm_builder: LLMBuilder,
+    options: &SummarizeOptions,
+    comparison: &PromptComparison,
+) -> Result<String, Error> {
+    let storage = Storage::new(db_path)?;
+    let run_id = storage.start_run(
+        "compare",
+        &format!(
+            "model={} prompts={} sample={} include_short={} filter={:?} sampling={:?}",
+            options.model_name,
+            comparison
+                .prompts
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            comparison.sample,
+            options.include_short,
+            options.filter,
+            options.sampling
+        ),
+    )?;
+
+    info!("Comparing prompts on {} pages...", comparison.sample);
+    let result = summarize_sample(&storage, llm_builder, options, comparison, run_id).await;
+    storage.finish_run(
+        run_id,
+        result.as_ref().ok().copied(),
+        result.as_ref().err().map(|error| error.to_string()),
+    )?;
+    result?;
+
+    Ok(render_report(
+        run_id,
+        comparison,
+        &storage.list_evaluations(run_id)?,
+    ))
+}
+
+/// Summarizes the sampled pages with every prompt and returns the number of sampled pages
+async fn summarize_sample(
+    storage: &Storage,
+    llm_builder: LLMBuilder,
+    options: &SummarizeOptions,
+    comparison: &PromptComparison,
+    run_id: i64,
+) -> Result<usize> {
+    let model = options
+        .sampling
+        .apply(llm_builder)
+        .build()
+        .map_err(|e| Error::from(e).context("Failed to build LLM model"))?;
+    let rate_limiter = options.rate_limiter();
+
+    let selection = PageSelection::Summarizable {
+        include_flagged: options.include_short,
+    };
+    let urls = storage.sample_urls(selection, &options.page_filter(), comparison.sample)?;
+    for url in &urls {
+        let Some(page) = storage.get_page(url)? else {
+            continue;
+        };
+        let content = page.text.as_deref().unwrap_or_default();
+        let variables = PromptVariables::of_page(&page).with_structured_data(
+            StructuredData::of_blocks(&storage.get_page_structured_data(url)?),
+        );
+        for (name, template) in &comparison.prompts {
+            let ctx = SummarizeContext {
+                model: model.as_ref(),
+                prompt_template: Some(template.as_str()),
+                rate_limiter: rate_limiter.as_deref(),
+                stop: &options.sampling.stop,
+                structured: false,
+                rules: &options.post_process,
+                language: options.language,
+                faq: &FaqPages::Ignored,
+            };
+            let summary = summarize_page(url, content, &variables, &ctx).await?;
+            storage.save_evaluation(run_id, url, name, &summary)?;
+            debug!("Summarized page {url} with prompt {name}");
+        }
+    }
+
+    info!("Compared prompts on {} pages", urls.len());
+    Ok(urls.len())
+}
+
+/// Renders the summaries of every page under each other, after a table
+/// with the average summary length of every prompt.
+fn render_report(run_id: i64, comparison: &PromptComparison, evaluations: &[Evaluation]) -> String {
+    let mut report = format!("# Prompt comparison (run #{run_id})\n\n");
+    report.push_str("| Prompt | Pages | Average summary length |\n|---|---|---|\n");
+    for (name, _) in &comparison.prompts {
+        let lengths: Vec<usize> = evaluations
+            .iter()
+            .filter(|evaluation| &evaluation.prompt == name)
+            .map(|evaluation| evaluation.summary.chars().count())
+            .collect();
+        let average = lengths.iter().sum::<usize>() / lengths.len().max(1);
+        report.push_str(&format!("| {name} | {} | {average} |\n", lengths.len()));
+    }
+
+    let mut last_url = None;
+    for evaluation in evaluations {
+        if last_url != Some(&evaluation.url) {
+            report.push_str(&format!("\n## {}\n", evaluation.url));
+            last_url = Some(&evaluation.url);
+        }
+        report.push_str(&format!(
+            "\n### {}\n\n{}\n",
+            evaluation.prompt, evaluation.summary
+        ));
+    }
+
+    report
+}
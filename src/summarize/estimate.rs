@@ -0,0 +1,173 @@
+//! Estimation of the cost of a summarization run from the stored texts, without calling the model.
+
+use std::fmt;
+use std::time::Duration;
+use url::Url;
+
+use super::{LOCAL_MODEL_SCHEME, SummarizeOptions, unsummarized_selection};
+use crate::SummarizeTarget;
+use crate::constants::{DEFAULT_PROMPT_TEMPLATE, STRUCTURED_PROMPT_TEMPLATE};
+use crate::error::Result;
+use crate::storage::{PageSelection, Storage};
+
+/// Average number of characters of a token of english text.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Number of tokens of a summary assumed when --max-tokens isn't set.
+const ESTIMATED_SUMMARY_TOKENS: u32 = 200;
+
+/// USD prices per million input and output tokens of hosted models, by model name prefix.
+/// The longest matching prefix wins.
+const MODEL_PRICES: [(&str, f64, f64); 16] = [
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("o3-mini", 1.1, 4.4),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("deepseek-chat", 0.27, 1.1),
+    ("mistral-small", 0.2, 0.6),
+];
+
+/// Expected requests, tokens, time and cost of a summarization run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Estimate {
+    /// Number of pages to summarize, one request each
+    pub pages: usize,
+    /// Estimated number of prompt tokens of all requests
+    pub prompt_tokens: u64,
+    /// Estimated number of summary tokens of all requests
+    pub completion_tokens: u64,
+    /// Time the rate limit makes the run take at least, None without a rate limit
+    pub wall_time: Option<Duration>,
+    /// Approximate cost in USD, None if the model isn't in the pricing table
+    pub cost: Option<f64>,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(formatter, "Pages (requests): {}", self.pages)?;
+        writeln!(formatter, "Prompt tokens: ~{}", self.prompt_tokens)?;
+        writeln!(formatter, "Summary tokens: ~{}", self.completion_tokens)?;
+        match self.wall_time {
+            Some(wall_time) => writeln!(
+                formatter,
+                "Rate-limited time: ~{} min",
+                wall_time.as_secs().div_ceil(60)
+            )?,
+            None => writeln!(formatter, "Rate-limited time: not rate limited")?,
+        }
+        match self.cost {
+            Some(cost) => write!(formatter, "Cost: ~${cost:.2}"),
+            None => write!(
+                formatter,
+                "Cost: unknown, the model isn't in the pricing table"
+            ),
+        }
+    }
+}
+
+/// Estimates the requests, tokens, time and cost of summarizing the target pages
+/// from the length of their stored texts, without calling the model.
+///
+/// # Arguments
+///
+/// * `db_path` - Path to the database containing scraped pages
+/// * `options` - Model, prompt, target, rate limit and generation parameters of the run
+///
+/// # Returns
+///
+/// Returns the estimate on success, or an error if database operations fail
+///
+/// # Errors
+///
+/// Returns an error if database operations fail
+pub fn estimate_summarize(db_path: &str, options: &SummarizeOptions) -> Result<Estimate> {
+    let storage = Storage::new(db_path)?;
+    let text_lengths = target_text_lengths(&storage, options)?;
+
+    let prompt_chars = options
+        .prompt_template
+        .as_deref()
+        .unwrap_or(if options.structured {
+            STRUCTURED_PROMPT_TEMPLATE
+        } else {
+            DEFAULT_PROMPT_TEMPLATE
+        })
+        .chars()
+        .count();
+    let prompt_tokens = text_lengths
+        .iter()
+        .map(|text_chars| ((prompt_chars + text_chars) / CHARS_PER_TOKEN) as u64)
+        .sum();
+    let completion_tokens = u64::from(
+        options
+            .sampling
+            .max_tokens
+            .unwrap_or(ESTIMATED_SUMMARY_TOKENS),
+    ) * text_lengths.len() as u64;
+
+    let wall_time = options.rpm.map(|rpm| {
+        Duration::from_secs_f64(text_lengths.len() as f64 * 60.0 / f64::from(rpm.max(1)))
+    });
+    let cost = model_prices(&options.model_name).map(|(input_price, output_price)| {
+        (prompt_tokens as f64 * input_price + completion_tokens as f64 * output_price) / 1_000_000.0
+    });
+
+    Ok(Estimate {
+        pages: text_lengths.len(),
+        prompt_tokens,
+        completion_tokens,
+        wall_time,
+        cost,
+    })
+}
+
+/// Returns the lengths in characters of the texts of the target pages
+fn target_text_lengths(storage: &Storage, options: &SummarizeOptions) -> Result<Vec<usize>> {
+    let summarizable = PageSelection::Summarizable {
+        include_flagged: options.include_short,
+    };
+    let (selection, filter) = match &options.target {
+        SummarizeTarget::Unsummarized => unsummarized_selection(options),
+        SummarizeTarget::All => (summarizable, options.page_filter()),
+        SummarizeTarget::Pattern { glob } => (summarizable, options.page_filter().url_glob(glob)),
+        SummarizeTarget::Page { url } => {
+            return Ok(storage
+                .fetch_page_content(url)?
+                .map(|text| text.chars().count())
+                .into_iter()
+                .collect());
+        }
+    };
+
+    storage
+        .iter_pages_where(selection)
+        .matching(filter)
+        .take(options.max_pages.unwrap_or(usize::MAX))
+        .map(|page| page.map(|page| page.text.map_or(0, |text| text.chars().count())))
+        .collect()
+}
+
+/// Returns the input and output prices of the model of a model URL, free for local Ollama models and summarizers
+fn model_prices(model_url: &str) -> Option<(f64, f64)> {
+    let model_url = Url::parse(model_url).ok()?;
+    if matches!(model_url.scheme(), "ollama" | LOCAL_MODEL_SCHEME) {
+        return Some((0.0, 0.0));
+    }
+
+    let model = model_url.host_str()?;
+    MODEL_PRICES
+        .iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(_, input_price, output_price)| (*input_price, *output_price))
+}
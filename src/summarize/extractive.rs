@@ -0,0 +1,225 @@
+//! The extractive module summarizes pages without an LLM, picking their most central sentences
+//! with TextRank, so a usable llms.txt can be composed without an API key or a local model.
+
+use async_trait::async_trait;
+use llm::ToolCall;
+use llm::chat::{ChatMessage, ChatProvider, ChatResponse, Tool, Usage};
+use llm::error::LLMError;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Scheme of model URLs of summarizers running in the process, e.g. `local://textrank`.
+pub const LOCAL_MODEL_SCHEME: &str = "local";
+
+/// Name of the TextRank summarizer in local model URLs.
+pub const TEXTRANK_MODEL: &str = "textrank";
+
+/// Prompt of the extractive summarizers, which read the page text alone.
+pub(super) const EXTRACTIVE_PROMPT_TEMPLATE: &str = "{text}";
+
+/// Number of sentences of a summary.
+const SUMMARY_SENTENCES: usize = 3;
+
+/// Sentences with fewer words (headings, menu items, captions) are never picked.
+const MIN_SENTENCE_WORDS: usize = 4;
+
+/// Shorter words (articles, prepositions) don't make sentences similar.
+const MIN_WORD_CHARS: usize = 3;
+
+/// Number of sentences ranked at most, the first ones of the text, so that the similarity
+/// matrix of a long page stays small.
+const MAX_CANDIDATE_SENTENCES: usize = 200;
+
+/// Probability of following a similarity link rather than jumping to a random sentence.
+const DAMPING: f64 = 0.85;
+
+/// Maximal number of score updates.
+const MAX_ITERATIONS: usize = 50;
+
+/// Scores are considered stable once their total change is below it.
+const CONVERGENCE: f64 = 1e-6;
+
+/// Summarizer answering with the most central sentences of the last message, in their order.
+#[derive(Clone, Copy, Debug)]
+pub struct TextRankSummarizer {
+    /// Number of sentences of a summary
+    pub sentences: usize,
+}
+
+impl Default for TextRankSummarizer {
+    fn default() -> Self {
+        TextRankSummarizer {
+            sentences: SUMMARY_SENTENCES,
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for TextRankSummarizer {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let text = messages
+            .last()
+            .map_or("", |message| message.content.as_str());
+        Ok(Box::new(ExtractiveSummary(textrank(text, self.sentences))))
+    }
+}
+
+/// Answer of an extractive summarizer.
+#[derive(Debug)]
+struct ExtractiveSummary(String);
+
+impl ChatResponse for ExtractiveSummary {
+    fn text(&self) -> Option<String> {
+        Some(self.0.clone())
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        None
+    }
+
+    fn thinking(&self) -> Option<String> {
+        None
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        None
+    }
+}
+
+impl fmt::Display for ExtractiveSummary {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+/// Picks the most central sentences of a text with TextRank: sentences are ranked like
+/// web pages, linked by the number of words they share, and the best ones are kept in their order.
+///
+/// # Arguments
+///
+/// * `text` - The text to summarize, sentences are split at line ends and sentence punctuation,
+///   and only the first 200 are ranked
+/// * `sentences` - The number of sentences to pick
+pub fn textrank(text: &str, sentences: usize) -> String {
+    let mut candidates = split_sentences(text);
+    candidates.truncate(MAX_CANDIDATE_SENTENCES);
+    if candidates.len() <= sentences {
+        return candidates.join(" ");
+    }
+
+    let words: Vec<HashSet<String>> = candidates
+        .iter()
+        .map(|sentence| sentence_words(sentence))
+        .collect();
+    let mut ranked: Vec<(usize, f64)> = rank(&words).into_iter().enumerate().collect();
+    // Stable, so earlier sentences win ties
+    ranked.sort_by(|(_, score), (_, other_score)| other_score.total_cmp(score));
+    ranked.truncate(sentences);
+    ranked.sort_by_key(|(index, _)| *index);
+
+    ranked
+        .iter()
+        .filter_map(|(index, _)| candidates.get(*index).copied())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits a text into sentences long enough to be picked, without markdown heading and list markers.
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_start_matches(['#', '-', '*', '>', ' ', '\t']);
+        let mut start = 0;
+        let mut chars = line.char_indices().peekable();
+        while let Some((index, char)) = chars.next() {
+            let ends_sentence = matches!(char, '.' | '!' | '?')
+                && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+            if ends_sentence {
+                let end = index + char.len_utf8();
+                sentences.push(line.get(start..end).unwrap_or_default().trim());
+                start = end;
+            }
+        }
+        sentences.push(line.get(start..).unwrap_or_default().trim());
+    }
+
+    sentences.retain(|sentence| sentence.split_whitespace().count() >= MIN_SENTENCE_WORDS);
+    sentences
+}
+
+/// Returns the distinct lowercase words of a sentence, skipping short ones.
+fn sentence_words(sentence: &str) -> HashSet<String> {
+    sentence
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_WORD_CHARS)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Similarity of two sentences: their common words, normalized by their lengths
+/// so long sentences aren't favored.
+fn similarity(words: &HashSet<String>, other_words: &HashSet<String>) -> f64 {
+    let common = words.intersection(other_words).count();
+    let norm = (words.len() as f64).ln() + (other_words.len() as f64).ln();
+    if common == 0 || norm <= 0.0 {
+        return 0.0;
+    }
+
+    common as f64 / norm
+}
+
+/// Returns the TextRank score of every sentence, iterating PageRank over the similarity graph.
+fn rank(words: &[HashSet<String>]) -> Vec<f64> {
+    let weights: Vec<Vec<f64>> = words
+        .iter()
+        .enumerate()
+        .map(|(index, sentence)| {
+            words
+                .iter()
+                .enumerate()
+                .map(|(other_index, other_sentence)| {
+                    if index == other_index {
+                        0.0
+                    } else {
+                        similarity(sentence, other_sentence)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let totals: Vec<f64> = weights.iter().map(|row| row.iter().sum()).collect();
+
+    let mut scores = vec![1.0; words.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let updated: Vec<f64> = (0..words.len())
+            .map(|index| {
+                // Weights are symmetric, so the row of a sentence holds its links to the others
+                let incoming: f64 = weights
+                    .iter()
+                    .zip(&totals)
+                    .zip(&scores)
+                    .filter(|((_, total), _)| **total > 0.0)
+                    .map(|((row, total), score)| {
+                        row.get(index).copied().unwrap_or_default() / total * score
+                    })
+                    .sum();
+                (1.0 - DAMPING) + DAMPING * incoming
+            })
+            .collect();
+        let change: f64 = updated
+            .iter()
+            .zip(&scores)
+            .map(|(updated_score, score)| (updated_score - score).abs())
+            .sum();
+        scores = updated;
+        if change < CONVERGENCE {
+            break;
+        }
+    }
+
+    scores
+}
@@ -0,0 +1,41 @@
+//! Detection of FAQ pages, summarized with a prompt keeping their questions and answers
+//! as pairs instead of flattening them into prose.
+
+use crate::parse::StructuredData;
+use crate::storage::{Page, Storage};
+use crate::{FaqPages, Result};
+
+/// Number of question headings marking a page without `FAQPage` structured data as an FAQ.
+const MIN_QUESTION_HEADINGS: usize = 3;
+
+/// Returns `true` if the page is summarized with the FAQ prompt.
+pub(super) fn is_faq_page(
+    faq: &FaqPages,
+    storage: &Storage,
+    page: &Page,
+    structured_data: &StructuredData,
+) -> Result<bool> {
+    match faq {
+        FaqPages::Detected => Ok(structured_data.faq
+            || count_question_headings(page.text.as_deref().unwrap_or_default())
+                >= MIN_QUESTION_HEADINGS),
+        FaqPages::Tagged { tag } => Ok(storage.get_page_tags(page.url.as_str())?.contains(tag)),
+        FaqPages::Ignored => Ok(false),
+    }
+}
+
+/// Counts the markdown headings and bold lines of a text which are questions.
+fn count_question_headings(text: &str) -> usize {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with('#') {
+                Some(line.trim_start_matches('#'))
+            } else {
+                line.strip_prefix("**")
+                    .and_then(|line| line.strip_suffix("**"))
+            }
+        })
+        .filter(|heading| heading.trim_end().ends_with('?'))
+        .count()
+}
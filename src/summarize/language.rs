@@ -0,0 +1,65 @@
+//! Summaries written in a given language regardless of the language of the page,
+//! checked by detecting the language of the answers.
+
+use std::fmt;
+use std::str::FromStr;
+use whatlang::Lang;
+
+/// Number of times a page is summarized while the answer is detected in another language.
+pub(super) const MAX_LANGUAGE_ATTEMPTS: u32 = 2;
+
+/// Language summaries are written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SummaryLanguage(Lang);
+
+impl SummaryLanguage {
+    /// ISO 639-3 code of the language, e.g. `deu`, under which its summaries are stored
+    pub fn code(self) -> &'static str {
+        self.0.code()
+    }
+
+    /// English name of the language, e.g. `German`
+    pub fn name(self) -> &'static str {
+        self.0.eng_name()
+    }
+
+    /// Instruction appended to the prompt
+    pub(super) fn instruction(self) -> String {
+        format!(
+            "Write the summary in {}, whatever the language of the page is.",
+            self.name()
+        )
+    }
+
+    /// Returns whether the text is written in the language,
+    /// or is too short or ambiguous for its language to be detected reliably.
+    pub fn is_used_by(self, text: &str) -> bool {
+        whatlang::detect(text).is_none_or(|info| !info.is_reliable() || info.lang() == self.0)
+    }
+}
+
+impl FromStr for SummaryLanguage {
+    type Err = crate::Error;
+
+    /// Parses an ISO 639-3 code (`deu`) or an English name (`German`) of a language.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        Lang::from_code(&value)
+            .or_else(|| {
+                Lang::all()
+                    .iter()
+                    .copied()
+                    .find(|lang| lang.eng_name().to_lowercase() == value)
+            })
+            .map(SummaryLanguage)
+            .ok_or_else(|| {
+                crate::Error::Config(format!("Unknown language {value}, expected an ISO 639-3 code like deu or an English name like German").into())
+            })
+    }
+}
+
+impl fmt::Display for SummaryLanguage {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.code())
+    }
+}
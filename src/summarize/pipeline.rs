@@ -0,0 +1,119 @@
+//! Pipelined scrape and summarize: pages are summarized as soon as the scrape has parsed them,
+//! so that both runs together take about as long as the slower of them instead of their sum.
+
+use log::debug;
+use tokio::sync::mpsc;
+use url::Url;
+
+use super::{
+    SummarizeContext, SummarizeOptions, SummaryModel, record_summarize_run, summarize_selection,
+    unsummarized_selection,
+};
+use crate::scrape::{ScrapeOptions, ScrapeReport, ScrapeSource, process_source};
+use crate::shutdown::Shutdown;
+use crate::storage::Storage;
+use crate::{Error, Result, SummarizeTarget};
+
+/// Scrapes the sitemaps into the database like [`crate::process_sitemaps`] and summarizes
+/// the unsummarized pages while the crawl goes on, as the scrape parses them.
+///
+/// The summaries are written under the rate limit of the summarize options, both runs are
+/// recorded in the audit log.
+///
+/// # Arguments
+///
+/// * `sitemap_urls` - The sitemaps or feeds listing the pages
+/// * `db_path` - Path to the database where pages will be stored
+/// * `scrape_options` - Options of the scrape, which must parse pages on scrape
+/// * `model` - The LLM builder to create the model for processing, or a local summarizer
+/// * `options` - Options of the summarize run, which must target unsummarized pages
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if pages aren't parsed on scrape, or the summarize run targets
+/// other pages, is resumed or has a page budget. Returns the error of the scrape first,
+/// then the one of the summarize run.
+pub async fn summarize_while_scraping(
+    sitemap_urls: Vec<Url>,
+    db_path: &str,
+    scrape_options: ScrapeOptions,
+    model: SummaryModel,
+    options: SummarizeOptions,
+) -> Result<ScrapeReport, Error> {
+    scrape_and_summarize(
+        ScrapeSource::Sitemaps(sitemap_urls),
+        Storage::new(db_path)?,
+        scrape_options,
+        model,
+        options,
+    )
+    .await
+}
+
+/// Scrapes the source into the storage and summarizes the pages it parses at the same time.
+pub(crate) async fn scrape_and_summarize(
+    source: ScrapeSource,
+    storage: Storage,
+    mut scrape_options: ScrapeOptions,
+    model: SummaryModel,
+    options: SummarizeOptions,
+) -> Result<ScrapeReport, Error> {
+    if scrape_options.parse.is_none() {
+        return Err(Error::Config(
+            "Pages are summarized while scraping only if they are parsed on scrape".into(),
+        ));
+    }
+    if !matches!(options.target, SummarizeTarget::Unsummarized)
+        || options.resume
+        || options.max_pages.is_some()
+    {
+        return Err(Error::Config("Only unsummarized pages are summarized while scraping, without resuming or a page budget".into()));
+    }
+
+    // The scrape drops the sender once the crawl ends, which ends the summarize run
+    let (sender, receiver) = mpsc::unbounded_channel();
+    scrape_options.parsed_pages = Some(sender);
+    let (scraped, summarized) = tokio::join!(
+        process_source(source, storage.clone(), scrape_options),
+        record_summarize_run(&storage, model, options, Some(receiver)),
+    );
+
+    let report = scraped?;
+    summarized?;
+    Ok(report)
+}
+
+/// Summarizes the unsummarized pages in passes, starting a pass once pages were parsed since
+/// the previous one, until the scrape ends. Passes share the rate limiter of the context,
+/// so the crawl never waits for the model and the model never exceeds its rate limit.
+/// Returns the number of summarized pages.
+pub(super) async fn summarize_parsed_pages(
+    ctx: &SummarizeContext<'_>,
+    storage: &Storage,
+    shutdown: &Shutdown,
+    options: &SummarizeOptions,
+    parsed_pages: &mut mpsc::UnboundedReceiver<String>,
+) -> Result<u32> {
+    let mut processed = 0;
+    loop {
+        // A last pass runs once the scrape ends, for pages left unsummarized by earlier runs
+        let scraping = parsed_pages.recv().await.is_some();
+        let mut parsed = usize::from(scraping);
+        while parsed_pages.try_recv().is_ok() {
+            parsed += 1;
+        }
+        debug!("Summarizing after {parsed} newly parsed pages");
+
+        processed += summarize_selection(
+            ctx,
+            storage,
+            shutdown,
+            options,
+            unsummarized_selection(options),
+        )
+        .await?;
+        if !scraping || shutdown.is_requested() {
+            return Ok(processed);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Post-processing of model answers before they are stored as summaries:
+//! reasoning blocks, stop sequences, code fences and "Summary:" prefixes are stripped,
+//! then the rules given by the user are applied.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::constants::{ANSWER_PREFIX_STRIPPER, CODE_FENCE_STRIPPER, REASONING_STRIPPER};
+use crate::{Error, Result};
+
+static REASONING_STRIPPER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(REASONING_STRIPPER).expect("Failed to compile REASONING_STRIPPER regex")
+});
+
+static CODE_FENCE_STRIPPER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(CODE_FENCE_STRIPPER).expect("Failed to compile CODE_FENCE_STRIPPER regex")
+});
+
+static ANSWER_PREFIX_STRIPPER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(ANSWER_PREFIX_STRIPPER).expect("Failed to compile ANSWER_PREFIX_STRIPPER regex")
+});
+
+/// Separator of the pattern and the replacement of a rule line.
+const RULE_REPLACEMENT_SEPARATOR: &str = " => ";
+
+/// A user rule replacing matches of a regex in model answers
+#[derive(Clone, Debug)]
+pub struct PostProcessRule {
+    pub pattern: Regex,
+    /// Replacement of the matches, may refer to capture groups as `$1`; empty to remove them
+    pub replacement: String,
+}
+
+/// Parses post-processing rules, one per line as `pattern => replacement`, or `pattern` alone
+/// to remove its matches. Empty lines and lines starting with `#` are skipped.
+///
+/// # Arguments
+///
+/// * `content` - The content of the rules file
+///
+/// # Errors
+///
+/// Returns an error if a pattern isn't a valid regex
+pub fn parse_rules(content: &str) -> Result<Vec<PostProcessRule>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            let (pattern, replacement) = line
+                .split_once(RULE_REPLACEMENT_SEPARATOR)
+                .unwrap_or((line, ""));
+            Ok(PostProcessRule {
+                pattern: Regex::new(pattern.trim()).map_err(|error| {
+                    Error::Config(error.into())
+                        .context(format!("Invalid rule pattern on line {}", index + 1))
+                })?,
+                replacement: replacement.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Turns a model answer into a summary: strips reasoning blocks (`<think>`, `<thinking>`,
+/// `<reasoning>` and alike), cuts the answer at the first stop sequence, strips a code fence
+/// around the whole answer and a leading "Summary:" prefix, then applies the user rules.
+///
+/// # Arguments
+///
+/// * `response` - The answer of the model
+/// * `stop` - Sequences the summary is cut at
+/// * `rules` - User rules applied last, in order
+pub fn post_process(response: &str, stop: &[String], rules: &[PostProcessRule]) -> String {
+    let answer = REASONING_STRIPPER_REGEX.replace_all(response, "");
+    let answer = stop
+        .iter()
+        .filter_map(|stop| answer.find(stop.as_str()))
+        .min()
+        .and_then(|end| answer.get(..end))
+        .unwrap_or(answer.as_ref())
+        .trim();
+    // An answer made of several code blocks isn't fenced as a whole
+    let answer = CODE_FENCE_STRIPPER_REGEX
+        .captures(answer)
+        .and_then(|captures| captures.name("content"))
+        .map(|content| content.as_str())
+        .filter(|content| !content.contains("```"))
+        .unwrap_or(answer)
+        .trim();
+    let mut answer = ANSWER_PREFIX_STRIPPER_REGEX
+        .replace(answer, "")
+        .into_owned();
+
+    for rule in rules {
+        answer = rule
+            .pattern
+            .replace_all(&answer, rule.replacement.as_str())
+            .into_owned();
+    }
+
+    answer.trim().to_owned()
+}
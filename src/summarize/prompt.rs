@@ -0,0 +1,112 @@
+//! Page metadata prompt templates can reference besides `{url}` and `{text}`,
+//! e.g. "Summarize this {section} page titled {title}" or "... written by {author}".
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::compose::section_of;
+use crate::parse::StructuredData;
+use crate::storage::Page;
+
+/// Matches the placeholders of prompt templates, capturing their name.
+static PLACEHOLDER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{([A-Za-z_]+)\}").expect("Failed to compile placeholder regex"));
+
+/// Names of the placeholders prompt templates can reference.
+pub const PROMPT_PLACEHOLDERS: [&str; 9] = [
+    "url",
+    "text",
+    "title",
+    "lang",
+    "section",
+    "lastmod",
+    "headline",
+    "published",
+    "author",
+];
+
+/// Returns the placeholders of a prompt template which aren't replaced, e.g. a misspelled `{titel}`,
+/// each once in their order.
+pub fn unknown_placeholders(template: &str) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    for captures in PLACEHOLDER_REGEX.captures_iter(template) {
+        let Some(name) = captures.get(1).map(|name| name.as_str()) else {
+            continue;
+        };
+        if !PROMPT_PLACEHOLDERS.contains(&name) && !unknown.iter().any(|known| known == name) {
+            unknown.push(name.to_string());
+        }
+    }
+    unknown
+}
+
+/// Matches the `lang` attribute of the `<html>` element, capturing the language code.
+static HTML_LANG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<html\b[^>]*\blang\s*=\s*["']?([a-z]{2,3}(?:-[a-z0-9]+)*)"#)
+        .expect("Failed to compile html lang regex")
+});
+
+/// Page metadata replacing the `{title}`, `{lang}`, `{section}`, `{lastmod}`, `{headline}`,
+/// `{published}` and `{author}` placeholders of prompt templates,
+/// missing values are replaced with an empty string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PromptVariables {
+    /// Title of the page
+    pub title: Option<String>,
+    /// Language of the page declared by its `<html lang="...">` attribute
+    pub lang: Option<String>,
+    /// Section of the site the page belongs to, named after the first segment of its URL path
+    pub section: Option<String>,
+    /// Date the page was last modified, as `YYYY-MM-DD`
+    pub lastmod: Option<String>,
+    /// Headline of the article or name of the product declared by the JSON-LD data of the page
+    pub headline: Option<String>,
+    /// Publication date of the article declared by the JSON-LD data of the page
+    pub published: Option<String>,
+    /// Authors of the article declared by the JSON-LD data of the page
+    pub author: Option<String>,
+}
+
+impl PromptVariables {
+    /// Resolves the variables from the stored metadata of a page.
+    pub fn of_page(page: &Page) -> Self {
+        PromptVariables {
+            title: page.title.clone().filter(|title| !title.trim().is_empty()),
+            lang: HTML_LANG_REGEX
+                .captures(&page.html)
+                .and_then(|captures| captures.get(1))
+                .map(|lang| lang.as_str().to_string()),
+            section: Some(section_of(page.url.as_str()).0),
+            lastmod: (page.lastmod.timestamp() > 0)
+                .then(|| page.lastmod.format("%Y-%m-%d").to_string()),
+            ..PromptVariables::default()
+        }
+    }
+
+    /// Adds the key fields of the structured data stored by parse for the page.
+    pub fn with_structured_data(self, data: StructuredData) -> Self {
+        PromptVariables {
+            headline: data.headline,
+            published: data.date_published,
+            author: data.author,
+            ..self
+        }
+    }
+
+    /// Replaces the placeholders of the variables in a prompt template.
+    pub(super) fn apply(&self, template: &str) -> String {
+        [
+            ("{title}", &self.title),
+            ("{lang}", &self.lang),
+            ("{section}", &self.section),
+            ("{lastmod}", &self.lastmod),
+            ("{headline}", &self.headline),
+            ("{published}", &self.published),
+            ("{author}", &self.author),
+        ]
+        .iter()
+        .fold(template.to_string(), |prompt, (placeholder, value)| {
+            prompt.replace(placeholder, value.as_deref().unwrap_or_default())
+        })
+    }
+}
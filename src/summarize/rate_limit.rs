@@ -0,0 +1,60 @@
+//! Limits of the pace of requests sent to the model. Runs of several sites summarized with one
+//! API key share a limiter, and limiters backed by a shared store like Redis can keep runs on
+//! several machines within the limit of the provider.
+
+use rate_guard::{RateLimit, StdTokenBucket, TokenBucketBuilder};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Future completing once a request may be sent
+pub type Acquire<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Paces the requests sent to the model.
+pub trait RateLimiter: Send + Sync + std::fmt::Debug {
+    /// Waits until a request may be sent, taking its slot.
+    fn acquire(&self) -> Acquire<'_>;
+}
+
+/// Limiter of a number of requests per minute, refilled one request at a time
+pub struct TokenBucketLimiter {
+    rpm: u32,
+    bucket: StdTokenBucket,
+}
+
+impl TokenBucketLimiter {
+    /// Creates a limiter allowing `rpm` requests per minute, None if the bucket can't be built.
+    pub fn per_minute(rpm: u32) -> Option<Self> {
+        let capacity = rpm.max(1) as u64;
+        let refill_interval = Duration::from_secs_f64(60.0 / capacity as f64);
+
+        TokenBucketBuilder::builder()
+            .capacity(capacity)
+            .refill_amount(1_u64)
+            .refill_every(refill_interval)
+            .with_time(rate_guard::StdTimeSource::new())
+            .with_precision::<rate_guard::Nanos>()
+            .build()
+            .ok()
+            .map(|bucket| Self { rpm, bucket })
+    }
+}
+
+impl RateLimiter for TokenBucketLimiter {
+    fn acquire(&self) -> Acquire<'_> {
+        Box::pin(async move {
+            while self.bucket.try_acquire(1).is_err() {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for TokenBucketLimiter {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("TokenBucketLimiter")
+            .field("rpm", &self.rpm)
+            .finish()
+    }
+}
@@ -0,0 +1,109 @@
+//! Structured summaries: the model answers with a JSON object holding the summary
+//! along with keywords and the audience of the page, stored in dedicated columns.
+
+use log::warn;
+use serde_json::Value;
+
+use super::{PromptVariables, SummarizeContext, summarize_page};
+use crate::{Error, Result};
+
+/// Number of times a page is summarized before a malformed answer fails the run.
+const MAX_STRUCTURED_ATTEMPTS: u32 = 3;
+
+/// A summary of a page with its keywords and audience
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StructuredSummary {
+    pub summary: String,
+    /// Topics of the page, e.g. "sitemap index", "robots.txt"
+    pub keywords: Vec<String>,
+    /// Who the page is written for, e.g. "web developers"
+    pub audience: String,
+}
+
+/// Summarizes a page asking the model for a JSON answer, and summarizes it again
+/// if the answer isn't a valid structured summary.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page
+/// * `text` - The content of the page
+/// * `variables` - Metadata of the page replacing placeholders of the prompt template
+/// * `ctx` - Context containing model, prompt template, rate limiter and stop sequences
+///
+/// # Returns
+///
+/// Returns the parsed structured summary
+///
+/// # Errors
+///
+/// Returns an error if LLM chat operation fails, or if every answer is malformed
+pub async fn summarize_page_structured(
+    url: &str,
+    text: &str,
+    variables: &PromptVariables,
+    ctx: &SummarizeContext<'_>,
+) -> Result<StructuredSummary, Error> {
+    let mut attempt = 1;
+    loop {
+        let response = summarize_page(url, text, variables, ctx).await?;
+        match parse_structured_summary(&response) {
+            Ok(summary) => return Ok(summary),
+            Err(error) if attempt < MAX_STRUCTURED_ATTEMPTS => {
+                warn!("Malformed structured summary of {url} (attempt {attempt}): {error:#}");
+                attempt += 1;
+            }
+            Err(error) => {
+                return Err(error.context(format!(
+                    "Malformed structured summary of {url} after {attempt} attempts"
+                )));
+            }
+        }
+    }
+}
+
+/// Parses and validates a structured summary answer: a JSON object, possibly in a code fence,
+/// with a non-empty "summary" string, a "keywords" array of strings and an "audience" string.
+///
+/// # Arguments
+///
+/// * `response` - The answer of the model
+///
+/// # Errors
+///
+/// Returns an error describing why the answer isn't a valid structured summary
+pub fn parse_structured_summary(response: &str) -> Result<StructuredSummary, Error> {
+    let json = response
+        .find('{')
+        .zip(response.rfind('}'))
+        .and_then(|(start, end)| response.get(start..=end))
+        .ok_or_else(|| Error::Llm("The answer has no JSON object".into()))?;
+    let value: Value = serde_json::from_str(json)
+        .map_err(|error| Error::Llm(error.into()).context("The answer isn't valid JSON"))?;
+
+    let summary = value
+        .get("summary")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|summary| !summary.is_empty())
+        .ok_or_else(|| Error::Llm("\"summary\" must be a non-empty string".into()))?;
+    let keywords = value
+        .get("keywords")
+        .and_then(Value::as_array)
+        .and_then(|keywords| {
+            keywords
+                .iter()
+                .map(|keyword| keyword.as_str().map(|keyword| keyword.trim().to_string()))
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| Error::Llm("\"keywords\" must be an array of strings".into()))?;
+    let audience = value
+        .get("audience")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::Llm("\"audience\" must be a string".into()))?;
+
+    Ok(StructuredSummary {
+        summary: summary.to_string(),
+        keywords,
+        audience: audience.trim().to_string(),
+    })
+}
@@ -0,0 +1,107 @@
+//! The throttle module adapts request pace to overload signals (HTTP 429/503
+//! and Retry-After headers) sent by scraped sites and LLM providers.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::time::Duration;
+
+/// Upper bound of the delay between requests in milliseconds.
+const MAX_DELAY: u64 = 60_000;
+
+/// Delay in milliseconds used when backing off from a zero configured delay.
+const MIN_BACKOFF_DELAY: u64 = 500;
+
+/// Upper bound of a single Retry-After pause.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Matches the 429 status of an error message, like `error status: 429`, but not 429
+/// within other numbers, e.g. of a token count.
+static RATE_LIMIT_STATUS_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"status\W{0,3}429").expect("Failed to compile rate limit status regex")
+});
+
+/// AdaptiveThrottle keeps the current crawl pace, halving it when the target
+/// signals overload and slowly ramping it back to the configured pace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdaptiveThrottle {
+    base_delay: u64,
+    base_concurrency: usize,
+    delay: u64,
+    concurrency: usize,
+}
+
+impl AdaptiveThrottle {
+    /// Creates a throttle starting at the configured delay (in milliseconds) and concurrency.
+    pub fn new(delay: u64, concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        Self {
+            base_delay: delay,
+            base_concurrency: concurrency,
+            delay,
+            concurrency,
+        }
+    }
+
+    /// Current delay between requests in milliseconds.
+    pub fn delay(&self) -> u64 {
+        self.delay
+    }
+
+    /// Current number of concurrent requests.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Returns `true` if the throttle runs slower than configured.
+    pub fn is_throttled(&self) -> bool {
+        self.delay > self.base_delay || self.concurrency < self.base_concurrency
+    }
+
+    /// Slows down after an overload signal: doubles the delay and halves the concurrency.
+    pub fn back_off(&mut self) {
+        self.delay = self
+            .delay
+            .saturating_mul(2)
+            .clamp(MIN_BACKOFF_DELAY, MAX_DELAY.max(self.base_delay));
+        self.concurrency = (self.concurrency / 2).max(1);
+    }
+
+    /// Speeds up after a round without overload signals: adds one concurrent
+    /// request and shortens the delay by a quarter, never exceeding the configured pace.
+    pub fn ramp_up(&mut self) {
+        self.concurrency = (self.concurrency + 1).min(self.base_concurrency);
+        self.delay = (self.delay * 3 / 4).max(self.base_delay);
+    }
+}
+
+/// Returns `true` for HTTP status codes signalling that the server is overloaded.
+pub fn is_overload_status(status: u16) -> bool {
+    status == 429 || status == 503
+}
+
+/// Returns `true` if an error message reports that the request was rate limited.
+pub fn is_rate_limit_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    RATE_LIMIT_STATUS_REGEX.is_match(&message)
+        || message.contains("too many requests")
+        || message.contains("rate limit")
+}
+
+/// Parses a Retry-After header value given either as seconds or as an HTTP date.
+///
+/// The resulting pause is capped to five minutes.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let duration = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => DateTime::parse_from_rfc2822(value)
+            .ok()?
+            .with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    };
+
+    Some(duration.min(MAX_RETRY_AFTER))
+}
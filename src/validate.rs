@@ -0,0 +1,208 @@
+//! The validate module checks a composed llms.txt file against the conventions of the spec:
+//! an H1 heading, a blockquote description, well-formed links and size limits.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+use url::Url;
+
+/// Matches a markdown link, capturing its text and URL (an optional title is skipped).
+pub(crate) static LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\[([^\]]*)\]\(([^)\s]*)[^)]*\)").expect("Failed to compile link regex")
+});
+
+/// Options of the checks performed on the file
+#[derive(Clone, Debug, Default)]
+pub struct ValidateOptions {
+    /// URL relative links are resolved against; relative links are reported without it
+    pub base_url: Option<Url>,
+    /// Maximal size of the file in bytes
+    pub max_bytes: Option<usize>,
+    /// Maximal number of links in the file
+    pub max_entries: Option<usize>,
+}
+
+/// A problem found in the file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// Number of the line with the problem starting from 1, `None` for problems of the whole file
+    pub line: Option<usize>,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl Finding {
+    fn at(line: usize, message: impl Into<String>) -> Self {
+        Finding {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+
+    fn file(message: impl Into<String>) -> Self {
+        Finding {
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(formatter, "line {line}: {}", self.message),
+            None => write!(formatter, "{}", self.message),
+        }
+    }
+}
+
+/// Checks an llms.txt file content and returns the problems found, file-level ones first.
+///
+/// Lines inside fenced code blocks are not checked.
+///
+/// # Arguments
+///
+/// * `content` - The content of the llms.txt file
+/// * `options` - Base URL of relative links and size limits
+pub fn validate(content: &str, options: &ValidateOptions) -> Vec<Finding> {
+    let lines = markdown_lines(content);
+
+    let mut findings = check_header(&lines);
+    let (link_findings, links) = check_links(&lines, options.base_url.as_ref());
+    findings.extend(link_findings);
+
+    if let Some(max_bytes) = options
+        .max_bytes
+        .filter(|max_bytes| content.len() > *max_bytes)
+    {
+        findings.push(Finding::file(format!(
+            "The file takes {} bytes, more than the limit of {max_bytes}",
+            content.len()
+        )));
+    }
+    if let Some(max_entries) = options
+        .max_entries
+        .filter(|max_entries| links > *max_entries)
+    {
+        findings.push(Finding::file(format!(
+            "The file has {links} links, more than the limit of {max_entries}"
+        )));
+    }
+
+    findings.sort_by_key(|finding| finding.line);
+    findings
+}
+
+/// Returns numbered lines of the content outside of fenced code blocks.
+fn markdown_lines(content: &str) -> Vec<(usize, &str)> {
+    let mut in_code_block = false;
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return None;
+            }
+            (!in_code_block).then_some((index + 1, line))
+        })
+        .collect()
+}
+
+/// Checks the file starts with a single H1 heading followed by a blockquote description.
+fn check_header(lines: &[(usize, &str)]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut content_lines = lines.iter().filter(|(_, line)| !line.trim().is_empty());
+
+    match content_lines.next() {
+        Some((_, line)) if is_h1(line) => {}
+        Some((number, _)) => findings.push(Finding::at(
+            *number,
+            "The file doesn't start with an H1 heading (\"# Site name\")",
+        )),
+        None => {
+            findings.push(Finding::file("The file is empty"));
+            return findings;
+        }
+    }
+    match content_lines.next() {
+        Some((_, line)) if line.starts_with('>') => {}
+        Some((number, _)) => findings.push(Finding::at(
+            *number,
+            "The heading isn't followed by a blockquote description (\"> ...\")",
+        )),
+        None => findings.push(Finding::file("The file has no blockquote description")),
+    }
+    for (number, _) in lines.iter().filter(|(_, line)| is_h1(line)).skip(1) {
+        findings.push(Finding::at(*number, "Only one H1 heading is allowed"));
+    }
+
+    findings
+}
+
+fn is_h1(line: &str) -> bool {
+    line.starts_with("# ")
+}
+
+/// Checks links of every line and returns the problems found along with the number of links.
+fn check_links(lines: &[(usize, &str)], base_url: Option<&Url>) -> (Vec<Finding>, usize) {
+    let mut findings = Vec::new();
+    let mut links = 0;
+
+    for (number, line) in lines {
+        for captures in LINK_REGEX.captures_iter(line) {
+            links += 1;
+            let text = captures.get(1).map_or("", |text| text.as_str());
+            let target = captures.get(2).map_or("", |target| target.as_str());
+            if text.trim().is_empty() {
+                findings.push(Finding::at(
+                    *number,
+                    format!("Link to {target} has no text"),
+                ));
+            }
+            if let Some(problem) = check_url(target, base_url) {
+                findings.push(Finding::at(*number, problem));
+            }
+        }
+
+        let list_item = line
+            .trim_start()
+            .strip_prefix("- ")
+            .or_else(|| line.trim_start().strip_prefix("* "));
+        if list_item.is_some_and(|item| {
+            item.starts_with('[') && !LINK_REGEX.find(item).is_some_and(|link| link.start() == 0)
+        }) {
+            findings.push(Finding::at(
+                *number,
+                "List item isn't a valid markdown link (\"- [title](url): notes\")",
+            ));
+        }
+    }
+
+    (findings, links)
+}
+
+/// Returns the problem of a link URL, if any.
+fn check_url(target: &str, base_url: Option<&Url>) -> Option<String> {
+    if target.is_empty() {
+        return Some("Link has an empty URL".to_string());
+    }
+
+    match Url::parse(target) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => None,
+        Ok(url) => Some(format!(
+            "Link {target} has an unsupported scheme {}",
+            url.scheme()
+        )),
+        Err(url::ParseError::RelativeUrlWithoutBase) => match base_url {
+            Some(base_url) => base_url
+                .join(target)
+                .err()
+                .map(|e| format!("Relative link {target} can't be resolved: {e}")),
+            None => Some(format!(
+                "Relative link {target} can't be resolved without a base URL"
+            )),
+        },
+        Err(e) => Some(format!("Link {target} is invalid: {e}")),
+    }
+}
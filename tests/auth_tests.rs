@@ -0,0 +1,29 @@
+use llamap::auth::Auth;
+use spectral::prelude::*;
+
+#[test]
+fn basic_credentials_are_sent_base64_encoded() {
+    let auth = Auth::basic("staging:s3cret:pass").expect("Valid credentials");
+
+    assert_that(&auth.header_value()).is_equal_to("Basic c3RhZ2luZzpzM2NyZXQ6cGFzcw==".to_string());
+    assert_that(&format!("{auth:?}")).is_equal_to("Basic(staging)".to_string());
+}
+
+#[test]
+fn basic_credentials_need_a_password() {
+    assert_that(&Auth::basic("staging").is_err()).is_true();
+}
+
+#[test]
+fn bearer_tokens_are_sent_as_is() {
+    let headers = Auth::Bearer("abc.def".into())
+        .headers()
+        .expect("Valid token");
+
+    assert_that(
+        &headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok()),
+    )
+    .is_equal_to(Some("Bearer abc.def"));
+}
@@ -0,0 +1,20 @@
+use llamap::parse::boilerplate::{find_boilerplate, strip_boilerplate};
+use spectral::prelude::*;
+
+const FOOTER: &str = "Subscribe to our newsletter to never miss an update!";
+
+#[test]
+fn blocks_repeated_across_pages_are_stripped() {
+    let texts = [
+        format!("# Pricing\n\nPlans start at $10 per month for small teams.\n\n{FOOTER}"),
+        format!("# About\n\nWe build tools for documentation writers.\n\n{FOOTER}"),
+        "# Blog\n\nRelease notes of the latest version are out.".to_string(),
+    ];
+
+    let boilerplate = find_boilerplate(texts.iter().map(String::as_str), 2);
+
+    assert_that(&boilerplate.len()).is_equal_to(1);
+    let pricing = texts.first().expect("Pricing page text");
+    assert_that(&strip_boilerplate(pricing, &boilerplate))
+        .is_equal_to("# Pricing\n\nPlans start at $10 per month for small teams.".to_string());
+}
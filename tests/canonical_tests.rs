@@ -0,0 +1,41 @@
+use llamap::TrailingSlash;
+use llamap::canonical::normalize_url_str;
+use spectral::prelude::*;
+
+#[test]
+fn fragments_tracking_params_and_default_ports_are_stripped() {
+    assert_that(&normalize_url_str(
+        "https://Example.com:443/page?utm_source=news&id=1&fbclid=abc#top",
+        &TrailingSlash::Keep,
+    ))
+    .is_equal_to("https://example.com/page?id=1".to_string());
+    assert_that(&normalize_url_str(
+        "https://example.com/page?utm_medium=email",
+        &TrailingSlash::Keep,
+    ))
+    .is_equal_to("https://example.com/page".to_string());
+}
+
+#[test]
+fn trailing_slash_policy_is_applied() {
+    assert_that(&normalize_url_str(
+        "https://example.com/page/",
+        &TrailingSlash::Strip,
+    ))
+    .is_equal_to("https://example.com/page".to_string());
+    assert_that(&normalize_url_str(
+        "https://example.com/",
+        &TrailingSlash::Strip,
+    ))
+    .is_equal_to("https://example.com/".to_string());
+    assert_that(&normalize_url_str(
+        "https://example.com/page",
+        &TrailingSlash::Add,
+    ))
+    .is_equal_to("https://example.com/page/".to_string());
+    assert_that(&normalize_url_str(
+        "https://example.com/file.pdf",
+        &TrailingSlash::Add,
+    ))
+    .is_equal_to("https://example.com/file.pdf".to_string());
+}
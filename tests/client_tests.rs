@@ -0,0 +1,768 @@
+use chrono::Utc;
+use llamap::parse::ParseOptions;
+use llamap::storage::Page;
+use llamap::summarize::{SummarizeOptions, SummaryModel, TextRankSummarizer};
+use llamap::{
+    CancellationToken, ComposeOptions, ContentFilter, Error, FailureReason, Llamap, NoindexPages,
+    ParseTarget, Progress, ProgressReporter, ScrapeOptions, ScrapeReport, SummarizeTarget, TextBy,
+    TrailingSlash,
+};
+use spectral::prelude::*;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+#[tokio::test]
+async fn pipeline_runs_share_the_client_database() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/sitemaps").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: String::new(),
+            title: Some("Sitemaps".to_string()),
+            text: Some("Sitemaps list pages of a site for search engines.".to_string()),
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+
+    client
+        .summarize(
+            SummaryModel::TextRank(TextRankSummarizer::default()),
+            SummarizeOptions {
+                model_name: "local://textrank".to_string(),
+                ..SummarizeOptions::default()
+            },
+        )
+        .await
+        .expect("Summarized pages");
+
+    let page = client
+        .storage()
+        .get_page("https://example.com/sitemaps")
+        .expect("Readable page")
+        .expect("Stored page");
+    assert_that(&page.summary).is_equal_to(Some(
+        "Sitemaps list pages of a site for search engines.".to_string(),
+    ));
+    let runs = client.storage().list_runs(10).expect("Listed runs");
+    assert_that(
+        &runs
+            .iter()
+            .map(|run| run.pages_affected)
+            .collect::<Vec<_>>(),
+    )
+    .is_equal_to(vec![Some(1)]);
+}
+
+#[tokio::test]
+async fn failures_are_reported_by_category() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+
+    let unknown_model = SummaryModel::local("local://nope");
+    let prompted_textrank = client
+        .summarize(
+            SummaryModel::TextRank(TextRankSummarizer::default()),
+            SummarizeOptions {
+                prompt_template: Some("Summarize {text}".to_string()),
+                ..SummarizeOptions::default()
+            },
+        )
+        .await;
+    let unreadable_list = client
+        .scrape_url_list("/nonexistent/urls.txt", Default::default())
+        .await;
+
+    assert_that(&matches!(unknown_model, Err(Error::Config(_)))).is_true();
+    assert_that(&matches!(prompted_textrank, Err(Error::Config(_)))).is_true();
+    assert_that(&matches!(unreadable_list, Err(Error::Io(_)))).is_true();
+}
+
+#[tokio::test]
+async fn pages_are_summarized_while_scraping_only_once_parsed() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    let sitemap_url = Url::parse("https://example.com/sitemap.xml").expect("Valid sitemap URL");
+
+    let unparsed = client
+        .scrape_and_summarize(
+            sitemap_url.clone(),
+            ScrapeOptions::default(),
+            SummaryModel::TextRank(TextRankSummarizer::default()),
+            SummarizeOptions::default(),
+        )
+        .await;
+    let single_page = client
+        .scrape_and_summarize(
+            sitemap_url,
+            ScrapeOptions {
+                parse: Some(ParseOptions::default()),
+                ..ScrapeOptions::default()
+            },
+            SummaryModel::TextRank(TextRankSummarizer::default()),
+            SummarizeOptions {
+                target: SummarizeTarget::Page {
+                    url: "https://example.com/".to_string(),
+                },
+                ..SummarizeOptions::default()
+            },
+        )
+        .await;
+
+    assert_that(&matches!(unparsed, Err(Error::Config(_)))).is_true();
+    assert_that(&matches!(single_page, Err(Error::Config(_)))).is_true();
+    assert_that(&client.storage().list_runs(10).map(|runs| runs.len()).ok()).is_equal_to(Some(0));
+}
+
+#[tokio::test]
+async fn cancelled_runs_stop_before_the_next_page() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/sitemaps").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: "<html><body><p>Sitemaps list pages of a site.</p></body></html>".to_string(),
+            title: None,
+            text: None,
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+
+    client
+        .parse(
+            ParseTarget::All,
+            &ParseOptions {
+                cancel: Some(cancel),
+                ..ParseOptions::default()
+            },
+        )
+        .await
+        .expect("Cancelled parse");
+
+    let page = client
+        .storage()
+        .get_page("https://example.com/sitemaps")
+        .expect("Readable page")
+        .expect("Stored page");
+    assert_that(&page.text).is_none();
+    let runs = client.storage().list_runs(10).expect("Listed runs");
+    assert_that(&runs.iter().map(|run| run.error.clone()).collect::<Vec<_>>())
+        .is_equal_to(vec![Some("Interrupted".to_string())]);
+}
+
+/// Records reported progress as lines like "summarized https://example.com/sitemaps".
+#[derive(Default)]
+struct RecordingReporter {
+    events: Mutex<Vec<String>>,
+}
+
+impl ProgressReporter for RecordingReporter {
+    fn on_page_summarized(&self, url: &str) {
+        self.events
+            .lock()
+            .expect("Events mutex")
+            .push(format!("summarized {url}"));
+    }
+
+    fn on_stage_complete(&self, stage: &str, pages: usize) {
+        self.events
+            .lock()
+            .expect("Events mutex")
+            .push(format!("{stage} completed with {pages} pages"));
+    }
+}
+
+#[tokio::test]
+async fn progress_is_reported_page_by_page() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/sitemaps").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: String::new(),
+            title: None,
+            text: Some("Sitemaps list pages of a site for search engines.".to_string()),
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+    let reporter = Arc::new(RecordingReporter::default());
+
+    client
+        .summarize(
+            SummaryModel::TextRank(TextRankSummarizer::default()),
+            SummarizeOptions {
+                progress: Progress::new(reporter.clone()),
+                ..SummarizeOptions::default()
+            },
+        )
+        .await
+        .expect("Summarized pages");
+
+    assert_that(&*reporter.events.lock().expect("Events mutex")).is_equal_to(vec![
+        "summarized https://example.com/sitemaps".to_string(),
+        "summarize completed with 1 pages".to_string(),
+    ]);
+}
+
+#[tokio::test]
+async fn scrape_reports_pages_removed_from_the_list() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/gone").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: String::new(),
+            title: None,
+            text: None,
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+    let list_path = std::env::temp_dir().join("llamap_empty_url_list.txt");
+    std::fs::write(&list_path, "# Nothing is listed anymore\n").expect("Written URL list");
+
+    let report = client
+        .scrape_url_list(
+            list_path.to_str().expect("UTF-8 path"),
+            ScrapeOptions::default(),
+        )
+        .await
+        .expect("Scraped URL list");
+
+    assert_that(&report).is_equal_to(ScrapeReport {
+        removed: 1,
+        ..ScrapeReport::default()
+    });
+    assert_that(
+        &client
+            .storage()
+            .get_page("https://example.com/gone")
+            .expect("Readable page"),
+    )
+    .is_none();
+}
+
+/// Serves an HTML page at every path of a local port, except robots.txt, and returns its address.
+fn serve_html_pages() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Bound local port");
+    let address = format!(
+        "http://{}",
+        listener.local_addr().expect("Local server address")
+    );
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut reader = BufReader::new(stream.try_clone().expect("Cloned stream"));
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                line.clear();
+            }
+            let (status, body) = if request_line.contains("/robots.txt") {
+                ("404 Not Found", String::new())
+            } else {
+                (
+                    "200 OK",
+                    format!("<html><body><p>Page served for {request_line}</p></body></html>"),
+                )
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    address
+}
+
+#[tokio::test]
+async fn pages_left_unstored_by_a_storage_failure_are_reported_failed() {
+    let db_path = std::env::temp_dir().join("llamap_storage_failure.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+    let client = Llamap::open(db_path).expect("Database");
+    rusqlite::Connection::open(db_path)
+        .expect("Second connection")
+        .execute(
+            "CREATE TRIGGER fail_page BEFORE INSERT ON pages WHEN NEW.url LIKE '%/page-3'
+             BEGIN SELECT RAISE(ABORT, 'disk is full'); END",
+            [],
+        )
+        .expect("Created failing trigger");
+    let server_url = serve_html_pages();
+    let urls: Vec<String> = (0..10)
+        .map(|index| format!("{server_url}/page-{index}"))
+        .collect();
+    let list_path = std::env::temp_dir().join("llamap_storage_failure_urls.txt");
+    std::fs::write(&list_path, urls.join("\n")).expect("Written URL list");
+
+    let report = client
+        .scrape_url_list(
+            list_path.to_str().expect("UTF-8 path"),
+            ScrapeOptions {
+                parse: Some(ParseOptions::default()),
+                ..ScrapeOptions::default()
+            },
+        )
+        .await
+        .expect("Scraped URL list");
+
+    assert_that(&(report.fetched + report.failed.len())).is_equal_to(urls.len());
+    assert_that(
+        &report
+            .failed
+            .iter()
+            .all(|(_, reason)| *reason == FailureReason::Storage),
+    )
+    .is_true();
+    assert_that(
+        &report
+            .failed
+            .iter()
+            .any(|(url, _)| url.as_str() == format!("{server_url}/page-3")),
+    )
+    .is_true();
+}
+
+#[tokio::test]
+async fn noindex_pages_are_left_out_unless_included() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    let pages = [
+        ("https://example.com/guide", None, ""),
+        ("https://example.com/drafts", Some("googlebot: noindex"), ""),
+        (
+            "https://example.com/search",
+            None,
+            r#"<meta name="Robots" content="NOINDEX, follow">"#,
+        ),
+    ];
+    for (url, robots_tag, head) in pages {
+        client
+            .storage()
+            .upsert_page(&Page {
+                url: Url::parse(url).expect("Valid page URL"),
+                added_at: Utc::now(),
+                lastmod: Utc::now(),
+                html: format!("<html><head>{head}</head><body><p>Text of {url}</p></body></html>"),
+                title: None,
+                text: None,
+                summary: None,
+                content_type: None,
+                content: None,
+                robots_tag: robots_tag.map(str::to_string),
+                html_hash: None,
+            })
+            .expect("Stored page");
+    }
+    client
+        .parse(
+            ParseTarget::All,
+            &ParseOptions {
+                text_by: TextBy::FastHtml2Md,
+                ..ParseOptions::default()
+            },
+        )
+        .await
+        .expect("Parsed pages");
+    let output_path = std::env::temp_dir().join("llamap_noindex.llms.txt");
+    let output_path = output_path.to_str().expect("UTF-8 path");
+
+    client
+        .compose(output_path, ComposeOptions::default(), None)
+        .await
+        .expect("Composed pages");
+    let composed = std::fs::read_to_string(output_path).expect("Composed file");
+    assert_that(&composed).contains("## https://example.com/guide");
+    assert_that(&composed.contains("https://example.com/drafts")).is_false();
+    assert_that(&composed.contains("https://example.com/search")).is_false();
+
+    client
+        .compose(
+            output_path,
+            ComposeOptions {
+                noindex: NoindexPages::Include,
+                ..ComposeOptions::default()
+            },
+            None,
+        )
+        .await
+        .expect("Composed pages");
+    let composed = std::fs::read_to_string(output_path).expect("Composed file");
+    assert_that(&composed).contains("## https://example.com/drafts");
+    assert_that(&composed).contains("## https://example.com/search");
+}
+
+#[tokio::test]
+async fn binary_documents_are_not_scraped() {
+    let filter = ContentFilter::default();
+    assert_that(&filter.skips_url("https://example.com/assets/logo.PNG")).is_true();
+    assert_that(&filter.skips_url("https://example.com/docs/v1.2/install")).is_false();
+    assert_that(&filter.skips_content_type("Image/webp")).is_true();
+    assert_that(&filter.skips_content_type("text/html; charset=utf-8")).is_false();
+
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    let list_path = std::env::temp_dir().join("llamap_binary_url_list.txt");
+    std::fs::write(
+        &list_path,
+        "https://example.com/logo.png\nhttps://example.com/intro.mp4\nhttps://example.com/site.zip\n",
+    )
+    .expect("Written URL list");
+
+    let report = client
+        .scrape_url_list(
+            list_path.to_str().expect("UTF-8 path"),
+            ScrapeOptions::default(),
+        )
+        .await
+        .expect("Scraped URL list");
+
+    assert_that(&report).is_equal_to(ScrapeReport {
+        skipped_filtered: 3,
+        ..ScrapeReport::default()
+    });
+}
+
+#[tokio::test]
+async fn fetched_pages_are_filtered_and_recorded_as_runs() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+
+    let report = client
+        .fetch(
+            &Url::parse("https://example.com/release.zip").expect("Valid URL"),
+            ScrapeOptions::default(),
+        )
+        .await
+        .expect("Fetched page");
+
+    assert_that(&report).is_equal_to(ScrapeReport {
+        skipped_filtered: 1,
+        ..ScrapeReport::default()
+    });
+    let runs = client.storage().list_runs(10).expect("Listed runs");
+    assert_that(
+        &runs
+            .iter()
+            .map(|run| run.command.as_str())
+            .collect::<Vec<_>>(),
+    )
+    .is_equal_to(vec!["fetch"]);
+}
+
+#[tokio::test]
+async fn long_pages_are_split_into_sections_listed_by_compose() {
+    use llamap::{ComposeFormat, SectionLinks};
+
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/reference").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: r#"<html><body><h1>Reference</h1><p>Everything about the tool.</p>
+                <h2 id="install">Install</h2><p>Run the installer.</p>
+                <h2>Unanchored</h2><p>Still about installing.</p>
+                <h2 id="usage">Usage</h2><p>Run the tool.</p></body></html>"#
+                .to_string(),
+            title: Some("Reference".to_string()),
+            text: None,
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+    let options = ParseOptions {
+        text_by: TextBy::FastHtml2Md,
+        split_sections_chars: Some(20),
+        ..ParseOptions::default()
+    };
+    client
+        .parse(ParseTarget::All, &options)
+        .await
+        .expect("Parsed pages");
+    client
+        .storage()
+        .update_section_summary(
+            "https://example.com/reference",
+            "install",
+            "How to install.",
+        )
+        .expect("Stored section summary");
+    client
+        .parse(ParseTarget::All, &options)
+        .await
+        .expect("Parsed pages again");
+
+    let sections = client
+        .storage()
+        .list_page_sections("https://example.com/reference")
+        .expect("Listed sections");
+    assert_that(
+        &sections
+            .iter()
+            .map(|section| section.anchor.as_str())
+            .collect::<Vec<_>>(),
+    )
+    .is_equal_to(vec!["install", "usage"]);
+    assert_that(
+        &sections
+            .first()
+            .map(|section| section.text.contains("Still about installing")),
+    )
+    .is_equal_to(Some(true));
+
+    let output_path = std::env::temp_dir().join("llamap_sections.llms.txt");
+    let output_path = output_path.to_str().expect("UTF-8 path");
+    client
+        .compose(
+            output_path,
+            ComposeOptions {
+                format: ComposeFormat::Spec,
+                sections: SectionLinks::Listed,
+                ..ComposeOptions::default()
+            },
+            None,
+        )
+        .await
+        .expect("Composed pages");
+    let composed = std::fs::read_to_string(output_path).expect("Composed file");
+    assert_that(&composed).contains(
+        "  - [Install](https://example.com/reference#install): How to install.\n  - [Usage](https://example.com/reference#usage)\n",
+    );
+}
+
+#[tokio::test]
+async fn parsed_links_score_the_importance_of_pages() {
+    use llamap::PageSort;
+    use llamap::storage::{PageFilter, PageSelection};
+
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    for (path, links) in [
+        (
+            "/a",
+            r##"<a href="/guide#install">Guide</a> <a href="https://other.com/">Other</a>"##,
+        ),
+        (
+            "/b",
+            r##"<a href="guide?utm_source=b">Guide</a> <a href="#top">Top</a>"##,
+        ),
+        (
+            "/guide",
+            r##"<a href="/a">A</a> <a href="/missing">Missing</a>"##,
+        ),
+        ("/z", r##"<a href="/guide">Guide</a>"##),
+    ] {
+        client
+            .storage()
+            .upsert_page(&Page {
+                url: Url::parse(&format!("https://example.com{path}")).expect("Valid page URL"),
+                added_at: Utc::now(),
+                lastmod: Utc::now(),
+                html: format!("<html><body><p>Page {path}.</p>{links}</body></html>"),
+                title: None,
+                text: None,
+                summary: None,
+                content_type: None,
+                content: None,
+                robots_tag: None,
+                html_hash: None,
+            })
+            .expect("Stored page");
+    }
+
+    client
+        .parse(
+            ParseTarget::All,
+            &ParseOptions {
+                text_by: TextBy::FastHtml2Md,
+                ..ParseOptions::default()
+            },
+        )
+        .await
+        .expect("Parsed pages");
+    client.storage().update_importance().expect("Scored pages");
+
+    assert_that(
+        &client
+            .storage()
+            .list_page_links("https://example.com/b")
+            .expect("Listed links"),
+    )
+    .is_equal_to(vec!["https://example.com/guide".to_string()]);
+    let urls = client
+        .storage()
+        .list_sorted_urls(
+            PageSelection::All,
+            &PageFilter::new(),
+            &PageSort::Importance,
+        )
+        .expect("Sorted URLs");
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/guide".to_string(),
+        "https://example.com/a".to_string(),
+        "https://example.com/b".to_string(),
+        "https://example.com/z".to_string(),
+    ]);
+}
+
+#[tokio::test]
+async fn plain_text_pages_are_parsed_as_served() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/notes.md").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: "# Release notes\n\nVersion 2 adds *feeds*.\n".to_string(),
+            title: None,
+            text: None,
+            summary: None,
+            content_type: Some("text/markdown; charset=utf-8".to_string()),
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+
+    client
+        .parse(ParseTarget::All, &ParseOptions::default())
+        .await
+        .expect("Parsed pages");
+
+    let page = client
+        .storage()
+        .get_page("https://example.com/notes.md")
+        .expect("Readable page")
+        .expect("Stored page");
+    assert_that(&page.title).is_equal_to(Some("Release notes".to_string()));
+    assert_that(&page.text).is_equal_to(Some(
+        "# Release notes\n\nVersion 2 adds *feeds*.".to_string(),
+    ));
+}
+
+#[tokio::test]
+async fn canonical_pages_keep_their_listed_url_as_a_redirect() {
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/old-guide").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: Utc::now(),
+            html: r#"<html><head><link rel="canonical" href="/guide/"></head><body><p>Guide</p></body></html>"#.to_string(),
+            title: None,
+            text: None,
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+
+    client
+        .parse(
+            ParseTarget::All,
+            &ParseOptions {
+                text_by: TextBy::FastHtml2Md,
+                trailing_slash: TrailingSlash::Strip,
+                ..ParseOptions::default()
+            },
+        )
+        .await
+        .expect("Parsed pages");
+
+    let storage = client.storage();
+    assert_that(&storage.list_urls().expect("Stored URLs"))
+        .is_equal_to(vec!["https://example.com/guide".to_string()]);
+    assert_that(
+        &storage
+            .get_lastmod("https://example.com/old-guide")
+            .expect("Readable lastmod"),
+    )
+    .is_some();
+}
+
+#[tokio::test]
+async fn boilerplate_found_by_a_run_is_stripped_by_later_runs() {
+    const FOOTER: &str = "Subscribe to our newsletter to never miss an update!";
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    let store = |path: &str| {
+        client
+            .storage()
+            .upsert_page(&Page {
+                url: Url::parse(&format!("https://example.com/{path}")).expect("Valid page URL"),
+                added_at: Utc::now(),
+                lastmod: Utc::now(),
+                html: format!("<html><body><p>Page about {path}.</p><p>{FOOTER}</p></body></html>"),
+                title: None,
+                text: None,
+                summary: None,
+                content_type: None,
+                content: None,
+                robots_tag: None,
+                html_hash: None,
+            })
+            .expect("Stored page");
+    };
+    let options = ParseOptions {
+        text_by: TextBy::FastHtml2Md,
+        boilerplate_min_pages: Some(2),
+        ..ParseOptions::default()
+    };
+
+    store("pricing");
+    store("about");
+    client
+        .parse(ParseTarget::All, &options)
+        .await
+        .expect("Parsed pages");
+    store("blog");
+    client
+        .parse(ParseTarget::from("https://example.com/blog"), &options)
+        .await
+        .expect("Parsed page");
+
+    for url in ["https://example.com/about", "https://example.com/blog"] {
+        let text = client
+            .storage()
+            .get_page_text(url)
+            .expect("Readable text")
+            .expect("Parsed text");
+        assert_that(&text.contains(FOOTER)).is_false();
+    }
+}
@@ -0,0 +1,66 @@
+use llamap::robots::parse_robots_txt;
+use llamap::storage::{Page, Storage};
+use spectral::prelude::*;
+use url::Url;
+
+#[test]
+fn longest_robots_txt_rule_decides_whether_urls_are_allowed() {
+    let robots = parse_robots_txt(
+        "User-agent: Googlebot\n\
+         Disallow: /\n\
+         \n\
+         User-agent: *\n\
+         Disallow: /private\n\
+         Allow: /private/press\n\
+         Disallow: /*.pdf$\n\
+         Disallow: /search?\n\
+         Disallow:\n",
+    );
+    let is_allowed = |url: &str| robots.is_allowed(&Url::parse(url).expect("Valid URL"));
+
+    assert_that(&robots.rules.len()).is_equal_to(4);
+    assert_that(&is_allowed("https://example.com/")).is_true();
+    assert_that(&is_allowed("https://example.com/private/team")).is_false();
+    assert_that(&is_allowed("https://example.com/private/press/launch")).is_true();
+    assert_that(&is_allowed("https://example.com/docs/manual.pdf")).is_false();
+    assert_that(&is_allowed("https://example.com/docs/manual.pdf?download")).is_true();
+    assert_that(&is_allowed("https://example.com/search?q=llms")).is_false();
+}
+
+#[test]
+fn linked_and_failed_urls_without_a_page_are_unstored() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    storage
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/").expect("Valid URL"),
+            added_at: chrono::Utc::now(),
+            lastmod: chrono::Utc::now(),
+            html: String::new(),
+            title: None,
+            text: Some("Home".to_string()),
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+    storage
+        .replace_page_links(
+            "https://example.com/",
+            &[
+                "https://example.com/".to_string(),
+                "https://example.com/private/team".to_string(),
+            ],
+        )
+        .expect("Stored links");
+    let run_id = storage.start_run("scrape", "").expect("Started run");
+    storage
+        .record_failure(run_id, "scrape", "https://example.com/gone", "status 404")
+        .expect("Recorded failure");
+
+    assert_that(&storage.list_unstored_urls().expect("Listed URLs")).is_equal_to(vec![
+        "https://example.com/gone".to_string(),
+        "https://example.com/private/team".to_string(),
+    ]);
+}
@@ -2,6 +2,9 @@ use llm::{
     chat::{ChatMessage, ChatProvider, ChatResponse, Tool},
     error::LLMError,
 };
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
 
 #[macro_export]
 macro_rules! assert_responses {
@@ -17,8 +20,13 @@ macro_rules! assert_responses {
                     model: &StubLlmProvider::new($response.to_owned()),
                     prompt_template: None,
                     rate_limiter: None,
+                    stop: &[],
+                    structured: false,
+                    rules: &[],
+                    language: None,
+                    faq: &llamap::FaqPages::Ignored,
                 };
-                let result = llamap::summarize::summarize_page("", "", &context)
+                let result = llamap::summarize::summarize_page("", "", &Default::default(), &context)
                     .await
                     .expect("Expected successful processing.");
 
@@ -106,3 +114,49 @@ impl ChatProvider for StubLlmProvider {
         panic!()
     }
 }
+
+/// Serves the Ollama chat API on a local port, answering every request with the given text.
+/// Returns the server address and the bodies of the requests it received.
+pub(crate) fn serve_ollama_chat(answer: &str) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Bound local port");
+    let address = format!(
+        "http://{}",
+        listener.local_addr().expect("Local server address")
+    );
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&requests);
+    let response = serde_json::json!({ "message": { "content": answer } }).to_string();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut reader = BufReader::new(stream.try_clone().expect("Cloned stream"));
+            let mut content_length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap_or_default();
+                }
+                line.clear();
+            }
+            let mut body = vec![0; content_length];
+            if reader.read_exact(&mut body).is_ok() {
+                received
+                    .lock()
+                    .expect("Requests mutex")
+                    .push(String::from_utf8_lossy(&body).into_owned());
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                response.len()
+            );
+        }
+    });
+
+    (address, requests)
+}
@@ -0,0 +1,43 @@
+use llamap::diff::{EntryDiff, FileEntry, diff_entries, parse_entries};
+use spectral::prelude::*;
+
+#[test]
+fn entries_are_parsed_from_both_layouts() {
+    let pages = "## [Install](https://example.com/install)\nRun it.\n\n```\n## Not a heading\n```\n\n## https://example.com/usage\nUse it.\n";
+    let spec = "# example.com\n\n> Example site.\n\n## Docs\n\n- [Install](https://example.com/install): Run it.\n";
+
+    assert_that(&parse_entries(pages)).is_equal_to(vec![
+        FileEntry {
+            url: "https://example.com/install".to_string(),
+            title: "Install".to_string(),
+            content: "Run it.\n\n```\n## Not a heading\n```".to_string(),
+        },
+        FileEntry {
+            url: "https://example.com/usage".to_string(),
+            title: String::new(),
+            content: "Use it.".to_string(),
+        },
+    ]);
+    assert_that(&parse_entries(spec)).is_equal_to(vec![FileEntry {
+        url: "https://example.com/install".to_string(),
+        title: "Install".to_string(),
+        content: "Run it.".to_string(),
+    }]);
+}
+
+#[test]
+fn entries_are_compared_by_url() {
+    let old = parse_entries(
+        "- [A](https://example.com/a): Same\n- [B](https://example.com/b): Old\n- [C](https://example.com/c): Gone\n",
+    );
+    let new = parse_entries(
+        "- [A](https://example.com/a): Same\n- [B](https://example.com/b): New\n- [D](https://example.com/d): Added\n",
+    );
+
+    assert_that(&diff_entries(&old, &new)).is_equal_to(EntryDiff {
+        added: vec!["https://example.com/d".to_string()],
+        removed: vec!["https://example.com/c".to_string()],
+        changed: vec!["https://example.com/b".to_string()],
+        unchanged: 1,
+    });
+}
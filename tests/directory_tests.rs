@@ -0,0 +1,119 @@
+use llamap::scrape::resolve_file_url;
+use spectral::prelude::*;
+use std::path::Path;
+use url::Url;
+
+fn resolve(base_url: &str, relative_path: &str) -> String {
+    resolve_file_url(
+        &Url::parse(base_url).expect("Valid base URL"),
+        Path::new(relative_path),
+    )
+    .expect("Resolvable file URL")
+    .to_string()
+}
+
+#[test]
+fn index_files_are_mapped_to_their_directory() {
+    assert_that(&resolve("https://example.com", "index.html"))
+        .is_equal_to("https://example.com/".to_string());
+    assert_that(&resolve("https://example.com", "docs/intro/index.html"))
+        .is_equal_to("https://example.com/docs/intro/".to_string());
+}
+
+#[test]
+fn files_are_mapped_relative_to_base_url_path() {
+    assert_that(&resolve("https://example.com/site", "blog/post.html"))
+        .is_equal_to("https://example.com/site/blog/post.html".to_string());
+    assert_that(&resolve("https://example.com/site/", "my page.htm"))
+        .is_equal_to("https://example.com/site/my%20page.htm".to_string());
+}
+
+#[tokio::test]
+async fn ingested_pages_are_parsed_on_scrape() {
+    use llamap::TextBy;
+    use llamap::parse::ParseOptions;
+    use llamap::scrape::{ScrapeOptions, process_directory};
+    use llamap::storage::Storage;
+
+    let site_dir = std::env::temp_dir().join("llamap_parse_on_scrape_site");
+    std::fs::create_dir_all(&site_dir).expect("Created site directory");
+    std::fs::write(
+        site_dir.join("index.html"),
+        "<html><head><title>Home</title></head><body><p>Welcome to the site.</p></body></html>",
+    )
+    .expect("Written page");
+    let db_path = std::env::temp_dir().join("llamap_parse_on_scrape.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+
+    process_directory(
+        &site_dir,
+        &Url::parse("https://example.com").expect("Valid base URL"),
+        db_path,
+        ScrapeOptions {
+            parse: Some(ParseOptions {
+                text_by: TextBy::FastHtml2Md,
+                ..ParseOptions::default()
+            }),
+            ..ScrapeOptions::default()
+        },
+    )
+    .await
+    .expect("Ingested directory");
+
+    let page = Storage::new(db_path)
+        .expect("Database")
+        .get_page("https://example.com/")
+        .expect("Readable page")
+        .expect("Stored page");
+    assert_that(&page.title).is_equal_to(Some("Home".to_string()));
+    assert_that(&page.text.unwrap_or_default().as_str()).contains("Welcome to the site.");
+}
+
+#[tokio::test]
+async fn pages_scraped_without_html_keep_their_text() {
+    use llamap::parse::{ParseOptions, parse_db_html};
+    use llamap::scrape::{ScrapeOptions, process_directory};
+    use llamap::storage::Storage;
+    use llamap::{ParseTarget, TextBy};
+
+    let site_dir = std::env::temp_dir().join("llamap_no_store_html_site");
+    std::fs::create_dir_all(&site_dir).expect("Created site directory");
+    std::fs::write(
+        site_dir.join("index.html"),
+        "<html><head><title>Home</title></head><body><p>Welcome to the site.</p></body></html>",
+    )
+    .expect("Written page");
+    let db_path = std::env::temp_dir().join("llamap_no_store_html.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+    let options = ParseOptions {
+        text_by: TextBy::FastHtml2Md,
+        ..ParseOptions::default()
+    };
+
+    process_directory(
+        &site_dir,
+        &Url::parse("https://example.com").expect("Valid base URL"),
+        db_path,
+        ScrapeOptions {
+            parse: Some(options.clone()),
+            store_html: false,
+            ..ScrapeOptions::default()
+        },
+    )
+    .await
+    .expect("Ingested directory");
+    parse_db_html(db_path, ParseTarget::All, &options)
+        .await
+        .expect("Parsed pages");
+
+    let page = Storage::new(db_path)
+        .expect("Database")
+        .get_page("https://example.com/")
+        .expect("Readable page")
+        .expect("Stored page");
+    assert_that(&page.html.as_str()).is_empty();
+    assert_that(&page.html_hash).is_some();
+    assert_that(&page.text.unwrap_or_default().as_str()).contains("Welcome to the site.");
+}
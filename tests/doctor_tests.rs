@@ -0,0 +1,37 @@
+use llamap::doctor::{Check, check_database, check_prompt_template};
+use llamap::storage::Storage;
+use spectral::prelude::*;
+
+#[test]
+fn databases_are_checked_without_creating_missing_ones() {
+    let db_path = std::env::temp_dir().join("llamap_doctor.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+
+    let missing = check_database(db_path);
+    assert_that(&missing.is_passed()).is_false();
+    assert_that(&std::path::Path::new(db_path).exists()).is_false();
+
+    Storage::new(db_path).expect("Created database");
+    assert_that(&check_database(db_path).to_string())
+        .is_equal_to(format!("ok   database {db_path}: integrity check passed"));
+}
+
+#[test]
+fn prompt_templates_with_unknown_placeholders_fail_the_check() {
+    let check = check_prompt_template(
+        "prompt.txt",
+        "Summarize {titel} in {lang}, by {autor} and {autor}: {text}",
+    );
+
+    assert_that(&check.is_passed()).is_false();
+    assert_that(&check.to_string()).contains("unknown placeholders {titel}, {autor}, use {url}");
+    assert_that(&check_prompt_template(
+        "prompt.txt",
+        "Summarize {title}:\n{text}",
+    ))
+    .is_equal_to(Check::passed(
+        "prompt template prompt.txt",
+        "placeholders are known",
+    ));
+}
@@ -0,0 +1,33 @@
+use llamap::environment::{find_api_key, interpolate_env, provider_api_key_env_name};
+use llamap::secrets::Secret;
+use spectral::prelude::*;
+
+#[test]
+fn hosted_providers_have_their_usual_api_key_variable() {
+    assert_that(&provider_api_key_env_name("openai")).is_equal_to(Some("OPENAI_API_KEY"));
+    assert_that(&provider_api_key_env_name("anthropic")).is_equal_to(Some("ANTHROPIC_API_KEY"));
+    assert_that(&provider_api_key_env_name("ollama")).is_none();
+    assert_that(&provider_api_key_env_name("local")).is_none();
+}
+
+#[test]
+fn custom_api_key_variable_must_be_set() {
+    // SAFETY: the variable is only used by this test
+    unsafe { std::env::set_var("LLAMAP_TEST_CUSTOM_KEY", "secret") };
+
+    let key = find_api_key("openai", Some("LLAMAP_TEST_CUSTOM_KEY")).expect("key is set");
+    assert_that(&key).is_equal_to(Some(("LLAMAP_TEST_CUSTOM_KEY", Secret::from("secret"))));
+    assert_that(&find_api_key("openai", Some("LLAMAP_TEST_UNSET_KEY")).is_err()).is_true();
+}
+
+#[test]
+fn env_references_are_replaced_by_their_values() {
+    // SAFETY: the variable is only used by this test
+    unsafe { std::env::set_var("LLAMAP_TEST_DEPLOYMENT", "eu-west") };
+
+    let value = interpolate_env("deployment-${LLAMAP_TEST_DEPLOYMENT}").expect("variable is set");
+    assert_that(&value).is_equal_to("deployment-eu-west".to_string());
+    assert_that(&interpolate_env("no $reference {here}").ok())
+        .is_equal_to(Some("no $reference {here}".to_string()));
+    assert_that(&interpolate_env("${LLAMAP_TEST_UNSET_VARIABLE}").is_err()).is_true();
+}
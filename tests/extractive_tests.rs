@@ -0,0 +1,38 @@
+use llamap::summarize::textrank;
+use spectral::prelude::*;
+
+const TEXT: &str = "# Sitemaps\n\
+Sitemaps tell search engines about pages of a site. A sitemap lists pages of the site with their last modification date.\n\
+- Search engines read the sitemap to crawl pages of the site.\n\
+Bananas are yellow fruits grown in tropical regions.\n";
+
+#[test]
+fn central_sentences_are_picked_in_their_order() {
+    assert_that(&textrank(TEXT, 2)).is_equal_to(
+        "Sitemaps tell search engines about pages of a site. Search engines read the sitemap to crawl pages of the site."
+            .to_string(),
+    );
+}
+
+#[test]
+fn short_texts_are_kept_without_headings() {
+    assert_that(&textrank(TEXT, 5)).is_equal_to(
+        "Sitemaps tell search engines about pages of a site. A sitemap lists pages of the site with their last modification date. Search engines read the sitemap to crawl pages of the site. Bananas are yellow fruits grown in tropical regions."
+            .to_string(),
+    );
+}
+
+#[test]
+fn only_the_first_sentences_of_long_texts_are_ranked() {
+    let first: String = (0..200)
+        .map(|index| format!("Filler number {index} says nothing about the topic.\n"))
+        .collect();
+    let rest: String = (0..800)
+        .map(|index| format!("Sitemaps list the pages crawlers should visit {index}.\n"))
+        .collect();
+
+    let summary = textrank(&format!("{first}{rest}"), 3);
+
+    assert_that(&summary).starts_with("Filler number");
+    assert_that(&summary.contains("Sitemaps")).is_false();
+}
@@ -0,0 +1,48 @@
+use llamap::init::SiteDiscovery;
+use llamap::robots::parse_robots_txt;
+use spectral::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn robots_txt_declares_sitemaps_and_the_crawl_delay_of_every_agent() {
+    let robots = parse_robots_txt(
+        "User-agent: Googlebot\n\
+         Crawl-delay: 30\n\
+         \n\
+         User-agent: Bingbot\n\
+         User-agent: *\n\
+         Disallow: /admin # private\n\
+         Crawl-delay: 2.5\n\
+         \n\
+         Sitemap: https://example.com/sitemap_index.xml\n\
+         sitemap: https://example.com/news-sitemap.xml\n\
+         Sitemap: not a URL\n",
+    );
+
+    assert_that(
+        &robots
+            .sitemaps
+            .iter()
+            .map(url::Url::as_str)
+            .collect::<Vec<_>>(),
+    )
+    .is_equal_to(vec![
+        "https://example.com/sitemap_index.xml",
+        "https://example.com/news-sitemap.xml",
+    ]);
+    assert_that(&robots.crawl_delay).is_equal_to(Some(Duration::from_millis(2500)));
+}
+
+#[test]
+fn discovered_crawl_delays_are_honoured_by_the_proposed_settings() {
+    let polite = SiteDiscovery {
+        sitemaps: Vec::new(),
+        crawl_delay: Some(Duration::from_secs(5)),
+    };
+    let unknown = SiteDiscovery::default();
+
+    assert_that(&polite.proposed_delay()).is_equal_to(5000);
+    assert_that(&polite.proposed_concurrency()).is_equal_to(1);
+    assert_that(&unknown.proposed_delay()).is_equal_to(1000);
+    assert_that(&unknown.proposed_concurrency()).is_equal_to(2);
+}
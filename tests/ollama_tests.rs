@@ -0,0 +1,37 @@
+use llamap::ollama::{is_model_listed, server_url};
+use serde_json::json;
+use spectral::prelude::*;
+use url::Url;
+
+#[test]
+fn models_are_matched_by_name_and_tag() {
+    let tags = json!({
+        "models": [
+            { "name": "qwen3:8b", "model": "qwen3:8b" },
+            { "name": "llama3.2:latest", "model": "llama3.2:latest" },
+        ]
+    });
+
+    assert_that(&is_model_listed(&tags, "qwen3:8b")).is_true();
+    assert_that(&is_model_listed(&tags, "llama3.2")).is_true();
+    assert_that(&is_model_listed(&tags, "qwen3")).is_false();
+    assert_that(&is_model_listed(&json!({}), "qwen3:8b")).is_false();
+}
+
+#[test]
+fn only_model_urls_with_a_port_name_a_server() {
+    let parsed_server_url = |model_url: &str| {
+        server_url(&Url::parse(model_url).expect("Valid model URL"))
+            .expect("Valid server URL")
+            .map(|url| url.to_string())
+    };
+
+    assert_that(&parsed_server_url("ollama://qwen3:8b@localhost:11434"))
+        .is_equal_to(Some("http://localhost:11434/".to_string()));
+    assert_that(&parsed_server_url("ollama://llama3@gpu-box:8080"))
+        .is_equal_to(Some("http://gpu-box:8080/".to_string()));
+    assert_that(&parsed_server_url("ollama://8b@qwen3")).is_none();
+    assert_that(&parsed_server_url("ollama://chat@llama2")).is_none();
+    assert_that(&parsed_server_url("ollama://e4b@gemma3n")).is_none();
+    assert_that(&parsed_server_url("ollama://qwen3")).is_none();
+}
@@ -0,0 +1,140 @@
+use llamap::{ParseOptions, ParseTarget, TextBy, extract_article};
+use spectral::prelude::*;
+
+const CODE_PAGE: &str = r#"<html><body><article>
+<h1>Install</h1>
+<p>Add the crate to the manifest and call it from your code.</p>
+<pre><code class="language-rust">fn main() {
+    llamap::run();
+}</code></pre>
+</article></body></html>"#;
+
+#[test]
+fn code_blocks_are_kept_as_fenced_blocks_with_language() {
+    let article = extract_article(
+        CODE_PAGE,
+        &ParseOptions {
+            text_by: TextBy::FastHtml2Md,
+            code: true,
+            ..Default::default()
+        },
+    )
+    .expect("Extractable article");
+
+    assert_that(&article.text.as_str())
+        .contains("```rust\nfn main() {\n    llamap::run();\n}\n```");
+}
+
+#[test]
+fn simple_tables_are_converted_to_markdown_tables() {
+    let article = extract_article(
+        r#"<html><body><table>
+            <tr><th>Option</th><th>Default</th></tr>
+            <tr><td>delay</td><td>1000</td></tr>
+            <tr><td>a | b</td></tr>
+        </table></body></html>"#,
+        &ParseOptions {
+            text_by: TextBy::FastHtml2Md,
+            tables: true,
+            ..Default::default()
+        },
+    )
+    .expect("Extractable article");
+
+    assert_that(&article.text.as_str())
+        .contains("| Option | Default |\n| --- | --- |\n| delay | 1000 |\n| a \\| b |  |");
+}
+
+#[test]
+fn targets_with_wildcards_are_patterns() {
+    assert_that(&ParseTarget::from("https://example.com/blog/*")).is_equal_to(
+        ParseTarget::Pattern {
+            glob: "https://example.com/blog/*".to_string(),
+        },
+    );
+    assert_that(&ParseTarget::from("https://example.com/search?q=llms")).is_equal_to(
+        ParseTarget::Page {
+            url: "https://example.com/search?q=llms".to_string(),
+        },
+    );
+}
+
+#[test]
+fn breadcrumbs_are_read_from_json_ld_or_navigation() {
+    let options = ParseOptions {
+        text_by: TextBy::FastHtml2Md,
+        ..Default::default()
+    };
+    let json_ld = extract_article(
+        r#"<html><head><script type="application/ld+json">{"@context": "https://schema.org", "@graph": [
+            {"@type": "WebPage", "name": "Install"},
+            {"@type": "BreadcrumbList", "itemListElement": [
+                {"@type": "ListItem", "position": 2, "name": "Guides", "item": "https://example.com/guides/"},
+                {"@type": "ListItem", "position": 1, "name": "Home", "item": "https://example.com/"},
+                {"@type": "ListItem", "position": 3, "item": {"@id": "https://example.com/guides/install", "name": "Install"}}
+            ]}
+        ]}</script></head><body><p>Run the installer.</p></body></html>"#,
+        &options,
+    )
+    .expect("Extractable article");
+    let navigation = extract_article(
+        r#"<html><body><nav aria-label="Main"><ul><li>Blog</li></ul></nav>
+            <nav aria-label="Breadcrumb"><ol><li><a href="/">Home</a> /</li><li><a href="/docs/">Docs</a> /</li><li>Setup</li></ol></nav>
+            <p>Set it up.</p></body></html>"#,
+        &options,
+    )
+    .expect("Extractable article");
+
+    assert_that(&json_ld.breadcrumbs).is_equal_to(vec![
+        "Home".to_string(),
+        "Guides".to_string(),
+        "Install".to_string(),
+    ]);
+    assert_that(&navigation.breadcrumbs).is_equal_to(vec![
+        "Home".to_string(),
+        "Docs".to_string(),
+        "Setup".to_string(),
+    ]);
+}
+
+#[test]
+fn structured_data_of_articles_products_and_faqs_is_captured() {
+    use llamap::parse::StructuredData;
+
+    let article = extract_article(
+        r#"<html><head>
+            <script type="application/ld+json">{"@context": "https://schema.org", "@graph": [
+                {"@type": "WebSite", "name": "Example"},
+                {"@type": "BlogPosting", "headline": " Installing   the CLI ", "datePublished": "2026-10-12",
+                 "author": [{"@type": "Person", "name": "Ada"}, "Grace"]}
+            ]}</script>
+            <script type="application/ld+json">[{"@type": "FAQPage", "mainEntity": []}, {"@type": "Product", "name": "CLI"}]</script>
+            <script type="application/ld+json">{ not json</script>
+        </head><body><p>Run the installer.</p></body></html>"#,
+        &ParseOptions {
+            text_by: TextBy::FastHtml2Md,
+            ..Default::default()
+        },
+    )
+    .expect("Extractable article");
+
+    let types: Vec<&str> = article
+        .structured_data
+        .iter()
+        .filter_map(|block| block.get("@type").and_then(|data_type| data_type.as_str()))
+        .collect();
+    assert_that(&types).is_equal_to(vec!["BlogPosting", "FAQPage", "Product"]);
+    assert_that(&StructuredData::of_blocks(&article.structured_data)).is_equal_to(StructuredData {
+        headline: Some("Installing the CLI".to_string()),
+        date_published: Some("2026-10-12".to_string()),
+        author: Some("Ada, Grace".to_string()),
+        faq: true,
+    });
+    assert_that(&StructuredData::of_blocks(
+        article.structured_data.get(2..).unwrap_or_default(),
+    ))
+    .is_equal_to(StructuredData {
+        headline: Some("CLI".to_string()),
+        ..StructuredData::default()
+    });
+}
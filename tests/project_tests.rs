@@ -0,0 +1,71 @@
+use llamap::Project;
+use spectral::prelude::*;
+
+const PROJECT: &str = r#"{
+    "model": "ollama://8b@qwen3",
+    "rpm": 60,
+    "sites": [
+        {"name": "shop", "sitemap": "https://shop.example.com/sitemap.xml", "db": "shop.sqlite", "output": "shop.txt"},
+        {"name": "blog", "sitemap": "https://blog.example.com/sitemap.xml", "db": "blog.sqlite", "output": "blog.txt"}
+    ]
+}"#;
+
+#[test]
+fn projects_list_their_sites() {
+    let project = Project::parse(PROJECT).expect("Valid project");
+
+    assert_that(&project.sites).has_length(2);
+    let blog = project.site("blog").expect("Listed site");
+    assert_that(&blog.sitemap.as_str()).is_equal_to("https://blog.example.com/sitemap.xml");
+    assert_that(&blog.db.as_str()).is_equal_to("blog.sqlite");
+    assert_that(&project.site("docs").is_err()).is_true();
+}
+
+#[test]
+fn concurrent_sites_share_the_rate_limit_of_the_model() {
+    let project = Project::parse(PROJECT).expect("Valid project");
+
+    let options = project.summarize_options().expect("Summarized project");
+    let site_options = options.clone();
+    assert_that(&options.model_name.as_str()).is_equal_to("ollama://8b@qwen3");
+    assert_that(&options.rpm).is_equal_to(Some(60));
+    let limiter = options.rate_limiter.expect("Shared rate limiter");
+    let site_limiter = site_options.rate_limiter.expect("Shared rate limiter");
+    assert_that(&std::sync::Arc::ptr_eq(&limiter, &site_limiter)).is_true();
+}
+
+#[test]
+fn sites_sharing_a_database_are_rejected() {
+    let project = Project::parse(
+        r#"{"sites": [
+            {"name": "a", "sitemap": "https://a.example.com/sitemap.xml", "db": "sites.sqlite", "output": "a.txt"},
+            {"name": "b", "sitemap": "https://b.example.com/sitemap.xml", "db": "sites.sqlite", "output": "b.txt"}
+        ]}"#,
+    );
+
+    assert_that(&project.is_err()).is_true();
+}
+
+#[test]
+fn projects_without_a_model_are_not_summarized() {
+    let project = Project::parse(r#"{"sites": []}"#).expect("Valid project");
+
+    assert_that(&project.summarize_options().is_none()).is_true();
+}
+
+#[test]
+fn written_projects_are_read_back_with_the_crawl_settings_of_their_sites() {
+    let project = Project::parse(
+        r#"{"model": "ollama://8b@qwen3", "sites": [
+            {"name": "docs", "sitemap": "https://docs.example.com/sitemap.xml", "db": "docs.sqlite",
+             "output": "docs.txt", "delay": 2500, "concurrency": 1}
+        ]}"#,
+    )
+    .expect("Valid project");
+
+    let docs = project.site("docs").expect("Listed site");
+    assert_that(&docs.delay).is_equal_to(Some(2500));
+    assert_that(&docs.concurrency).is_equal_to(Some(1));
+    assert_that(&Project::parse(&project.to_json()).expect("Valid written project"))
+        .is_equal_to(project);
+}
@@ -0,0 +1,31 @@
+use llamap::sample::seeded_sample;
+use spectral::prelude::*;
+
+fn urls(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|page| format!("https://example.com/page-{page}"))
+        .collect()
+}
+
+#[test]
+fn the_same_seed_samples_the_same_pages() {
+    let sample = seeded_sample(urls(50), 5, 7);
+
+    assert_that(&sample).has_length(5);
+    assert_that(&seeded_sample(urls(50).into_iter().rev().collect(), 5, 7)).is_equal_to(&sample);
+    assert_that(&seeded_sample(urls(50), 5, 8)).is_not_equal_to(&sample);
+
+    let mut sorted = sample.clone();
+    sorted.sort();
+    assert_that(&sample).is_equal_to(sorted);
+}
+
+#[test]
+fn pages_stored_since_replace_only_the_pages_they_outrank() {
+    let sample = seeded_sample(urls(50), 5, 7);
+    let grown = seeded_sample(urls(51), 5, 7);
+
+    let kept = grown.iter().filter(|url| sample.contains(url)).count();
+    assert_that(&kept).is_greater_than_or_equal_to(4);
+    assert_that(&seeded_sample(urls(3), 5, 7)).is_equal_to(urls(3));
+}
@@ -0,0 +1,21 @@
+use llamap::auth::Auth;
+use llamap::secrets::Secret;
+use spectral::prelude::*;
+
+#[test]
+fn secrets_are_masked_when_printed() {
+    let key = Secret::from("sk-live-1234");
+
+    assert_that(&key.to_string().contains("1234")).is_false();
+    assert_that(&format!("{key:?}").contains("1234")).is_false();
+    assert_that(&format!("{:?}", Some(&key)).contains("1234")).is_false();
+    assert_that(&key.expose()).is_equal_to("sk-live-1234");
+}
+
+#[test]
+fn credentials_of_protected_sites_are_masked_when_printed() {
+    let auth = Auth::Bearer(Secret::new("abc.def"));
+
+    assert_that(&format!("{auth:?}").contains("abc.def")).is_false();
+    assert_that(&auth.header_value()).is_equal_to("Bearer abc.def".to_string());
+}
@@ -0,0 +1,181 @@
+use chrono::{TimeZone, Utc};
+use llamap::NoindexPages;
+use llamap::sitemap::{extract_sitemap_url_entries, render_sitemap, write_sitemap};
+use llamap::storage::{Page, PageFilter, SitemapEntry, Storage};
+use spectral::prelude::*;
+use url::Url;
+
+#[test]
+fn rendered_sitemaps_escape_urls() {
+    let sitemap = render_sitemap(&[SitemapEntry {
+        url: "https://example.com/search?q=a&page=2".to_string(),
+        lastmod: Utc
+            .with_ymd_and_hms(2026, 10, 12, 8, 30, 0)
+            .single()
+            .expect("Valid date"),
+        priority: Some(0.8),
+    }]);
+
+    assert_that(&sitemap).contains("<loc>https://example.com/search?q=a&amp;page=2</loc>");
+    assert_that(&sitemap).contains("<lastmod>2026-10-12T08:30:00Z</lastmod>");
+    assert_that(&sitemap).contains("<priority>0.8</priority>");
+}
+
+#[test]
+fn rendered_sitemaps_leave_out_unknown_lastmods() {
+    let sitemap = render_sitemap(&[SitemapEntry {
+        url: "https://example.com/".to_string(),
+        lastmod: chrono::DateTime::UNIX_EPOCH,
+        priority: None,
+    }]);
+
+    assert_that(&sitemap.contains("<lastmod>")).is_false();
+}
+
+#[tokio::test]
+async fn generated_sitemaps_list_stored_pages_with_their_lastmod() {
+    let db_path = std::env::temp_dir().join("llamap_sitemap_out.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+    let storage = Storage::new(db_path).expect("Database");
+    let lastmod = Utc
+        .with_ymd_and_hms(2026, 10, 12, 0, 0, 0)
+        .single()
+        .expect("Valid date");
+    for url in ["https://example.com/", "https://example.com/legal"] {
+        storage
+            .upsert_page(&Page {
+                url: Url::parse(url).expect("Valid page URL"),
+                added_at: Utc::now(),
+                lastmod,
+                html: String::new(),
+                title: None,
+                text: None,
+                summary: None,
+                content_type: None,
+                content: None,
+                robots_tag: None,
+                html_hash: None,
+            })
+            .expect("Stored page");
+    }
+    storage.add_exclusion("/legal").expect("Excluded");
+    let output_path = std::env::temp_dir().join("llamap_sitemap_out.xml");
+    let output_path = output_path.to_str().expect("UTF-8 path");
+
+    let listed = write_sitemap(
+        db_path,
+        output_path,
+        PageFilter::new(),
+        NoindexPages::Exclude,
+    )
+    .expect("Written sitemap");
+    let entries = extract_sitemap_url_entries(output_path, None)
+        .await
+        .expect("Read sitemap");
+
+    assert_that(&listed).is_equal_to(1);
+    assert_that(&entries.keys().collect::<Vec<_>>())
+        .is_equal_to(vec![&"https://example.com/".to_string()]);
+}
+
+#[tokio::test]
+async fn news_video_and_image_extensions_enrich_entries() {
+    use llamap::compose::ComposeOptions;
+    use llamap::sitemap::extract_sitemap_listing;
+    use llamap::{Byline, Llamap, MediaNotes};
+    use sitemap::structs::LastMod;
+
+    let sitemap_path = std::env::temp_dir().join("llamap_news_sitemap.xml");
+    std::fs::write(
+        &sitemap_path,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+    xmlns:news="http://www.google.com/schemas/sitemap-news/0.9"
+    xmlns:video="http://www.google.com/schemas/sitemap-video/1.1"
+    xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+  <url>
+    <loc>https://example.com/news/launch</loc>
+    <news:news>
+      <news:publication><news:name>Example</news:name><news:language>en</news:language></news:publication>
+      <news:publication_date>2026-10-10T09:00:00+02:00</news:publication_date>
+      <news:title>Launch &amp; roadmap</news:title>
+    </news:news>
+    <video:video><video:title>Demo</video:title><video:duration>245</video:duration></video:video>
+    <image:image><image:loc>https://example.com/chart.png</image:loc><image:caption>A chart</image:caption></image:image>
+    <image:image><image:loc>https://example.com/map.png</image:loc><image:caption><![CDATA[A map]]></image:caption></image:image>
+  </url>
+  <url><loc>https://example.com/about</loc></url>
+</urlset>"#,
+    )
+    .expect("Written sitemap");
+
+    let listing = extract_sitemap_listing(sitemap_path.to_str().expect("UTF-8 path"), None)
+        .await
+        .expect("Read sitemap");
+
+    let extensions = listing
+        .extensions
+        .get("https://example.com/news/launch")
+        .expect("News entry extensions")
+        .clone();
+    let published = Utc
+        .with_ymd_and_hms(2026, 10, 10, 7, 0, 0)
+        .single()
+        .expect("Valid date");
+    assert_that(&extensions.publication_date).is_equal_to(Some(published));
+    assert_that(&extensions.news_title).is_equal_to(Some("Launch & roadmap".to_string()));
+    assert_that(&extensions.video_duration).is_equal_to(Some(245));
+    assert_that(&extensions.image_captions)
+        .is_equal_to(vec!["A chart".to_string(), "A map".to_string()]);
+    assert_that(&listing.extensions.contains_key("https://example.com/about")).is_false();
+    let lastmod = &listing
+        .entries
+        .get("https://example.com/news/launch")
+        .expect("News entry")
+        .lastmod;
+    assert_that(&matches!(lastmod, LastMod::DateTime(lastmod) if lastmod.to_utc() == published))
+        .is_true();
+
+    let client = Llamap::open(":memory:").expect("In-memory database");
+    client
+        .storage()
+        .upsert_page(&Page {
+            url: Url::parse("https://example.com/news/launch").expect("Valid page URL"),
+            added_at: Utc::now(),
+            lastmod: published,
+            html: String::new(),
+            title: None,
+            text: Some("We launched.".to_string()),
+            summary: None,
+            content_type: None,
+            content: None,
+            robots_tag: None,
+            html_hash: None,
+        })
+        .expect("Stored page");
+    client
+        .storage()
+        .save_sitemap_extensions(&listing.extensions)
+        .expect("Stored extensions");
+    let output_path = std::env::temp_dir().join("llamap_news.llms.txt");
+    let output_path = output_path.to_str().expect("UTF-8 path");
+    client
+        .compose(
+            output_path,
+            ComposeOptions {
+                byline: Byline::Shown,
+                media: MediaNotes::Shown,
+                ..ComposeOptions::default()
+            },
+            None,
+        )
+        .await
+        .expect("Composed pages");
+
+    let composed = std::fs::read_to_string(output_path).expect("Composed file");
+    assert_that(&composed).contains("## [Launch & roadmap](https://example.com/news/launch)");
+    assert_that(&composed).contains("Published: 2026-10-10");
+    assert_that(&composed).contains("Video: 4:05");
+    assert_that(&composed).contains("Images: A chart; A map");
+}
@@ -0,0 +1,970 @@
+use chrono::Utc;
+use llamap::PageSort;
+use llamap::storage::{
+    BrokenLink, Page, PageFilter, PageSelection, PageStatus, Redirect, ResponseHeaders, Storage,
+};
+use spectral::prelude::*;
+use url::Url;
+
+fn page(url: &str, text: Option<&str>) -> Page {
+    Page {
+        url: Url::parse(url).expect("Valid page URL"),
+        added_at: Utc::now(),
+        lastmod: Utc::now(),
+        html: String::new(),
+        title: None,
+        text: text.map(str::to_string),
+        summary: None,
+        content_type: None,
+        content: None,
+        robots_tag: None,
+        html_hash: None,
+    }
+}
+
+#[test]
+fn pages_are_iterated_across_batches_in_url_order() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for index in (0..250).rev() {
+        let text = (index % 2 == 0).then_some("Parsed text");
+        storage
+            .upsert_page(&page(&format!("https://example.com/{index:03}"), text))
+            .expect("Stored page");
+    }
+
+    let urls = storage
+        .iter_pages()
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Iterable pages");
+    let mut sorted_urls = urls.clone();
+    sorted_urls.sort();
+
+    assert_that(&urls.len()).is_equal_to(250);
+    assert_that(&urls).is_equal_to(sorted_urls);
+    assert_that(&storage.iter_pages_where(PageSelection::Composable).count()).is_equal_to(125);
+}
+
+#[test]
+fn pages_are_found_by_url_glob_and_text() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for (url, text) in [
+        (
+            "https://example.com/docs/install",
+            "Run cargo install llamap",
+        ),
+        ("https://example.com/docs/usage", "Scrape a sitemap"),
+        (
+            "https://example.com/blog/release",
+            "Run cargo install to upgrade",
+        ),
+    ] {
+        storage
+            .upsert_page(&page(url, Some(text)))
+            .expect("Stored page");
+    }
+
+    let filter = PageFilter::new()
+        .url_glob("https://example.com/docs/*")
+        .text_contains("cargo install");
+    let urls = storage
+        .find_pages(filter)
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Found pages");
+
+    assert_that(&urls).is_equal_to(vec!["https://example.com/docs/install".to_string()]);
+}
+
+#[test]
+fn pages_are_found_by_listed_urls() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in [
+        "https://example.com/",
+        "https://example.com/docs/install",
+        "https://example.com/blog/release",
+    ] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+    let list_path = std::env::temp_dir().join("llamap_listed_urls.txt");
+    std::fs::write(
+        &list_path,
+        "# Pages to refresh\nhttps://example.com\n\nhttps://example.com/docs/install\nnot a URL\n",
+    )
+    .expect("Written list");
+
+    let listed =
+        llamap::stdio::read_url_list(list_path.to_str().expect("UTF-8 path")).expect("Read list");
+    let urls = storage
+        .find_pages(PageFilter::new().urls(listed))
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Found pages");
+
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/".to_string(),
+        "https://example.com/docs/install".to_string(),
+    ]);
+}
+
+#[test]
+fn missing_url_list_is_an_io_error() {
+    let list_path = std::env::temp_dir().join("llamap_missing_url_list.txt");
+    let _ = std::fs::remove_file(&list_path);
+
+    let listed = llamap::stdio::read_url_list(list_path.to_str().expect("UTF-8 path"));
+
+    assert_that(&matches!(listed, Err(llamap::Error::Io(_)))).is_true();
+}
+
+#[test]
+fn path_patterns_tag_pages_by_url_path() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in [
+        "https://example.com/docs/install",
+        "https://example.com/blog/docs/release",
+    ] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+
+    let tagged = storage.tag_pages("docs", "/docs/*").expect("Tagged pages");
+    let urls = storage
+        .find_pages(PageFilter::new().tagged("docs"))
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Found pages");
+
+    assert_that(&tagged).is_equal_to(1);
+    assert_that(&urls).is_equal_to(vec!["https://example.com/docs/install".to_string()]);
+}
+
+#[test]
+fn excluded_pages_are_skipped_by_path_or_url_pattern() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in [
+        "https://example.com/legal/terms",
+        "https://example.com/blog/legal/news",
+        "https://example.com/drafts",
+    ] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+
+    assert_that(&storage.add_exclusion("/legal/*").expect("Excluded")).is_true();
+    assert_that(&storage.add_exclusion("/legal/*").expect("Excluded")).is_false();
+    storage
+        .add_exclusion("https://example.com/drafts*")
+        .expect("Excluded");
+    let urls = storage
+        .find_pages(PageFilter::new().skip_excluded())
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Found pages");
+
+    assert_that(&urls).is_equal_to(vec!["https://example.com/blog/legal/news".to_string()]);
+    assert_that(
+        &storage
+            .is_excluded("https://example.com/legal/privacy?lang=de")
+            .expect("Checked"),
+    )
+    .is_true();
+    assert_that(&storage.remove_exclusion("/legal/*").expect("Removed")).is_true();
+    assert_that(&storage.list_exclusions().expect("Listed"))
+        .is_equal_to(vec!["https://example.com/drafts*".to_string()]);
+}
+
+#[test]
+fn sorted_urls_break_ties_by_url() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for (url, title) in [
+        ("https://example.com/c", Some("Alpha")),
+        ("https://example.com/b", None),
+        ("https://example.com/a", Some("alpha")),
+    ] {
+        let mut stored_page = page(url, Some("Parsed text"));
+        stored_page.title = title.map(str::to_string);
+        storage.upsert_page(&stored_page).expect("Stored page");
+    }
+
+    let urls = storage
+        .list_sorted_urls(
+            PageSelection::Composable,
+            &PageFilter::new(),
+            &PageSort::Title,
+        )
+        .expect("Sorted URLs");
+
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/a".to_string(),
+        "https://example.com/c".to_string(),
+        "https://example.com/b".to_string(),
+    ]);
+}
+
+#[test]
+fn sampled_urls_are_limited_to_selected_pages() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for index in 0..10 {
+        let text = (index % 2 == 0).then_some("Parsed text");
+        storage
+            .upsert_page(&page(&format!("https://example.com/{index}"), text))
+            .expect("Stored page");
+    }
+
+    let urls = storage
+        .sample_urls(
+            PageSelection::Summarizable {
+                include_flagged: true,
+            },
+            &PageFilter::new(),
+            3,
+        )
+        .expect("Sampled URLs");
+
+    assert_that(&urls).has_length(3);
+    for url in &urls {
+        let index: usize = url
+            .trim_start_matches("https://example.com/")
+            .parse()
+            .expect("Page index");
+        assert_that(&(index % 2)).is_equal_to(0);
+    }
+}
+
+#[test]
+fn iteration_resumes_after_a_checkpoint_url() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for index in 0..5 {
+        storage
+            .upsert_page(&page(&format!("https://example.com/{index}"), None))
+            .expect("Stored page");
+    }
+
+    let urls = storage
+        .iter_pages()
+        .after("https://example.com/2")
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Iterable pages");
+
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/3".to_string(),
+        "https://example.com/4".to_string(),
+    ]);
+}
+
+#[test]
+fn untranslated_pages_lack_a_summary_in_the_language() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for index in 0..3 {
+        storage
+            .upsert_page(&page(&format!("https://example.com/{index}"), Some("Text")))
+            .expect("Stored page");
+    }
+    storage
+        .update_page_translation("https://example.com/1", "deu", "Zusammenfassung")
+        .expect("Stored translation");
+    storage
+        .update_page_translation("https://example.com/2", "fra", "Résumé")
+        .expect("Stored translation");
+
+    let urls = storage
+        .find_pages(PageFilter::new().untranslated("deu"))
+        .map(|page| page.map(|page| page.url.to_string()))
+        .collect::<llamap::Result<Vec<_>>>()
+        .expect("Iterable pages");
+
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/0".to_string(),
+        "https://example.com/2".to_string(),
+    ]);
+    assert_that(
+        &storage
+            .get_page_translation("https://example.com/1", "deu")
+            .expect("Readable translation"),
+    )
+    .is_equal_to(Some("Zusammenfassung".to_string()));
+}
+
+#[test]
+fn broken_links_point_at_failed_or_missing_pages() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in ["https://example.com/a", "https://example.com/b"] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+    storage
+        .replace_page_links(
+            "https://example.com/a",
+            &[
+                "https://example.com/b".to_string(),
+                "https://example.com/gone".to_string(),
+                "https://example.com/drafts/1".to_string(),
+            ],
+        )
+        .expect("Stored links");
+    storage
+        .replace_page_links(
+            "https://example.com/b",
+            &["https://example.com/new".to_string()],
+        )
+        .expect("Stored links");
+    storage
+        .replace_page_links(
+            "https://example.com/removed",
+            &["https://example.com/x".to_string()],
+        )
+        .expect("Stored links");
+    storage.add_exclusion("/drafts/*").expect("Excluded");
+    let run_id = storage.start_run("scrape", "").expect("Started run");
+    storage
+        .record_failure(
+            run_id,
+            "scrape",
+            "https://example.com/gone",
+            "HTTP status 404",
+        )
+        .expect("Recorded failure");
+
+    let broken_links = storage.list_broken_links().expect("Listed broken links");
+
+    assert_that(&broken_links).is_equal_to(vec![
+        BrokenLink {
+            source: "https://example.com/a".to_string(),
+            target: "https://example.com/gone".to_string(),
+            error: Some("HTTP status 404".to_string()),
+        },
+        BrokenLink {
+            source: "https://example.com/b".to_string(),
+            target: "https://example.com/new".to_string(),
+            error: None,
+        },
+    ]);
+}
+
+#[test]
+fn response_headers_are_replaced_on_every_scrape() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    let url = "https://example.com/a";
+
+    assert_that(&storage.get_page_headers(url).expect("Readable headers")).is_none();
+    storage
+        .update_page_headers(
+            url,
+            &ResponseHeaders {
+                content_type: Some("text/html".to_string()),
+                etag: Some("\"v1\"".to_string()),
+                ..ResponseHeaders::default()
+            },
+        )
+        .expect("Stored headers");
+    let headers = ResponseHeaders {
+        content_type: Some("text/html; charset=utf-8".to_string()),
+        last_modified: Some("Wed, 21 Oct 2026 07:28:00 GMT".to_string()),
+        etag: None,
+        cache_control: Some("max-age=600".to_string()),
+    };
+    storage
+        .update_page_headers(url, &headers)
+        .expect("Stored headers");
+
+    assert_that(&storage.get_page_headers(url).expect("Readable headers"))
+        .is_equal_to(Some(headers));
+}
+
+#[test]
+fn redirected_urls_are_collapsed_into_their_target() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in ["http://example.com/a", "https://example.com/a"] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+
+    storage
+        .record_redirect(&Redirect {
+            url: "http://example.com/a".to_string(),
+            target: "https://example.com/a".to_string(),
+        })
+        .expect("Recorded redirect");
+    storage
+        .replace_page_links(
+            "https://example.com/a",
+            &["http://example.com/a".to_string()],
+        )
+        .expect("Stored links");
+
+    assert_that(
+        &storage
+            .get_page("http://example.com/a")
+            .expect("Readable page"),
+    )
+    .is_none();
+    assert_that(&storage.list_redirects().expect("Listed redirects")).is_equal_to(vec![Redirect {
+        url: "http://example.com/a".to_string(),
+        target: "https://example.com/a".to_string(),
+    }]);
+    assert_that(
+        &storage
+            .get_lastmod("http://example.com/a")
+            .expect("Readable lastmod"),
+    )
+    .is_equal_to(
+        storage
+            .get_lastmod("https://example.com/a")
+            .expect("Readable lastmod"),
+    );
+    assert_that(&storage.list_broken_links().expect("Listed broken links")).is_empty();
+}
+
+#[test]
+fn run_traffic_is_recorded_for_scrape_runs_only() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    let scrape_run = storage.start_run("scrape", "").expect("Started run");
+    storage
+        .finish_run(scrape_run, Some(2), None)
+        .expect("Finished run");
+    storage
+        .record_run_traffic(scrape_run, 3, 4096)
+        .expect("Recorded traffic");
+    storage.start_run("parse", "").expect("Started run");
+
+    let runs = storage.list_runs(10).expect("Listed runs");
+
+    let traffic: Vec<_> = runs
+        .iter()
+        .map(|run| (run.command.as_str(), run.requests, run.bytes_downloaded))
+        .collect();
+    assert_that(&traffic).is_equal_to(vec![("parse", None, None), ("scrape", Some(3), Some(4096))]);
+}
+
+#[test]
+fn in_memory_storage_is_empty_until_a_page_is_stored() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    assert_that(&storage.is_empty().expect("Checked pages")).is_true();
+
+    storage
+        .upsert_page(&page("https://example.com/a", None))
+        .expect("Stored page");
+
+    assert_that(&storage.is_empty().expect("Checked pages")).is_false();
+    assert_that(&storage.clone().is_empty().expect("Checked pages")).is_false();
+}
+
+#[test]
+fn page_status_follows_the_stages() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in ["https://example.com/a", "https://example.com/b"] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+    storage
+        .upsert_page(&page("https://example.com/a", Some("Parsed text")))
+        .expect("Parsed page");
+    storage
+        .update_page_summary("https://example.com/a", "Summary")
+        .expect("Summarized page");
+    let run_id = storage.start_run("parse", "").expect("Started run");
+    storage
+        .record_failure(run_id, "parse", "https://example.com/b", "Broken HTML")
+        .expect("Recorded failure");
+
+    assert_that(
+        &storage
+            .get_page_status("https://example.com/a")
+            .expect("Readable status"),
+    )
+    .is_equal_to(Some(PageStatus::Summarized));
+    assert_that(&storage.count_pages_by_status().expect("Counted pages")).is_equal_to(vec![
+        (PageStatus::Summarized, 1),
+        (PageStatus::Failed("parse".to_string()), 1),
+    ]);
+    assert_that(
+        &storage
+            .iter_pages_where(PageSelection::Unsummarized {
+                include_flagged: true,
+            })
+            .count(),
+    )
+    .is_equal_to(0);
+
+    storage
+        .update_page_status("https://example.com/a", &PageStatus::Composed)
+        .expect("Composed page");
+    assert_that(&storage.iter_pages_where(PageSelection::Composable).count()).is_equal_to(1);
+}
+
+#[test]
+fn page_metadata_is_kept_when_the_page_is_stored_again() {
+    use llamap::summarize::StructuredSummary;
+
+    let url = "https://example.com/guide";
+    let storage = Storage::in_memory().expect("In-memory database");
+    storage
+        .upsert_page(&page(url, Some("Parsed text")))
+        .expect("Stored page");
+    storage
+        .update_page_structured_summary(
+            url,
+            &StructuredSummary {
+                summary: "Summary".to_string(),
+                keywords: vec!["sitemap".to_string()],
+                audience: "web developers".to_string(),
+            },
+        )
+        .expect("Summarized page");
+    storage
+        .update_page_breadcrumbs(url, &["Docs".to_string(), "Guide".to_string()])
+        .expect("Stored breadcrumbs");
+    storage
+        .update_page_structured_data(url, &[serde_json::json!({"@type": "Article"})])
+        .expect("Stored structured data");
+
+    let mut stored_page = storage
+        .get_page(url)
+        .expect("Readable page")
+        .expect("Stored page");
+    stored_page.text = Some("Parsed again".to_string());
+    storage
+        .upsert_page(&stored_page)
+        .expect("Stored page again");
+
+    assert_that(&storage.get_page_keywords(url).expect("Readable keywords"))
+        .is_equal_to(vec!["sitemap".to_string()]);
+    assert_that(
+        &storage
+            .get_page_breadcrumbs(url)
+            .expect("Readable breadcrumbs"),
+    )
+    .is_equal_to(vec!["Docs".to_string(), "Guide".to_string()]);
+    assert_that(
+        &storage
+            .get_page_structured_data(url)
+            .expect("Readable structured data"),
+    )
+    .is_equal_to(vec![serde_json::json!({"@type": "Article"})]);
+    assert_that(&storage.get_page_text(url).expect("Readable text"))
+        .is_equal_to(Some("Parsed again".to_string()));
+}
+
+#[test]
+fn broken_rows_are_fixed_or_quarantined_on_repair() {
+    use llamap::storage::{RepairReport, RowProblem};
+
+    let db_path = std::env::temp_dir().join("llamap_repair.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+    let storage = Storage::new(db_path).expect("Created database");
+    for (url, html) in [
+        ("https://example.com/empty", ""),
+        ("https://example.com/dated", "<p>Dated</p>"),
+        ("https://example.com/sound", "<p>Sound</p>"),
+    ] {
+        storage
+            .upsert_page(&Page {
+                html: html.to_string(),
+                ..page(url, None)
+            })
+            .expect("Stored page");
+    }
+    let conn = rusqlite::Connection::open(db_path).expect("Opened database");
+    conn.execute_batch(
+        "UPDATE pages SET lastmod = 'yesterday' WHERE url = 'https://example.com/dated';
+         INSERT INTO pages (url, added_at, lastmod, html) VALUES ('not a url', 0, 0, '<p>Lost</p>');",
+    )
+    .expect("Broken rows");
+    assert_that(&storage.get_page("https://example.com/dated").is_err()).is_true();
+
+    let problems: Vec<(String, Vec<RowProblem>)> = storage
+        .check_rows()
+        .expect("Checked rows")
+        .into_iter()
+        .map(|issue| (issue.url, issue.problems))
+        .collect();
+    assert_that(&problems).is_equal_to(vec![
+        (
+            "https://example.com/dated".to_string(),
+            vec![RowProblem::InvalidLastmod],
+        ),
+        (
+            "https://example.com/empty".to_string(),
+            vec![RowProblem::EmptyHtml],
+        ),
+        ("not a url".to_string(), vec![RowProblem::InvalidUrl]),
+    ]);
+
+    let report = storage.repair_rows().expect("Repaired rows");
+    assert_that(&report).is_equal_to(RepairReport {
+        fixed: 1,
+        quarantined: 2,
+    });
+    assert_that(&storage.check_rows().expect("Checked rows")).is_empty();
+    let dated = storage
+        .get_page("https://example.com/dated")
+        .expect("Readable page")
+        .expect("Kept page");
+    assert_that(&dated.lastmod.timestamp()).is_equal_to(0);
+    let quarantined: i64 = conn
+        .query_row("SELECT COUNT(*) FROM quarantined_pages", [], |row| {
+            row.get(0)
+        })
+        .expect("Counted quarantined rows");
+    assert_that(&quarantined).is_equal_to(2);
+}
+
+#[test]
+fn invalid_timestamps_of_older_databases_are_read_as_unknown() {
+    use llamap::storage::UNKNOWN_TIMESTAMP;
+
+    let db_path = std::env::temp_dir().join("llamap_unknown_timestamps.sqlite");
+    let _ = std::fs::remove_file(&db_path);
+    let db_path = db_path.to_str().expect("UTF-8 path");
+    let storage = Storage::new(db_path).expect("Created database");
+    for url in ["https://example.com/old", "https://example.com/docs/guide"] {
+        storage
+            .upsert_page(&Page {
+                html: "<p>Page</p>".to_string(),
+                ..page(url, None)
+            })
+            .expect("Stored page");
+    }
+    rusqlite::Connection::open(db_path)
+        .expect("Opened database")
+        .execute_batch(
+            "UPDATE pages SET lastmod = -86400, added_at = 'last year'
+             WHERE url = 'https://example.com/old'",
+        )
+        .expect("Older values");
+
+    let storage = Storage::new(db_path).expect("Reopened database");
+    let old = storage
+        .get_page("https://example.com/old")
+        .expect("Readable page")
+        .expect("Stored page");
+    assert_that(&old.lastmod.timestamp()).is_equal_to(UNKNOWN_TIMESTAMP);
+    assert_that(&old.added_at.timestamp()).is_equal_to(UNKNOWN_TIMESTAMP);
+
+    let reset = storage
+        .reset_lastmod(&PageFilter::new().url_glob("https://example.com/docs/*"))
+        .expect("Reset lastmod");
+    assert_that(&reset).is_equal_to(1);
+    assert_that(&storage.get_lastmod("https://example.com/docs/guide"))
+        .is_ok()
+        .is_equal_to(Some(UNKNOWN_TIMESTAMP));
+}
+
+#[test]
+fn only_unstored_urls_are_new() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    storage
+        .upsert_page(&page("https://example.com/stored", None))
+        .expect("Stored page");
+
+    let new_urls = storage
+        .resolve_new([
+            "https://example.com/stored".to_string(),
+            "https://example.com/new".to_string(),
+        ])
+        .expect("Resolved new URLs");
+
+    assert_that(&new_urls).is_equal_to(vec!["https://example.com/new".to_string()]);
+}
+
+#[test]
+fn urls_of_pages_matching_a_filter_are_found() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in [
+        "https://example.com/blog/b",
+        "https://example.com/blog/a",
+        "https://example.com/about",
+    ] {
+        storage.upsert_page(&page(url, None)).expect("Stored page");
+    }
+
+    let urls = storage
+        .find_urls(&PageFilter::new().url_glob("https://example.com/blog/*"))
+        .expect("Found URLs");
+
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/blog/a".to_string(),
+        "https://example.com/blog/b".to_string(),
+    ]);
+}
+
+#[test]
+fn unvisited_pages_are_archived_until_stored_again() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in ["https://example.com/kept", "https://example.com/gone"] {
+        storage
+            .upsert_page(&page(url, Some("Parsed text")))
+            .expect("Stored page");
+    }
+
+    let archived = storage
+        .archive_unvisited_pages(["https://example.com/kept".to_string()])
+        .expect("Archived pages");
+    let archived_urls = || {
+        storage
+            .list_archived_pages()
+            .expect("Listed archived pages")
+            .into_iter()
+            .map(|page| page.url)
+            .collect::<Vec<_>>()
+    };
+
+    assert_that(&archived).is_equal_to(1);
+    assert_that(&storage.list_urls().expect("Listed URLs"))
+        .is_equal_to(vec!["https://example.com/kept".to_string()]);
+    assert_that(&archived_urls()).is_equal_to(vec!["https://example.com/gone".to_string()]);
+
+    storage
+        .upsert_page(&page("https://example.com/gone", None))
+        .expect("Stored page again");
+    assert_that(&archived_urls()).is_empty();
+}
+
+#[test]
+fn sitemap_snapshots_are_compared_with_the_previous_run() {
+    use chrono::TimeZone;
+    use sitemap::structs::{LastMod, UrlEntry};
+    use std::collections::HashMap;
+
+    let storage = Storage::in_memory().expect("In-memory database");
+    let entries = |listed: &[(&str, u32)]| -> HashMap<String, UrlEntry> {
+        listed
+            .iter()
+            .map(|&(url, day)| {
+                let mut entry = UrlEntry::builder().loc(url).build().expect("Valid entry");
+                let lastmod = Utc
+                    .with_ymd_and_hms(2026, 10, day, 0, 0, 0)
+                    .single()
+                    .expect("Valid date");
+                entry.lastmod = LastMod::DateTime(lastmod.fixed_offset());
+                (url.to_string(), entry)
+            })
+            .collect()
+    };
+
+    let first_run = storage.start_run("scrape", "").expect("Started run");
+    storage
+        .save_sitemap_snapshot(
+            first_run,
+            &entries(&[
+                ("https://example.com/kept", 1),
+                ("https://example.com/bumped", 1),
+                ("https://example.com/gone", 1),
+            ]),
+        )
+        .expect("Saved snapshot");
+    assert_that(&storage.sitemap_diff(None).expect("Compared snapshots")).is_none();
+
+    let second_run = storage.start_run("scrape", "").expect("Started run");
+    storage
+        .save_sitemap_snapshot(
+            second_run,
+            &entries(&[
+                ("https://example.com/kept", 1),
+                ("https://example.com/bumped", 2),
+                ("https://example.com/new", 2),
+            ]),
+        )
+        .expect("Saved snapshot");
+
+    let diff = storage
+        .sitemap_diff(None)
+        .expect("Compared snapshots")
+        .expect("Two snapshots");
+    assert_that(&(diff.previous_run, diff.run)).is_equal_to((first_run, second_run));
+    assert_that(&diff.added).is_equal_to(vec!["https://example.com/new".to_string()]);
+    assert_that(&diff.removed).is_equal_to(vec!["https://example.com/gone".to_string()]);
+    assert_that(&diff.bumped).is_equal_to(vec!["https://example.com/bumped".to_string()]);
+    assert_that(
+        &storage
+            .sitemap_diff(Some(first_run))
+            .expect("Compared snapshots"),
+    )
+    .is_none();
+}
+
+#[test]
+fn summary_audits_are_listed_worst_first_until_summarized_again() {
+    use llamap::storage::{SummaryAudit, SummaryScores};
+
+    let storage = Storage::in_memory().expect("In-memory database");
+    for url in [
+        "https://example.com/good",
+        "https://example.com/bad",
+        "https://example.com/new",
+    ] {
+        storage
+            .upsert_page(&page(url, Some("Parsed text")))
+            .expect("Stored page");
+        storage
+            .update_page_summary(url, "A summary")
+            .expect("Stored summary");
+    }
+    let run_id = storage.start_run("audit", "").expect("Started run");
+    for (url, faithfulness) in [
+        ("https://example.com/good", 5),
+        ("https://example.com/bad", 2),
+    ] {
+        let audit = SummaryAudit {
+            url: url.to_string(),
+            scores: SummaryScores {
+                faithfulness,
+                length: 4,
+                language: 5,
+            },
+            comment: String::new(),
+            audited_at: Utc::now(),
+        };
+        storage
+            .save_summary_audit(run_id, "A summary", &audit)
+            .expect("Saved audit");
+    }
+
+    let worst_urls = || {
+        storage
+            .list_worst_audits(10)
+            .expect("Listed audits")
+            .into_iter()
+            .map(|audit| audit.url)
+            .collect::<Vec<_>>()
+    };
+    assert_that(&worst_urls()).is_equal_to(vec![
+        "https://example.com/bad".to_string(),
+        "https://example.com/good".to_string(),
+    ]);
+    assert_that(
+        &storage
+            .list_unaudited_urls(&PageFilter::new())
+            .expect("Listed URLs"),
+    )
+    .is_equal_to(vec!["https://example.com/new".to_string()]);
+
+    storage
+        .update_page_summary("https://example.com/bad", "A better summary")
+        .expect("Stored summary");
+    assert_that(&worst_urls()).is_equal_to(vec!["https://example.com/good".to_string()]);
+    assert_that(
+        &storage
+            .list_unaudited_urls(&PageFilter::new())
+            .expect("Listed URLs"),
+    )
+    .is_equal_to(vec![
+        "https://example.com/bad".to_string(),
+        "https://example.com/new".to_string(),
+    ]);
+}
+
+#[test]
+fn pages_are_searched_by_text_and_summary_as_they_change() {
+    use llamap::storage::plain_search_query;
+
+    let storage = Storage::in_memory().expect("In-memory database");
+    storage
+        .upsert_page(&page(
+            "https://example.com/protocol",
+            Some("The sitemap.xml protocol lists URLs."),
+        ))
+        .expect("Stored page");
+    storage
+        .upsert_page(&page(
+            "https://example.com/faq",
+            Some("Questions about robots."),
+        ))
+        .expect("Stored page");
+    let search = |query: &str| {
+        storage
+            .search_pages(&plain_search_query(query), &PageFilter::new(), 10)
+            .expect("Searched pages")
+            .into_iter()
+            .map(|hit| hit.url)
+            .collect::<Vec<_>>()
+    };
+
+    assert_that(&search("sitemap.xml"))
+        .is_equal_to(vec!["https://example.com/protocol".to_string()]);
+    assert_that(&search("robots lists")).is_empty();
+
+    storage
+        .update_page_summary(
+            "https://example.com/faq",
+            "Answers about sitemaps and robots.",
+        )
+        .expect("Stored summary");
+    assert_that(&search("robots answers")).is_equal_to(vec!["https://example.com/faq".to_string()]);
+
+    storage
+        .upsert_page(&page("https://example.com/protocol", Some("A new text.")))
+        .expect("Stored page again");
+    assert_that(&search("protocol")).is_empty();
+    assert_that(&search("new text")).is_equal_to(vec!["https://example.com/protocol".to_string()]);
+
+    storage
+        .remove_page("https://example.com/faq")
+        .expect("Removed page");
+    assert_that(&search("robots")).is_empty();
+}
+
+#[test]
+fn questions_match_pages_containing_any_of_their_words() {
+    use llamap::storage::any_word_search_query;
+
+    let storage = Storage::in_memory().expect("In-memory database");
+    storage
+        .upsert_page(&page(
+            "https://example.com/protocol",
+            Some("A sitemap index lists up to 50,000 sitemaps."),
+        ))
+        .expect("Stored page");
+    storage
+        .upsert_page(&page(
+            "https://example.com/faq",
+            Some("Sitemaps can be gzipped."),
+        ))
+        .expect("Stored page");
+
+    let query = any_word_search_query("How many sitemaps can a sitemap index list?");
+    assert_that(&query).does_not_contain("\"a\"");
+    let urls: Vec<String> = storage
+        .search_pages(&query, &PageFilter::new(), 10)
+        .expect("Searched pages")
+        .into_iter()
+        .map(|hit| hit.url)
+        .collect();
+    assert_that(&urls).is_equal_to(vec![
+        "https://example.com/protocol".to_string(),
+        "https://example.com/faq".to_string(),
+    ]);
+}
+
+#[test]
+fn write_batches_are_committed_or_rolled_back_as_a_whole() {
+    let storage = Storage::in_memory().expect("In-memory database");
+    storage
+        .write_batch(|batch| {
+            batch.upsert_page(&page("https://example.com/old", Some("Old text")))?;
+            batch.upsert_page(&page("https://example.com/new", Some("New text")))?;
+            batch.record_redirect(&Redirect {
+                url: "https://example.com/old".to_string(),
+                target: "https://example.com/new".to_string(),
+            })
+        })
+        .expect("Committed batch");
+
+    let failed: llamap::Result<()> = storage.write_batch(|batch| {
+        batch.update_page_text("https://example.com/new", "Lost text")?;
+        Err(llamap::Error::Storage("Failed write".into()))
+    });
+
+    assert_that(&failed).is_err();
+    assert_that(
+        &storage
+            .get_page("https://example.com/old")
+            .expect("Read page"),
+    )
+    .is_none();
+    assert_that(
+        &storage
+            .get_page_text("https://example.com/new")
+            .expect("Read page text"),
+    )
+    .is_equal_to(Some("New text".to_string()));
+    assert_that(&storage.list_redirects().expect("Listed redirects")).has_length(1);
+}
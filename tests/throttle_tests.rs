@@ -0,0 +1,56 @@
+use llamap::throttle::{AdaptiveThrottle, is_rate_limit_error, parse_retry_after};
+use spectral::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn back_off_slows_down_and_ramp_up_restores_configured_pace() {
+    let mut throttle = AdaptiveThrottle::new(1000, 4);
+
+    throttle.back_off();
+    assert_that(&throttle.delay()).is_equal_to(2000);
+    assert_that(&throttle.concurrency()).is_equal_to(2);
+    assert_that(&throttle.is_throttled()).is_true();
+
+    for _ in 0..10 {
+        throttle.ramp_up();
+    }
+    assert_that(&throttle).is_equal_to(AdaptiveThrottle::new(1000, 4));
+    assert_that(&throttle.is_throttled()).is_false();
+}
+
+#[test]
+fn back_off_from_zero_delay_uses_minimal_delay() {
+    let mut throttle = AdaptiveThrottle::new(0, 1);
+
+    throttle.back_off();
+    assert_that(&throttle.delay()).is_equal_to(500);
+    assert_that(&throttle.concurrency()).is_equal_to(1);
+}
+
+#[test]
+fn retry_after_is_parsed_from_seconds_and_capped() {
+    assert_that(&parse_retry_after("120")).is_equal_to(Some(Duration::from_secs(120)));
+    assert_that(&parse_retry_after("100000")).is_equal_to(Some(Duration::from_secs(300)));
+    assert_that(&parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"))
+        .is_equal_to(Some(Duration::ZERO));
+    assert_that(&parse_retry_after("soon")).is_none();
+}
+
+#[test]
+fn rate_limit_errors_are_detected() {
+    assert_that(&is_rate_limit_error("HTTP Error: 429 Too Many Requests")).is_true();
+    assert_that(&is_rate_limit_error("Provider Error: Rate limit reached")).is_true();
+    assert_that(&is_rate_limit_error(
+        "OpenAI API returned error status: 429",
+    ))
+    .is_true();
+    assert_that(&is_rate_limit_error("Auth Error: invalid key")).is_false();
+    assert_that(&is_rate_limit_error(
+        "Provider Error: prompt of 14290 tokens exceeds the context",
+    ))
+    .is_false();
+    assert_that(&is_rate_limit_error(
+        "Response Format Error: no choice at index 429",
+    ))
+    .is_false();
+}
@@ -0,0 +1,46 @@
+use llamap::validate::{Finding, ValidateOptions, validate};
+use spectral::prelude::*;
+use url::Url;
+
+#[test]
+fn spec_file_has_no_findings() {
+    let content = "# example.com\n\n> Example site.\n\n## Docs\n\n- [Install](https://example.com/docs/install): How to install\n- [Usage](/docs/usage)\n\n```\n# Not a heading\n- [not a link\n```\n";
+    let options = ValidateOptions {
+        base_url: Some(Url::parse("https://example.com").expect("Valid base URL")),
+        ..ValidateOptions::default()
+    };
+
+    assert_that(&validate(content, &options)).is_empty();
+}
+
+#[test]
+fn structure_links_and_limits_are_reported() {
+    let content = "## Docs\n\n- [Usage](/docs/usage)\n- [Broken(https://example.com)\n# Second\n";
+    let options = ValidateOptions {
+        max_bytes: Some(10),
+        ..ValidateOptions::default()
+    };
+
+    let findings = validate(content, &options);
+
+    assert_that(&findings).contains(Finding {
+        line: None,
+        message: format!(
+            "The file takes {} bytes, more than the limit of 10",
+            content.len()
+        ),
+    });
+    assert_that(&findings).contains(Finding {
+        line: Some(1),
+        message: "The file doesn't start with an H1 heading (\"# Site name\")".to_string(),
+    });
+    assert_that(&findings).contains(Finding {
+        line: Some(3),
+        message: "Relative link /docs/usage can't be resolved without a base URL".to_string(),
+    });
+    assert_that(&findings).contains(Finding {
+        line: Some(4),
+        message: "List item isn't a valid markdown link (\"- [title](url): notes\")".to_string(),
+    });
+    assert_that(&findings).has_length(5);
+}